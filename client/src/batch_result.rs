@@ -0,0 +1,170 @@
+use std::fmt::Write;
+
+/// Outcome of a single item of a batch operation.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum BatchOutcome {
+  Succeeded,
+  /// Failed with a reason.
+  Failed(String),
+  /// Skipped with a reason, for example because the item was already in the desired state.
+  Skipped(String),
+}
+
+/// Item of a batch operation, identified by `name`, along with its outcome.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BatchItem {
+  pub name: String,
+  pub outcome: BatchOutcome,
+}
+
+/// Number of succeeded, failed, and skipped items of a batch operation.
+#[derive(Default, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct BatchCounts {
+  pub succeeded: usize,
+  pub failed: usize,
+  pub skipped: usize,
+}
+
+/// Per-item outcomes of a batch operation such as following or unfollowing many crates, aggregated into a report.
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct BatchResult {
+  title: String,
+  items: Vec<BatchItem>,
+}
+
+impl BatchResult {
+  /// Creates an empty result for the batch operation described by `title`.
+  #[inline]
+  pub fn new(title: impl Into<String>) -> Self {
+    Self { title: title.into(), items: Vec::new() }
+  }
+
+  /// Adds item `name` with `outcome`.
+  #[inline]
+  pub fn push(&mut self, name: impl Into<String>, outcome: BatchOutcome) {
+    self.items.push(BatchItem { name: name.into(), outcome });
+  }
+  /// Adds item `name` that succeeded.
+  #[inline]
+  pub fn push_succeeded(&mut self, name: impl Into<String>) {
+    self.push(name, BatchOutcome::Succeeded);
+  }
+  /// Adds item `name` that failed because of `reason`.
+  #[inline]
+  pub fn push_failed(&mut self, name: impl Into<String>, reason: impl Into<String>) {
+    self.push(name, BatchOutcome::Failed(reason.into()));
+  }
+  /// Adds item `name` that was skipped because of `reason`.
+  #[inline]
+  pub fn push_skipped(&mut self, name: impl Into<String>, reason: impl Into<String>) {
+    self.push(name, BatchOutcome::Skipped(reason.into()));
+  }
+
+  #[inline]
+  pub fn title(&self) -> &str { &self.title }
+  #[inline]
+  pub fn items(&self) -> &[BatchItem] { &self.items }
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.items.is_empty() }
+
+  /// Counts the succeeded, failed, and skipped items.
+  pub fn counts(&self) -> BatchCounts {
+    let mut counts = BatchCounts::default();
+    for item in &self.items {
+      match item.outcome {
+        BatchOutcome::Succeeded => counts.succeeded += 1,
+        BatchOutcome::Failed(_) => counts.failed += 1,
+        BatchOutcome::Skipped(_) => counts.skipped += 1,
+      }
+    }
+    counts
+  }
+
+  /// Returns the failed items along with their reasons.
+  pub fn failed(&self) -> impl Iterator<Item=(&str, &str)> {
+    self.items.iter().filter_map(|item| match &item.outcome {
+      BatchOutcome::Failed(reason) => Some((item.name.as_str(), reason.as_str())),
+      _ => None,
+    })
+  }
+  /// Returns the skipped items along with their reasons.
+  pub fn skipped(&self) -> impl Iterator<Item=(&str, &str)> {
+    self.items.iter().filter_map(|item| match &item.outcome {
+      BatchOutcome::Skipped(reason) => Some((item.name.as_str(), reason.as_str())),
+      _ => None,
+    })
+  }
+
+  /// Formats the counts as a single line, for example `"Unfollow crates: 3 succeeded, 1 failed, 0 skipped"`.
+  pub fn counts_line(&self) -> String {
+    let BatchCounts { succeeded, failed, skipped } = self.counts();
+    format!("{}: {succeeded} succeeded, {failed} failed, {skipped} skipped", self.title)
+  }
+
+  /// Formats a plain-text summary with the counts, followed by the failed and skipped items with their reasons, suitable
+  /// for copying to the clipboard.
+  pub fn summary(&self) -> String {
+    let mut summary = self.counts_line();
+    write_reasons(&mut summary, "Failed", self.failed());
+    write_reasons(&mut summary, "Skipped", self.skipped());
+    summary
+  }
+}
+
+fn write_reasons<'a>(summary: &mut String, heading: &str, mut items: impl Iterator<Item=(&'a str, &'a str)>) {
+  let Some((name, reason)) = items.next() else { return };
+  let _ = write!(summary, "\n\n{heading}:\n- {name}: {reason}");
+  for (name, reason) in items {
+    let _ = write!(summary, "\n- {name}: {reason}");
+  }
+}
+
+impl Extend<BatchItem> for BatchResult {
+  #[inline]
+  fn extend<I: IntoIterator<Item=BatchItem>>(&mut self, iter: I) {
+    self.items.extend(iter);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn batch_result() -> BatchResult {
+    let mut batch_result = BatchResult::new("Follow crates");
+    batch_result.push_succeeded("serde");
+    batch_result.push_failed("tokio", "server error");
+    batch_result.push_skipped("rand", "already followed");
+    batch_result.push_succeeded("axum");
+    batch_result.push_failed("nonexistent", "not found");
+    batch_result
+  }
+
+  #[test]
+  fn counts_outcomes() {
+    assert_eq!(batch_result().counts(), BatchCounts { succeeded: 2, failed: 2, skipped: 1 });
+    assert_eq!(BatchResult::new("Empty").counts(), BatchCounts::default());
+  }
+
+  #[test]
+  fn failed_and_skipped_with_reasons() {
+    let batch_result = batch_result();
+    assert_eq!(batch_result.failed().collect::<Vec<_>>(), [("tokio", "server error"), ("nonexistent", "not found")]);
+    assert_eq!(batch_result.skipped().collect::<Vec<_>>(), [("rand", "already followed")]);
+  }
+
+  #[test]
+  fn summary_lists_failed_and_skipped_items() {
+    assert_eq!(batch_result().summary(), "Follow crates: 2 succeeded, 2 failed, 1 skipped\n\n\
+      Failed:\n- tokio: server error\n- nonexistent: not found\n\n\
+      Skipped:\n- rand: already followed");
+  }
+
+  #[test]
+  fn summary_of_successful_batch_is_counts_line() {
+    let mut batch_result = BatchResult::new("Unfollow crates");
+    batch_result.push_succeeded("serde");
+    assert_eq!(batch_result.summary(), "Unfollow crates: 1 succeeded, 0 failed, 0 skipped");
+    assert_eq!(batch_result.summary(), batch_result.counts_line());
+  }
+}
@@ -5,19 +5,169 @@ use futures::FutureExt;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error};
 
-use att_core::crates::{CratesQuery, FullCrate};
+use att_core::crates::{CrateId, CratesPage, CratesQuery, FullCrate, PageInfo};
 use att_core::query::{Query, QueryMessage};
 use att_core::service::{Catalog, Service};
+use att_core::table::{as_markdown_table, AsTableRow, ColumnSelection};
 use att_core::util::future::OptFutureExt;
 use att_core::util::maybe_send::{MaybeSend, MaybeSendFuture, MaybeSendOptFuture};
 
+use crate::batch_result::BatchResult;
+use crate::failed_operation::FailedOperation;
 use crate::http_client::{AttHttpClient, AttHttpClientError};
 use crate::query_sender::{QuerySender, QuerySenderRequest, QuerySenderResponse};
+use crate::search_history::SearchHistory;
 
 /// Crates state that can be (de)serialized.
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct CratesState {
-  id_to_crate: BTreeMap<i32, FullCrate>,
+  id_to_crate: BTreeMap<CrateId, FullCrate>,
+  /// Crate ids in display order.
+  #[serde(default)]
+  order: Vec<CrateId>,
+  /// Whether `order` was customized by [reordering](Self::reorder), in which case it is kept when all crates are set.
+  #[serde(default)]
+  custom_order: bool,
+}
+
+impl CratesState {
+  #[inline]
+  pub fn len(&self) -> usize { self.order.len() }
+
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.order.is_empty() }
+
+  #[inline]
+  pub fn get(&self, index: usize) -> Option<&FullCrate> {
+    self.order.get(index).and_then(|crate_id| self.id_to_crate.get(crate_id))
+  }
+
+  #[inline]
+  pub fn iter(&self) -> impl Iterator<Item=&FullCrate> {
+    self.order.iter().filter_map(|crate_id| self.id_to_crate.get(crate_id))
+  }
+
+  #[inline]
+  pub fn get_by_id(&self, crate_id: CrateId) -> Option<&FullCrate> { self.id_to_crate.get(&crate_id) }
+
+  #[inline]
+  pub fn contains(&self, crate_id: CrateId) -> bool { self.id_to_crate.contains_key(&crate_id) }
+
+  /// Renders the name, downloads, version, and update time of all crates, in display order, as a GitHub-flavored
+  /// markdown table. Columns hidden in `column_selection` are left out.
+  pub fn as_markdown_table(&self, column_selection: &ColumnSelection) -> String {
+    const COLUMN_INDICES: &[u8] = &[1, 4, 3, 2];
+    let column_indices: Vec<u8> = COLUMN_INDICES.iter()
+      .copied()
+      .filter(|i| column_selection.is_visible(&FullCrate::COLUMNS[*i as usize]))
+      .collect();
+    as_markdown_table(self.iter(), &column_indices)
+  }
+
+  /// Gets the ids of all crates, in display order.
+  #[inline]
+  pub fn crate_ids(&self) -> &[CrateId] { &self.order }
+
+  /// Returns whether the display order was customized by [reordering](Self::reorder).
+  #[inline]
+  pub fn has_custom_order(&self) -> bool { self.custom_order }
+
+  /// Moves the crate at index `from` to index `to`, shifting the crates in between. Returns `false` if either index is
+  /// out of bounds.
+  pub fn reorder(&mut self, from: usize, to: usize) -> bool {
+    if from >= self.order.len() || to >= self.order.len() {
+      return false;
+    }
+    if from != to {
+      let crate_id = self.order.remove(from);
+      self.order.insert(to, crate_id);
+      self.custom_order = true;
+    }
+    true
+  }
+
+  /// Merges `full_crates` into this state, deduplicating them by crate id. If a crate is already present, the
+  /// [richer](FullCrate::is_richer_than) of the two records is kept.
+  pub fn merge(&mut self, full_crates: impl IntoIterator<Item=FullCrate>) {
+    for full_crate in full_crates {
+      let is_poorer = self.id_to_crate.get(&full_crate.krate.id)
+        .is_some_and(|existing| existing.is_richer_than(&full_crate));
+      if !is_poorer {
+        self.insert(full_crate);
+      }
+    }
+  }
+
+  /// [Merges](Self::merge) the crates of `other` that are also in this state, so that crates present in both are shown
+  /// once, with the richer of the two records.
+  pub fn merge_present(&mut self, other: &CratesState) {
+    let present: Vec<_> = other.iter()
+      .filter(|full_crate| self.contains(full_crate.krate.id))
+      .cloned()
+      .collect();
+    self.merge(present);
+  }
+
+  /// Inserts `full_crate`, replacing the crate with the same id if present.
+  pub(crate) fn upsert(&mut self, full_crate: FullCrate) {
+    self.insert(full_crate);
+  }
+
+  /// Sets the display order to `order`, keeping the invariant that it contains exactly the ids of all crates.
+  pub(crate) fn set_order(&mut self, order: Vec<CrateId>, custom_order: bool) {
+    self.order = order;
+    self.custom_order = custom_order;
+    self.normalize_order();
+  }
+
+  fn insert(&mut self, full_crate: FullCrate) {
+    let crate_id = full_crate.krate.id;
+    if self.id_to_crate.insert(crate_id, full_crate).is_none() {
+      self.order.push(crate_id);
+    }
+  }
+
+  pub(crate) fn remove(&mut self, crate_id: CrateId) {
+    if self.id_to_crate.remove(&crate_id).is_some() {
+      self.order.retain(|id| *id != crate_id);
+    }
+  }
+
+  fn set_all(&mut self, full_crates: Vec<FullCrate>) {
+    let previous_order = std::mem::take(&mut self.order);
+    self.id_to_crate.clear();
+    for full_crate in full_crates {
+      self.insert(full_crate);
+    }
+    if self.custom_order {
+      // Keep the custom order for crates that are still present, followed by new crates in the order they were given.
+      let mut order: Vec<CrateId> = previous_order.into_iter()
+        .filter(|crate_id| self.id_to_crate.contains_key(crate_id))
+        .collect();
+      let kept: BTreeSet<CrateId> = order.iter().copied().collect();
+      order.extend(self.order.iter().filter(|crate_id| !kept.contains(crate_id)));
+      self.order = order;
+    }
+  }
+
+  fn clear(&mut self) {
+    self.id_to_crate.clear();
+    self.order.clear();
+    self.custom_order = false;
+  }
+
+  /// Restores the invariant that `order` contains exactly the ids of `id_to_crate`, which may not hold for state
+  /// deserialized from an older format.
+  fn normalize_order(&mut self) {
+    let mut seen = BTreeSet::new();
+    let id_to_crate = &self.id_to_crate;
+    self.order.retain(|crate_id| id_to_crate.contains_key(crate_id) && seen.insert(*crate_id));
+    for crate_id in self.id_to_crate.keys() {
+      if !seen.contains(crate_id) {
+        self.order.push(*crate_id);
+      }
+    }
+  }
 }
 
 /// Keep track of crates.
@@ -26,8 +176,11 @@ pub struct Crates {
   http_client: AttHttpClient,
   query_sender: QuerySender<CratesQuery>,
   state: CratesState,
-  crates_being_modified: BTreeSet<i32>,
+  crates_being_modified: BTreeSet<CrateId>,
   all_crates_being_modified: bool,
+  page_info: Option<PageInfo>,
+  last_failure: Option<FailedOperation<CratesRequest>>,
+  search_history: Option<SearchHistory>,
 }
 
 impl Crates {
@@ -35,17 +188,28 @@ impl Crates {
   pub fn new(
     http_client: AttHttpClient,
     query_sender: QuerySender<CratesQuery>,
-    state: CratesState,
+    mut state: CratesState,
   ) -> Self {
+    state.normalize_order();
     Self {
       http_client,
       state,
       crates_being_modified: Default::default(),
       all_crates_being_modified: false,
+      page_info: None,
+      last_failure: None,
+      search_history: None,
       query_sender,
     }
   }
 
+  /// Records queries sent by the query sender into `search_history`.
+  #[inline]
+  pub fn with_search_history(mut self, search_history: SearchHistory) -> Self {
+    self.search_history = Some(search_history);
+    self
+  }
+
   #[inline]
   pub fn with_default_state(http_client: AttHttpClient, query_sender: QuerySender<CratesQuery>) -> Self {
     Self::new(http_client, query_sender, CratesState::default())
@@ -54,9 +218,27 @@ impl Crates {
   #[inline]
   pub fn state(&self) -> &CratesState { &self.state }
 
+  /// [Merges](CratesState::merge_present) the crates of `other` that are also in the state.
+  #[inline]
+  pub fn merge_present(&mut self, other: &CratesState) { self.state.merge_present(other); }
+
+  /// Exports the state as JSON, which can be [imported](Self::import_state) again.
+  pub fn export_state(&self) -> String {
+    serde_json::to_string_pretty(&self.state).expect("serializing crates state to JSON cannot fail")
+  }
+
+  /// Imports crates state from `json`, [merging](CratesState::merge) its crates into the current state instead of
+  /// replacing it, so that crates that are already present are not duplicated. Imported crates are not followed on the
+  /// server: that must be done by [sending follow requests](Self::send_follow) for them.
+  pub fn import_state(&mut self, json: &str) -> Result<(), serde_json::Error> {
+    let imported: CratesState = serde_json::from_str(json)?;
+    self.state.merge(imported.iter().cloned());
+    Ok(())
+  }
+
 
   #[inline]
-  pub fn is_crate_being_modified(&self, crate_id: i32) -> bool {
+  pub fn is_crate_being_modified(&self, crate_id: CrateId) -> bool {
     self.all_crates_being_modified || self.crates_being_modified.contains(&crate_id)
   }
 
@@ -65,11 +247,50 @@ impl Crates {
     self.all_crates_being_modified
   }
 
+  /// Gets the position of the current page of crates, if a query has been answered.
+  #[inline]
+  pub fn page_info(&self) -> Option<&PageInfo> {
+    self.page_info.as_ref()
+  }
+
+  /// Gets the error of the last response that failed to be processed, if any.
+  #[inline]
+  pub fn last_error(&self) -> Option<&AttHttpClientError> {
+    self.last_failure.as_ref().map(|f| &f.error)
+  }
+
+  /// Gets the last operation that failed, if any.
+  #[inline]
+  pub fn last_failure(&self) -> Option<&FailedOperation<CratesRequest>> {
+    self.last_failure.as_ref()
+  }
+
+  #[inline]
+  pub fn clear_last_error(&mut self) {
+    self.last_failure = None;
+  }
+
+  /// Sends the request of the last failed operation again, clearing the failure. Returns `None` if there is no failed
+  /// operation.
+  pub fn send_retry(&mut self) -> Option<impl Future<Output=CratesResponse> + MaybeSend + 'static> {
+    let failure = self.last_failure.take()?;
+    debug!(request = ?failure.request, "retrying failed request");
+    self.send(failure.request)
+  }
+
+  /// Gets the history of sent queries, if queries are being recorded.
+  #[inline]
+  pub fn search_history(&self) -> Option<&SearchHistory> {
+    self.search_history.as_ref()
+  }
+
 
   pub fn reset(&mut self) {
-    self.state.id_to_crate.clear();
+    self.state.clear();
     self.crates_being_modified.clear();
     self.all_crates_being_modified = false;
+    self.page_info = None;
+    self.last_failure = None;
     self.query_sender.reset();
   }
 }
@@ -79,18 +300,17 @@ impl Catalog for Crates {
 
   #[inline]
   fn len(&self) -> usize {
-    self.state.id_to_crate.len()
+    self.state.len()
   }
 
   #[inline]
   fn get(&self, index: usize) -> Option<&Self::Data> {
-    // OPTO: instead of going through iterator with `nth`, can we directly go to the index efficiently?
-    self.state.id_to_crate.values().nth(index)
+    self.state.get(index)
   }
 
   #[inline]
   fn iter(&self) -> impl Iterator<Item=&Self::Data> {
-    self.state.id_to_crate.values()
+    self.state.iter()
   }
 
 
@@ -119,11 +339,11 @@ impl Crates {
     self.all_crates_being_modified = true;
     let future = self.http_client.search_crates(self.query_sender.query().clone());
     async move {
-      UpdateAll { result: future.await }
+      UpdateAll::from_page(future.await, None)
     }
   }
 
-  pub fn send_refresh(&mut self, crate_id: i32) -> impl Future<Output=UpdateOne> {
+  pub fn send_refresh(&mut self, crate_id: CrateId) -> impl Future<Output=UpdateOne> {
     self.crates_being_modified.insert(crate_id);
     let future = self.http_client.refresh_crate(crate_id);
     async move {
@@ -135,7 +355,7 @@ impl Crates {
     self.all_crates_being_modified = true;
     let future = self.http_client.refresh_followed();
     async move {
-      UpdateAll { result: future.await }
+      UpdateAll { result: future.await, page_info: None, query_request_id: None }
     }
   }
 
@@ -148,7 +368,7 @@ impl Crates {
     }
   }
 
-  pub fn send_unfollow(&mut self, crate_id: i32) -> impl Future<Output=Unfollow> {
+  pub fn send_unfollow(&mut self, crate_id: CrateId) -> impl Future<Output=Unfollow> {
     self.crates_being_modified.insert(crate_id);
     let future = self.http_client.unfollow_crate(crate_id);
     async move {
@@ -156,6 +376,44 @@ impl Crates {
     }
   }
 
+  pub fn send_unfollow_many(&mut self, crate_ids: Vec<CrateId>) -> impl Future<Output=UnfollowMany> {
+    self.crates_being_modified.extend(crate_ids.iter().copied());
+    let future = self.http_client.unfollow_crates(crate_ids.clone());
+    async move {
+      UnfollowMany { crate_ids, result: future.await }
+    }
+  }
+
+  pub fn reorder(&mut self, from: usize, to: usize) {
+    if self.state.reorder(from, to) {
+      debug!(from, to, "reorder crate");
+    }
+  }
+
+  /// Sets whether already followed crates are included in the query results, returning a future producing a response
+  /// that must be processed to send the updated query.
+  pub fn send_show_followed(&mut self, show_followed: bool) -> Option<impl Future<Output=QuerySenderResponse>> {
+    let mut query = self.query_sender.query().clone();
+    query.followed = if show_followed { None } else { Some(false) };
+    query.page = None;
+    self.send_set_query(query)
+  }
+
+  /// Sets the page of the query to `page`, returning a future producing a response that must be processed to send the
+  /// updated query.
+  pub fn send_set_page(&mut self, page: u32) -> Option<impl Future<Output=QuerySenderResponse>> {
+    let mut query = self.query_sender.query().clone();
+    query.page = Some(page);
+    self.send_set_query(query)
+  }
+
+  /// Sets the query to `query`, for example to re-run a query from the search history, returning a future producing a
+  /// response that must be processed to send the query.
+  pub fn send_set_query(&mut self, query: CratesQuery) -> Option<impl Future<Output=QuerySenderResponse>> {
+    self.query_sender.set_query(query)
+      .opt_map_into()
+  }
+
   pub fn send_query(
     &mut self,
     request: QuerySenderRequest
@@ -169,9 +427,13 @@ impl Crates {
 /// Update one crate response.
 #[derive(Debug)]
 pub struct UpdateOne {
-  crate_id: i32,
+  crate_id: CrateId,
   result: Result<FullCrate, AttHttpClientError>,
 }
+impl UpdateOne {
+  #[inline]
+  fn request(&self) -> CratesRequest { CratesRequest::Refresh(self.crate_id) }
+}
 
 pub type FullCratesResult = Result<Vec<FullCrate>, AttHttpClientError>;
 
@@ -179,6 +441,24 @@ pub type FullCratesResult = Result<Vec<FullCrate>, AttHttpClientError>;
 #[derive(Debug)]
 pub struct UpdateAll<const SET: bool> {
   result: FullCratesResult,
+  page_info: Option<PageInfo>,
+  /// Id of the query sender request that sent the query, or `None` if the query was not sent by the query sender.
+  query_request_id: Option<u64>,
+}
+impl UpdateAll<true> {
+  #[inline]
+  fn from_page(result: Result<CratesPage, AttHttpClientError>, query_request_id: Option<u64>) -> Self {
+    match result {
+      Ok(crates_page) => Self { result: Ok(crates_page.crates), page_info: Some(crates_page.page_info), query_request_id },
+      Err(e) => Self { result: Err(e), page_info: None, query_request_id },
+    }
+  }
+}
+impl<const SET: bool> UpdateAll<SET> {
+  #[inline]
+  fn request(&self) -> CratesRequest {
+    if SET { CratesRequest::InitialQuery } else { CratesRequest::RefreshFollowed }
+  }
 }
 
 /// Follow crate response.
@@ -187,13 +467,48 @@ pub struct Follow {
   full_crate: FullCrate,
   result: Result<(), AttHttpClientError>,
 }
+impl Follow {
+  #[inline]
+  fn request(&self) -> CratesRequest { CratesRequest::Follow(self.full_crate.clone()) }
+}
 
 /// Unfollow crate response.
 #[derive(Debug)]
 pub struct Unfollow {
-  crate_id: i32,
+  crate_id: CrateId,
   result: Result<(), AttHttpClientError>,
 }
+impl Unfollow {
+  #[inline]
+  fn request(&self) -> CratesRequest { CratesRequest::Unfollow(self.crate_id) }
+}
+
+/// Unfollow many crates response.
+#[derive(Debug)]
+pub struct UnfollowMany {
+  crate_ids: Vec<CrateId>,
+  result: Result<(), AttHttpClientError>,
+}
+impl UnfollowMany {
+  #[inline]
+  fn request(&self) -> CratesRequest { CratesRequest::UnfollowMany(self.crate_ids.clone()) }
+
+  /// Creates a [`BatchResult`] of this response, naming crates by their name in `state`, or by their ID if they are not
+  /// in `state`. Crates are unfollowed all at once, so all crates share the same outcome.
+  pub fn batch_result(&self, state: &CratesState) -> BatchResult {
+    let mut batch_result = BatchResult::new("Unfollow crates");
+    for crate_id in &self.crate_ids {
+      let name = state.get_by_id(*crate_id)
+        .map(|full_crate| full_crate.krate.name.clone())
+        .unwrap_or_else(|| crate_id.to_string());
+      match &self.result {
+        Ok(_) => batch_result.push_succeeded(name),
+        Err(cause) => batch_result.push_failed(name, cause.to_string()),
+      }
+    }
+    batch_result
+  }
+}
 
 impl Crates {
   pub fn process_update_one(&mut self, response: UpdateOne) -> Result<(), AttHttpClientError> {
@@ -201,24 +516,33 @@ impl Crates {
     self.crates_being_modified.remove(&crate_id);
 
     let full_crate = response.result
-      .inspect_err(|cause| error!(crate_id, %cause, "failed to update crate: {cause:?}"))?;
-    debug!(crate_id, "update crate");
-    self.state.id_to_crate.insert(crate_id, full_crate);
+      .inspect_err(|cause| error!(%crate_id, %cause, "failed to update crate: {cause:?}"))?;
+    debug!(%crate_id, "update crate");
+    self.state.insert(full_crate);
 
     Ok(())
   }
 
   pub fn process_update_all<const SET: bool>(&mut self, response: UpdateAll<SET>) -> Result<(), AttHttpClientError> {
+    if let Some(query_request_id) = response.query_request_id {
+      if !self.query_sender.is_latest_request(query_request_id) {
+        debug!(query_request_id, "dropping stale query response");
+        return Ok(());
+      }
+    }
     self.all_crates_being_modified = false;
 
     let full_crates = response.result
       .inspect_err(|cause| error!(%cause, "failed to update crates: {cause:?}"))?;
     if SET {
-      self.state.id_to_crate.clear();
-    }
-    for full_crate in full_crates {
-      debug!(crate_id = full_crate.krate.id, "update crate");
-      self.state.id_to_crate.insert(full_crate.krate.id, full_crate);
+      debug!(count = full_crates.len(), "set crates");
+      self.page_info = response.page_info;
+      self.state.set_all(full_crates);
+    } else {
+      for full_crate in full_crates {
+        debug!(crate_id = %full_crate.krate.id, "update crate");
+        self.state.insert(full_crate);
+      }
     }
 
     Ok(())
@@ -231,7 +555,7 @@ impl Crates {
     response.result
       .inspect_err(|cause| error!(crate = ?response.full_crate, %cause, "failed to follow crate: {cause:?}"))?;
     debug!(crate = ?response.full_crate, "follow crate");
-    self.state.id_to_crate.insert(crate_id, response.full_crate);
+    self.state.merge([response.full_crate]);
 
     Ok(())
   }
@@ -241,9 +565,24 @@ impl Crates {
     self.crates_being_modified.remove(&crate_id);
 
     response.result
-      .inspect_err(|cause| error!(crate_id, %cause, "failed to unfollow crate: {cause:?}"))?;
-    debug!(crate_id, "unfollow crate");
-    self.state.id_to_crate.remove(&crate_id);
+      .inspect_err(|cause| error!(%crate_id, %cause, "failed to unfollow crate: {cause:?}"))?;
+    debug!(%crate_id, "unfollow crate");
+    self.state.remove(crate_id);
+
+    Ok(())
+  }
+
+  pub fn process_unfollow_many(&mut self, response: UnfollowMany) -> Result<(), AttHttpClientError> {
+    for crate_id in &response.crate_ids {
+      self.crates_being_modified.remove(crate_id);
+    }
+
+    response.result
+      .inspect_err(|cause| error!(crate_ids = ?response.crate_ids, %cause, "failed to unfollow crates: {cause:?}"))?;
+    debug!(crate_ids = ?response.crate_ids, "unfollow crates");
+    for crate_id in response.crate_ids {
+      self.state.remove(crate_id);
+    }
 
     Ok(())
   }
@@ -251,9 +590,13 @@ impl Crates {
   pub fn process_query(&mut self, response: QuerySenderResponse) -> Option<impl Future<Output=UpdateAll<true>>> {
     match self.query_sender.process(response) {
       Some(query) => {
+        if let Some(search_history) = &mut self.search_history {
+          search_history.push(query.clone());
+        }
+        let query_request_id = self.query_sender.request_id();
         let future = self.http_client
           .search_crates(query)
-          .map(|result| UpdateAll { result });
+          .map(move |result| UpdateAll::from_page(result, Some(query_request_id)));
         return Some(future);
       },
       None => None,
@@ -282,9 +625,17 @@ impl Service for Crates {
 pub enum CratesRequest {
   InitialQuery,
   Follow(FullCrate),
-  Unfollow(i32),
-  Refresh(i32),
+  Unfollow(CrateId),
+  UnfollowMany(Vec<CrateId>),
+  Refresh(CrateId),
   RefreshFollowed,
+  Reorder(usize, usize),
+  ShowFollowed(bool),
+  SetPage(u32),
+  SetQuery(CratesQuery),
+  /// Open a URL in the browser. This is an effect that must be handled by the front-end, and is ignored by
+  /// [Crates::send].
+  OpenUrl(String),
   Query(QuerySenderRequest),
 }
 
@@ -298,8 +649,20 @@ impl Crates {
       InitialQuery => self.send_initial_query().map_into().boxed_maybe_send(),
       Follow(krate) => self.send_follow(krate).map_into().boxed_maybe_send(),
       Unfollow(crate_id) => self.send_unfollow(crate_id).map_into().boxed_maybe_send(),
+      UnfollowMany(crate_ids) => self.send_unfollow_many(crate_ids).map_into().boxed_maybe_send(),
       Refresh(crate_id) => self.send_refresh(crate_id).map_into().boxed_maybe_send(),
       RefreshFollowed => self.send_refresh_followed().map_into().boxed_maybe_send(),
+      Reorder(from, to) => {
+        self.reorder(from, to);
+        return None;
+      }
+      ShowFollowed(show_followed) => return self.send_show_followed(show_followed).opt_map_into().opt_boxed_maybe_send(),
+      SetPage(page) => return self.send_set_page(page).opt_map_into().opt_boxed_maybe_send(),
+      SetQuery(query) => return self.send_set_query(query).opt_map_into().opt_boxed_maybe_send(),
+      OpenUrl(url) => {
+        debug!(url, "ignoring open URL request, which must be handled by the front-end");
+        return None;
+      }
       Query(r) => return self.send_query(r).opt_map_into().opt_boxed_maybe_send(),
     };
     Some(future)
@@ -314,6 +677,7 @@ pub enum CratesResponse {
   SetAll(UpdateAll<true>),
   Follow(Follow),
   Unfollow(Unfollow),
+  UnfollowMany(UnfollowMany),
   Query(QuerySenderResponse),
 }
 impl From<UpdateOne> for CratesResponse {
@@ -336,6 +700,10 @@ impl From<Unfollow> for CratesResponse {
   #[inline]
   fn from(s: Unfollow) -> Self { Self::Unfollow(s) }
 }
+impl From<UnfollowMany> for CratesResponse {
+  #[inline]
+  fn from(s: UnfollowMany) -> Self { Self::UnfollowMany(s) }
+}
 impl From<QuerySenderResponse> for CratesResponse {
   #[inline]
   fn from(s: QuerySenderResponse) -> Self { Self::Query(s) }
@@ -347,14 +715,126 @@ impl Crates {
     response: CratesResponse
   ) -> Option<impl Future<Output=CratesResponse> + MaybeSend + 'static> {
     use CratesResponse::*;
-    match response {
-      UpdateOne(s) => { let _ = self.process_update_one(s); }
-      UpdateAll(s) => { let _ = self.process_update_all(s); }
-      SetAll(s) => { let _ = self.process_update_all(s); }
-      Follow(s) => { let _ = self.process_follow(s); }
-      Unfollow(s) => { let _ = self.process_unfollow(s); }
+    let (request, result) = match response {
+      UpdateOne(s) => (s.request(), self.process_update_one(s)),
+      UpdateAll(s) => (s.request(), self.process_update_all(s)),
+      SetAll(s) => (s.request(), self.process_update_all(s)),
+      Follow(s) => (s.request(), self.process_follow(s)),
+      Unfollow(s) => (s.request(), self.process_unfollow(s)),
+      UnfollowMany(s) => (s.request(), self.process_unfollow_many(s)),
       Query(s) => return self.process_query(s).opt_map_into(),
+    };
+    if let Err(cause) = result {
+      self.last_failure = Some(FailedOperation::new(request, cause));
     }
     None
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use chrono::{DateTime, TimeDelta};
+
+  use att_core::crates::{Crate, CrateError, CratesQueryConfig};
+
+  use super::*;
+
+  fn full_crate(id: i32, updated_days: i64) -> FullCrate {
+    let krate = Crate {
+      id: CrateId(id),
+      name: format!("crate{id}"),
+      updated_at: DateTime::UNIX_EPOCH + TimeDelta::days(updated_days),
+      ..Crate::default()
+    };
+    FullCrate { krate, ..FullCrate::default() }
+  }
+
+  fn state(full_crates: impl IntoIterator<Item=FullCrate>) -> CratesState {
+    let mut state = CratesState::default();
+    state.merge(full_crates);
+    state
+  }
+
+  fn crates(state: CratesState) -> Crates {
+    let http_client = AttHttpClient::from_base_url("http://localhost/api/").unwrap();
+    let query_sender = QuerySender::new(CratesQuery::default(), CratesQueryConfig::default(), Duration::from_millis(300), true);
+    Crates::new(http_client, query_sender, state)
+  }
+
+  #[test]
+  fn merge_deduplicates_keeping_richer_record() {
+    let mut state = state([full_crate(1, 10), full_crate(2, 10)]);
+    state.merge([full_crate(1, 5), full_crate(2, 20), full_crate(3, 0)]);
+
+    assert_eq!(state.crate_ids(), [CrateId(1), CrateId(2), CrateId(3)]);
+    assert_eq!(state.get_by_id(CrateId(1)), Some(&full_crate(1, 10)));
+    assert_eq!(state.get_by_id(CrateId(2)), Some(&full_crate(2, 20)));
+  }
+
+  #[test]
+  fn merge_present_only_merges_crates_in_both() {
+    let mut found = state([full_crate(2, 0), full_crate(1, 0)]);
+    let followed = state([full_crate(1, 10), full_crate(3, 10)]);
+    found.merge_present(&followed);
+
+    assert_eq!(found.crate_ids(), [CrateId(2), CrateId(1)]);
+    assert_eq!(found.get_by_id(CrateId(1)), Some(&full_crate(1, 10)));
+    assert!(!found.contains(CrateId(3)));
+  }
+
+  #[test]
+  fn successful_unfollow_many_empties_state() {
+    let mut crates = crates(state([full_crate(1, 0), full_crate(2, 0), full_crate(3, 0)]));
+    let crate_ids = crates.state().crate_ids().to_vec();
+    drop(crates.send_unfollow_many(crate_ids.clone()));
+    assert!(crate_ids.iter().all(|id| crates.is_crate_being_modified(*id)));
+
+    crates.process_unfollow_many(UnfollowMany { crate_ids: crate_ids.clone(), result: Ok(()) }).unwrap();
+    assert!(crates.state().crate_ids().is_empty());
+    assert!(crate_ids.iter().all(|id| !crates.is_crate_being_modified(*id)));
+  }
+
+  #[test]
+  fn retry_sends_failed_request_again() {
+    let mut crates = crates(CratesState::default());
+    let response = Follow { full_crate: full_crate(4, 3), result: Err(CrateError::Unavailable.into()) };
+    assert!(crates.process(response.into()).is_none());
+    assert!(matches!(
+      crates.last_failure().map(|f| &f.request),
+      Some(CratesRequest::Follow(retried)) if *retried == full_crate(4, 3)
+    ));
+
+    drop(crates.send_retry().expect("failed follow request should be sent again"));
+    assert!(crates.last_failure().is_none());
+    assert!(crates.is_crate_being_modified(CrateId(4)));
+    assert!(crates.send_retry().is_none());
+  }
+
+  #[test]
+  fn retry_sends_failed_unfollow_many_with_same_crates() {
+    let mut crates = crates(state([full_crate(1, 0), full_crate(2, 0), full_crate(3, 0)]));
+    let crate_ids = vec![CrateId(1), CrateId(3)];
+    let response = UnfollowMany { crate_ids: crate_ids.clone(), result: Err(CrateError::Unavailable.into()) };
+    assert!(crates.process(response.into()).is_none());
+    assert!(crate_ids.iter().all(|id| !crates.is_crate_being_modified(*id)));
+
+    drop(crates.send_retry().expect("failed unfollow many request should be sent again"));
+    assert!(crate_ids.iter().all(|id| crates.is_crate_being_modified(*id)));
+    assert!(!crates.is_crate_being_modified(CrateId(2)));
+  }
+
+  #[test]
+  fn markdown_table_leaves_out_hidden_columns() {
+    let state = state([full_crate(1, 0)]);
+    let mut column_selection = ColumnSelection::default();
+    assert!(state.as_markdown_table(&column_selection).starts_with("| Name | Downloads | Latest Version | Updated At |\n"));
+
+    column_selection.set_visible(&FullCrate::COLUMNS[4], false);
+    column_selection.set_visible(&FullCrate::COLUMNS[2], false);
+    let markdown = state.as_markdown_table(&column_selection);
+    assert!(markdown.starts_with("| Name | Latest Version |\n"), "{markdown}");
+    assert!(markdown.contains("| crate1 |"));
+  }
+}
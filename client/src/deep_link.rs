@@ -0,0 +1,107 @@
+use url::Url;
+
+/// URL scheme of deep links, for example `att://crate/serde`.
+pub const SCHEME: &str = "att";
+
+/// Link directly to a view of the application.
+///
+/// Deep links are either `att://` URLs, such as `att://crate/serde` and `att://search?q=async`, or web client URLs with
+/// a `crate` or `search` query parameter, such as `https://example.com/?crate=serde`.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum DeepLink {
+  /// Show the crate with this name.
+  Crate(String),
+  /// Search for crates with this search term.
+  Search(String),
+}
+
+impl DeepLink {
+  /// Parses `url` into a deep link, or returns `None` if `url` is not a deep link.
+  pub fn parse(url: &str) -> Option<Self> {
+    let url = Url::parse(url).ok()?;
+    let deep_link = if url.scheme() == SCHEME {
+      // In `att://crate/serde`, `crate` is the host and `/serde` is the path.
+      match url.host_str()? {
+        "crate" => Self::Crate(url.path().trim_matches('/').to_string()),
+        "search" => Self::Search(query_value(&url, "q")?),
+        _ => return None,
+      }
+    } else if let Some(name) = query_value(&url, "crate") {
+      Self::Crate(name)
+    } else {
+      Self::Search(query_value(&url, "search")?)
+    };
+    (!deep_link.search_term().is_empty()).then_some(deep_link)
+  }
+
+  /// Gets the deep link the application was started with, if any.
+  ///
+  /// On desktop, this is the first command-line argument. The operating system passes the `att://` URL as that argument
+  /// when the client is registered as the handler of the [scheme](SCHEME). On the web, this is the URL of the current
+  /// page.
+  pub fn from_startup() -> Option<Self> {
+    #[cfg(not(target_arch = "wasm32"))] {
+      std::env::args().nth(1).and_then(|url| Self::parse(&url))
+    }
+    #[cfg(target_arch = "wasm32")] {
+      web_sys::window()
+        .and_then(|window| window.location().href().ok())
+        .and_then(|url| Self::parse(&url))
+    }
+  }
+
+  /// Gets the term to search for to navigate to this deep link.
+  #[inline]
+  pub fn search_term(&self) -> &str {
+    match self {
+      Self::Crate(name) => name,
+      Self::Search(search_term) => search_term,
+    }
+  }
+}
+
+fn query_value(url: &Url, key: &str) -> Option<String> {
+  url.query_pairs().find(|(k, _)| k == key).map(|(_, value)| value.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_crate() {
+    assert_eq!(DeepLink::parse("att://crate/serde"), Some(DeepLink::Crate("serde".to_string())));
+    assert_eq!(DeepLink::parse("att://crate/serde/"), Some(DeepLink::Crate("serde".to_string())));
+    assert_eq!(DeepLink::parse("https://example.com/?crate=serde_json"), Some(DeepLink::Crate("serde_json".to_string())));
+  }
+
+  #[test]
+  fn parse_search() {
+    assert_eq!(DeepLink::parse("att://search?q=async"), Some(DeepLink::Search("async".to_string())));
+    assert_eq!(DeepLink::parse("att://search?q=async%20runtime"), Some(DeepLink::Search("async runtime".to_string())));
+    assert_eq!(DeepLink::parse("https://example.com/?search=async+runtime"), Some(DeepLink::Search("async runtime".to_string())));
+  }
+
+  #[test]
+  fn parse_crate_takes_precedence_over_search() {
+    assert_eq!(DeepLink::parse("https://example.com/?search=async&crate=tokio"), Some(DeepLink::Crate("tokio".to_string())));
+  }
+
+  #[test]
+  fn parse_non_deep_links() {
+    assert_eq!(DeepLink::parse("not a url"), None);
+    assert_eq!(DeepLink::parse("att://unknown/serde"), None);
+    assert_eq!(DeepLink::parse("att://crate"), None);
+    assert_eq!(DeepLink::parse("att://crate/"), None);
+    assert_eq!(DeepLink::parse("att://search"), None);
+    assert_eq!(DeepLink::parse("att://search?q="), None);
+    assert_eq!(DeepLink::parse("https://example.com/"), None);
+    assert_eq!(DeepLink::parse("https://example.com/?q=serde"), None);
+  }
+
+  #[test]
+  fn search_term() {
+    assert_eq!(DeepLink::Crate("serde".to_string()).search_term(), "serde");
+    assert_eq!(DeepLink::Search("async".to_string()).search_term(), "async");
+  }
+}
@@ -0,0 +1,23 @@
+use crate::http_client::AttHttpClientError;
+
+/// Operation that failed with `error`, along with the `request` that started it, so that the exact same request can be
+/// sent again to retry the operation.
+#[derive(Debug)]
+pub struct FailedOperation<R> {
+  pub request: R,
+  pub error: AttHttpClientError,
+}
+
+impl<R> FailedOperation<R> {
+  #[inline]
+  pub fn new(request: R, error: AttHttpClientError) -> Self {
+    Self { request, error }
+  }
+
+  /// Returns whether retrying the request could succeed, which is the case when the error is
+  /// [transient](AttHttpClientError::is_transient).
+  #[inline]
+  pub fn is_retryable(&self) -> bool {
+    self.error.is_transient()
+  }
+}
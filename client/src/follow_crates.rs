@@ -1,5 +1,6 @@
 use att_core::action::{Action, ActionDef};
-use att_core::crates::FullCrate;
+use att_core::crates::{CrateId, FullCrate};
+use att_core::icon::Icon;
 use att_core::service::{DataActions, ServiceActions};
 
 use crate::crates::{Crates, CratesRequest};
@@ -12,14 +13,17 @@ impl ServiceActions<Crates> for FollowCrates {
   fn action_definitions(&self, _crates: &Crates) -> &[ActionDef] {
     const ACTION_DEFS: &'static [ActionDef] = &[
       ActionDef::from_text("Refresh Followed"),
+      ActionDef::from_text("Unfollow All").with_danger_style(),
     ];
     ACTION_DEFS
   }
 
   fn actions(&self, crates: &Crates) -> impl IntoIterator<Item=impl Action<Request=CratesRequest>> {
     let disabled = crates.are_all_crates_being_modified();
+    let crate_ids = crates.state().crate_ids();
     [
       ServiceAction { kind: ServiceActionKind::RefreshFollowed, disabled },
+      ServiceAction { kind: ServiceActionKind::UnfollowAll(crate_ids.to_vec()), disabled: disabled || crate_ids.is_empty() },
     ]
   }
 }
@@ -31,6 +35,7 @@ struct ServiceAction {
 
 enum ServiceActionKind {
   RefreshFollowed,
+  UnfollowAll(Vec<CrateId>),
 }
 
 impl Action for ServiceAction {
@@ -41,8 +46,9 @@ impl Action for ServiceAction {
 
   #[inline]
   fn request(&self) -> CratesRequest {
-    match self.kind {
+    match &self.kind {
       ServiceActionKind::RefreshFollowed => CratesRequest::RefreshFollowed,
+      ServiceActionKind::UnfollowAll(crate_ids) => CratesRequest::UnfollowMany(crate_ids.clone()),
     }
   }
 }
@@ -51,10 +57,10 @@ impl Action for ServiceAction {
 
 impl DataActions<Crates> for FollowCrates {
   fn data_action_definitions(&self, _crates: &Crates) -> &[ActionDef] {
-    const ICON_FONT: &'static str = "bootstrap-icons";
     const ACTION_DEFS: &'static [ActionDef] = &[
-      ActionDef::from_table_row_icon("\u{F116}", ICON_FONT),
-      ActionDef::from_table_row_icon("\u{F5DE}", ICON_FONT).with_danger_style(),
+      ActionDef::from_table_row_icon(Icon::Refresh).with_tooltip("Refresh"),
+      ActionDef::from_table_row_icon(Icon::OpenExternal).with_secondary_style().with_tooltip("Open releases"),
+      ActionDef::from_table_row_icon(Icon::Delete).with_danger_style().with_confirm("Unfollow this crate?").with_tooltip("Unfollow"),
     ];
     ACTION_DEFS
   }
@@ -64,21 +70,31 @@ impl DataActions<Crates> for FollowCrates {
     let disabled = crates.is_crate_being_modified(crate_id);
     let action = match index {
       0 => DataAction { kind: DataActionKind::Refresh, disabled, crate_id },
-      1 => DataAction { kind: DataActionKind::Unfollow, disabled, crate_id },
+      1 => match full_crate.krate.releases_url() {
+        Some(url) => DataAction { kind: DataActionKind::OpenReleases(url), disabled: false, crate_id },
+        None => DataAction { kind: DataActionKind::OpenReleases(String::new()), disabled: true, crate_id },
+      },
+      2 => DataAction { kind: DataActionKind::Unfollow, disabled, crate_id },
       _ => return None,
     };
     Some(action)
   }
+
+  #[inline]
+  fn data_reorder_request(&self, _crates: &Crates) -> Option<fn(usize, usize) -> CratesRequest> {
+    Some(CratesRequest::Reorder)
+  }
 }
 
 struct DataAction {
   kind: DataActionKind,
   disabled: bool,
-  crate_id: i32,
+  crate_id: CrateId,
 }
 
 enum DataActionKind {
   Refresh,
+  OpenReleases(String),
   Unfollow,
 }
 
@@ -90,9 +106,55 @@ impl Action for DataAction {
 
   #[inline]
   fn request(&self) -> CratesRequest {
-    match self.kind {
+    match &self.kind {
       DataActionKind::Refresh => CratesRequest::Refresh(self.crate_id),
+      DataActionKind::OpenReleases(url) => CratesRequest::OpenUrl(url.clone()),
       DataActionKind::Unfollow => CratesRequest::Unfollow(self.crate_id),
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use att_core::crates::{Crate, CratesQuery, CratesQueryConfig};
+
+  use crate::crates::CratesState;
+  use crate::http_client::AttHttpClient;
+  use crate::query_sender::QuerySender;
+
+  use super::*;
+
+  fn crates(crate_ids: impl IntoIterator<Item=i32>) -> Crates {
+    let mut state = CratesState::default();
+    state.merge(crate_ids.into_iter().map(|id| {
+      let krate = Crate { id: CrateId(id), name: format!("crate{id}"), ..Crate::default() };
+      FullCrate { krate, ..FullCrate::default() }
+    }));
+    let http_client = AttHttpClient::from_base_url("http://localhost/api/").unwrap();
+    let query_sender = QuerySender::new(CratesQuery::default(), CratesQueryConfig::default(), Duration::from_millis(300), true);
+    Crates::new(http_client, query_sender, state)
+  }
+
+  fn unfollow_all_request(crates: &Crates) -> (bool, CratesRequest) {
+    let action = FollowCrates.actions(crates).into_iter().nth(1).unwrap();
+    (action.is_disabled(), action.request())
+  }
+
+  #[test]
+  fn unfollow_all_unfollows_all_followed_crates() {
+    let crates = crates([3, 1, 2]);
+    let (disabled, request) = unfollow_all_request(&crates);
+    assert!(!disabled);
+    assert!(matches!(request, CratesRequest::UnfollowMany(ref ids) if *ids == crates.state().crate_ids()));
+    assert!(matches!(request, CratesRequest::UnfollowMany(ref ids) if ids.len() == 3));
+  }
+
+  #[test]
+  fn unfollow_all_is_disabled_without_followed_crates() {
+    let (disabled, request) = unfollow_all_request(&crates([]));
+    assert!(disabled);
+    assert!(matches!(request, CratesRequest::UnfollowMany(ref ids) if ids.is_empty()));
+  }
+}
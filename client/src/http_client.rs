@@ -1,23 +1,29 @@
 use std::future::Future;
+use std::hash::{BuildHasher, RandomState};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
 
 use reqwest::{Method, RequestBuilder};
 use serde::de::DeserializeOwned;
 use thiserror::Error;
-use tracing::{debug, instrument};
+use tracing::{debug, info_span, instrument, Instrument};
 use url::Url;
 
-use att_core::crates::{CrateError, CratesQuery, FullCrate};
+use att_core::crates::{CrateError, CrateId, CratesPage, CratesQuery, FullCrate, NewSavedSearch, SavedSearch};
 use att_core::users::{AuthError, UserCredentials};
+use att_core::util::request_id::REQUEST_ID_HEADER;
+use att_core::util::RetryPolicy;
 
 #[derive(Clone, Debug)]
 pub struct AttHttpClient {
   http_client: reqwest::Client,
   base_url: Url,
+  retry_policy: RetryPolicy,
 }
 
 impl AttHttpClient {
   pub fn new(http_client: reqwest::Client, base_url: Url) -> Self {
-    Self { http_client, base_url }
+    Self { http_client, base_url, retry_policy: RetryPolicy::default() }
   }
   pub fn from_base_url(base_url: impl reqwest::IntoUrl) -> Result<Self, reqwest::Error> {
     #[cfg(not(target_arch = "wasm32"))] let http_client = {
@@ -32,17 +38,43 @@ impl AttHttpClient {
     let base_url = base_url.into_url()?;
     Ok(Self::new(http_client, base_url))
   }
+
+  /// Retry idempotent requests according to `retry_policy` when they fail with a transient error.
+  #[inline]
+  pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+    self.retry_policy = retry_policy;
+    self
+  }
 }
 
 #[derive(Debug, Error)]
 pub enum AttHttpClientError {
   #[error("HTTP request failed")]
   Request(#[from] reqwest::Error),
-  #[error("Users request failed")]
+  #[error("Users request failed: {0}")]
   Login(#[from] AuthError),
-  #[error("Crate request failed")]
+  #[error("Crate request failed: {0}")]
   Crate(#[from] CrateError),
 }
+impl AttHttpClientError {
+  /// Returns whether this error is transient, such as a connection failure or timeout, and the request may succeed when
+  /// retried.
+  pub fn is_transient(&self) -> bool {
+    match self {
+      Self::Request(e) => e.is_request() || e.is_timeout(),
+      Self::Crate(CrateError::Unavailable) => true,
+      _ => false,
+    }
+  }
+
+  /// Returns the number of seconds after which to retry if this error is due to the server being under maintenance.
+  pub fn maintenance_retry_after(&self) -> Option<u32> {
+    match self {
+      Self::Crate(CrateError::UnderMaintenance(retry_after)) => Some(*retry_after),
+      _ => None,
+    }
+  }
+}
 
 impl AttHttpClient {
   #[instrument(skip_all, fields(user_credentials.name = user_credentials.name), err)]
@@ -58,25 +90,41 @@ impl AttHttpClient {
   }
 
   #[instrument(skip(self), err)]
-  pub fn search_crates(&self, crate_search: CratesQuery) -> impl Future<Output=Result<Vec<FullCrate>, AttHttpClientError>> {
+  pub fn search_crates(&self, crate_search: CratesQuery) -> impl Future<Output=Result<CratesPage, AttHttpClientError>> {
     let rb = self.request_builder(Method::GET, "crates")
       .query(&crate_search);
-    async move { Self::send::<_, CrateError>(rb).await }
+    let retry_policy = self.retry_policy;
+    async move { Self::send_with_retry::<_, CrateError>(retry_policy, rb).await }
   }
 
   #[instrument(skip(self), err)]
-  pub fn follow_crate(&self, crate_id: i32) -> impl Future<Output=Result<(), AttHttpClientError>> {
+  pub fn follow_crate(&self, crate_id: CrateId) -> impl Future<Output=Result<(), AttHttpClientError>> {
     let rb = self.request_builder(Method::POST, format!("crates/{crate_id}/follow"));
     async move { Self::send::<_, CrateError>(rb).await }
   }
   #[instrument(skip(self), err)]
-  pub fn unfollow_crate(&self, crate_id: i32) -> impl Future<Output=Result<(), AttHttpClientError>> {
+  pub fn unfollow_crate(&self, crate_id: CrateId) -> impl Future<Output=Result<(), AttHttpClientError>> {
     let rb = self.request_builder(Method::DELETE, format!("crates/{crate_id}/follow"));
     async move { Self::send::<_, CrateError>(rb).await }
   }
 
   #[instrument(skip(self), err)]
-  pub fn refresh_crate(&self, crate_id: i32) -> impl Future<Output=Result<FullCrate, AttHttpClientError>> {
+  pub fn unfollow_crates(&self, crate_ids: Vec<CrateId>) -> impl Future<Output=Result<(), AttHttpClientError>> {
+    let rb = self.request_builder(Method::POST, "crates/unfollow")
+      .json(&crate_ids);
+    async move { Self::send::<_, CrateError>(rb).await }
+  }
+
+  /// Sets the interval in seconds at which followed crate `crate_id` is refreshed, or `None` to use the server's default.
+  #[instrument(skip(self), err)]
+  pub fn set_crate_refresh_interval(&self, crate_id: CrateId, refresh_interval_secs: Option<u32>) -> impl Future<Output=Result<(), AttHttpClientError>> {
+    let rb = self.request_builder(Method::PUT, format!("crates/{crate_id}/refresh_interval"))
+      .json(&refresh_interval_secs);
+    async move { Self::send::<_, CrateError>(rb).await }
+  }
+
+  #[instrument(skip(self), err)]
+  pub fn refresh_crate(&self, crate_id: CrateId) -> impl Future<Output=Result<FullCrate, AttHttpClientError>> {
     let rb = self.request_builder(Method::POST, format!("crates/{crate_id}/refresh"));
     async move { Self::send::<_, CrateError>(rb).await }
   }
@@ -86,6 +134,30 @@ impl AttHttpClient {
     async move { Self::send::<_, CrateError>(rb).await }
   }
 
+  #[instrument(skip(self), err)]
+  pub fn get_saved_searches(&self) -> impl Future<Output=Result<Vec<SavedSearch>, AttHttpClientError>> {
+    let rb = self.request_builder(Method::GET, "crates/saved_searches");
+    let retry_policy = self.retry_policy;
+    async move { Self::send_with_retry::<_, CrateError>(retry_policy, rb).await }
+  }
+  #[instrument(skip(self), err)]
+  pub fn save_search(&self, new_saved_search: NewSavedSearch) -> impl Future<Output=Result<SavedSearch, AttHttpClientError>> {
+    let rb = self.request_builder(Method::POST, "crates/saved_searches")
+      .json(&new_saved_search);
+    async move { Self::send::<_, CrateError>(rb).await }
+  }
+  #[instrument(skip(self), err)]
+  pub fn update_saved_search(&self, saved_search_id: i32, new_saved_search: NewSavedSearch) -> impl Future<Output=Result<SavedSearch, AttHttpClientError>> {
+    let rb = self.request_builder(Method::PUT, format!("crates/saved_searches/{saved_search_id}"))
+      .json(&new_saved_search);
+    async move { Self::send::<_, CrateError>(rb).await }
+  }
+  #[instrument(skip(self), err)]
+  pub fn delete_saved_search(&self, saved_search_id: i32) -> impl Future<Output=Result<(), AttHttpClientError>> {
+    let rb = self.request_builder(Method::DELETE, format!("crates/saved_searches/{saved_search_id}"));
+    async move { Self::send::<_, CrateError>(rb).await }
+  }
+
   fn request_builder(&self, method: Method, join_url: impl AsRef<str>) -> RequestBuilder {
     let url = self.base_url.join(join_url.as_ref()).expect("BUG: creating URL failed");
     let request_builder = self.http_client.request(method, url);
@@ -114,9 +186,48 @@ impl AttHttpClient {
   ) -> Result<T, AttHttpClientError> where
     AttHttpClientError: From<E>
   {
-    debug!(request = ?request_builder, "sending HTTP request");
-    let response = request_builder.send().await?;
-    let body: Result<T, E> = response.json().await?;
-    Ok(body?)
+    let request_id = next_request_id();
+    let request_builder = request_builder.header(REQUEST_ID_HEADER, &request_id);
+    async move {
+      debug!(request = ?request_builder, "sending HTTP request");
+      let response = request_builder.send().await?;
+      let body: Result<T, E> = response.json().await?;
+      Ok(body?)
+    }.instrument(info_span!("http_request", request_id)).await
+  }
+  async fn send_with_retry<T: DeserializeOwned, E: DeserializeOwned>(
+    retry_policy: RetryPolicy,
+    request_builder: RequestBuilder,
+  ) -> Result<T, AttHttpClientError> where
+    AttHttpClientError: From<E>
+  {
+    retry_policy.retry(
+      || Self::send::<T, E>(request_builder.try_clone().expect("BUG: cloning request without streaming body failed")),
+      AttHttpClientError::is_transient,
+    ).await
+  }
+}
+
+/// Creates a new request ID, consisting of a random prefix that is unique to this process, and a counter.
+fn next_request_id() -> String {
+  static PREFIX: OnceLock<u32> = OnceLock::new();
+  static COUNTER: AtomicU64 = AtomicU64::new(0);
+  let prefix = PREFIX.get_or_init(|| RandomState::new().hash_one(0u8) as u32);
+  let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+  format!("{prefix:08x}-{count}")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn request_ids_share_prefix_but_are_unique() {
+    let first = next_request_id();
+    let second = next_request_id();
+    assert_ne!(first, second);
+    let prefix = |request_id: &str| request_id.split_once('-').unwrap().0.to_string();
+    assert_eq!(prefix(&first), prefix(&second));
+    assert_eq!(prefix(&first).len(), 8);
   }
 }
@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+
+use att_core::crates::{CrateId, FullCrate};
+
+use crate::{Data, DataRef};
+use crate::crates::CratesState;
+use crate::settings::Settings;
+
+/// Change to [`Data`], written to a journal so that only changes since the last save need to be persisted.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Change {
+  /// Insert or replace a followed crate.
+  UpsertCrate(FullCrate),
+  /// Remove the followed crate with the given id.
+  RemoveCrate(CrateId),
+  /// Set the display order of followed crates.
+  SetCrateOrder { order: Vec<CrateId>, custom_order: bool },
+  /// Replace the settings.
+  SetSettings(Settings),
+}
+
+impl Data {
+  /// Applies `change` to this data.
+  pub fn apply(&mut self, change: Change) {
+    match change {
+      Change::UpsertCrate(full_crate) => self.follow_crates.upsert(full_crate),
+      Change::RemoveCrate(crate_id) => self.follow_crates.remove(crate_id),
+      Change::SetCrateOrder { order, custom_order } => self.follow_crates.set_order(order, custom_order),
+      Change::SetSettings(settings) => self.settings = settings,
+    }
+  }
+}
+
+impl CratesState {
+  /// Pushes the changes that turn `previous` into this state into `changes`.
+  fn diff(&self, previous: &CratesState, changes: &mut Vec<Change>) {
+    for crate_id in previous.crate_ids() {
+      if !self.contains(*crate_id) {
+        changes.push(Change::RemoveCrate(*crate_id));
+      }
+    }
+    for full_crate in self.iter() {
+      if previous.get_by_id(full_crate.krate.id) != Some(full_crate) {
+        changes.push(Change::UpsertCrate(full_crate.clone()));
+      }
+    }
+    if self.crate_ids() != previous.crate_ids() || self.has_custom_order() != previous.has_custom_order() {
+      changes.push(Change::SetCrateOrder { order: self.crate_ids().to_vec(), custom_order: self.has_custom_order() });
+    }
+  }
+}
+
+/// Default number of journal entries after which the journal is compacted into a full snapshot.
+pub const DEFAULT_COMPACT_AFTER: usize = 256;
+
+/// Keeps track of the last persisted [`Data`] to determine which changes need to be written to a journal on save, and
+/// when the journal should be compacted into a full snapshot.
+#[derive(Debug)]
+pub struct Journal {
+  persisted: Data,
+  journal_len: usize,
+  compact_after: usize,
+}
+
+/// What to write when saving with a [`Journal`].
+#[derive(Debug)]
+pub enum JournalWrite {
+  /// Append these changes to the journal.
+  Append(Vec<Change>),
+  /// Write a full snapshot and clear the journal.
+  Compact,
+}
+
+impl Journal {
+  /// Creates a journal where `persisted` is the data reconstructed from the last snapshot and its journal.
+  /// `journal_len` is the number of entries in that journal.
+  pub fn new(persisted: Data, journal_len: usize) -> Self {
+    Self { persisted, journal_len, compact_after: DEFAULT_COMPACT_AFTER }
+  }
+
+  /// Compact the journal into a full snapshot once it has `compact_after` entries.
+  #[inline]
+  pub fn with_compact_after(mut self, compact_after: usize) -> Self {
+    self.compact_after = compact_after;
+    self
+  }
+
+  /// Records that `data` is being saved, returning what needs to be written.
+  pub fn record(&mut self, data: DataRef) -> JournalWrite {
+    let mut changes = Vec::new();
+    data.follow_crates.diff(&self.persisted.follow_crates, &mut changes);
+    if *data.settings != self.persisted.settings {
+      changes.push(Change::SetSettings(data.settings.clone()));
+    }
+
+    self.persisted = Data { follow_crates: data.follow_crates.clone(), settings: data.settings.clone() };
+    self.journal_len += changes.len();
+    if self.journal_len >= self.compact_after {
+      self.journal_len = 0;
+      JournalWrite::Compact
+    } else {
+      JournalWrite::Append(changes)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{DateTime, TimeDelta};
+
+  use att_core::crates::Crate;
+
+  use crate::DATA_VERSION;
+
+  use super::*;
+
+  fn full_crate(id: i32, updated_days: i64) -> FullCrate {
+    let krate = Crate {
+      id: CrateId(id),
+      name: format!("crate{id}"),
+      updated_at: DateTime::UNIX_EPOCH + TimeDelta::days(updated_days),
+      ..Crate::default()
+    };
+    FullCrate { krate, ..FullCrate::default() }
+  }
+
+  fn state(full_crates: impl IntoIterator<Item=FullCrate>) -> CratesState {
+    let mut state = CratesState::default();
+    state.merge(full_crates);
+    state
+  }
+
+  /// Applies the changes that turn `previous` into `current` to `previous`, returning the result.
+  fn apply_diff(previous: &CratesState, current: &CratesState) -> CratesState {
+    let mut changes = Vec::new();
+    current.diff(previous, &mut changes);
+    let mut data = Data { follow_crates: previous.clone(), ..Data::default() };
+    for change in changes {
+      data.apply(change);
+    }
+    data.follow_crates
+  }
+
+  #[test]
+  fn applying_diff_reconstructs_inserted_crates() {
+    let previous = state([full_crate(1, 0)]);
+    let current = state([full_crate(1, 0), full_crate(2, 0), full_crate(3, 0)]);
+    assert_eq!(apply_diff(&previous, &current), current);
+  }
+
+  #[test]
+  fn applying_diff_reconstructs_removed_and_updated_crates() {
+    let previous = state([full_crate(1, 0), full_crate(2, 0), full_crate(3, 0)]);
+    let current = state([full_crate(1, 10), full_crate(3, 0)]);
+    assert_eq!(apply_diff(&previous, &current), current);
+  }
+
+  #[test]
+  fn applying_diff_reconstructs_reordered_crates() {
+    let previous = state([full_crate(1, 0), full_crate(2, 0), full_crate(3, 0)]);
+    let mut current = previous.clone();
+    current.reorder(2, 0);
+    assert_eq!(current.crate_ids(), [CrateId(3), CrateId(1), CrateId(2)]);
+    assert_eq!(apply_diff(&previous, &current), current);
+  }
+
+  #[test]
+  fn applying_recorded_changes_reconstructs_data() {
+    let mut journal = Journal::new(Data::default(), 0);
+    let mut reconstructed = Data::default();
+    let mut follow_crates = state([full_crate(1, 0), full_crate(2, 0), full_crate(3, 0)]);
+    let settings = Settings::default();
+    let save = |journal: &mut Journal, follow_crates: &CratesState, reconstructed: &mut Data| {
+      let data = DataRef { version: DATA_VERSION, follow_crates, settings: &settings };
+      let JournalWrite::Append(changes) = journal.record(data) else { panic!("journal should not be compacted yet"); };
+      for change in changes {
+        reconstructed.apply(change);
+      }
+      assert_eq!(&reconstructed.follow_crates, follow_crates);
+    };
+
+    save(&mut journal, &follow_crates, &mut reconstructed);
+    follow_crates.reorder(0, 2);
+    save(&mut journal, &follow_crates, &mut reconstructed);
+    follow_crates.remove(CrateId(2));
+    follow_crates.upsert(full_crate(4, 0));
+    save(&mut journal, &follow_crates, &mut reconstructed);
+  }
+}
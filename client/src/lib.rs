@@ -1,20 +1,121 @@
 use serde::{Deserialize, Serialize};
 
 use crates::CratesState;
+use settings::Settings;
 
 pub mod http_client;
+pub mod journal;
 pub mod auth;
+pub mod batch_result;
 pub mod crates;
+pub mod deep_link;
+pub mod failed_operation;
 pub mod follow_crates;
 pub mod search_crates;
 pub mod query_sender;
+pub mod saved_searches;
+pub mod search_history;
+pub mod settings;
+pub mod whats_new;
 
-#[derive(Default, Debug, Deserialize)]
+/// Version of the layout of serialized [`Data`], which must be incremented when the layout changes, along with adding a
+/// migration step to [Data::migrate].
+pub const DATA_VERSION: u32 = 1;
+
+#[derive(Default, Clone, Debug, Deserialize)]
 pub struct Data {
   pub follow_crates: CratesState,
+  #[serde(default)]
+  pub settings: Settings,
+}
+
+impl Data {
+  /// Migrates `value`, serialized data of any version, to the current [version](DATA_VERSION), then deserializes it.
+  /// Data without a version is from before versioning was introduced, and is treated as version `0`.
+  pub fn migrate(mut value: serde_json::Value) -> Result<Self, serde_json::Error> {
+    let version = value.get("version").and_then(serde_json::Value::as_u64).unwrap_or(0);
+    if version > DATA_VERSION as u64 {
+      tracing::warn!(version, "deserializing data of newer version {version} than current version {DATA_VERSION}");
+    }
+    // When the layout changes, add a step here that transforms `value` from the previous version if `version` is older.
+    if version < 1 {
+      Self::migrate_v0_to_v1(&mut value);
+    }
+    serde_json::from_value(value)
+  }
+
+  /// Migrates version `0` data, which only has the followed crates, to version `1`, which adds the version and settings.
+  fn migrate_v0_to_v1(value: &mut serde_json::Value) {
+    if let Some(object) = value.as_object_mut() {
+      object.insert("version".to_string(), 1.into());
+      object.entry("settings").or_insert_with(|| serde_json::Value::Object(Default::default()));
+    }
+  }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Copy, Clone, Debug, Serialize)]
 pub struct DataRef<'a> {
+  /// Version of the layout of the serialized data, always [`DATA_VERSION`].
+  pub version: u32,
   pub follow_crates: &'a CratesState,
+  pub settings: &'a Settings,
+}
+
+impl<'a> DataRef<'a> {
+  #[inline]
+  pub fn new(follow_crates: &'a CratesState, settings: &'a Settings) -> Self {
+    Self { version: DATA_VERSION, follow_crates, settings }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use att_core::crates::{CRATES_IO_REGISTRY, CrateId};
+
+  use super::*;
+
+  #[test]
+  fn migrate_v0() {
+    // Version 0 has no version, no settings, no crate order, and no registry on crates.
+    let v0 = json!({
+      "follow_crates": {
+        "id_to_crate": {
+          "1": {
+            "krate": {
+              "id": 1,
+              "name": "serde",
+              "updated_at": "2024-01-01T00:00:00Z",
+              "created_at": "2015-01-01T00:00:00Z",
+              "description": "A serialization framework",
+              "homepage": null,
+              "readme": null,
+              "repository": "https://github.com/serde-rs/serde",
+              "downloads": 1000,
+              "default_version_id": 2
+            },
+            "default_version": { "id": 2, "crate_id": 1, "number": "1.0.0" }
+          }
+        }
+      }
+    });
+
+    let data = Data::migrate(v0).unwrap();
+    let full_crate = data.follow_crates.get_by_id(CrateId(1)).unwrap();
+    assert_eq!(full_crate.krate.name, "serde");
+    assert_eq!(full_crate.krate.registry, CRATES_IO_REGISTRY);
+    assert_eq!(full_crate.default_version.number, "1.0.0");
+    assert_eq!(data.settings, Settings::default());
+
+    let migrated = serde_json::to_value(DataRef::new(&data.follow_crates, &data.settings)).unwrap();
+    assert_eq!(migrated["version"], DATA_VERSION);
+    assert_eq!(Data::migrate(migrated).unwrap().follow_crates, data.follow_crates);
+  }
+
+  #[test]
+  fn migrate_wrong_layout_fails() {
+    assert!(Data::migrate(json!({ "follow_crates": [1, 2, 3] })).is_err());
+    assert!(Data::migrate(json!([])).is_err());
+  }
 }
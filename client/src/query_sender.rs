@@ -15,6 +15,9 @@ pub struct QuerySender<Q: Query> {
   wait_until: Option<Instant>,
   wait_duration: Duration,
   send_query_if_empty: bool,
+  /// Id of the latest dispatched request, incremented for every request so that responses to older requests can be
+  /// dropped.
+  request_id: u64,
 }
 impl<Q: Query + Clone> QuerySender<Q> {
   pub fn new(query: Q, query_config: Q::Config, wait_duration: Duration, send_query_if_empty: bool) -> Self {
@@ -26,6 +29,7 @@ impl<Q: Query + Clone> QuerySender<Q> {
       wait_until: None,
       wait_duration,
       send_query_if_empty,
+      request_id: 0,
     }
   }
 
@@ -37,11 +41,25 @@ impl<Q: Query + Clone> QuerySender<Q> {
   #[inline]
   pub fn query_config(&self) -> &Q::Config { &self.query_config }
 
+  /// Returns the duration to wait for the query to stop changing before sending it.
+  #[inline]
+  pub fn debounce(&self) -> Duration { self.wait_duration }
+
+  /// Returns the id of the latest dispatched request.
+  #[inline]
+  pub fn request_id(&self) -> u64 { self.request_id }
+
+  /// Returns whether `request_id` is the id of the latest dispatched request. Responses to older requests are stale, as
+  /// the query has changed since.
+  #[inline]
+  pub fn is_latest_request(&self, request_id: u64) -> bool { self.request_id == request_id }
+
 
   /// Reset the query and stops any ongoing queries.
   pub fn reset(&mut self) {
     self.query = self.default_query.clone();
     self.wait_until = None;
+    self.request_id += 1;
   }
 }
 
@@ -55,6 +73,34 @@ impl<Q: Query> QuerySender<Q> {
   /// when the wait is cleared.
   pub fn update_query(&mut self, message: QueryMessage) -> Option<impl Future<Output=WaitCleared>> {
     message.update_query(&mut self.query, &self.query_config);
+    self.wait_for_query()
+  }
+
+  /// Set the query to `query`, returning a future producing a [response](WaitCleared) that must be
+  /// [processed](Self::process_wait_cleared).
+  ///
+  /// If `send_query_if_empty` is `false` and the query is empty: returns `None` and any ongoing query will not be sent
+  /// when the wait is cleared.
+  pub fn set_query(&mut self, query: Q) -> Option<impl Future<Output=WaitCleared>> {
+    self.query = query;
+    self.wait_for_query()
+  }
+
+  /// Set the duration to wait for the query to stop changing before sending it to `debounce`. If a query is currently
+  /// waiting to be sent, the wait is restarted with the new duration, returning a future producing a
+  /// [response](WaitCleared) that must be [processed](Self::process_wait_cleared).
+  pub fn set_debounce(&mut self, debounce: Duration) -> Option<impl Future<Output=WaitCleared>> {
+    self.wait_duration = debounce;
+    if self.wait_until.is_some() {
+      self.wait_for_query()
+    } else {
+      None
+    }
+  }
+
+  fn wait_for_query(&mut self) -> Option<impl Future<Output=WaitCleared>> {
+    self.request_id += 1;
+    let request_id = self.request_id;
     if !self.send_query_if_empty && self.query.is_empty(&self.query_config) {
       self.wait_until = None;
       None
@@ -64,7 +110,7 @@ impl<Q: Query> QuerySender<Q> {
       let future = sleep(self.wait_duration);
       let future = async move {
         future.await;
-        WaitCleared
+        WaitCleared { request_id }
       };
       Some(future)
     }
@@ -75,12 +121,28 @@ impl<Q: Query> QuerySender<Q> {
 
 /// Wait cleared response.
 #[derive(Debug)]
-pub struct WaitCleared;
+pub struct WaitCleared {
+  request_id: u64,
+}
+impl WaitCleared {
+  /// Returns the id of the request this is a response to.
+  #[inline]
+  pub fn request_id(&self) -> u64 { self.request_id }
+}
 
 impl<Q: Query + Clone> QuerySender<Q> {
-  /// Process a wait cleared response, returning `Some(query)` if the query should be sent, `None` otherwise.
-  pub fn process_wait_cleared(&mut self, _: WaitCleared) -> Option<Q> {
-    self.wait_until.is_some_and(|i| Instant::now() > i).then(|| self.query.clone())
+  /// Process a wait cleared response, returning `Some(query)` if the query should be sent, `None` otherwise. Responses
+  /// to requests older than the [latest request](Self::request_id) are dropped.
+  pub fn process_wait_cleared(&mut self, response: WaitCleared) -> Option<Q> {
+    if !self.is_latest_request(response.request_id) {
+      return None;
+    }
+    if self.wait_until.is_some_and(|i| Instant::now() > i) {
+      self.wait_until = None;
+      Some(self.query.clone())
+    } else {
+      None
+    }
   }
 }
 
@@ -90,6 +152,7 @@ impl<Q: Query + Clone> QuerySender<Q> {
 #[derive(Clone, Debug)]
 pub enum QuerySenderRequest {
   UpdateQuery(QueryMessage),
+  SetDebounce(Duration),
 }
 
 impl<Q: Query + 'static> QuerySender<Q> {
@@ -99,6 +162,7 @@ impl<Q: Query + 'static> QuerySender<Q> {
     use QuerySenderRequest::*;
     match request {
       UpdateQuery(message) => self.update_query(message).opt_map_into().opt_boxed_maybe_send(),
+      SetDebounce(debounce) => self.set_debounce(debounce).opt_map_into().opt_boxed_maybe_send(),
     }
   }
 }
@@ -110,6 +174,15 @@ impl<Q: Query + 'static> QuerySender<Q> {
 pub enum QuerySenderResponse {
   WaitCleared(WaitCleared),
 }
+impl QuerySenderResponse {
+  /// Returns the id of the request this is a response to.
+  #[inline]
+  pub fn request_id(&self) -> u64 {
+    match self {
+      Self::WaitCleared(e) => e.request_id(),
+    }
+  }
+}
 impl From<WaitCleared> for QuerySenderResponse {
   #[inline]
   fn from(e: WaitCleared) -> Self { Self::WaitCleared(e) }
@@ -124,3 +197,40 @@ impl<Q: Query + Clone> QuerySender<Q> {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use att_core::crates::{CratesQuery, CratesQueryConfig};
+
+  use super::*;
+
+  const DEBOUNCE: Duration = Duration::from_millis(20);
+
+  fn query_sender() -> QuerySender<CratesQuery> {
+    QuerySender::new(CratesQuery::default(), CratesQueryConfig::default(), DEBOUNCE, true)
+  }
+
+  #[test]
+  fn set_debounce_while_idle_does_not_wait() {
+    let mut query_sender = query_sender();
+    assert!(query_sender.set_debounce(Duration::from_millis(100)).is_none());
+    assert_eq!(query_sender.debounce(), Duration::from_millis(100));
+  }
+
+  #[tokio::test]
+  async fn set_debounce_while_waiting_restarts_wait_with_new_duration() {
+    let mut query_sender = query_sender();
+    let old_wait = query_sender.update_query(QueryMessage::SetFollowed(true)).unwrap();
+    let start = Instant::now();
+    let new_debounce = DEBOUNCE * 5;
+    let new_wait = query_sender.set_debounce(new_debounce).unwrap();
+
+    // The old wait is cleared first, but was superseded by the new wait.
+    let old_wait_cleared = old_wait.await;
+    assert!(query_sender.process_wait_cleared(old_wait_cleared).is_none());
+
+    let new_wait_cleared = new_wait.await;
+    assert!(start.elapsed() >= new_debounce);
+    assert_eq!(query_sender.process_wait_cleared(new_wait_cleared), Some(CratesQuery::from_followed(true)));
+  }
+}
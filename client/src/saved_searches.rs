@@ -0,0 +1,168 @@
+use std::future::Future;
+
+use tracing::{debug, error};
+
+use att_core::crates::{CratesQuery, NewSavedSearch, SavedSearch};
+
+use crate::http_client::{AttHttpClient, AttHttpClientError};
+
+/// Keep track of the saved searches of the logged-in user, which are stored on the server.
+#[derive(Debug)]
+pub struct SavedSearches {
+  http_client: AttHttpClient,
+  saved_searches: Vec<SavedSearch>,
+  being_modified: bool,
+  last_error: Option<AttHttpClientError>,
+}
+
+impl SavedSearches {
+  #[inline]
+  pub fn new(http_client: AttHttpClient) -> Self {
+    Self { http_client, saved_searches: Vec::new(), being_modified: false, last_error: None }
+  }
+
+  /// Gets the saved searches, ordered by name.
+  #[inline]
+  pub fn saved_searches(&self) -> &[SavedSearch] { &self.saved_searches }
+
+  #[inline]
+  pub fn is_being_modified(&self) -> bool { self.being_modified }
+
+  /// Gets the error of the last response that failed to be processed, if any.
+  #[inline]
+  pub fn last_error(&self) -> Option<&AttHttpClientError> { self.last_error.as_ref() }
+
+  #[inline]
+  pub fn clear_last_error(&mut self) { self.last_error = None; }
+}
+
+// Send specific requests
+
+impl SavedSearches {
+  pub fn send_get_all(&mut self) -> impl Future<Output=GetAll> {
+    self.being_modified = true;
+    let future = self.http_client.get_saved_searches();
+    async move {
+      GetAll { result: future.await }
+    }
+  }
+
+  pub fn send_save(&mut self, name: String, query: CratesQuery) -> impl Future<Output=Save> {
+    self.being_modified = true;
+    let future = self.http_client.save_search(NewSavedSearch { name, query });
+    async move {
+      Save { result: future.await }
+    }
+  }
+
+  pub fn send_update(&mut self, saved_search_id: i32, name: String, query: CratesQuery) -> impl Future<Output=Save> {
+    self.being_modified = true;
+    let future = self.http_client.update_saved_search(saved_search_id, NewSavedSearch { name, query });
+    async move {
+      Save { result: future.await }
+    }
+  }
+
+  pub fn send_delete(&mut self, saved_search_id: i32) -> impl Future<Output=Delete> {
+    self.being_modified = true;
+    let future = self.http_client.delete_saved_search(saved_search_id);
+    async move {
+      Delete { saved_search_id, result: future.await }
+    }
+  }
+}
+
+// Process specific responses
+
+/// Get all saved searches response.
+#[derive(Debug)]
+pub struct GetAll {
+  result: Result<Vec<SavedSearch>, AttHttpClientError>,
+}
+
+/// Save or update saved search response.
+#[derive(Debug)]
+pub struct Save {
+  result: Result<SavedSearch, AttHttpClientError>,
+}
+
+/// Delete saved search response.
+#[derive(Debug)]
+pub struct Delete {
+  saved_search_id: i32,
+  result: Result<(), AttHttpClientError>,
+}
+
+impl SavedSearches {
+  pub fn process_get_all(&mut self, response: GetAll) -> Result<(), AttHttpClientError> {
+    self.being_modified = false;
+
+    let saved_searches = response.result
+      .inspect_err(|cause| error!(%cause, "failed to get saved searches: {cause:?}"))?;
+    debug!(count = saved_searches.len(), "set saved searches");
+    self.saved_searches = saved_searches;
+
+    Ok(())
+  }
+
+  pub fn process_save(&mut self, response: Save) -> Result<(), AttHttpClientError> {
+    self.being_modified = false;
+
+    let saved_search = response.result
+      .inspect_err(|cause| error!(%cause, "failed to save search: {cause:?}"))?;
+    debug!(?saved_search, "save search");
+    self.saved_searches.retain(|s| s.id != saved_search.id);
+    let index = self.saved_searches.partition_point(|s| s.name < saved_search.name);
+    self.saved_searches.insert(index, saved_search);
+
+    Ok(())
+  }
+
+  pub fn process_delete(&mut self, response: Delete) -> Result<(), AttHttpClientError> {
+    self.being_modified = false;
+
+    let saved_search_id = response.saved_search_id;
+    response.result
+      .inspect_err(|cause| error!(saved_search_id, %cause, "failed to delete saved search: {cause:?}"))?;
+    debug!(saved_search_id, "delete saved search");
+    self.saved_searches.retain(|s| s.id != saved_search_id);
+
+    Ok(())
+  }
+}
+
+// Process enumerated responses
+
+/// Saved searches responses.
+#[derive(Debug)]
+pub enum SavedSearchesResponse {
+  GetAll(GetAll),
+  Save(Save),
+  Delete(Delete),
+}
+impl From<GetAll> for SavedSearchesResponse {
+  #[inline]
+  fn from(r: GetAll) -> Self { Self::GetAll(r) }
+}
+impl From<Save> for SavedSearchesResponse {
+  #[inline]
+  fn from(r: Save) -> Self { Self::Save(r) }
+}
+impl From<Delete> for SavedSearchesResponse {
+  #[inline]
+  fn from(r: Delete) -> Self { Self::Delete(r) }
+}
+
+impl SavedSearches {
+  pub fn process(&mut self, response: SavedSearchesResponse) {
+    use SavedSearchesResponse::*;
+    let result = match response {
+      GetAll(r) => self.process_get_all(r),
+      Save(r) => self.process_save(r),
+      Delete(r) => self.process_delete(r),
+    };
+    if let Err(cause) = result {
+      self.last_error = Some(cause);
+    }
+  }
+}
@@ -1,10 +1,27 @@
 use att_core::action::{Action, ActionDef};
-use att_core::crates::FullCrate;
+use att_core::crates::{CrateId, FullCrate};
 use att_core::service::{DataActions, ServiceActions};
 
-use crate::crates::{Crates, CratesRequest};
+use crate::crates::{Crates, CratesRequest, CratesState};
 
-pub struct SearchCrates;
+#[derive(Default, Debug)]
+pub struct SearchCrates {
+  followed_crates: CratesState,
+}
+
+impl SearchCrates {
+  /// Sets the crates that are already followed, for which the follow action is disabled.
+  pub fn set_followed_crates(&mut self, followed_crates: &CratesState) {
+    self.followed_crates.clone_from(followed_crates);
+  }
+
+  /// Gets the crates that are already followed.
+  #[inline]
+  pub fn followed_crates(&self) -> &CratesState { &self.followed_crates }
+
+  #[inline]
+  pub fn is_followed(&self, crate_id: CrateId) -> bool { self.followed_crates.contains(crate_id) }
+}
 
 // Service actions
 
@@ -44,7 +61,11 @@ impl DataActions<Crates> for SearchCrates {
 
   fn data_action<'d>(&self, _crates: &Crates, index: usize, full_crate: &'d FullCrate) -> Option<impl Action<Request=CratesRequest> + 'd> {
     let action = match index {
-      0 => DataAction { kind: DataActionKind::Follow, full_crate: full_crate.clone() },
+      0 => DataAction {
+        kind: DataActionKind::Follow,
+        full_crate: full_crate.clone(),
+        disabled: self.is_followed(full_crate.krate.id),
+      },
       _ => return None,
     };
     Some(action)
@@ -54,6 +75,7 @@ impl DataActions<Crates> for SearchCrates {
 struct DataAction {
   kind: DataActionKind,
   full_crate: FullCrate,
+  disabled: bool,
 }
 
 enum DataActionKind {
@@ -64,7 +86,7 @@ impl Action for DataAction {
   type Request = CratesRequest;
 
   #[inline]
-  fn is_disabled(&self) -> bool { false }
+  fn is_disabled(&self) -> bool { self.disabled }
 
   #[inline]
   fn request(&self) -> CratesRequest {
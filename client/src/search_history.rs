@@ -0,0 +1,90 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use att_core::crates::CratesQuery;
+
+/// Bounded history of recently sent search queries, most recent first.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SearchHistory {
+  queries: VecDeque<CratesQuery>,
+  capacity: usize,
+}
+
+impl SearchHistory {
+  /// Default maximum number of queries kept in the history.
+  pub const DEFAULT_CAPACITY: usize = 20;
+
+  #[inline]
+  pub fn with_capacity(capacity: usize) -> Self {
+    Self { queries: VecDeque::with_capacity(capacity), capacity }
+  }
+
+  #[inline]
+  pub fn len(&self) -> usize { self.queries.len() }
+
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.queries.is_empty() }
+
+  /// Iterates over the queries in the history, most recent first.
+  #[inline]
+  pub fn iter(&self) -> impl Iterator<Item=&CratesQuery> { self.queries.iter() }
+
+  /// Pushes `query` as the most recent query, unless it is identical to the current most recent query. Removes the
+  /// oldest queries if the history exceeds its capacity.
+  pub fn push(&mut self, query: CratesQuery) {
+    if self.queries.front() == Some(&query) {
+      return;
+    }
+    self.queries.push_front(query);
+    self.queries.truncate(self.capacity);
+  }
+
+  #[inline]
+  pub fn clear(&mut self) { self.queries.clear(); }
+}
+
+impl Default for SearchHistory {
+  #[inline]
+  fn default() -> Self { Self::with_capacity(Self::DEFAULT_CAPACITY) }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn query(name: &str) -> CratesQuery {
+    CratesQuery { name: Some(name.to_string()), ..CratesQuery::default() }
+  }
+
+  fn names(history: &SearchHistory) -> Vec<&str> {
+    history.iter().filter_map(|q| q.name.as_deref()).collect()
+  }
+
+  #[test]
+  fn consecutive_duplicates_are_deduplicated() {
+    let mut history = SearchHistory::default();
+    history.push(query("serde"));
+    history.push(query("serde"));
+    assert_eq!(names(&history), ["serde"]);
+  }
+
+  #[test]
+  fn non_consecutive_duplicates_are_kept() {
+    let mut history = SearchHistory::default();
+    history.push(query("serde"));
+    history.push(query("tokio"));
+    history.push(query("serde"));
+    assert_eq!(names(&history), ["serde", "tokio", "serde"]);
+  }
+
+  #[test]
+  fn exceeding_capacity_evicts_oldest() {
+    let mut history = SearchHistory::with_capacity(2);
+    history.push(query("serde"));
+    history.push(query("tokio"));
+    history.push(query("axum"));
+    assert_eq!(history.len(), 2);
+    assert_eq!(names(&history), ["axum", "tokio"]);
+  }
+}
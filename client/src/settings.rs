@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use att_core::table::ColumnSelection;
+use att_core::theme::ThemeMode;
+
+use crate::search_history::SearchHistory;
+
+/// User settings that are persisted along with the data.
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Settings {
+  /// Columns to display in crate tables.
+  #[serde(default)]
+  pub crate_columns: ColumnSelection,
+  /// Recently sent crate search queries.
+  #[serde(default)]
+  pub search_history: SearchHistory,
+  /// Whether to gzip compress the data file.
+  #[serde(default)]
+  pub compress_data: bool,
+  /// Light/dark theme mode.
+  #[serde(default)]
+  pub theme_mode: ThemeMode,
+  /// When the application was last launched.
+  #[serde(default)]
+  pub last_launch_at: Option<DateTime<Utc>>,
+}
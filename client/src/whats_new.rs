@@ -0,0 +1,110 @@
+use chrono::{DateTime, Utc};
+
+use att_core::crates::FullCrate;
+
+use crate::crates::CratesState;
+
+/// Digest of followed crates that were updated since the last launch.
+#[derive(Default, Clone, Debug)]
+pub struct WhatsNew {
+  since: Option<DateTime<Utc>>,
+  updated_crates: Vec<FullCrate>,
+}
+
+impl WhatsNew {
+  /// Creates a digest that will contain crates updated after `last_launch_at`, or an empty digest if this is the first
+  /// launch.
+  #[inline]
+  pub fn new(last_launch_at: Option<DateTime<Utc>>) -> Self {
+    Self { since: last_launch_at, updated_crates: Vec::new() }
+  }
+
+  /// Computes the digest from the crates in `state`, most recently updated first. Only computes the digest once, as it
+  /// should be computed from the first up-to-date state after launching; subsequent calls do nothing.
+  pub fn compute(&mut self, state: &CratesState) {
+    if let Some(since) = self.since.take() {
+      self.updated_crates = updated_since(state, since);
+    }
+  }
+
+  #[inline]
+  pub fn updated_crates(&self) -> &[FullCrate] { &self.updated_crates }
+
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.updated_crates.is_empty() }
+
+  /// Dismisses the digest, clearing it.
+  #[inline]
+  pub fn dismiss(&mut self) {
+    self.since = None;
+    self.updated_crates.clear();
+  }
+}
+
+/// Gets the crates in `state` that were updated after `since`, most recently updated first.
+pub fn updated_since(state: &CratesState, since: DateTime<Utc>) -> Vec<FullCrate> {
+  let mut updated_crates: Vec<FullCrate> = state.iter()
+    .filter(|full_crate| full_crate.krate.updated_at > since)
+    .cloned()
+    .collect();
+  updated_crates.sort_by(|a, b| b.krate.updated_at.cmp(&a.krate.updated_at));
+  updated_crates
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::TimeDelta;
+
+  use att_core::crates::{Crate, CrateId};
+
+  use super::*;
+
+  fn day(days: i64) -> DateTime<Utc> {
+    DateTime::UNIX_EPOCH + TimeDelta::days(days)
+  }
+
+  fn state(updated_days: impl IntoIterator<Item=(i32, i64)>) -> CratesState {
+    let mut state = CratesState::default();
+    state.merge(updated_days.into_iter().map(|(id, updated_days)| {
+      let krate = Crate { id: CrateId(id), name: format!("crate{id}"), updated_at: day(updated_days), ..Crate::default() };
+      FullCrate { krate, ..FullCrate::default() }
+    }));
+    state
+  }
+
+  fn updated_ids(whats_new: &WhatsNew) -> Vec<CrateId> {
+    whats_new.updated_crates().iter().map(|full_crate| full_crate.krate.id).collect()
+  }
+
+  #[test]
+  fn digest_contains_crates_updated_since_last_launch() {
+    // Last launched on day 10, and crates were updated before, on, and after that day before this launch.
+    let mut whats_new = WhatsNew::new(Some(day(10)));
+    whats_new.compute(&state([(1, 5), (2, 12), (3, 10), (4, 20)]));
+    assert_eq!(updated_ids(&whats_new), [CrateId(4), CrateId(2)]);
+  }
+
+  #[test]
+  fn digest_is_computed_once() {
+    let mut whats_new = WhatsNew::new(Some(day(10)));
+    whats_new.compute(&state([(1, 12)]));
+    // Crates updated while running are not part of the digest of this launch.
+    whats_new.compute(&state([(1, 12), (2, 15)]));
+    assert_eq!(updated_ids(&whats_new), [CrateId(1)]);
+  }
+
+  #[test]
+  fn first_launch_has_empty_digest() {
+    let mut whats_new = WhatsNew::new(None);
+    whats_new.compute(&state([(1, 12)]));
+    assert!(whats_new.is_empty());
+  }
+
+  #[test]
+  fn dismissed_digest_stays_empty() {
+    let mut whats_new = WhatsNew::new(Some(day(10)));
+    whats_new.dismiss();
+    whats_new.compute(&state([(1, 12)]));
+    assert!(whats_new.is_empty());
+  }
+}
@@ -1,20 +1,30 @@
+use std::collections::HashSet;
 use std::time::Duration;
 
 use iced::{Element, Task};
-use tracing::instrument;
+use tracing::{error, info, instrument};
 
+use att_client::batch_result::BatchResult;
 use att_client::crates::{Crates, CratesRequest, CratesResponse, CratesState};
+use att_client::deep_link::DeepLink;
 use att_client::follow_crates::FollowCrates;
 use att_client::http_client::AttHttpClient;
 use att_client::query_sender::QuerySender;
-use att_core::crates::{CratesQuery, CratesQueryConfig};
-use att_core::iced_impls::as_full_table;
+use att_client::search_history::SearchHistory;
+use att_client::whats_new::WhatsNew;
+use att_core::app::storage::{DirectoryKind, Storage};
+use att_core::crates::{CrateId, CratesQuery, CratesQueryConfig, FullCrate};
+use att_core::iced_impls::{as_full_selectable_table, view_column_chooser, view_selection_controls};
+use att_core::service::Catalog;
+use att_core::table::{AsTableRow, ColumnSelection, RowSelection, SelectionMessage};
 use iced_builder::{ElementExt, WidgetBuilder};
 
 use crate::app::search_crates;
 use crate::app::search_crates::SearchCratesComponent;
 use crate::perform::{OptionPerformExt, PerformExt};
 use crate::update::Update;
+use crate::widget::batch_report::batch_report;
+use crate::widget::confirm::confirm_modal;
 use crate::widget::modal::Modal;
 
 pub struct FollowCratesComponent {
@@ -22,19 +32,54 @@ pub struct FollowCratesComponent {
   follow_crates: FollowCrates,
   search_crates: SearchCratesComponent,
   search_crates_modal_open: bool,
+  unfollow_all_to_confirm: Option<Vec<CrateId>>,
+  request_to_confirm: Option<(&'static str, CratesRequest)>,
+  column_selection: ColumnSelection,
+  column_chooser_open: bool,
+  selection: RowSelection<CrateId>,
+  whats_new: WhatsNew,
+  batch_result: Option<BatchResult>,
+  import_error: Option<String>,
 }
 
 #[derive(Debug)]
 pub enum Message {
   ToSearchCrates(search_crates::Message),
   OpenSearchCratesModal,
+  OpenDeepLink(DeepLink),
   CloseSearchCratesModal,
+  DismissError,
+  RetryFailed,
+  DismissWhatsNew,
+  CopyBatchSummary,
+  DismissBatchResult,
+  CopyAsMarkdown,
+  ExportCsv,
+  CopyStateAsJson,
+  ImportStateFromClipboard,
+  ImportState(Option<String>),
+  DismissImportError,
+  ToggleColumnChooser,
+  SetColumnVisible(u8, bool),
+  Select(SelectionMessage<CrateId>),
+  UnfollowSelected,
+  ConfirmUnfollowAll,
+  CancelUnfollowAll,
   SendRequest(CratesRequest),
+  ConfirmRequest(&'static str, CratesRequest),
+  AcceptConfirmRequest,
+  CancelConfirmRequest,
   ProcessResponse(CratesResponse),
 }
 
 impl FollowCratesComponent {
-  pub fn new(http_client: AttHttpClient, state: CratesState) -> Self {
+  pub fn new(
+    http_client: AttHttpClient,
+    state: CratesState,
+    column_selection: ColumnSelection,
+    search_history: SearchHistory,
+    whats_new: WhatsNew,
+  ) -> Self {
     let query_sender = QuerySender::new(
       CratesQuery::from_followed(true),
       CratesQueryConfig {
@@ -47,11 +92,27 @@ impl FollowCratesComponent {
     Self {
       crates: Crates::new(http_client.clone(), query_sender, state),
       follow_crates: FollowCrates,
-      search_crates: SearchCratesComponent::new(http_client),
+      search_crates: SearchCratesComponent::new(http_client, search_history),
       search_crates_modal_open: false,
+      unfollow_all_to_confirm: None,
+      request_to_confirm: None,
+      column_selection,
+      column_chooser_open: false,
+      selection: RowSelection::default(),
+      whats_new,
+      batch_result: None,
+      import_error: None,
     }
   }
 
+  pub fn column_selection(&self) -> &ColumnSelection {
+    &self.column_selection
+  }
+
+  pub fn search_history(&self) -> Option<&SearchHistory> {
+    self.search_crates.search_history()
+  }
+
   pub fn state(&self) -> &CratesState {
     self.crates.state()
   }
@@ -75,31 +136,178 @@ impl FollowCratesComponent {
         }
         return search_command.into();
       }
-      OpenSearchCratesModal => {
-        self.search_crates_modal_open = true;
-        return self.search_crates.focus_search_term_input().into();
+      OpenSearchCratesModal => return self.open_search_crates_modal().into(),
+      OpenDeepLink(deep_link) => {
+        // Navigate to crate and search deep links by searching for them.
+        let open_task = self.open_search_crates_modal();
+        let search_task = self.search_crates.search(deep_link.search_term().to_string()).map(ToSearchCrates);
+        return Task::batch([open_task, search_task]).into();
       }
       CloseSearchCratesModal => {
         self.search_crates.reset();
         self.search_crates_modal_open = false;
       }
-      SendRequest(request) => return self.crates.send(request).opt_perform(ProcessResponse).into(),
-      ProcessResponse(response) => return self.crates.process(response).opt_perform(ProcessResponse).into(),
+      DismissError => self.crates.clear_last_error(),
+      RetryFailed => return self.crates.send_retry().opt_perform(ProcessResponse).into(),
+      DismissWhatsNew => self.whats_new.dismiss(),
+      CopyBatchSummary => if let Some(batch_result) = &self.batch_result {
+        return iced::clipboard::write(batch_result.summary()).into();
+      }
+      DismissBatchResult => self.batch_result = None,
+      CopyAsMarkdown => return iced::clipboard::write(self.crates.state().as_markdown_table(&self.column_selection)).into(),
+      ExportCsv => self.export_csv(),
+      CopyStateAsJson => return iced::clipboard::write(self.crates.export_state()).into(),
+      ImportStateFromClipboard => return iced::clipboard::read().map(ImportState).into(),
+      ImportState(Some(json)) => return self.import_state(&json).into(),
+      ImportState(None) => self.import_error = Some("Failed to import followed crates: the clipboard is empty".to_string()),
+      DismissImportError => self.import_error = None,
+      ToggleColumnChooser => self.column_chooser_open = !self.column_chooser_open,
+      SetColumnVisible(column_index, visible) => if let Some(column) = FullCrate::COLUMNS.get(column_index as usize) {
+        self.column_selection.set_visible(column, visible);
+      }
+      Select(message) => self.selection.update(message, self.crates.state().crate_ids().iter().copied()),
+      UnfollowSelected => {
+        let crate_ids = self.selection.selected_in(self.crates.state().crate_ids().iter().copied());
+        if !crate_ids.is_empty() {
+          self.unfollow_all_to_confirm = Some(crate_ids);
+        }
+      }
+      ConfirmUnfollowAll => if let Some(crate_ids) = self.unfollow_all_to_confirm.take() {
+        return self.crates.send_unfollow_many(crate_ids).perform_into(ProcessResponse).into();
+      }
+      CancelUnfollowAll => self.unfollow_all_to_confirm = None,
+      SendRequest(request) => match request {
+        // Intercept unfollowing many crates to ask for confirmation first.
+        CratesRequest::UnfollowMany(crate_ids) => self.unfollow_all_to_confirm = Some(crate_ids),
+        CratesRequest::OpenUrl(url) => open_url(&url),
+        _ => return self.crates.send(request).opt_perform(ProcessResponse).into(),
+      },
+      ConfirmRequest(message, request) => self.request_to_confirm = Some((message, request)),
+      AcceptConfirmRequest => if let Some((_, request)) = self.request_to_confirm.take() {
+        return self.update(SendRequest(request));
+      }
+      CancelConfirmRequest => self.request_to_confirm = None,
+      ProcessResponse(response) => {
+        // Compute what's new from the first full set of followed crates after launching.
+        let is_set_all = matches!(response, CratesResponse::SetAll(_));
+        // Report the outcome of unfollowing many crates, before they are removed from the state.
+        if let CratesResponse::UnfollowMany(unfollow_many) = &response {
+          self.batch_result = Some(unfollow_many.batch_result(self.crates.state()));
+        }
+        let task = self.crates.process(response).opt_perform(ProcessResponse);
+        // Deselect crates that are no longer followed.
+        self.selection.retain(self.crates.state().crate_ids().iter().copied());
+        if is_set_all && self.crates.last_error().is_none() {
+          self.whats_new.compute(self.crates.state());
+        }
+        return task.into();
+      }
     }
     Update::default()
   }
 
+  fn export_csv(&self) {
+    const FILE_NAME: &str = "followed_crates.csv";
+    let storage = Storage::new("client_iced");
+    match storage.write_file(DirectoryKind::Data, FILE_NAME, |writer| Ok(self.crates.export_csv(writer)?)) {
+      Ok(Some(file_path)) => info!("exported followed crates to '{}'", file_path.display()),
+      Ok(None) => error!("failed to export followed crates: no data directory"),
+      Err(cause) => error!(%cause, "failed to export followed crates: {cause:?}"),
+    }
+  }
+
+  /// Imports followed crates from `json`, following crates that were not followed yet on the server.
+  fn import_state(&mut self, json: &str) -> Task<Message> {
+    let previous_crate_ids: HashSet<CrateId> = self.crates.state().crate_ids().iter().copied().collect();
+    if let Err(cause) = self.crates.import_state(json) {
+      self.import_error = Some(format!("Failed to import followed crates: {cause}"));
+      return Task::none();
+    }
+    self.import_error = None;
+    let imported_crates: Vec<FullCrate> = self.crates.state().iter()
+      .filter(|full_crate| !previous_crate_ids.contains(&full_crate.krate.id))
+      .cloned()
+      .collect();
+    let follow_tasks = imported_crates.into_iter()
+      .map(|full_crate| self.crates.send_follow(full_crate).perform_into(Message::ProcessResponse));
+    Task::batch(follow_tasks)
+  }
+
+  fn open_search_crates_modal(&mut self) -> Task<Message> {
+    self.search_crates.set_followed_crates(self.crates.state());
+    self.search_crates_modal_open = true;
+    let saved_searches_command = self.search_crates.request_saved_searches().map(Message::ToSearchCrates);
+    Task::batch([self.search_crates.focus_search_term_input(), saved_searches_command])
+  }
+
   pub fn view(&self) -> Element<Message> {
     let custom_button = WidgetBuilder::once()
       .button("Add")
       .success_style()
       .on_press(|| Message::OpenSearchCratesModal)
       .add();
-    let table = as_full_table(&self.crates, &self.follow_crates, Some("Followed Crates"), [custom_button], Message::SendRequest);
+    let copy_button = WidgetBuilder::once()
+      .button("Copy as Markdown")
+      .secondary_style()
+      .disabled(self.crates.state().is_empty())
+      .on_press(|| Message::CopyAsMarkdown)
+      .add();
+    let export_button = WidgetBuilder::once()
+      .button("Export CSV")
+      .secondary_style()
+      .disabled(self.crates.state().is_empty())
+      .on_press(|| Message::ExportCsv)
+      .add();
+    let export_json_button = WidgetBuilder::once()
+      .button("Copy as JSON")
+      .secondary_style()
+      .disabled(self.crates.state().is_empty())
+      .on_press(|| Message::CopyStateAsJson)
+      .add();
+    let import_json_button = WidgetBuilder::once()
+      .button("Import JSON")
+      .secondary_style()
+      .on_press(|| Message::ImportStateFromClipboard)
+      .add();
+    let columns_button = WidgetBuilder::once()
+      .button("Columns")
+      .secondary_style()
+      .on_press(|| Message::ToggleColumnChooser)
+      .add();
+    let selection_controls = view_selection_controls(&self.selection, self.crates.state().crate_ids().iter().copied(), Message::Select);
+    let unfollow_selected_button = WidgetBuilder::once()
+      .button("Unfollow Selected")
+      .danger_style()
+      .disabled(self.selection.is_empty())
+      .on_press(|| Message::UnfollowSelected)
+      .add();
+    let custom_buttons = [custom_button, copy_button, export_button, export_json_button, import_json_button, columns_button, selection_controls, unfollow_selected_button];
+    let table = as_full_selectable_table(&self.crates, &self.follow_crates, Some("Followed Crates"), custom_buttons, &self.column_selection, &self.selection, Message::Select, Message::SendRequest, Message::ConfirmRequest);
+    let failure_notification = self.crates.last_failure().map(|failure| {
+      let on_retry = failure.is_retryable().then_some(|| Message::RetryFailed);
+      let message = match failure.error.maintenance_retry_after() {
+        Some(retry_after) => format!("The server is under maintenance and cannot make changes right now. Try again in {} minute(s).", retry_after.div_ceil(60)),
+        None => failure.error.to_string(),
+      };
+      error_notification(message, on_retry, || Message::DismissError)
+    });
+    let import_error_notification = self.import_error.as_ref()
+      .map(|import_error| error_notification(import_error.clone(), None::<fn() -> Message>, || Message::DismissImportError));
+    let batch_report_notification = self.batch_result.as_ref()
+      .map(|batch_result| batch_report(batch_result, || Message::CopyBatchSummary, || Message::DismissBatchResult));
+    let table = WidgetBuilder::heap()
+      .add_maybe(failure_notification)
+      .add_maybe(import_error_notification)
+      .add_maybe(batch_report_notification)
+      .add_if(!self.whats_new.is_empty(), |builder| builder.add_element(whats_new_notification(&self.whats_new, || Message::DismissWhatsNew)))
+      .add_if(self.column_chooser_open, |builder| builder.add_element(view_column_chooser::<FullCrate, _>(&self.column_selection, Message::SetColumnVisible)))
+      .add_element(table)
+      .column().spacing(10.0).fill().add()
+      .take();
 
     if self.search_crates_modal_open {
       let overlay = self.search_crates
-        .view()
+        .view(&self.column_selection)
         .map(Message::ToSearchCrates)
         .into_stack_builder()
         .container().padding(5).width(1200).height(900).add()
@@ -107,8 +315,50 @@ impl FollowCratesComponent {
       let modal = Modal::with_container(overlay, table)
         .on_close_modal(|| Message::CloseSearchCratesModal);
       modal.into()
+    } else if let Some(crate_ids) = &self.unfollow_all_to_confirm {
+      confirm_modal(format!("Unfollow {} crates?", crate_ids.len()), "Unfollow", table, || Message::CancelUnfollowAll, || Message::ConfirmUnfollowAll)
+    } else if let Some((message, _)) = &self.request_to_confirm {
+      confirm_modal(*message, "Confirm", table, || Message::CancelConfirmRequest, || Message::AcceptConfirmRequest)
     } else {
       table
     }
   }
 }
+
+fn open_url(url: &str) {
+  if let Err(cause) = webbrowser::open(url) {
+    error!(url, %cause, "failed to open URL: {cause:?}");
+  }
+}
+
+fn whats_new_notification<'a, M: 'a>(whats_new: &WhatsNew, on_dismiss: impl Fn() -> M + 'a) -> Element<'a, M> {
+  let updated_crates = whats_new.updated_crates().iter()
+    .map(|full_crate| format!("{} {}", full_crate.krate.name, full_crate.default_version.number))
+    .collect::<Vec<_>>()
+    .join(", ");
+  WidgetBuilder::stack()
+    .text(format!("{} followed crates were updated since the last launch: {}", whats_new.updated_crates().len(), updated_crates)).add()
+    .add_space_fill_width()
+    .button("Dismiss").secondary_style().on_press(on_dismiss).add()
+    .row().spacing(10.0).align_center().fill_width().add()
+    .container().padding(5).style(iced::widget::container::rounded_box).add()
+    .take()
+}
+
+fn error_notification<'a, M: 'a>(
+  message: String,
+  on_retry: Option<impl Fn() -> M + 'a>,
+  on_dismiss: impl Fn() -> M + 'a,
+) -> Element<'a, M> {
+  let mut builder = WidgetBuilder::heap_with_capacity(4)
+    .text(message).add()
+    .add_space_fill_width();
+  if let Some(on_retry) = on_retry {
+    builder = builder.button("Retry").primary_style().on_press(on_retry).add();
+  }
+  builder
+    .button("Dismiss").danger_style().on_press(on_dismiss).add()
+    .row().spacing(10.0).align_center().fill_width().add()
+    .container().padding(5).style(iced::widget::container::rounded_box).add()
+    .take()
+}
@@ -1,21 +1,29 @@
 use std::error::Error;
 
-use iced::{Element, Event, event, executor, Subscription, Task, window};
+use iced::{Element, Event, event, executor, keyboard, Subscription, Task, window};
 use iced_winit::Program;
 use tracing::error;
 
 use att_client::{Data, DataRef};
 use att_client::auth::{Auth, LoggedIn};
+use att_client::crates::CratesRequest;
+use att_client::deep_link::DeepLink;
 use att_client::http_client::AttHttpClient;
+use att_client::settings::Settings;
+use att_client::whats_new::WhatsNew;
+use att_core::theme::ThemeMode;
 use att_core::users::UserCredentials;
 use iced_builder::WidgetBuilder;
 
 use crate::app::follow_crates::FollowCratesComponent;
+use crate::app::shortcut::ShortcutAction;
 use crate::perform::PerformExt;
-use crate::widget::icon::icon_button;
+use crate::widget::modal::Modal;
+use crate::widget::theme_toggle::ThemeToggle;
 
 pub mod search_crates;
 pub mod follow_crates;
+pub mod shortcut;
 
 pub type SaveFn = Box<dyn for<'a> FnMut(DataRef<'a>) -> Result<(), Box<dyn Error>> + 'static>;
 
@@ -23,21 +31,28 @@ pub struct Flags {
   pub http_client: AttHttpClient,
   pub save_fn: SaveFn,
   pub data: Data,
-  pub dark_mode: bool,
+  pub system_dark_mode: bool,
+  pub deep_link: Option<DeepLink>,
 }
 
 pub struct App {
   save_fn: SaveFn,
+  settings: Settings,
   follow_crates: FollowCratesComponent,
   auth: Auth,
-  dark_mode: bool,
+  system_dark_mode: bool,
+  shortcut_help_open: bool,
+  /// Deep link to navigate to once logged in.
+  deep_link: Option<DeepLink>,
 }
 
 #[derive(Debug)]
 pub enum Message {
   ToFollowCrates(follow_crates::Message),
   Login(LoggedIn),
-  ToggleLightDarkMode,
+  SetThemeMode(ThemeMode),
+  Shortcut(ShortcutAction),
+  CloseShortcutHelp,
   Exit(window::Id),
 }
 
@@ -52,11 +67,23 @@ impl Program for App {
     let mut auth = Auth::new(flags.http_client.clone());
     let login_command = auth.login(UserCredentials::default()).perform(Message::Login);
 
+    let mut settings = flags.data.settings;
+    let whats_new = WhatsNew::new(settings.last_launch_at.replace(chrono::Utc::now()));
+    let follow_crates = FollowCratesComponent::new(
+      flags.http_client,
+      flags.data.follow_crates,
+      settings.crate_columns.clone(),
+      settings.search_history.clone(),
+      whats_new,
+    );
     let app = App {
       save_fn: flags.save_fn,
-      follow_crates: FollowCratesComponent::new(flags.http_client, flags.data.follow_crates),
+      settings,
+      follow_crates,
       auth,
-      dark_mode: flags.dark_mode,
+      system_dark_mode: flags.system_dark_mode,
+      shortcut_help_open: false,
+      deep_link: flags.deep_link,
     };
     let command = Task::batch([login_command]);
     (app, command)
@@ -69,13 +96,32 @@ impl Program for App {
         return self.follow_crates.update(message).into_task().map(ToFollowCrates);
       }
       Login(response) => if self.auth.process_logged_in(response).is_ok() {
-        return self.follow_crates.request_followed_crates().map(ToFollowCrates);
+        let request_task = self.follow_crates.request_followed_crates();
+        let deep_link_task = match self.deep_link.take() {
+          Some(deep_link) => self.follow_crates.update(follow_crates::Message::OpenDeepLink(deep_link)).into_task(),
+          None => Task::none(),
+        };
+        return Task::batch([request_task, deep_link_task]).map(ToFollowCrates);
       }
-      ToggleLightDarkMode => { self.dark_mode = !self.dark_mode; }
-      Exit(window_id) => {
-        let data = DataRef {
-          follow_crates: self.follow_crates.state(),
+      SetThemeMode(theme_mode) => { self.settings.theme_mode = theme_mode; }
+      Shortcut(action) => {
+        let message = match action {
+          ShortcutAction::ToggleHelp => {
+            self.shortcut_help_open = !self.shortcut_help_open;
+            return Task::none();
+          }
+          ShortcutAction::SearchCrates => follow_crates::Message::OpenSearchCratesModal,
+          ShortcutAction::RefreshFollowed => follow_crates::Message::SendRequest(CratesRequest::RefreshFollowed),
         };
+        return self.follow_crates.update(message).into_task().map(ToFollowCrates);
+      }
+      CloseShortcutHelp => { self.shortcut_help_open = false; }
+      Exit(window_id) => {
+        self.settings.crate_columns = self.follow_crates.column_selection().clone();
+        if let Some(search_history) = self.follow_crates.search_history() {
+          self.settings.search_history = search_history.clone();
+        }
+        let data = DataRef::new(self.follow_crates.state(), &self.settings);
         if let Err(cause) = (self.save_fn)(data) {
           error!(%cause, "failed to save data: {cause:?}");
         }
@@ -93,19 +139,34 @@ impl Program for App {
         None
       }
     });
-    exit_subscription
+    let shortcut_subscription = event::listen_with::<Message>(|event, status, _| {
+      // Ignore key presses that were handled by a widget, such as typing into a text input.
+      if let (Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }), event::Status::Ignored) = (event, status) {
+        shortcut::find_action(&key, modifiers).map(Message::Shortcut)
+      } else {
+        None
+      }
+    });
+    Subscription::batch([exit_subscription, shortcut_subscription])
   }
 
   fn view(&self, _window_id: window::Id) -> Element<Message> {
-    WidgetBuilder::stack()
+    let content = WidgetBuilder::stack()
       .text("All The Things").size(20.0).add()
       .add_space_fill_width()
-      .add_element(light_dark_toggle(self.dark_mode, || Message::ToggleLightDarkMode))
+      .add_element(ThemeToggle::new(self.settings.theme_mode, self.system_dark_mode, Message::SetThemeMode).three_state(true))
       .row().spacing(10.0).align_center().fill_width().add()
       .add_horizontal_rule(1.0)
       .add_element(self.follow_crates.view().map(Message::ToFollowCrates))
       .column().spacing(10.0).padding(10).fill().add()
-      .take()
+      .take();
+    if self.shortcut_help_open {
+      Modal::with_scrollable_container(shortcut::view_help(), content)
+        .on_close_modal(|| Message::CloseShortcutHelp)
+        .into()
+    } else {
+      content
+    }
   }
 
   fn title(&self, _window_id: window::Id) -> String {
@@ -113,14 +174,9 @@ impl Program for App {
   }
 
   fn theme(&self, _window_id: window::Id) -> iced::Theme {
-    match self.dark_mode {
+    match self.settings.theme_mode.is_dark(self.system_dark_mode) {
       false => iced::Theme::Light,
       true => iced::Theme::Dark,
     }
   }
 }
-
-fn light_dark_toggle<'a, M: 'a>(dark_mode_enabled: bool, on_press: impl Fn() -> M + 'a) -> Element<'a, M> {
-  let icon = if dark_mode_enabled { "\u{f5a2}" } else { "\u{f496}" };
-  icon_button(icon, on_press)
-}
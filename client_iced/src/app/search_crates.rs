@@ -1,33 +1,52 @@
 use std::time::Duration;
 
 use iced::{Element, Task};
-use iced::widget::text_input;
+use iced::widget::{pick_list, text_input};
 use tracing::instrument;
 
 use att_client::crates::{Crates, CratesRequest, CratesResponse, CratesState};
 use att_client::http_client::AttHttpClient;
 use att_client::query_sender::QuerySender;
+use att_client::saved_searches::{SavedSearches, SavedSearchesResponse};
 use att_client::search_crates::SearchCrates;
-use att_core::crates::{CratesQuery, CratesQueryConfig, FullCrate};
+use att_client::search_history::SearchHistory;
+use att_core::crates::{CratesQuery, CratesQueryConfig, FullCrate, SavedSearch};
 use att_core::iced_impls::as_full_table;
+use att_core::service::Catalog;
+use att_core::table::ColumnSelection;
+use iced_builder::WidgetBuilder;
 
-use crate::perform::OptionPerformExt;
+use crate::perform::{OptionPerformExt, PerformExt};
 use crate::update::Update;
+use crate::widget::confirm::confirm_modal;
 
 pub struct SearchCratesComponent {
   search_term_id: text_input::Id,
   crates: Crates,
   search_crates: SearchCrates,
+  saved_searches: SavedSearches,
+  selected_saved_search: Option<SavedSearch>,
+  saved_search_name: String,
+  request_to_confirm: Option<(&'static str, CratesRequest)>,
 }
 
 #[derive(Debug)]
 pub enum Message {
   SendRequest(CratesRequest),
+  ConfirmRequest(&'static str, CratesRequest),
+  AcceptConfirmRequest,
+  CancelConfirmRequest,
   ProcessResponse(CratesResponse),
+  RunSavedSearch(SavedSearch),
+  SavedSearchNameChanged(String),
+  SaveSearch,
+  DeleteSavedSearch(i32),
+  DismissSavedSearchesError,
+  ProcessSavedSearchesResponse(SavedSearchesResponse),
 }
 
 impl SearchCratesComponent {
-  pub fn new(http_client: AttHttpClient) -> Self {
+  pub fn new(http_client: AttHttpClient, search_history: SearchHistory) -> Self {
     let query_sender = QuerySender::new(
       CratesQuery::from_followed(false),
       CratesQueryConfig {
@@ -39,17 +58,46 @@ impl SearchCratesComponent {
     );
     Self {
       search_term_id: text_input::Id::unique(),
-      crates: Crates::new(http_client, query_sender, CratesState::default()),
-      search_crates: SearchCrates,
+      crates: Crates::new(http_client.clone(), query_sender, CratesState::default())
+        .with_search_history(search_history),
+      search_crates: SearchCrates::default(),
+      saved_searches: SavedSearches::new(http_client),
+      selected_saved_search: None,
+      saved_search_name: String::new(),
+      request_to_confirm: None,
     }
   }
 
+  pub fn request_saved_searches(&mut self) -> Task<Message> {
+    self.saved_searches.send_get_all().perform_into(Message::ProcessSavedSearchesResponse)
+  }
+
+  /// Sets the crates that are already followed, which cannot be followed again from search results. Followed crates
+  /// that are found are shown with the richer of the followed and found records.
+  pub fn set_followed_crates(&mut self, followed_crates: &CratesState) {
+    self.search_crates.set_followed_crates(followed_crates);
+  }
+
+  pub fn search_history(&self) -> Option<&SearchHistory> {
+    self.crates.search_history()
+  }
+
   pub fn focus_search_term_input<M: 'static>(&self) -> Task<M> {
     text_input::focus(self.search_term_id.clone())
   }
 
+  /// Searches for crates with `search_term`.
+  pub fn search(&mut self, search_term: String) -> Task<Message> {
+    let mut query = self.crates.query().clone();
+    query.name = Some(search_term);
+    self.crates.send_set_query(query).opt_perform_into(Message::ProcessResponse)
+  }
+
   pub fn reset(&mut self) {
     self.crates.reset();
+    self.selected_saved_search = None;
+    self.saved_search_name.clear();
+    self.request_to_confirm = None;
   }
 }
 
@@ -63,11 +111,122 @@ impl SearchCratesComponent {
         CratesRequest::Follow(full_crate) => Update::from_action(Some(full_crate)),
         _ => self.crates.send(request).opt_perform(ProcessResponse).into()
       },
-      ProcessResponse(response) => self.crates.process(response).opt_perform(ProcessResponse).into(),
+      ConfirmRequest(message, request) => {
+        self.request_to_confirm = Some((message, request));
+        Update::default()
+      }
+      AcceptConfirmRequest => match self.request_to_confirm.take() {
+        Some((_, request)) => self.update(SendRequest(request)),
+        None => Update::default(),
+      },
+      CancelConfirmRequest => {
+        self.request_to_confirm = None;
+        Update::default()
+      }
+      ProcessResponse(response) => {
+        let task = self.crates.process(response).opt_perform(ProcessResponse);
+        self.crates.merge_present(self.search_crates.followed_crates());
+        task.into()
+      }
+      RunSavedSearch(saved_search) => {
+        let query = saved_search.query.clone();
+        self.saved_search_name = saved_search.name.clone();
+        self.selected_saved_search = Some(saved_search);
+        self.crates.send_set_query(query).opt_perform_into(ProcessResponse).into()
+      }
+      SavedSearchNameChanged(name) => {
+        self.saved_search_name = name;
+        Update::default()
+      }
+      SaveSearch => {
+        let name = self.saved_search_name.clone();
+        let query = self.crates.query().clone();
+        // Update the saved search with the same name if it exists, otherwise save a new one.
+        let existing_id = self.saved_searches.saved_searches().iter().find(|s| s.name == name).map(|s| s.id);
+        let task = match existing_id {
+          Some(saved_search_id) => self.saved_searches.send_update(saved_search_id, name, query).perform_into(ProcessSavedSearchesResponse),
+          None => self.saved_searches.send_save(name, query).perform_into(ProcessSavedSearchesResponse),
+        };
+        task.into()
+      }
+      DeleteSavedSearch(saved_search_id) => {
+        self.selected_saved_search = None;
+        self.saved_searches.send_delete(saved_search_id).perform_into(ProcessSavedSearchesResponse).into()
+      }
+      DismissSavedSearchesError => {
+        self.saved_searches.clear_last_error();
+        Update::default()
+      }
+      ProcessSavedSearchesResponse(response) => {
+        self.saved_searches.process(response);
+        Update::default()
+      }
+    }
+  }
+
+  pub fn view(&self, column_selection: &ColumnSelection) -> Element<Message> {
+    let show_followed = self.crates.query().followed.is_none();
+    let show_followed_toggler = WidgetBuilder::once()
+      .toggler(Some("Show followed"), show_followed, |show| Message::SendRequest(CratesRequest::ShowFollowed(show)))
+      .width_shrink()
+      .add();
+    let recent_queries: Vec<CratesQuery> = self.crates.search_history()
+      .map(|history| history.iter().cloned().collect())
+      .unwrap_or_default();
+    let search_history_pick_list = pick_list(recent_queries, None::<CratesQuery>, |query| Message::SendRequest(CratesRequest::SetQuery(query)))
+      .placeholder("Recent searches")
+      .into();
+    let table = as_full_table(&self.crates, &self.search_crates, None, [search_history_pick_list, show_followed_toggler], column_selection, Message::SendRequest, Message::ConfirmRequest);
+
+    let view = WidgetBuilder::heap_with_capacity(3)
+      .add_element(self.view_saved_searches())
+      .add_element(table)
+      .add_maybe(self.view_pagination())
+      .column().spacing(10.0).fill().add()
+      .take();
+    if let Some((message, _)) = &self.request_to_confirm {
+      confirm_modal(*message, "Confirm", view, || Message::CancelConfirmRequest, || Message::AcceptConfirmRequest)
+    } else {
+      view
     }
   }
 
-  pub fn view(&self) -> Element<Message> {
-    as_full_table(&self.crates, &self.search_crates, None, [], Message::SendRequest)
+  fn view_pagination(&self) -> Option<Element<Message>> {
+    let page_info = *self.crates.page_info()?;
+    let disabled = self.crates.are_all_crates_being_modified();
+    let pagination = WidgetBuilder::heap_with_capacity(5)
+      .add_space_fill_width()
+      .button("Previous").secondary_style().disabled(disabled || !page_info.has_previous())
+      .on_press(move || Message::SendRequest(CratesRequest::SetPage(page_info.page - 1))).add()
+      .text(format!("Page {} of {} ({} crates)", page_info.page, page_info.page_count(), page_info.total_count)).add()
+      .button("Next").secondary_style().disabled(disabled || !page_info.has_next())
+      .on_press(move || Message::SendRequest(CratesRequest::SetPage(page_info.page + 1))).add()
+      .add_space_fill_width()
+      .row().spacing(10.0).align_center().fill_width().add()
+      .take();
+    Some(pagination)
+  }
+
+  fn view_saved_searches(&self) -> Element<Message> {
+    let disabled = self.saved_searches.is_being_modified();
+    let saved_searches_pick_list = pick_list(self.saved_searches.saved_searches(), self.selected_saved_search.clone(), Message::RunSavedSearch)
+      .placeholder("Saved searches");
+    let selected_saved_search_id = self.selected_saved_search.as_ref().map(|s| s.id);
+    let mut builder = WidgetBuilder::heap_with_capacity(6)
+      .element(saved_searches_pick_list).add()
+      .button("Delete").danger_style().disabled(disabled || selected_saved_search_id.is_none())
+      .on_press(move || Message::DeleteSavedSearch(selected_saved_search_id.unwrap_or_default())).add()
+      .add_space_fill_width()
+      .text_input("Saved search name", &self.saved_search_name).on_input(Message::SavedSearchNameChanged).width(250).add()
+      .button("Save Search").success_style().disabled(disabled || self.saved_search_name.is_empty())
+      .on_press(|| Message::SaveSearch).add();
+    if let Some(error) = self.saved_searches.last_error() {
+      builder = builder
+        .text(error.to_string()).add()
+        .button("Dismiss").secondary_style().on_press(|| Message::DismissSavedSearchesError).add()
+    }
+    builder
+      .row().spacing(5.0).align_center().fill_width().add()
+      .take()
   }
 }
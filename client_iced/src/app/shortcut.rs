@@ -0,0 +1,132 @@
+use iced::Element;
+use iced::keyboard::{Key, Modifiers};
+use iced::keyboard::key::Named;
+
+use iced_builder::WidgetBuilder;
+
+/// Action performed when a [`Shortcut`] is pressed.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ShortcutAction {
+  ToggleHelp,
+  SearchCrates,
+  RefreshFollowed,
+}
+
+/// Key that triggers a [`Shortcut`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ShortcutKey {
+  Character(&'static str),
+  Named(Named),
+}
+
+/// Keyboard shortcut.
+#[derive(Copy, Clone, Debug)]
+pub struct Shortcut {
+  /// Key that must be pressed.
+  pub key: ShortcutKey,
+  /// Whether the platform-specific command modifier (Ctrl, or Cmd on macOS) must be held.
+  pub command: bool,
+  /// Human-readable description of the keys, shown in the help overlay.
+  pub keys: &'static str,
+  /// Human-readable description of the action, shown in the help overlay.
+  pub description: &'static str,
+  pub action: ShortcutAction,
+}
+
+impl Shortcut {
+  const fn new(key: ShortcutKey, command: bool, keys: &'static str, description: &'static str, action: ShortcutAction) -> Self {
+    Self { key, command, keys, description, action }
+  }
+
+  /// Returns whether pressing `key` while holding `modifiers` triggers this shortcut.
+  pub fn matches(&self, key: &Key, modifiers: Modifiers) -> bool {
+    if self.command != modifiers.command() { return false; }
+    match (self.key, key) {
+      (ShortcutKey::Character(c), Key::Character(pressed)) => pressed.as_str().eq_ignore_ascii_case(c),
+      (ShortcutKey::Named(named), Key::Named(pressed)) => named == *pressed,
+      _ => false,
+    }
+  }
+}
+
+/// All keyboard shortcuts, in the order shown in the help overlay.
+pub const SHORTCUTS: &[Shortcut] = &[
+  Shortcut::new(ShortcutKey::Character("?"), false, "?", "Show or hide keyboard shortcuts", ShortcutAction::ToggleHelp),
+  Shortcut::new(ShortcutKey::Character("f"), true, "Ctrl+F", "Search crates to follow", ShortcutAction::SearchCrates),
+  Shortcut::new(ShortcutKey::Named(Named::F5), false, "F5", "Refresh followed crates", ShortcutAction::RefreshFollowed),
+];
+
+/// Returns the action of the shortcut triggered by pressing `key` while holding `modifiers`, if any.
+pub fn find_action(key: &Key, modifiers: Modifiers) -> Option<ShortcutAction> {
+  SHORTCUTS.iter().find(|shortcut| shortcut.matches(key, modifiers)).map(|shortcut| shortcut.action)
+}
+
+/// Returns the `(keys, description)` rows shown in the help overlay, in order.
+pub fn help_rows() -> impl Iterator<Item=(&'static str, &'static str)> {
+  SHORTCUTS.iter().map(|shortcut| (shortcut.keys, shortcut.description))
+}
+
+/// Creates the content of the help overlay, listing all [shortcuts](SHORTCUTS).
+pub fn view_help<'a, M: 'a>() -> Element<'a, M> {
+  let keys = WidgetBuilder::heap_with_capacity(SHORTCUTS.len())
+    .add_elements(help_rows().map(|(keys, _)| WidgetBuilder::once().text(keys).add()))
+    .column().spacing(5.0).add()
+    .take();
+  let descriptions = WidgetBuilder::heap_with_capacity(SHORTCUTS.len())
+    .add_elements(help_rows().map(|(_, description)| WidgetBuilder::once().text(description).add()))
+    .column().spacing(5.0).add()
+    .take();
+  let shortcuts = WidgetBuilder::stack()
+    .add_element(keys)
+    .add_element(descriptions)
+    .row().spacing(20.0).add()
+    .take();
+  WidgetBuilder::stack()
+    .text("Keyboard shortcuts").size(20.0).add()
+    .add_element(shortcuts)
+    .column().spacing(10.0).add()
+    .take()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn pressed_key(shortcut: &Shortcut) -> (Key, Modifiers) {
+    let key = match shortcut.key {
+      ShortcutKey::Character(c) => Key::Character(c.into()),
+      ShortcutKey::Named(named) => Key::Named(named),
+    };
+    let modifiers = if shortcut.command { Modifiers::COMMAND } else { Modifiers::empty() };
+    (key, modifiers)
+  }
+
+  #[test]
+  fn help_lists_each_shortcut_exactly_once() {
+    let rows: Vec<_> = help_rows().collect();
+    assert_eq!(rows.len(), SHORTCUTS.len());
+    for shortcut in SHORTCUTS {
+      let count = rows.iter().filter(|row| **row == (shortcut.keys, shortcut.description)).count();
+      assert_eq!(count, 1, "shortcut {:?} should be listed exactly once", shortcut.keys);
+    }
+  }
+
+  #[test]
+  fn each_shortcut_triggers_its_own_action() {
+    for shortcut in SHORTCUTS {
+      let (key, modifiers) = pressed_key(shortcut);
+      assert_eq!(find_action(&key, modifiers), Some(shortcut.action), "pressing {:?}", shortcut.keys);
+      let matching = SHORTCUTS.iter().filter(|other| other.matches(&key, modifiers)).count();
+      assert_eq!(matching, 1, "{:?} is bound to more than one shortcut", shortcut.keys);
+    }
+  }
+
+  #[test]
+  fn command_modifier_must_match() {
+    for shortcut in SHORTCUTS {
+      let (key, modifiers) = pressed_key(shortcut);
+      let toggled = if shortcut.command { Modifiers::empty() } else { modifiers | Modifiers::COMMAND };
+      assert!(!shortcut.matches(&key, toggled));
+    }
+  }
+}
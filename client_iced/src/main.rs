@@ -6,15 +6,17 @@ use iced::window;
 use iced::window::settings::PlatformSpecific;
 use iced_winit::program::run;
 use iced_winit::Settings;
-use att_client::DataRef;
+use att_client::{Data, DataRef};
 
+use att_client::deep_link::DeepLink;
 use att_client::http_client::AttHttpClient;
+use att_client::journal::{Change, Journal, JournalWrite};
 use att_core::app::env;
 use att_core::app::storage::{DirectoryKind, Storage};
 use att_core::app::tracing::AppTracingBuilder;
 use att_core::run_or_compile_time_env;
 
-use crate::app::{App, Flags};
+use crate::app::{App, Flags, SaveFn};
 use crate::widget::icon;
 
 pub mod widget;
@@ -22,23 +24,55 @@ pub mod perform;
 pub mod update;
 pub mod app;
 
+/// Number of backups of the data file to keep, which are used when the data file is corrupt.
+const DATA_BACKUP_COUNT: usize = 3;
+
 fn main() -> Result<(), Box<dyn Error>> {
   env::load_dotenv_into_env();
-  let storage = Storage::new("client_iced");
+  let mut storage = Storage::new("client_iced");
   let _tracing = AppTracingBuilder::default()
     .with_log_file_path_opt(storage.local_data_file("log.txt"))
     .build();
 
-  let data = storage.deserialize_json_file(DirectoryKind::Data, "data.json")?.unwrap_or_default();
-  let save_fn = Box::new(move |data: DataRef| {
-    storage.serialize_json_file(DirectoryKind::Data, "data.json", &data)?;
-    Ok(())
-  });
+  // Data that fails to migrate is handled like a corrupt file: it is backed up, and a backup or default data is used.
+  let mut data = storage.deserialize_json_file_or_backup_with(DirectoryKind::Data, "data.json", Data::migrate)?
+    .unwrap_or_default();
+  // Always apply the journal, even when journaled saving is disabled, so that its changes are not lost.
+  let changes: Vec<Change> = storage.deserialize_json_lines_file(DirectoryKind::Data, "data.journal")?.unwrap_or_default();
+  let journal_len = changes.len();
+  for change in changes {
+    data.apply(change);
+  }
+  // Journaled saving only writes changes since the last save, which is faster when following many crates.
+  let journal_save = std::env::var("ATT_CLIENT_JOURNAL_SAVE").is_ok_and(|v| v == "1" || v == "true");
+  let save_fn: SaveFn = if journal_save {
+    let mut journal = Journal::new(data.clone(), journal_len);
+    Box::new(move |data: DataRef| {
+      storage.set_compress(data.settings.compress_data);
+      match journal.record(data) {
+        JournalWrite::Append(changes) => {
+          storage.append_json_lines_file(DirectoryKind::Data, "data.journal", &changes)?;
+        }
+        JournalWrite::Compact => {
+          storage.serialize_json_file_with_backup(DirectoryKind::Data, "data.json", &data, DATA_BACKUP_COUNT)?;
+          storage.remove_file(DirectoryKind::Data, "data.journal")?;
+        }
+      }
+      Ok(())
+    })
+  } else {
+    Box::new(move |data: DataRef| {
+      storage.set_compress(data.settings.compress_data);
+      storage.serialize_json_file_with_backup(DirectoryKind::Data, "data.json", &data, DATA_BACKUP_COUNT)?;
+      storage.remove_file(DirectoryKind::Data, "data.journal")?;
+      Ok(())
+    })
+  };
 
   let base_url = run_or_compile_time_env!("ATT_CLIENT_BASE_URL");
   let http_client = AttHttpClient::from_base_url(base_url)?;
 
-  let dark_mode = match dark_light::detect() {
+  let system_dark_mode = match dark_light::detect() {
     dark_light::Mode::Dark => true,
     dark_light::Mode::Light | dark_light::Mode::Default => false,
   };
@@ -74,7 +108,8 @@ fn main() -> Result<(), Box<dyn Error>> {
     http_client,
     save_fn,
     data,
-    dark_mode,
+    system_dark_mode,
+    deep_link: DeepLink::from_startup(),
   };
 
   run::<App, iced_renderer::Compositor>(settings, graphics_settings, Some(window_settings), flags)?;
@@ -0,0 +1,32 @@
+use iced::Element;
+
+use att_client::batch_result::BatchResult;
+use iced_builder::WidgetBuilder;
+
+/// Report of a [`BatchResult`], showing the succeeded, failed, and skipped counts along with the reasons for failed and
+/// skipped items, and buttons to copy the summary and to dismiss the report.
+pub fn batch_report<'a, M: 'a>(
+  batch_result: &'a BatchResult,
+  on_copy: impl Fn() -> M + 'a,
+  on_dismiss: impl Fn() -> M + 'a,
+) -> Element<'a, M> {
+  let header = WidgetBuilder::stack()
+    .text(batch_result.counts_line()).add()
+    .add_space_fill_width()
+    .button("Copy Summary").secondary_style().on_press(on_copy).add()
+    .button("Dismiss").secondary_style().on_press(on_dismiss).add()
+    .row().spacing(10.0).align_center().fill_width().add()
+    .take();
+  let reasons = batch_result.failed()
+    .map(|(name, reason)| format!("Failed {name}: {reason}"))
+    .chain(batch_result.skipped().map(|(name, reason)| format!("Skipped {name}: {reason}")));
+  let mut builder = WidgetBuilder::heap()
+    .add_element(header);
+  for reason in reasons {
+    builder = builder.text(reason).add();
+  }
+  builder
+    .column().spacing(5.0).fill_width().add()
+    .container().padding(5).style(iced::widget::container::rounded_box).add()
+    .take()
+}
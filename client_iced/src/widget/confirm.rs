@@ -0,0 +1,30 @@
+use iced::Element;
+
+use iced_builder::WidgetBuilder;
+
+use crate::widget::modal::Modal;
+
+/// Modal dialog over `underlay` that asks to confirm `message`, with a cancel button and a danger-styled confirm button
+/// with `confirm_label`. Pressing the area outside of the dialog or the escape key cancels, pressing enter confirms.
+pub fn confirm_modal<'a, M: 'a>(
+  message: impl Into<String>,
+  confirm_label: &'a str,
+  underlay: impl Into<Element<'a, M>>,
+  on_cancel: impl Fn() -> M + Copy + 'static,
+  on_confirm: impl Fn() -> M + Copy + 'static,
+) -> Element<'a, M> {
+  let buttons = WidgetBuilder::stack()
+    .button("Cancel").secondary_style().on_press(on_cancel).add()
+    .button(confirm_label).danger_style().on_press(on_confirm).add()
+    .row().spacing(5.0).add()
+    .take();
+  let overlay = WidgetBuilder::stack()
+    .text(message.into()).add()
+    .add_element(buttons)
+    .column().spacing(10.0).add()
+    .take();
+  Modal::with_container(overlay, underlay)
+    .on_close_modal(on_cancel)
+    .on_enter_pressed(on_confirm)
+    .into()
+}
@@ -2,8 +2,12 @@ use iced::advanced::Renderer;
 use iced::Element;
 
 pub mod modal;
+pub mod modal_stack;
+pub mod batch_report;
+pub mod confirm;
 pub mod font;
 pub mod icon;
+pub mod theme_toggle;
 
 /// Conversion into an [`Element`]. So we don't have to disambiguate `widget.into()` calls.
 pub trait IntoElement<'a, M, T, R> {
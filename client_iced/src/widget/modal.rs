@@ -1,17 +1,19 @@
 use std::rc::Rc;
+use std::time::Duration;
 
-use iced::{Background, Border, Color, Element, Event, keyboard, Length, Rectangle, Size, Theme, Vector};
+use iced::{Background, Border, Color, Element, Event, keyboard, Length, Point, Rectangle, Size, Theme, Vector, window};
 use iced::advanced::{Clipboard, Renderer, Shell};
 use iced::advanced::graphics::core::touch;
 use iced::advanced::layout::{Layout, Limits, Node};
 use iced::advanced::overlay;
 use iced::advanced::renderer::{self, Style};
-use iced::advanced::widget::{Operation, Tree, Widget};
+use iced::advanced::widget::{Operation, tree, Tree, Widget};
 use iced::alignment::{Horizontal, Vertical};
 use iced::event;
 use iced::keyboard::key::Named;
 use iced::mouse::{self, Cursor};
-use iced::widget::container;
+use iced::time::Instant;
+use iced::widget::{container, Scrollable};
 
 /// A widget that overlays an element over an underlay element in a modal way, disabling the underlay element.
 pub struct Modal<'a, M, T, R, S> {
@@ -20,10 +22,14 @@ pub struct Modal<'a, M, T, R, S> {
 
   on_press_underlay_area: Option<Rc<dyn Fn() -> M>>,
   on_esc_pressed: Option<Rc<dyn Fn() -> M>>,
+  on_enter_pressed: Option<Rc<dyn Fn() -> M>>,
 
   draw_over_underlay_only: bool,
   horizontal_alignment: Horizontal,
   vertical_alignment: Vertical,
+  max_width: f32,
+  max_height: f32,
+  fade_in_duration: Duration,
 
   style: S,
 }
@@ -43,10 +49,14 @@ impl<'a, M, T, R, S> Modal<'a, M, T, R, S> where
 
       on_press_underlay_area: None,
       on_esc_pressed: None,
+      on_enter_pressed: None,
 
       draw_over_underlay_only: false,
       horizontal_alignment: Horizontal::Center,
       vertical_alignment: Vertical::Center,
+      max_width: f32::INFINITY,
+      max_height: f32::INFINITY,
+      fade_in_duration: Duration::ZERO,
 
       style: S::default(),
     }
@@ -72,6 +82,12 @@ impl<'a, M, T, R, S> Modal<'a, M, T, R, S> where
     self.on_esc_pressed = Some(Rc::new(message_producer));
     self
   }
+  /// Sets the `message_producer` to call when the [enter key](Named::Enter) is pressed, for example to confirm a dialog.
+  /// Does not call `message_producer` when a widget in the overlay captures the key press, such as a text editor.
+  pub fn on_enter_pressed(mut self, message_producer: impl Fn() -> M + 'static) -> Self {
+    self.on_enter_pressed = Some(Rc::new(message_producer));
+    self
+  }
 
   /// Sets whether the modal background should be drawn over the underlay only (`true`), or whether it should be drawn
   /// over everything (`false`, the default).
@@ -90,6 +106,29 @@ impl<'a, M, T, R, S> Modal<'a, M, T, R, S> where
     self
   }
 
+  /// Sets the `max_width` of the overlay of this modal. The overlay is still aligned within the full modal area.
+  pub fn max_width(mut self, max_width: f32) -> Self {
+    self.max_width = max_width;
+    self
+  }
+  /// Sets the `max_height` of the overlay of this modal. The overlay is still aligned within the full modal area. Use
+  /// [with_scrollable_container](Modal::with_scrollable_container) to scroll overlays that are taller than this.
+  pub fn max_height(mut self, max_height: f32) -> Self {
+    self.max_height = max_height;
+    self
+  }
+
+  /// Sets the `fade_in_duration` over which the modal background fades in after the modal is first shown. The
+  /// background is shown immediately when this is zero (the default).
+  ///
+  /// Only the background fades, and only in: the overlay itself is drawn at full opacity as the renderer does not support
+  /// drawing elements with opacity, and the modal disappears immediately when it is removed from the view, as its state
+  /// is removed along with it. The fade-in restarts when the modal is shown again.
+  pub fn fade_in_duration(mut self, fade_in_duration: Duration) -> Self {
+    self.fade_in_duration = fade_in_duration;
+    self
+  }
+
   /// Sets the `style` of this modal.
   pub fn style(mut self, style: S) -> Self {
     self.style = style;
@@ -105,26 +144,42 @@ impl<'a, M, R> Modal<'a, M, Theme, R, ModalStyleForTheme> where
     overlay: impl Into<Element<'a, M, Theme, R>>,
     underlay: impl Into<Element<'a, M, Theme, R>>,
   ) -> Self {
-    let overlay = container::Container::new(overlay)
-      .padding(10)
-      .style(|theme: &Theme| {
-        let palette = theme.extended_palette();
-        let background = palette.background.base;
-        container::Style {
-          text_color: Some(background.text),
-          background: Some(background.color.into()),
-          border: Border {
-            radius: 10.0.into(),
-            width: 2.0,
-            color: palette.primary.weak.color,
-          },
-          ..Default::default()
-        }
-      });
-    Self::new(overlay, underlay)
+    Self::new(modal_container(overlay), underlay)
+  }
+
+  /// Creates a new [`Modal`] like [with_container](Self::with_container), but wraps `overlay` in a [`Scrollable`] inside
+  /// the container, so that overlays taller than the [maximum height](Self::max_height) or the window can be scrolled.
+  pub fn with_scrollable_container(
+    overlay: impl Into<Element<'a, M, Theme, R>>,
+    underlay: impl Into<Element<'a, M, Theme, R>>,
+  ) -> Self {
+    Self::new(modal_container(Scrollable::new(overlay)), underlay)
   }
 }
 
+/// Wraps `overlay` in a modal-styled container.
+pub(crate) fn modal_container<'a, M: 'a, R: Renderer + 'a>(
+  overlay: impl Into<Element<'a, M, Theme, R>>,
+) -> Element<'a, M, Theme, R> {
+  container::Container::new(overlay)
+    .padding(10)
+    .style(|theme: &Theme| {
+      let palette = theme.extended_palette();
+      let background = palette.background.base;
+      container::Style {
+        text_color: Some(background.text),
+        background: Some(background.color.into()),
+        border: Border {
+          radius: 10.0.into(),
+          width: 2.0,
+          color: palette.primary.weak.color,
+        },
+        ..Default::default()
+      }
+    })
+    .into()
+}
+
 /// The appearance of a modal.
 #[derive(Clone, Copy, Debug)]
 pub struct ModalAppearance {
@@ -178,11 +233,25 @@ impl<'a, M, T, R, S> From<Modal<'a, M, T, R, S>> for Element<'a, M, T, R> where
 }
 
 
+/// State of a modal, stored in its [`Tree`].
+#[derive(Default)]
+struct State {
+  /// Time at which the modal was first shown, to animate the background.
+  shown_at: Option<Instant>,
+}
+
 // Widget implementation
 impl<M, T, R, S> Widget<M, T, R> for Modal<'_, M, T, R, S> where
   R: Renderer,
   S: ModalStyle<Theme=T>,
 {
+  fn tag(&self) -> tree::Tag {
+    tree::Tag::of::<State>()
+  }
+  fn state(&self) -> tree::State {
+    tree::State::new(State::default())
+  }
+
   fn children(&self) -> Vec<Tree> {
     vec![
       Tree::new(&self.underlay),
@@ -215,15 +284,21 @@ impl<M, T, R, S> Widget<M, T, R> for Modal<'_, M, T, R, S> where
     _renderer: &R,
     translation: Vector,
   ) -> Option<overlay::Element<'o, M, T, R>> {
+    let shown_at = *tree.state.downcast_mut::<State>().shown_at.get_or_insert_with(Instant::now);
     let modal_overlay = ModalOverlay {
       underlay_bounds: self.draw_over_underlay_only.then(|| layout.bounds() + translation),
       horizontal_alignment: self.horizontal_alignment,
       vertical_alignment: self.vertical_alignment,
+      max_width: self.max_width,
+      max_height: self.max_height,
+      shown_at,
+      fade_in_duration: self.fade_in_duration,
       overlay: &mut self.overlay,
       overlay_tree: &mut tree.children[1],
 
       on_press_underlay_area: self.on_press_underlay_area.clone(),
       on_esc_pressed: self.on_esc_pressed.clone(),
+      on_enter_pressed: self.on_enter_pressed.clone(),
 
       style: self.style.clone(),
     };
@@ -254,19 +329,66 @@ impl<M, T, R, S> Widget<M, T, R> for Modal<'_, M, T, R, S> where
   }
 }
 
+/// Gets the bounds of an overlay with `content` size, clamped to `max_width` and `max_height` and to the size of
+/// `viewport`, then aligned within `viewport` according to `horizontal_alignment` and `vertical_alignment`.
+pub fn overlay_bounds(
+  viewport: Rectangle,
+  content: Size,
+  max_width: f32,
+  max_height: f32,
+  horizontal_alignment: Horizontal,
+  vertical_alignment: Vertical,
+) -> Rectangle {
+  let width = content.width.min(max_width).min(viewport.width).max(0.0);
+  let height = content.height.min(max_height).min(viewport.height).max(0.0);
+  let x = match horizontal_alignment {
+    Horizontal::Left => viewport.x,
+    Horizontal::Center => viewport.x + (viewport.width - width) / 2.0,
+    Horizontal::Right => viewport.x + viewport.width - width,
+  };
+  let y = match vertical_alignment {
+    Vertical::Top => viewport.y,
+    Vertical::Center => viewport.y + (viewport.height - height) / 2.0,
+    Vertical::Bottom => viewport.y + viewport.height - height,
+  };
+  Rectangle { x, y, width, height }
+}
+
 // Overlay implementation
 struct ModalOverlay<'a, 'o, M, T, R, S> {
   underlay_bounds: Option<Rectangle>,
   horizontal_alignment: Horizontal,
   vertical_alignment: Vertical,
+  max_width: f32,
+  max_height: f32,
+  shown_at: Instant,
+  fade_in_duration: Duration,
   overlay: &'o mut Element<'a, M, T, R>,
   overlay_tree: &'o mut Tree,
 
   on_press_underlay_area: Option<Rc<dyn Fn() -> M>>,
   on_esc_pressed: Option<Rc<dyn Fn() -> M>>,
+  on_enter_pressed: Option<Rc<dyn Fn() -> M>>,
 
   style: S,
 }
+impl<M, T, R, S> ModalOverlay<'_, '_, M, T, R, S> {
+  /// Gets the opacity (between `0.0` and `1.0`) of the modal background at time `now`.
+  #[inline]
+  fn opacity(&self, now: Instant) -> f32 {
+    fade_in_opacity(self.shown_at, now, self.fade_in_duration)
+  }
+}
+
+/// Gets the opacity (between `0.0` and `1.0`) at time `now` of something that was shown at `shown_at` and fades in over
+/// `fade_in_duration`.
+fn fade_in_opacity(shown_at: Instant, now: Instant, fade_in_duration: Duration) -> f32 {
+  if fade_in_duration.is_zero() {
+    return 1.0;
+  }
+  let elapsed = now.saturating_duration_since(shown_at);
+  (elapsed.as_secs_f32() / fade_in_duration.as_secs_f32()).min(1.0)
+}
 impl<M, T, R, S> overlay::Overlay<M, T, R> for ModalOverlay<'_, '_, M, T, R, S> where
   R: Renderer,
   S: ModalStyle<Theme=T>,
@@ -278,13 +400,18 @@ impl<M, T, R, S> overlay::Overlay<M, T, R> for ModalOverlay<'_, '_, M, T, R, S>
   ) -> Node {
     let limits = Limits::new(Size::ZERO, self.underlay_bounds.map_or(bounds, |b| b.size()));
     let max_size = limits.max();
-    let overlay_node = self.overlay.as_widget()
-      .layout(self.overlay_tree, renderer, &limits)
-      .align(
-        self.horizontal_alignment.into(),
-        self.vertical_alignment.into(),
-        max_size,
-      );
+    // Constrain the overlay but align it within the full area, so that alignment is preserved.
+    let overlay_limits = limits.max_width(self.max_width).max_height(self.max_height);
+    let overlay_node = self.overlay.as_widget().layout(self.overlay_tree, renderer, &overlay_limits);
+    let bounds = overlay_bounds(
+      Rectangle::new(Point::ORIGIN, max_size),
+      overlay_node.size(),
+      self.max_width,
+      self.max_height,
+      self.horizontal_alignment,
+      self.vertical_alignment,
+    );
+    let overlay_node = overlay_node.move_to(bounds.position());
     let node = Node::with_children(max_size, vec![overlay_node]);
     if let Some(underlay_bounds) = self.underlay_bounds {
       node.move_to(underlay_bounds.position())
@@ -317,6 +444,13 @@ impl<M, T, R, S> overlay::Overlay<M, T, R> for ModalOverlay<'_, '_, M, T, R, S>
   ) -> event::Status {
     let overlay_layout = layout.children().next().unwrap();
 
+    // Keep redrawing until the background has faded in.
+    if let Event::Window(window::Event::RedrawRequested(now)) = event {
+      if self.opacity(now) < 1.0 {
+        shell.request_redraw(window::RedrawRequest::NextFrame);
+      }
+    }
+
     if let Some(on_press_underlay_area) = self.on_press_underlay_area.as_ref() {
       let overlay_bounds = overlay_layout.bounds();
       let pressed_underlay_area = match event {
@@ -337,16 +471,28 @@ impl<M, T, R, S> overlay::Overlay<M, T, R> for ModalOverlay<'_, '_, M, T, R, S>
       }
     }
 
-    self.overlay.as_widget_mut().on_event(
+    let status = self.overlay.as_widget_mut().on_event(
       self.overlay_tree,
-      event,
+      event.clone(),
       overlay_layout,
       cursor,
       renderer,
       clipboard,
       shell,
       &layout.bounds(),
-    )
+    );
+
+    // Handle enter after the overlay, so that widgets in the overlay that want the enter key can capture it first.
+    if status == event::Status::Ignored {
+      if let Some(on_enter_pressed) = self.on_enter_pressed.as_ref() {
+        if let Event::Keyboard(keyboard::Event::KeyPressed { key: keyboard::Key::Named(Named::Enter), .. }) = event {
+          shell.publish(on_enter_pressed());
+          return event::Status::Captured;
+        }
+      }
+    }
+
+    status
   }
   fn mouse_interaction(
     &self,
@@ -399,7 +545,7 @@ impl<M, T, R, S> overlay::Overlay<M, T, R> for ModalOverlay<'_, '_, M, T, R, S>
         },
         ..renderer::Quad::default()
       },
-      appearance.background,
+      appearance.background.scale_alpha(self.opacity(Instant::now())),
     );
 
     let overlay_layout = layout.children().next().unwrap();
@@ -414,3 +560,54 @@ impl<M, T, R, S> overlay::Overlay<M, T, R> for ModalOverlay<'_, '_, M, T, R, S>
     );
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const VIEWPORT: Rectangle = Rectangle { x: 0.0, y: 0.0, width: 800.0, height: 600.0 };
+
+  #[test]
+  fn zero_fade_in_duration_is_fully_opaque() {
+    let now = Instant::now();
+    assert_eq!(fade_in_opacity(now, now, Duration::ZERO), 1.0);
+  }
+
+  #[test]
+  fn background_fades_in_over_duration() {
+    let shown_at = Instant::now();
+    let duration = Duration::from_millis(200);
+    assert_eq!(fade_in_opacity(shown_at, shown_at, duration), 0.0);
+    assert_eq!(fade_in_opacity(shown_at, shown_at + Duration::from_millis(50), duration), 0.25);
+    assert_eq!(fade_in_opacity(shown_at, shown_at + duration, duration), 1.0);
+    assert_eq!(fade_in_opacity(shown_at, shown_at + Duration::from_secs(10), duration), 1.0);
+  }
+
+  #[test]
+  fn overlay_smaller_than_viewport_is_centered() {
+    let bounds = overlay_bounds(VIEWPORT, Size::new(200.0, 100.0), f32::INFINITY, f32::INFINITY, Horizontal::Center, Vertical::Center);
+    assert_eq!(bounds, Rectangle { x: 300.0, y: 250.0, width: 200.0, height: 100.0 });
+  }
+
+  #[test]
+  fn overlay_is_clamped_to_max_size_and_stays_centered() {
+    let bounds = overlay_bounds(VIEWPORT, Size::new(700.0, 2000.0), 400.0, 300.0, Horizontal::Center, Vertical::Center);
+    assert_eq!(bounds, Rectangle { x: 200.0, y: 150.0, width: 400.0, height: 300.0 });
+  }
+
+  #[test]
+  fn overlay_is_clamped_to_viewport() {
+    let bounds = overlay_bounds(VIEWPORT, Size::new(1000.0, 2000.0), f32::INFINITY, f32::INFINITY, Horizontal::Center, Vertical::Center);
+    assert_eq!(bounds, VIEWPORT);
+  }
+
+  #[test]
+  fn clamped_overlay_honors_alignment() {
+    let viewport = Rectangle { x: 10.0, y: 20.0, width: 800.0, height: 600.0 };
+    let content = Size::new(1000.0, 1000.0);
+    let top_left = overlay_bounds(viewport, content, 400.0, 300.0, Horizontal::Left, Vertical::Top);
+    assert_eq!(top_left, Rectangle { x: 10.0, y: 20.0, width: 400.0, height: 300.0 });
+    let bottom_right = overlay_bounds(viewport, content, 400.0, 300.0, Horizontal::Right, Vertical::Bottom);
+    assert_eq!(bottom_right, Rectangle { x: 410.0, y: 320.0, width: 400.0, height: 300.0 });
+  }
+}
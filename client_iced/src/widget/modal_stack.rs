@@ -0,0 +1,401 @@
+use std::rc::Rc;
+
+use iced::{Element, Event, keyboard, Length, Rectangle, Size, Theme, Vector};
+use iced::advanced::{Clipboard, Renderer, Shell};
+use iced::advanced::graphics::core::touch;
+use iced::advanced::layout::{Layout, Limits, Node};
+use iced::advanced::overlay;
+use iced::advanced::renderer::{self, Style};
+use iced::advanced::widget::{Operation, Tree, Widget};
+use iced::alignment::{Horizontal, Vertical};
+use iced::event;
+use iced::keyboard::key::Named;
+use iced::mouse::{self, Cursor};
+
+use crate::widget::modal::{modal_container, ModalStyle, ModalStyleForTheme};
+
+/// A widget that overlays a stack of elements over an underlay element in a modal way, for example a confirmation
+/// dialog on top of a details dialog. A single background is drawn between the underlay and the stack, and only the
+/// topmost element of the stack is enabled: pressing escape or the area outside of it only closes the topmost element.
+pub struct ModalStack<'a, M, T, R, S> {
+  underlay: Element<'a, M, T, R>,
+  layers: Vec<Layer<'a, M, T, R>>,
+
+  horizontal_alignment: Horizontal,
+  vertical_alignment: Vertical,
+
+  style: S,
+}
+
+/// Element in a [`ModalStack`], along with the message producer to call when it should be closed.
+struct Layer<'a, M, T, R> {
+  overlay: Element<'a, M, T, R>,
+  on_close: Option<Rc<dyn Fn() -> M>>,
+}
+
+impl<'a, M, T, R, S> ModalStack<'a, M, T, R, S> where
+  M: 'a,
+  R: Renderer + 'a,
+  S: ModalStyle<Theme=T>
+{
+  /// Creates a new [`ModalStack`] without any overlays over `underlay`. Nothing is overlaid until an overlay is
+  /// [pushed](Self::push).
+  pub fn new(underlay: impl Into<Element<'a, M, T, R>>) -> Self {
+    Self {
+      underlay: underlay.into(),
+      layers: Vec::new(),
+
+      horizontal_alignment: Horizontal::Center,
+      vertical_alignment: Vertical::Center,
+
+      style: S::default(),
+    }
+  }
+
+  /// Pushes `overlay` on top of the stack. `message_producer` is called when `overlay` is the topmost element of the stack
+  /// and either the area outside of it or the [escape key](Named::Escape) is pressed.
+  pub fn push(mut self, overlay: impl Into<Element<'a, M, T, R>>, message_producer: impl Fn() -> M + 'static) -> Self {
+    self.layers.push(Layer { overlay: overlay.into(), on_close: Some(Rc::new(message_producer)) });
+    self
+  }
+  /// Pushes `overlay` on top of the stack, which cannot be closed by pressing the area outside of it or the
+  /// [escape key](Named::Escape).
+  pub fn push_without_close(mut self, overlay: impl Into<Element<'a, M, T, R>>) -> Self {
+    self.layers.push(Layer { overlay: overlay.into(), on_close: None });
+    self
+  }
+
+  /// Sets the `horizontal_alignment` of the overlays.
+  pub fn horizontal_alignment(mut self, horizontal_alignment: Horizontal) -> Self {
+    self.horizontal_alignment = horizontal_alignment;
+    self
+  }
+  /// Sets the `vertical_alignment` of the overlays.
+  pub fn vertical_alignment(mut self, vertical_alignment: Vertical) -> Self {
+    self.vertical_alignment = vertical_alignment;
+    self
+  }
+
+  /// Sets the `style` of this modal stack.
+  pub fn style(mut self, style: S) -> Self {
+    self.style = style;
+    self
+  }
+}
+impl<'a, M, R> ModalStack<'a, M, Theme, R, ModalStyleForTheme> where
+  M: 'a,
+  R: Renderer + 'a,
+{
+  /// Pushes `overlay` wrapped in a modal-styled container on top of the stack, like [`push`](Self::push).
+  pub fn push_with_container(
+    self,
+    overlay: impl Into<Element<'a, M, Theme, R>>,
+    message_producer: impl Fn() -> M + 'static,
+  ) -> Self {
+    self.push(modal_container(overlay), message_producer)
+  }
+}
+
+
+/// Conversion into [`Element`].
+impl<'a, M, T, R, S> From<ModalStack<'a, M, T, R, S>> for Element<'a, M, T, R> where
+  M: 'a,
+  R: Renderer + 'a,
+  T: 'a,
+  S: ModalStyle<Theme=T> + 'a,
+{
+  fn from(modal_stack: ModalStack<'a, M, T, R, S>) -> Self {
+    Self::new(modal_stack)
+  }
+}
+
+
+// Widget implementation
+impl<M, T, R, S> Widget<M, T, R> for ModalStack<'_, M, T, R, S> where
+  R: Renderer,
+  S: ModalStyle<Theme=T>,
+{
+  fn children(&self) -> Vec<Tree> {
+    std::iter::once(&self.underlay)
+      .chain(self.layers.iter().map(|l| &l.overlay))
+      .map(Tree::new)
+      .collect()
+  }
+  fn diff(&self, tree: &mut Tree) {
+    let elements: Vec<_> = std::iter::once(&self.underlay)
+      .chain(self.layers.iter().map(|l| &l.overlay))
+      .collect();
+    tree.diff_children(&elements);
+  }
+
+  fn size(&self) -> Size<Length> {
+    self.underlay.as_widget().size()
+  }
+  fn layout(
+    &self,
+    tree: &mut Tree,
+    renderer: &R,
+    limits: &Limits,
+  ) -> Node {
+    self.underlay.as_widget().layout(
+      &mut tree.children[0],
+      renderer,
+      limits,
+    )
+  }
+  fn overlay<'o>(
+    &'o mut self,
+    tree: &'o mut Tree,
+    _layout: Layout<'_>,
+    _renderer: &R,
+    _translation: Vector,
+  ) -> Option<overlay::Element<'o, M, T, R>> {
+    if self.layers.is_empty() {
+      return None;
+    }
+    let modal_stack_overlay = ModalStackOverlay {
+      horizontal_alignment: self.horizontal_alignment,
+      vertical_alignment: self.vertical_alignment,
+      layers: &mut self.layers,
+      layer_trees: &mut tree.children[1..],
+      style: self.style.clone(),
+    };
+    Some(overlay::Element::new(Box::new(modal_stack_overlay)))
+  }
+
+  // Note: did not override `on_event`, `mouse_interaction`, and `operate` as the overlay disables the underlay.
+
+  fn draw(
+    &self,
+    tree: &Tree,
+    renderer: &mut R,
+    theme: &T,
+    style: &Style,
+    layout: Layout<'_>,
+    cursor: Cursor,
+    viewport: &Rectangle,
+  ) {
+    self.underlay.as_widget().draw(
+      &tree.children[0],
+      renderer,
+      theme,
+      style,
+      layout,
+      cursor,
+      viewport,
+    );
+  }
+}
+
+// Overlay implementation
+struct ModalStackOverlay<'a, 'o, M, T, R, S> {
+  horizontal_alignment: Horizontal,
+  vertical_alignment: Vertical,
+  layers: &'o mut [Layer<'a, M, T, R>],
+  layer_trees: &'o mut [Tree],
+  style: S,
+}
+impl<'a, M, T, R, S> ModalStackOverlay<'a, '_, M, T, R, S> {
+  /// Gets the topmost layer along with its tree and layout.
+  fn topmost<'s, 'l>(&'s mut self, layout: Layout<'l>) -> (&'s mut Layer<'a, M, T, R>, &'s mut Tree, Layout<'l>) {
+    let layer = self.layers.last_mut().unwrap();
+    let tree = self.layer_trees.last_mut().unwrap();
+    let layout = layout.children().last().unwrap();
+    (layer, tree, layout)
+  }
+}
+impl<M, T, R, S> overlay::Overlay<M, T, R> for ModalStackOverlay<'_, '_, M, T, R, S> where
+  R: Renderer,
+  S: ModalStyle<Theme=T>,
+{
+  fn layout(
+    &mut self,
+    renderer: &R,
+    bounds: Size,
+  ) -> Node {
+    let limits = Limits::new(Size::ZERO, bounds);
+    let nodes = self.layers.iter().zip(self.layer_trees.iter_mut())
+      .map(|(layer, tree)| layer.overlay.as_widget()
+        .layout(tree, renderer, &limits)
+        .align(self.horizontal_alignment.into(), self.vertical_alignment.into(), bounds)
+      )
+      .collect();
+    Node::with_children(bounds, nodes)
+  }
+  fn overlay(
+    &mut self,
+    layout: Layout<'_>,
+    renderer: &R,
+  ) -> Option<overlay::Element<M, T, R>> {
+    let (layer, tree, layout) = self.topmost(layout);
+    layer.overlay.as_widget_mut().overlay(tree, layout, renderer, Vector::ZERO)
+  }
+
+  fn on_event(
+    &mut self,
+    event: Event,
+    layout: Layout<'_>,
+    cursor: Cursor,
+    renderer: &R,
+    clipboard: &mut dyn Clipboard,
+    shell: &mut Shell<'_, M>,
+  ) -> event::Status {
+    let bounds = layout.bounds();
+    // Only the topmost layer receives events and can be closed.
+    let (layer, tree, layout) = self.topmost(layout);
+
+    if let Some(on_close) = layer.on_close.as_ref() {
+      let overlay_bounds = layout.bounds();
+      let close = match event {
+        Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => !cursor.is_over(overlay_bounds),
+        Event::Touch(touch::Event::FingerPressed { position, .. }) => !overlay_bounds.contains(position),
+        Event::Keyboard(keyboard::Event::KeyPressed { key: keyboard::Key::Named(Named::Escape), .. }) => true,
+        _ => false,
+      };
+      if close {
+        shell.publish(on_close());
+        return event::Status::Captured;
+      }
+    }
+
+    layer.overlay.as_widget_mut().on_event(
+      tree,
+      event,
+      layout,
+      cursor,
+      renderer,
+      clipboard,
+      shell,
+      &bounds,
+    )
+  }
+  fn mouse_interaction(
+    &self,
+    layout: Layout<'_>,
+    cursor: Cursor,
+    viewport: &Rectangle,
+    renderer: &R,
+  ) -> mouse::Interaction {
+    let (Some(layer), Some(tree), Some(layout)) = (self.layers.last(), self.layer_trees.last(), layout.children().last()) else {
+      return mouse::Interaction::default();
+    };
+    layer.overlay.as_widget().mouse_interaction(tree, layout, cursor, viewport, renderer)
+  }
+  fn operate(
+    &mut self,
+    layout: Layout<'_>,
+    renderer: &R,
+    operation: &mut dyn Operation<()>,
+  ) {
+    let (layer, tree, layout) = self.topmost(layout);
+    layer.overlay.as_widget().operate(tree, layout, renderer, operation);
+  }
+
+  fn draw(
+    &self,
+    renderer: &mut R,
+    theme: &T,
+    style: &Style,
+    layout: Layout<'_>,
+    cursor: Cursor,
+  ) {
+    let bounds = layout.bounds();
+    let appearance = self.style.appearance(theme);
+    renderer.fill_quad(renderer::Quad { bounds, ..renderer::Quad::default() }, appearance.background);
+
+    // Draw all layers from bottom to top, but only let the topmost layer see the cursor. Each layer is drawn in its own
+    // renderer layer so that (text) primitives of lower layers are not drawn over higher layers.
+    let layer_count = self.layers.len();
+    for (index, ((layer, tree), layout)) in self.layers.iter().zip(self.layer_trees.iter()).zip(layout.children()).enumerate() {
+      let cursor = if index + 1 == layer_count { cursor } else { Cursor::Unavailable };
+      renderer.with_layer(bounds, |renderer| {
+        layer.overlay.as_widget().draw(tree, renderer, theme, style, layout, cursor, &bounds);
+      });
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use iced::{Point, widget};
+  use iced::advanced::clipboard;
+  use iced::keyboard::{Location, Modifiers};
+  use iced::keyboard::key::{Code, Physical};
+
+  use super::*;
+
+  #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+  enum Message {
+    CloseDetails,
+    CloseConfirm,
+  }
+
+  const BOUNDS: Size = Size::new(200.0, 200.0);
+
+  /// Creates a modal stack with a 100x100 details layer, and on top of that a centered 20x20 confirm layer that can be
+  /// closed if `closable_confirm` is `true`.
+  fn modal_stack<'a>(closable_confirm: bool) -> Element<'a, Message, Theme, ()> {
+    let modal_stack = ModalStack::<_, _, _, ModalStyleForTheme>::new(widget::Space::new(Length::Fill, Length::Fill))
+      .push(widget::Space::new(100.0, 100.0), || Message::CloseDetails);
+    let confirm = widget::Space::new(20.0, 20.0);
+    let modal_stack = if closable_confirm {
+      modal_stack.push(confirm, || Message::CloseConfirm)
+    } else {
+      modal_stack.push_without_close(confirm)
+    };
+    modal_stack.into()
+  }
+
+  /// Sends `event` with the cursor at `cursor` to the overlay of `element`, returning the published messages.
+  fn send_event(element: &mut Element<'_, Message, Theme, ()>, event: Event, cursor: Cursor) -> Vec<Message> {
+    let mut tree = Tree::new(&*element);
+    let node = element.as_widget().layout(&mut tree, &(), &Limits::new(Size::ZERO, BOUNDS));
+    let mut overlay = element.as_widget_mut().overlay(&mut tree, Layout::new(&node), &(), Vector::ZERO)
+      .expect("modal stack with layers should have an overlay");
+    let overlay_node = overlay.layout(&(), BOUNDS);
+
+    let mut messages = Vec::new();
+    let mut shell = Shell::new(&mut messages);
+    overlay.on_event(event, Layout::new(&overlay_node), cursor, &(), &mut clipboard::Null, &mut shell);
+    messages
+  }
+
+  fn escape_pressed() -> Event {
+    Event::Keyboard(keyboard::Event::KeyPressed {
+      key: keyboard::Key::Named(Named::Escape),
+      modified_key: keyboard::Key::Named(Named::Escape),
+      physical_key: Physical::Code(Code::Escape),
+      location: Location::Standard,
+      modifiers: Modifiers::empty(),
+      text: None,
+    })
+  }
+
+  fn click_at(element: &mut Element<'_, Message, Theme, ()>, x: f32, y: f32) -> Vec<Message> {
+    let event = Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left));
+    send_event(element, event, Cursor::Available(Point::new(x, y)))
+  }
+
+  #[test]
+  fn escape_closes_only_topmost_layer() {
+    assert_eq!(send_event(&mut modal_stack(true), escape_pressed(), Cursor::Unavailable), [Message::CloseConfirm]);
+  }
+
+  #[test]
+  fn clicking_outside_topmost_layer_closes_only_topmost_layer() {
+    let mut modal_stack = modal_stack(true);
+    // Inside the details layer, but outside the confirm layer.
+    assert_eq!(click_at(&mut modal_stack, 60.0, 60.0), [Message::CloseConfirm]);
+    // Outside both layers.
+    assert_eq!(click_at(&mut modal_stack, 5.0, 5.0), [Message::CloseConfirm]);
+    // Inside the confirm layer.
+    assert_eq!(click_at(&mut modal_stack, 100.0, 100.0), []);
+  }
+
+  #[test]
+  fn unclosable_topmost_layer_does_not_close_lower_layers() {
+    let mut modal_stack = modal_stack(false);
+    assert_eq!(send_event(&mut modal_stack, escape_pressed(), Cursor::Unavailable), []);
+    assert_eq!(click_at(&mut modal_stack, 5.0, 5.0), []);
+  }
+}
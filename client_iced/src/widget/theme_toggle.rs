@@ -0,0 +1,47 @@
+use iced::Element;
+
+use att_core::icon::Icon;
+use att_core::theme::ThemeMode;
+
+use crate::widget::icon::icon_button;
+
+/// Button that toggles between light and dark [theme modes](ThemeMode), or cycles through light, dark, and system
+/// theme modes when [three-state](Self::three_state) is enabled. Shows the icon of the mode it switches to.
+pub struct ThemeToggle<'a, M> {
+  mode: ThemeMode,
+  system_dark: bool,
+  three_state: bool,
+  on_change: Box<dyn Fn(ThemeMode) -> M + 'a>,
+}
+
+impl<'a, M: 'a> ThemeToggle<'a, M> {
+  /// Creates a new toggle for the current `mode`, where `system_dark` is whether the system is in dark mode, calling
+  /// `on_change` with the next mode when pressed.
+  pub fn new(mode: ThemeMode, system_dark: bool, on_change: impl Fn(ThemeMode) -> M + 'a) -> Self {
+    Self { mode, system_dark, three_state: false, on_change: Box::new(on_change) }
+  }
+
+  /// Sets whether to cycle through light, dark, and system theme modes, instead of toggling between light and dark.
+  pub fn three_state(mut self, three_state: bool) -> Self {
+    self.three_state = three_state;
+    self
+  }
+
+  /// Returns the mode this toggle switches to when pressed.
+  pub fn next_mode(&self) -> ThemeMode {
+    if self.three_state { self.mode.cycle() } else { self.mode.toggle(self.system_dark) }
+  }
+}
+
+impl<'a, M: 'a> From<ThemeToggle<'a, M>> for Element<'a, M> {
+  fn from(theme_toggle: ThemeToggle<'a, M>) -> Self {
+    let next_mode = theme_toggle.next_mode();
+    let icon = match next_mode {
+      ThemeMode::Light => Icon::Light,
+      ThemeMode::Dark => Icon::Dark,
+      ThemeMode::System => Icon::System,
+    };
+    let on_change = theme_toggle.on_change;
+    icon_button(icon, move || on_change(next_mode))
+  }
+}
@@ -1,3 +1,5 @@
+use crate::icon::Icon;
+
 #[derive(Default, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub enum ActionStyle {
   #[default]
@@ -22,12 +24,17 @@ pub struct ActionDef {
   pub font_name: Option<&'static str>, // TODO: abstract over icon/font name
   pub layout: ActionLayout,
   pub style: ActionStyle,
+  /// Message to show to confirm the action before its request is sent, or `None` if the action does not need to be
+  /// confirmed.
+  pub confirm: Option<&'static str>,
+  /// Text to show in a tooltip when hovering over the action, for example to explain icon-only actions.
+  pub tooltip: Option<&'static str>,
 }
 
 impl ActionDef {
   #[inline]
   pub const fn new(text: &'static str, font_name: Option<&'static str>, layout: ActionLayout, style: ActionStyle) -> Self {
-    Self { text, font_name, layout, style }
+    Self { text, font_name, layout, style, confirm: None, tooltip: None }
   }
   #[inline]
   pub const fn from_text(text: &'static str) -> Self {
@@ -38,8 +45,8 @@ impl ActionDef {
     Self::new(text, None, ActionLayout::TableRow, ActionStyle::Primary)
   }
   #[inline]
-  pub const fn from_table_row_icon(icon: &'static str, font_name: &'static str) -> Self {
-    Self::new(icon, Some(font_name), ActionLayout::TableRowIcon, ActionStyle::Primary)
+  pub const fn from_table_row_icon(icon: Icon) -> Self {
+    Self::new(icon.glyph(), Some(Icon::FONT_NAME), ActionLayout::TableRowIcon, ActionStyle::Primary)
   }
 
   #[inline]
@@ -67,6 +74,20 @@ impl ActionDef {
   pub const fn with_success_style(self) -> Self { self.with_style(ActionStyle::Success) }
   #[inline]
   pub const fn with_danger_style(self) -> Self { self.with_style(ActionStyle::Danger) }
+
+  /// Requires the action to be confirmed by showing `message` before its request is sent.
+  #[inline]
+  pub const fn with_confirm(mut self, message: &'static str) -> Self {
+    self.confirm = Some(message);
+    self
+  }
+
+  /// Shows `tooltip` when hovering over the action.
+  #[inline]
+  pub const fn with_tooltip(mut self, tooltip: &'static str) -> Self {
+    self.tooltip = Some(tooltip);
+    self
+  }
 }
 
 pub trait Action {
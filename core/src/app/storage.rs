@@ -1,23 +1,45 @@
 use std::fs::{create_dir_all, File, OpenOptions};
 use std::io;
-use std::io::BufWriter;
+use std::io::{BufRead, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 
 use directories::ProjectDirs;
+#[cfg(feature = "app_storage_json")]
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
 
 #[derive(Default, Clone, Debug)]
 pub struct Storage {
   project_directories: Option<ProjectDirs>,
+  directory: Option<PathBuf>,
+  compress: bool,
 }
 impl Storage {
   pub fn new(application: &str) -> Self {
     let project_directories = ProjectDirs::from("", "ATT", application);
-    Self { project_directories }
+    Self { project_directories, directory: None, compress: false }
+  }
+  /// Creates storage that stores files of all [kinds](DirectoryKind) directly in `directory`, instead of in the project
+  /// directories.
+  pub fn from_directory(directory: impl Into<PathBuf>) -> Self {
+    Self { project_directories: None, directory: Some(directory.into()), compress: false }
   }
 
   pub fn project_directories(&self) -> Option<&ProjectDirs> {
     self.project_directories.as_ref()
   }
+
+  /// Sets whether serialized files are gzip compressed. Compressed files are always detected and decompressed when
+  /// deserializing, regardless of this setting.
+  #[inline]
+  pub fn with_compress(mut self, compress: bool) -> Self {
+    self.compress = compress;
+    self
+  }
+  /// Sets whether serialized files are gzip compressed.
+  #[inline]
+  pub fn set_compress(&mut self, compress: bool) {
+    self.compress = compress;
+  }
 }
 
 pub enum DirectoryKind {
@@ -27,7 +49,9 @@ pub enum DirectoryKind {
 }
 impl Storage {
   pub fn directory(&self, kind: DirectoryKind) -> Option<&Path> {
-    if let Some(project_directories) = &self.project_directories {
+    if let Some(directory) = &self.directory {
+      Some(directory)
+    } else if let Some(project_directories) = &self.project_directories {
       let path = match kind {
         DirectoryKind::Data => project_directories.data_dir(),
         DirectoryKind::LocalData => project_directories.data_local_dir(),
@@ -60,10 +84,73 @@ impl Storage {
   pub fn cache_file(&self, file_path: impl AsRef<Path>) -> Option<PathBuf> {
     self.file(DirectoryKind::Cache, file_path)
   }
+
+  /// Creates a buffered writer for a new or truncated file, creating its parent directories if needed. Returns `None`
+  /// if there is no directory for `directory_kind`.
+  pub fn create_file(&self, directory_kind: DirectoryKind, file_name: impl AsRef<Path>) -> Result<Option<BufWriter<File>>, io::Error> {
+    let Some(file_path) = self.file(directory_kind, file_name) else {
+      return Ok(None);
+    };
+    if let Some(parent) = file_path.parent() {
+      create_dir_all(parent)?;
+    }
+    Ok(Some(BufWriter::new(File::create(file_path)?)))
+  }
+
+  /// Writes a file with `write`, replacing it atomically like [write_file_atomically](Self::write_file_atomically).
+  /// Returns the path of the written file, or `None` if there is no directory for `directory_kind`.
+  pub fn write_file(
+    &self,
+    directory_kind: DirectoryKind,
+    file_name: impl AsRef<Path>,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<(), io::Error>,
+  ) -> Result<Option<PathBuf>, io::Error> {
+    let Some(file_path) = self.file(directory_kind, file_name) else {
+      return Ok(None);
+    };
+    Self::write_file_atomically(&file_path, write)?;
+    Ok(Some(file_path))
+  }
+
+  /// Writes the file at `file_path` with `write`, creating its parent directories if needed. The file is first written
+  /// to a temporary file next to it and synced to disk, which then replaces the file, so that the file is not corrupted
+  /// when writing is interrupted, for example by a crash.
+  fn write_file_atomically(
+    file_path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<(), io::Error>,
+  ) -> Result<(), io::Error> {
+    if let Some(parent) = file_path.parent() {
+      create_dir_all(parent)?;
+    }
+
+    let mut temp_file_path = file_path.to_path_buf().into_os_string();
+    temp_file_path.push(".tmp");
+    let temp_file_path = PathBuf::from(temp_file_path);
+    let result = File::create(&temp_file_path).and_then(|file| {
+      let mut writer = BufWriter::new(file);
+      write(&mut writer)?;
+      writer.into_inner().map_err(|e| e.into_error())?.sync_all()
+    });
+    if let Err(e) = result {
+      let _ = std::fs::remove_file(&temp_file_path);
+      return Err(e);
+    }
+    std::fs::rename(&temp_file_path, file_path)
+  }
+
+  /// Removes a file, doing nothing if it does not exist.
+  pub fn remove_file(&self, directory_kind: DirectoryKind, file_name: impl AsRef<Path>) -> Result<(), io::Error> {
+    match self.file(directory_kind, file_name).map(std::fs::remove_file) {
+      Some(Err(e)) if e.kind() != io::ErrorKind::NotFound => Err(e),
+      _ => Ok(()),
+    }
+  }
 }
 
 #[cfg(feature = "app_storage_json")]
 impl Storage {
+  /// Deserializes a JSON file, returning `None` if it does not exist. If the file fails to deserialize, falls back to
+  /// the most recent valid [backup](Self::serialize_json_file_with_backup) if there is one.
   pub fn deserialize_json_file<T: serde::de::DeserializeOwned>(
     &self,
     directory_kind: DirectoryKind,
@@ -73,8 +160,19 @@ impl Storage {
 
     let mut open_options = OpenOptions::new();
     open_options.read(true);
-    let file_opt = Self::open_file_opt(file_path, open_options)?;
-    let result = file_opt.map(|file| serde_json::from_reader(io::BufReader::new(file))).transpose();
+    let file_opt = Self::open_file_opt(file_path.as_ref(), open_options)?;
+    let reader_opt = match file_opt.map(Self::decompressing_reader).transpose() {
+      Err(e) => return file_path.as_deref()
+        .and_then(|file_path| Self::deserialize_json_backup(file_path, Self::read_json))
+        .map_or(Err(e), |v| Ok(Some(v))),
+      Ok(reader_opt) => reader_opt,
+    };
+    let result = reader_opt.map(serde_json::from_reader).transpose();
+    if result.is_err() {
+      if let Some(value) = file_path.as_deref().and_then(|file_path| Self::deserialize_json_backup(file_path, Self::read_json)) {
+        return Ok(Some(value));
+      }
+    }
     if let Err(cause) = &result {
       if cause.classify() == serde_json::error::Category::Data {
         tracing::error!(%cause, "failed to deserialize JSON due to data format changes; returning None");
@@ -83,11 +181,186 @@ impl Storage {
     }
     Ok(result?)
   }
+  /// Deserializes a JSON file like [deserialize_json_file](Self::deserialize_json_file), but instead of failing when
+  /// the file is corrupt, moves it to a backup file next to it and returns the most recent valid
+  /// [backup](Self::serialize_json_file_with_backup), or `None` if there is none.
+  pub fn deserialize_json_file_or_backup<T: serde::de::DeserializeOwned>(
+    &self,
+    directory_kind: DirectoryKind,
+    file_name: impl AsRef<Path>
+  ) -> Result<Option<T>, io::Error> {
+    let Some(file_path) = self.file(directory_kind, file_name) else {
+      return Ok(None);
+    };
+    Self::deserialize_json_file_or_backup_at(&file_path, Self::read_json)
+  }
+  /// Deserializes a JSON file like [deserialize_json_file_or_backup](Self::deserialize_json_file_or_backup), but
+  /// deserializes it into a JSON value first, which is then converted with `convert`, for example to migrate an older
+  /// layout. A file that fails to convert is handled like a corrupt file.
+  pub fn deserialize_json_file_or_backup_with<T>(
+    &self,
+    directory_kind: DirectoryKind,
+    file_name: impl AsRef<Path>,
+    convert: impl Fn(serde_json::Value) -> Result<T, serde_json::Error>,
+  ) -> Result<Option<T>, io::Error> {
+    let Some(file_path) = self.file(directory_kind, file_name) else {
+      return Ok(None);
+    };
+    Self::deserialize_json_file_or_backup_at(&file_path, |reader| Ok(convert(Self::read_json(reader)?)?))
+  }
+
+  fn deserialize_json_file_or_backup_at<T>(
+    file_path: &Path,
+    read: impl Fn(Box<dyn Read>) -> Result<T, io::Error>,
+  ) -> Result<Option<T>, io::Error> {
+    let mut open_options = OpenOptions::new();
+    open_options.read(true);
+    let Some(file) = Self::open_file_opt(Some(file_path), open_options)? else {
+      return Ok(None);
+    };
+    let result = Self::decompressing_reader(file).and_then(&read);
+    match result {
+      Ok(value) => Ok(Some(value)),
+      Err(e) if matches!(e.kind(), io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput | io::ErrorKind::UnexpectedEof) => {
+        let mut backup_file_path = file_path.to_path_buf().into_os_string();
+        backup_file_path.push(format!(".corrupt-{}", chrono::Utc::now().format("%Y%m%d%H%M%S")));
+        std::fs::rename(file_path, &backup_file_path)?;
+        tracing::warn!(cause = %e, "failed to deserialize corrupt JSON file '{}'; moved it to '{}'", file_path.display(), Path::new(&backup_file_path).display());
+        Ok(Self::deserialize_json_backup(file_path, read))
+      }
+      Err(e) => Err(e),
+    }
+  }
+  /// Serializes `value` as JSON to a file. The JSON is first written to a temporary file next to it, which then replaces
+  /// the file, so that the file is not corrupted when writing is interrupted, for example by a crash.
   pub fn serialize_json_file<T: serde::Serialize>(
     &self,
     directory_kind: DirectoryKind,
     file_name: impl AsRef<Path>,
     value: &T
+  ) -> Result<(), io::Error> {
+    let Some(file_path) = self.file(directory_kind, file_name) else {
+      return Ok(());
+    };
+    self.serialize_json_file_atomically(&file_path, value)
+  }
+  /// Serializes `value` as JSON to a file like [serialize_json_file](Self::serialize_json_file), but first copies the
+  /// existing file to a backup file next to it, keeping up to `backup_count` backups. The most recent backup is named
+  /// `<file_name>.bak`, older backups are named `<file_name>.bak.1`, `<file_name>.bak.2`, and so on.
+  ///
+  /// When the file fails to deserialize, [deserialize_json_file](Self::deserialize_json_file) falls back to the most
+  /// recent valid backup.
+  pub fn serialize_json_file_with_backup<T: serde::Serialize>(
+    &self,
+    directory_kind: DirectoryKind,
+    file_name: impl AsRef<Path>,
+    value: &T,
+    backup_count: usize,
+  ) -> Result<(), io::Error> {
+    let Some(file_path) = self.file(directory_kind, file_name) else {
+      return Ok(());
+    };
+    if backup_count > 0 && file_path.exists() {
+      for index in (1..backup_count).rev() {
+        let backup_file_path = Self::backup_file_path(&file_path, index - 1);
+        if backup_file_path.exists() {
+          std::fs::rename(&backup_file_path, Self::backup_file_path(&file_path, index))?;
+        }
+      }
+      std::fs::copy(&file_path, Self::backup_file_path(&file_path, 0))?;
+    }
+    self.serialize_json_file_atomically(&file_path, value)
+  }
+
+  fn serialize_json_file_atomically<T: serde::Serialize>(&self, file_path: &Path, value: &T) -> Result<(), io::Error> {
+    Self::write_file_atomically(file_path, |writer| {
+      if self.compress {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        serde_json::to_writer(&mut encoder, value)?;
+        encoder.finish()?;
+      } else {
+        serde_json::to_writer(writer, value)?;
+      }
+      Ok(())
+    })
+  }
+
+  /// Gets the path of backup `index` of the file at `file_path`, where backup `0` is the most recent.
+  fn backup_file_path(file_path: &Path, index: usize) -> PathBuf {
+    let mut backup_file_path = file_path.to_path_buf().into_os_string();
+    backup_file_path.push(".bak");
+    if index > 0 {
+      backup_file_path.push(format!(".{index}"));
+    }
+    backup_file_path.into()
+  }
+  /// Deserializes the most recent valid backup of the file at `file_path` with `read`, or returns `None` if there is
+  /// none.
+  fn deserialize_json_backup<T>(file_path: &Path, read: impl Fn(Box<dyn Read>) -> Result<T, io::Error>) -> Option<T> {
+    for index in 0.. {
+      let backup_file_path = Self::backup_file_path(file_path, index);
+      let Ok(file) = File::open(&backup_file_path) else {
+        break;
+      };
+      match Self::decompressing_reader(file).and_then(&read) {
+        Ok(value) => {
+          tracing::warn!("failed to deserialize JSON file '{}'; falling back to backup '{}'", file_path.display(), backup_file_path.display());
+          return Some(value);
+        }
+        Err(cause) => tracing::warn!(%cause, "failed to deserialize JSON backup file '{}'; trying older backup", backup_file_path.display()),
+      }
+    }
+    None
+  }
+
+  #[inline]
+  fn read_json<T: serde::de::DeserializeOwned>(reader: Box<dyn Read>) -> Result<T, io::Error> {
+    Ok(serde_json::from_reader(reader)?)
+  }
+  /// Creates a buffered reader for `file` that transparently decompresses it if it starts with the gzip magic bytes.
+  fn decompressing_reader(file: File) -> Result<Box<dyn Read>, io::Error> {
+    const GZIP_MAGIC_BYTES: [u8; 2] = [0x1f, 0x8b];
+    let mut reader = io::BufReader::new(file);
+    let reader: Box<dyn Read> = if reader.fill_buf()?.starts_with(&GZIP_MAGIC_BYTES) {
+      Box::new(io::BufReader::new(GzDecoder::new(reader)))
+    } else {
+      Box::new(reader)
+    };
+    Ok(reader)
+  }
+
+  /// Deserializes all JSON values from a file with one JSON value per line. Returns `None` if the file does not exist.
+  /// Stops at the first line that fails to deserialize, which can happen when appending was interrupted.
+  pub fn deserialize_json_lines_file<T: serde::de::DeserializeOwned>(
+    &self,
+    directory_kind: DirectoryKind,
+    file_name: impl AsRef<Path>
+  ) -> Result<Option<Vec<T>>, io::Error> {
+    let file_path = self.file(directory_kind, file_name);
+
+    let mut open_options = OpenOptions::new();
+    open_options.read(true);
+    let Some(file) = Self::open_file_opt(file_path, open_options)? else {
+      return Ok(None);
+    };
+    let mut values = Vec::new();
+    for line in io::BufReader::new(file).lines() {
+      match serde_json::from_str(&line?) {
+        Ok(value) => values.push(value),
+        Err(cause) => {
+          tracing::error!(%cause, "failed to deserialize JSON line; ignoring it and all subsequent lines");
+          break;
+        }
+      }
+    }
+    Ok(Some(values))
+  }
+  /// Serializes `values` as JSON, appending them to a file with one JSON value per line.
+  pub fn append_json_lines_file<'v, T: serde::Serialize + 'v>(
+    &self,
+    directory_kind: DirectoryKind,
+    file_name: impl AsRef<Path>,
+    values: impl IntoIterator<Item=&'v T>
   ) -> Result<(), io::Error> {
     let file_path = self.file(directory_kind, file_name);
     if let Some(parent) = file_path.as_ref().and_then(|p| p.parent()) {
@@ -95,9 +368,15 @@ impl Storage {
     }
 
     let mut open_options = OpenOptions::new();
-    open_options.write(true).truncate(true).create(true);
-    let file_opt = Self::open_file_opt(file_path, open_options)?;
-    file_opt.map(|file| serde_json::to_writer(BufWriter::new(file), value)).transpose()?;
+    open_options.append(true).create(true);
+    if let Some(file) = Self::open_file_opt(file_path, open_options)? {
+      let mut writer = BufWriter::new(file);
+      for value in values {
+        serde_json::to_writer(&mut writer, value)?;
+        writer.write_all(b"\n")?;
+      }
+      writer.flush()?;
+    }
     Ok(())
   }
 
@@ -108,3 +387,235 @@ impl Storage {
     }).transpose()
   }
 }
+
+#[cfg(feature = "app_storage_toml")]
+impl Storage {
+  /// Deserializes a TOML file, returning `None` if it does not exist.
+  pub fn deserialize_toml_file<T: serde::de::DeserializeOwned>(
+    &self,
+    directory_kind: DirectoryKind,
+    file_name: impl AsRef<Path>
+  ) -> Result<Option<T>, io::Error> {
+    let Some(file_path) = self.file(directory_kind, file_name) else {
+      return Ok(None);
+    };
+    let text = match std::fs::read_to_string(file_path) {
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+      result => result?,
+    };
+    let value = toml::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(Some(value))
+  }
+  /// Serializes `value` as TOML to a file. The file is replaced atomically like
+  /// [serialize_json_file](Self::serialize_json_file), and is never compressed so that it can be edited by hand.
+  pub fn serialize_toml_file<T: serde::Serialize>(
+    &self,
+    directory_kind: DirectoryKind,
+    file_name: impl AsRef<Path>,
+    value: &T
+  ) -> Result<(), io::Error> {
+    let Some(file_path) = self.file(directory_kind, file_name) else {
+      return Ok(());
+    };
+    let text = toml::to_string_pretty(value).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Self::write_file_atomically(&file_path, |writer| writer.write_all(text.as_bytes()))
+  }
+}
+
+#[cfg(all(test, feature = "app_storage_json"))]
+mod tests {
+  use serde::Deserialize;
+  use tempfile::TempDir;
+
+  use super::*;
+
+  fn storage() -> (TempDir, Storage) {
+    let directory = tempfile::tempdir().unwrap();
+    let storage = Storage::from_directory(directory.path());
+    (directory, storage)
+  }
+
+  fn corrupt_file_count(directory: &Path, file_name: &str) -> usize {
+    let prefix = format!("{file_name}.corrupt-");
+    std::fs::read_dir(directory).unwrap()
+      .filter(|entry| entry.as_ref().unwrap().file_name().to_string_lossy().starts_with(&prefix))
+      .count()
+  }
+
+  #[test]
+  fn failed_conversion_backs_up_file() {
+    #[derive(Deserialize)]
+    struct Data {
+      #[allow(dead_code)]
+      count: u32,
+    }
+
+    let (directory, storage) = storage();
+    storage.serialize_json_file(DirectoryKind::Data, "data.json", &serde_json::json!({ "count": "many" })).unwrap();
+
+    let data: Option<Data> = storage
+      .deserialize_json_file_or_backup_with(DirectoryKind::Data, "data.json", serde_json::from_value)
+      .unwrap();
+    assert!(data.is_none());
+    assert!(!directory.path().join("data.json").exists());
+    assert_eq!(corrupt_file_count(directory.path(), "data.json"), 1);
+  }
+
+  #[test]
+  fn uncompressed_round_trip() {
+    let (directory, storage) = storage();
+    let value = serde_json::json!({ "name": "serde", "downloads": 1234 });
+    storage.serialize_json_file(DirectoryKind::Data, "data.json", &value).unwrap();
+
+    let text = std::fs::read_to_string(directory.path().join("data.json")).unwrap();
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&text).unwrap(), value);
+    let deserialized: Option<serde_json::Value> = storage.deserialize_json_file(DirectoryKind::Data, "data.json").unwrap();
+    assert_eq!(deserialized, Some(value));
+  }
+
+  #[test]
+  fn compressed_round_trip() {
+    let (directory, storage) = storage();
+    let storage = storage.with_compress(true);
+    let value = serde_json::json!({ "name": "serde", "downloads": 1234 });
+    storage.serialize_json_file(DirectoryKind::Data, "data.json", &value).unwrap();
+
+    let bytes = std::fs::read(directory.path().join("data.json")).unwrap();
+    assert!(bytes.starts_with(&[0x1f, 0x8b]));
+    let deserialized: Option<serde_json::Value> = storage.deserialize_json_file(DirectoryKind::Data, "data.json").unwrap();
+    assert_eq!(deserialized, Some(value.clone()));
+    // Compressed files are detected when reading, regardless of whether compression is enabled.
+    let deserialized: Option<serde_json::Value> = storage.with_compress(false)
+      .deserialize_json_file_or_backup(DirectoryKind::Data, "data.json")
+      .unwrap();
+    assert_eq!(deserialized, Some(value));
+  }
+
+  #[test]
+  fn deserialize_missing_file() {
+    let (_directory, storage) = storage();
+    let deserialized: Option<serde_json::Value> = storage.deserialize_json_file(DirectoryKind::Data, "data.json").unwrap();
+    assert_eq!(deserialized, None);
+  }
+
+  #[test]
+  fn corrupt_file_is_backed_up_and_starts_from_default() {
+    let (directory, storage) = storage();
+    std::fs::write(directory.path().join("data.json"), "{not json").unwrap();
+
+    let deserialized: Option<Vec<u32>> = storage.deserialize_json_file_or_backup(DirectoryKind::Data, "data.json").unwrap();
+    assert_eq!(deserialized, None);
+    assert!(!directory.path().join("data.json").exists());
+    assert_eq!(corrupt_file_count(directory.path(), "data.json"), 1);
+  }
+
+  #[test]
+  fn corrupt_file_falls_back_to_backup() {
+    let (directory, storage) = storage();
+    storage.serialize_json_file_with_backup(DirectoryKind::Data, "data.json", &vec![1, 2], 1).unwrap();
+    storage.serialize_json_file_with_backup(DirectoryKind::Data, "data.json", &vec![1, 2, 3], 1).unwrap();
+    std::fs::write(directory.path().join("data.json"), "{not json").unwrap();
+
+    let deserialized: Option<Vec<u32>> = storage.deserialize_json_file_or_backup(DirectoryKind::Data, "data.json").unwrap();
+    assert_eq!(deserialized, Some(vec![1, 2]));
+    assert_eq!(corrupt_file_count(directory.path(), "data.json"), 1);
+  }
+
+  #[test]
+  fn failed_atomic_write_keeps_existing_file() {
+    let (directory, storage) = storage();
+    storage.serialize_json_file(DirectoryKind::Data, "data.json", &vec![1, 2, 3]).unwrap();
+    let file_path = directory.path().join("data.json");
+
+    let result = Storage::write_file_atomically(&file_path, |writer| {
+      writer.write_all(b"[4, 5")?;
+      Err(io::Error::other("interrupted"))
+    });
+    assert!(result.is_err());
+    assert!(!directory.path().join("data.json.tmp").exists());
+    let deserialized: Option<Vec<u32>> = storage.deserialize_json_file(DirectoryKind::Data, "data.json").unwrap();
+    assert_eq!(deserialized, Some(vec![1, 2, 3]));
+  }
+
+  #[test]
+  fn write_file_returns_written_path() {
+    let (directory, storage) = storage();
+    let file_path = storage.write_file(DirectoryKind::Data, "export/data.csv", |writer| writer.write_all(b"a,b\n")).unwrap();
+    assert_eq!(file_path, Some(directory.path().join("export/data.csv")));
+    assert_eq!(std::fs::read_to_string(directory.path().join("export/data.csv")).unwrap(), "a,b\n");
+  }
+
+  #[test]
+  fn backups_are_rotated() {
+    let (directory, storage) = storage();
+    for value in 1..=4 {
+      storage.serialize_json_file_with_backup(DirectoryKind::Data, "data.json", &value, 2).unwrap();
+    }
+
+    let read = |file_name: &str| std::fs::read_to_string(directory.path().join(file_name)).unwrap();
+    assert_eq!(read("data.json"), "4");
+    assert_eq!(read("data.json.bak"), "3");
+    assert_eq!(read("data.json.bak.1"), "2");
+    assert!(!directory.path().join("data.json.bak.2").exists());
+  }
+
+  #[test]
+  fn corrupt_file_falls_back_to_older_backup() {
+    let (directory, storage) = storage();
+    for value in 1..=3 {
+      storage.serialize_json_file_with_backup(DirectoryKind::Data, "data.json", &value, 2).unwrap();
+    }
+    std::fs::write(directory.path().join("data.json"), "{not json").unwrap();
+    std::fs::write(directory.path().join("data.json.bak"), "{not json").unwrap();
+
+    let deserialized: Option<u32> = storage.deserialize_json_file(DirectoryKind::Data, "data.json").unwrap();
+    assert_eq!(deserialized, Some(1));
+  }
+}
+
+#[cfg(all(test, feature = "app_storage_toml"))]
+mod toml_tests {
+  use serde::{Deserialize, Serialize};
+
+  use super::*;
+
+  #[derive(Serialize, Deserialize, PartialEq, Debug)]
+  struct Settings {
+    name: String,
+    count: u32,
+    dark_mode: bool,
+    tags: Vec<String>,
+  }
+
+  #[test]
+  fn round_trip() {
+    let directory = tempfile::tempdir().unwrap();
+    // Compression is ignored for TOML files, so that they stay editable by hand.
+    let storage = Storage::from_directory(directory.path()).with_compress(true);
+    let settings = Settings { name: "att".to_string(), count: 3, dark_mode: true, tags: vec!["a".to_string(), "b".to_string()] };
+    storage.serialize_toml_file(DirectoryKind::Data, "settings.toml", &settings).unwrap();
+
+    let text = std::fs::read_to_string(directory.path().join("settings.toml")).unwrap();
+    assert!(text.contains("name = \"att\""));
+    assert!(!directory.path().join("settings.toml.tmp").exists());
+    let deserialized: Option<Settings> = storage.deserialize_toml_file(DirectoryKind::Data, "settings.toml").unwrap();
+    assert_eq!(deserialized, Some(settings));
+  }
+
+  #[test]
+  fn deserialize_missing_file() {
+    let directory = tempfile::tempdir().unwrap();
+    let storage = Storage::from_directory(directory.path());
+    let deserialized: Option<Settings> = storage.deserialize_toml_file(DirectoryKind::Data, "settings.toml").unwrap();
+    assert_eq!(deserialized, None);
+  }
+
+  #[test]
+  fn invalid_file_is_invalid_data() {
+    let directory = tempfile::tempdir().unwrap();
+    let storage = Storage::from_directory(directory.path());
+    std::fs::write(directory.path().join("settings.toml"), "name = ").unwrap();
+    let result: Result<Option<Settings>, _> = storage.deserialize_toml_file(DirectoryKind::Data, "settings.toml");
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+  }
+}
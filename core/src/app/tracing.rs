@@ -1,16 +1,65 @@
 #![allow(dead_code)]
 
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
 
+use tracing::Subscriber;
 use tracing_subscriber::{EnvFilter, Layer};
+use tracing_subscriber::fmt::MakeWriter;
 use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::reload;
 use tracing_subscriber::util::SubscriberInitExt;
 
+/// Format of log lines.
+#[derive(Default, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum LogFormat {
+  /// Human-readable single-line format.
+  #[default]
+  Full,
+  /// Human-readable single-line format that is more compact than [`Full`](Self::Full).
+  Compact,
+  /// Human-readable multi-line format, for development.
+  Pretty,
+  /// Newline-delimited JSON format, for ingestion by log aggregators.
+  #[cfg(feature = "app_tracing_json")]
+  Json,
+}
+
+impl LogFormat {
+  /// Gets the log format from the `ATT_LOG_FORMAT` environment variable, or the default format if the variable is not
+  /// set or not a valid log format.
+  pub fn from_env() -> Self {
+    std::env::var("ATT_LOG_FORMAT").ok()
+      .and_then(|format| format.parse().ok())
+      .unwrap_or_default()
+  }
+}
+
+impl FromStr for LogFormat {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let format = match s.trim().to_ascii_lowercase().as_str() {
+      "full" => Self::Full,
+      "compact" => Self::Compact,
+      "pretty" => Self::Pretty,
+      #[cfg(feature = "app_tracing_json")]
+      "json" => Self::Json,
+      _ => return Err(()),
+    };
+    Ok(format)
+  }
+}
+
 #[derive(Default)]
 pub struct AppTracingBuilder {
   console_filter: Option<EnvFilter>,
   log_file_path: Option<PathBuf>,
   file_filter: Option<EnvFilter>,
+  format: Option<LogFormat>,
+  directives: Vec<String>,
 }
 impl AppTracingBuilder {
   pub fn with_console_filter(mut self, console_filter: EnvFilter) -> Self {
@@ -18,6 +67,27 @@ impl AppTracingBuilder {
     self
   }
 
+  /// Sets the log `format`. If not set, the format is taken from the `ATT_LOG_FORMAT` environment variable.
+  pub fn with_format(mut self, format: LogFormat) -> Self {
+    self.format = Some(format);
+    self
+  }
+  /// Sets whether to log in the [JSON format](LogFormat::Json) if `json` is `true`, or in the default human-readable
+  /// format if `json` is `false`. The format applies to both the console and the log file.
+  #[cfg(feature = "app_tracing_json")]
+  pub fn with_json(self, json: bool) -> Self {
+    self.with_format(if json { LogFormat::Json } else { LogFormat::default() })
+  }
+
+  /// Adds comma-separated `directives` (e.g., `att_server=trace,hyper=info`) to both the console and file filters,
+  /// overriding levels of existing directives for the same targets. Invalid directives are ignored.
+  ///
+  /// Directives from the `RUST_LOG` environment variable are always added after these directives.
+  pub fn with_directives(mut self, directives: impl Into<String>) -> Self {
+    self.directives.push(directives.into());
+    self
+  }
+
   pub fn with_log_file_path(mut self, log_file_path: impl ToOwned<Owned=PathBuf>) -> Self {
     self.log_file_path = Some(log_file_path.to_owned());
     self
@@ -43,20 +113,78 @@ impl AppTracingBuilder {
       }};
     }
 
-    let console_filter = self.console_filter.unwrap_or_else(|| filter!("CONSOLE_LOG"));
+    let mut directives = self.directives;
+    if let Ok(rust_log) = std::env::var("RUST_LOG") {
+      directives.push(rust_log);
+    }
+    let add_directives = |mut filter: EnvFilter| {
+      for directive in directives.iter().flat_map(|d| d.split(',')).filter(|d| !d.trim().is_empty()) {
+        if let Ok(directive) = directive.trim().parse() {
+          filter = filter.add_directive(directive);
+        }
+      }
+      filter
+    };
+
+    let console_filter = add_directives(self.console_filter.unwrap_or_else(|| filter!("CONSOLE_LOG")));
+    let format = self.format.unwrap_or_else(LogFormat::from_env);
 
     #[cfg(not(target_arch = "wasm32"))] {
-      let file = self.log_file_path.as_ref().map(|p| (p.as_ref(), self.file_filter.unwrap_or_else(|| filter!("FILE_LOG"))));
-      AppTracing::new(console_filter, file)
+      let file_filter = self.file_filter;
+      let file = self.log_file_path.as_ref()
+        .map(|p| (p.as_ref(), add_directives(file_filter.unwrap_or_else(|| filter!("FILE_LOG")))));
+      AppTracing::new(console_filter, file, format)
     }
     #[cfg(target_arch = "wasm32")] {
+      let _ = format;
       AppTracing::new_wasm(console_filter)
     }
   }
 }
 
+/// Creates a formatting layer with `format` that writes to `writer`, with ANSI colors if `ansi` is `true`.
+#[cfg(not(target_arch = "wasm32"))]
+fn fmt_layer<S, W>(format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<S> + Send + Sync> where
+  S: Subscriber + for<'a> LookupSpan<'a>,
+  W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+  let layer = tracing_subscriber::fmt::layer()
+    .with_writer(writer)
+    .with_ansi(ansi);
+  match format {
+    LogFormat::Full => layer.boxed(),
+    LogFormat::Compact => layer.compact().boxed(),
+    LogFormat::Pretty => layer.pretty().boxed(),
+    #[cfg(feature = "app_tracing_json")]
+    LogFormat::Json => layer.json().boxed(),
+  }
+}
+
 pub struct AppTracing {
   _file_tracing: FileTracing,
+  filter_reloader: FilterReloader,
+}
+impl AppTracing {
+  /// Gets a [`FilterReloader`] for changing the filters of this tracing setup at runtime.
+  #[inline]
+  pub fn filter_reloader(&self) -> FilterReloader { self.filter_reloader.clone() }
+}
+
+/// Replaces the console and file filters of an [`AppTracing`] at runtime.
+#[derive(Clone)]
+pub struct FilterReloader(Arc<dyn Fn(&str) + Send + Sync>);
+impl FilterReloader {
+  /// Creates a filter reloader that calls `reload` with the directives to reload filters with.
+  #[inline]
+  pub fn new(reload: impl Fn(&str) + Send + Sync + 'static) -> Self {
+    Self(Arc::new(reload))
+  }
+
+  /// Replaces the console and file filters with filters created from comma-separated `directives` (e.g.,
+  /// `att_server=trace,hyper=info`). Invalid directives are ignored.
+  pub fn reload(&self, directives: &str) {
+    (self.0)(directives)
+  }
 }
 #[cfg(feature = "app_tracing_file")]
 #[derive(Default)]
@@ -71,17 +199,19 @@ impl AppTracing {
   fn new(
     console_filter: EnvFilter,
     file: Option<(&std::path::Path, EnvFilter)>,
+    format: LogFormat,
   ) -> Self {
     use std::fs::{create_dir_all, File};
     use std::io::{self, BufWriter};
 
+    let (console_filter, console_filter_handle) = reload::Layer::new(console_filter);
     let layered = tracing_subscriber::registry();
     let layered = layered.with(
-      tracing_subscriber::fmt::layer()
-        .with_writer(io::stderr)
+      fmt_layer(format, io::stderr, true)
         .with_filter(console_filter)
     );
 
+    let mut file_filter_handle = None;
     let _file_tracing = if let Some((file_path, filter)) = file {
       let result = (|| {
         if let Some(parent) = file_path.parent() {
@@ -98,10 +228,10 @@ impl AppTracing {
         Ok(log_file) => {
           let writer = BufWriter::new(log_file);
           let (non_blocking, guard) = tracing_appender::non_blocking(writer);
+          let (filter, handle) = reload::Layer::new(filter);
+          file_filter_handle = Some(handle);
           let layered = layered.with(
-            tracing_subscriber::fmt::layer()
-              .with_writer(non_blocking)
-              .with_ansi(false)
+            fmt_layer(format, non_blocking, false)
               .with_filter(filter)
           );
           layered.init();
@@ -113,7 +243,18 @@ impl AppTracing {
       FileTracing::default()
     };
 
-    Self { _file_tracing }
+    let filter_reloader = FilterReloader(Arc::new(move |directives: &str| {
+      if let Err(cause) = console_filter_handle.reload(EnvFilter::new(directives)) {
+        tracing::error!(%cause, "failed to reload console log filter: {cause:?}");
+      }
+      if let Some(file_filter_handle) = &file_filter_handle {
+        if let Err(cause) = file_filter_handle.reload(EnvFilter::new(directives)) {
+          tracing::error!(%cause, "failed to reload file log filter: {cause:?}");
+        }
+      }
+    }));
+
+    Self { _file_tracing, filter_reloader }
   }
 
   #[cfg(target_arch = "wasm32")]
@@ -131,7 +272,90 @@ impl AppTracing {
     layered.init();
 
     let _file_tracing = FileTracing::default();
-    Self { _file_tracing }
+    let filter_reloader = FilterReloader(Arc::new(|_: &str| {})); // Reloading filters is not supported on the web.
+    Self { _file_tracing, filter_reloader }
   }
 }
 
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+  use std::io;
+  use std::sync::Mutex;
+
+  use super::*;
+
+  /// In-memory writer that can be cloned to read back what was written.
+  #[derive(Default, Clone)]
+  struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+  impl SharedBuffer {
+    fn lines(&self) -> Vec<String> {
+      String::from_utf8(self.0.lock().unwrap().clone()).unwrap().lines().map(str::to_string).collect()
+    }
+  }
+  impl io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      self.0.lock().unwrap().write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+  }
+  impl<'w> MakeWriter<'w> for SharedBuffer {
+    type Writer = Self;
+    fn make_writer(&'w self) -> Self::Writer { self.clone() }
+  }
+
+  #[test]
+  fn log_format_from_str() {
+    assert_eq!("full".parse(), Ok(LogFormat::Full));
+    assert_eq!("compact".parse(), Ok(LogFormat::Compact));
+    assert_eq!(" Pretty ".parse(), Ok(LogFormat::Pretty));
+    #[cfg(feature = "app_tracing_json")]
+    assert_eq!("JSON".parse(), Ok(LogFormat::Json));
+    assert_eq!("yaml".parse::<LogFormat>(), Err(()));
+    assert_eq!("".parse::<LogFormat>(), Err(()));
+  }
+
+  #[cfg(feature = "app_tracing_json")]
+  #[test]
+  fn json_fmt_layer_writes_json_lines() {
+    let buffer = SharedBuffer::default();
+    let subscriber = tracing_subscriber::registry().with(fmt_layer(LogFormat::Json, buffer.clone(), false));
+    tracing::subscriber::with_default(subscriber, || {
+      tracing::info!(crate_id = 42, "refreshed crate");
+      tracing::warn!("rate limited");
+    });
+
+    let lines: Vec<serde_json::Value> = buffer.lines().iter()
+      .map(|line| serde_json::from_str(line).unwrap())
+      .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["level"], "INFO");
+    assert_eq!(lines[0]["fields"]["message"], "refreshed crate");
+    assert_eq!(lines[0]["fields"]["crate_id"], 42);
+    assert_eq!(lines[1]["level"], "WARN");
+  }
+
+  /// Initializes the global subscriber, so this must be the only test that builds an [`AppTracing`].
+  #[cfg(all(feature = "app_tracing_json", feature = "app_tracing_file"))]
+  #[test]
+  fn json_log_file_contains_json_lines() {
+    let directory = tempfile::tempdir().unwrap();
+    let log_file_path = directory.path().join("log").join("log.txt");
+    let app_tracing = AppTracingBuilder::default()
+      .with_json(true)
+      .with_console_filter(EnvFilter::new("off"))
+      .with_file_filter(EnvFilter::new("info"))
+      .with_log_file_path_opt(Some(log_file_path.clone()))
+      .build();
+    tracing::info!(crate_id = 42, "refreshed crate");
+    drop(app_tracing); // Flushes the non-blocking file writer.
+
+    let text = std::fs::read_to_string(&log_file_path).unwrap();
+    let lines: Vec<serde_json::Value> = text.lines()
+      .map(|line| serde_json::from_str(line).unwrap())
+      .collect();
+    let line = lines.iter().find(|line| line["fields"]["message"] == "refreshed crate").unwrap();
+    assert_eq!(line["level"], "INFO");
+    assert_eq!(line["fields"]["crate_id"], 42);
+  }
+}
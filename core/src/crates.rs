@@ -1,4 +1,5 @@
-use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -7,8 +8,54 @@ use thiserror::Error;
 #[cfg(feature = "diesel")]
 use {crate::schema, diesel::{pg::Pg, prelude::*}};
 
-use crate::query::{Facet, FacetDef, FacetRef, FacetType, Query};
-use crate::table::{AsTableRow, ColumnDef};
+use crate::query::{Facet, FacetDef, FacetRef, FacetType, Query, Sort, SortField};
+use crate::table::{AsTableRow, CellFormat, CellValue, ColumnDef, KeyedTableRow};
+
+/// Name of the crates.io registry, matching the name Cargo uses for it.
+pub const CRATES_IO_REGISTRY: &str = "crates-io";
+
+/// ID of a [`Crate`]. Serialized as a bare integer, and stored as an `integer` in the database.
+#[cfg_attr(feature = "diesel", derive(diesel::expression::AsExpression, diesel::deserialize::FromSqlRow), diesel(sql_type = diesel::sql_types::Int4))]
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+#[repr(transparent)]
+pub struct CrateId(pub i32);
+
+impl From<i32> for CrateId {
+  #[inline]
+  fn from(id: i32) -> Self { Self(id) }
+}
+impl From<CrateId> for i32 {
+  #[inline]
+  fn from(id: CrateId) -> Self { id.0 }
+}
+impl Display for CrateId {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result { self.0.fmt(f) }
+}
+
+#[cfg(feature = "diesel")]
+mod crate_id_sql {
+  use diesel::deserialize::{self, FromSql};
+  use diesel::pg::{Pg, PgValue};
+  use diesel::serialize::{self, Output, ToSql};
+  use diesel::sql_types::Int4;
+
+  use super::CrateId;
+
+  impl ToSql<Int4, Pg> for CrateId {
+    #[inline]
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+      <i32 as ToSql<Int4, Pg>>::to_sql(&self.0, out)
+    }
+  }
+  impl FromSql<Int4, Pg> for CrateId {
+    #[inline]
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+      <i32 as FromSql<Int4, Pg>>::from_sql(bytes).map(CrateId)
+    }
+  }
+}
 
 /// A Rust crate.
 #[cfg_attr(feature = "diesel",
@@ -19,7 +66,7 @@ use crate::table::{AsTableRow, ColumnDef};
 )]
 #[derive(Default, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 pub struct Crate {
-  pub id: i32,
+  pub id: CrateId,
   pub name: String,
   pub updated_at: DateTime<Utc>,
   pub created_at: DateTime<Utc>,
@@ -31,7 +78,12 @@ pub struct Crate {
   pub downloads: i64,
 
   pub default_version_id: i32,
+
+  /// Name of the registry this crate came from, [`CRATES_IO_REGISTRY`] for crates.io.
+  #[serde(default = "crates_io_registry")]
+  pub registry: String,
 }
+fn crates_io_registry() -> String { CRATES_IO_REGISTRY.to_string() }
 
 /// A version of a crate.
 #[cfg_attr(feature = "diesel",
@@ -44,7 +96,7 @@ pub struct Crate {
 #[derive(Default, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 pub struct CrateVersion {
   pub id: i32,
-  pub crate_id: i32,
+  pub crate_id: CrateId,
   pub number: String,
 }
 
@@ -58,44 +110,234 @@ pub struct FullCrate {
   pub default_version: CrateVersion,
 }
 
+impl Crate {
+  /// Gets the URL to the [releases page](releases_url) of this crate's repository, or `None` if this crate has no
+  /// repository.
+  pub fn releases_url(&self) -> Option<String> {
+    self.repository.as_deref()
+      .filter(|repository| !repository.trim().is_empty())
+      .map(releases_url)
+  }
+
+  /// Returns the number of optional data fields that are set.
+  fn data_count(&self) -> usize {
+    [!self.description.is_empty(), self.homepage.is_some(), self.readme.is_some(), self.repository.is_some()]
+      .into_iter()
+      .filter(|b| *b)
+      .count()
+  }
+}
+
+/// Creates the URL to the releases page of `repository`, for repositories hosted on GitHub, GitLab, or Codeberg. Falls
+/// back to `repository` itself for repositories on unknown hosts.
+pub fn releases_url(repository: &str) -> String {
+  let repository = repository.trim();
+  let location = repository.strip_prefix("git+").unwrap_or(repository);
+  let location = if let Some(ssh_location) = location.strip_prefix("git@") {
+    ssh_location.replacen(':', "/", 1)
+  } else {
+    location.split_once("://").map_or(location, |(_, location)| location).to_string()
+  };
+
+  let mut segments = location.split(['/', '?', '#']).filter(|segment| !segment.is_empty());
+  let host = segments.next().map(|host| host.strip_prefix("www.").unwrap_or(host).to_ascii_lowercase());
+  let owner = segments.next();
+  let repo = segments.next().map(|repo| repo.strip_suffix(".git").unwrap_or(repo));
+  match (host.as_deref(), owner, repo) {
+    (Some("github.com"), Some(owner), Some(repo)) => format!("https://github.com/{owner}/{repo}/releases"),
+    (Some("gitlab.com"), Some(owner), Some(repo)) => format!("https://gitlab.com/{owner}/{repo}/-/releases"),
+    (Some("codeberg.org"), Some(owner), Some(repo)) => format!("https://codeberg.org/{owner}/{repo}/releases"),
+    _ => repository.to_string(),
+  }
+}
+
+impl FullCrate {
+  /// Returns whether this is a richer record of a crate than `other`: it was updated more recently, or it was updated
+  /// at the same time but has more optional data set.
+  pub fn is_richer_than(&self, other: &FullCrate) -> bool {
+    match self.krate.updated_at.cmp(&other.krate.updated_at) {
+      Ordering::Greater => true,
+      Ordering::Less => false,
+      Ordering::Equal => self.krate.data_count() > other.krate.data_count(),
+    }
+  }
+}
+
 impl AsTableRow for FullCrate {
   const COLUMNS: &'static [ColumnDef] = &[
     ColumnDef::with_default_alignment("Id", 0.5),
     ColumnDef::with_default_alignment("Name", 1.0),
-    ColumnDef::with_default_alignment("Updated At", 1.0),
+    ColumnDef::with_default_alignment("Updated At", 1.0).with_format(CellFormat::RelativeTime),
     ColumnDef::with_default_alignment("Latest Version", 1.0),
-    ColumnDef::with_default_alignment("Downloads", 1.0),
+    ColumnDef::with_default_alignment("Downloads", 1.0).with_format(CellFormat::Count),
     ColumnDef::with_default_alignment("Description", 2.0),
   ];
 
-  fn cell(&self, column_index: u8) -> Option<Cow<str>> {
-    let str = match column_index {
-      0 => Cow::from(format!("{}", self.krate.id)),
-      1 => Cow::from(&self.krate.name),
-      2 => Cow::from(self.krate.updated_at.format("%Y-%m-%d").to_string()),
-      3 => Cow::from(&self.default_version.number),
-      4 => Cow::from(format!("{}", self.krate.downloads)),
-      5 => Cow::from(&self.krate.description),
+  fn cell_value(&self, column_index: u8) -> Option<CellValue> {
+    let value = match column_index {
+      0 => self.krate.id.0.into(),
+      1 => (&self.krate.name).into(),
+      2 => self.krate.updated_at.into(),
+      3 => (&self.default_version.number).into(),
+      4 => self.krate.downloads.into(),
+      5 => (&self.krate.description).into(),
       _ => return None,
     };
-    Some(str)
+    Some(value)
   }
 }
+impl KeyedTableRow for FullCrate {
+  type Key = CrateId;
+
+  #[inline]
+  fn key(&self) -> CrateId { self.krate.id }
+}
 
 
+/// Default number of crates per page of a [`CratesQuery`].
+pub const DEFAULT_PER_PAGE: u32 = 25;
+/// Maximum number of crates per page of a [`CratesQuery`].
+pub const MAX_PER_PAGE: u32 = 100;
+
+/// Field to sort crates by.
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum CratesSort {
+  /// Sort by relevance to the name being searched for, or by ID when not searching by name. Cannot be reversed.
+  #[default]
+  Relevance,
+  Name,
+  Downloads,
+  UpdatedAt,
+}
+
+impl CratesSort {
+  pub const ALL: [Self; 4] = [Self::Relevance, Self::Name, Self::Downloads, Self::UpdatedAt];
+
+  #[inline]
+  pub const fn label(&self) -> &'static str {
+    match self {
+      Self::Relevance => "Relevance",
+      Self::Name => "Name",
+      Self::Downloads => "Downloads",
+      Self::UpdatedAt => "Updated At",
+    }
+  }
+}
+
+impl SortField for CratesSort {
+  const ALL: &'static [Self] = &CratesSort::ALL;
+  #[inline]
+  fn label(&self) -> &'static str { CratesSort::label(self) }
+}
+
 #[derive(Default, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
 pub struct CratesQuery {
   pub followed: Option<bool>,
   pub name: Option<String>,
+  #[serde(default)]
+  pub sort_by: CratesSort,
+  /// Whether to sort in descending order. Ignored when sorting by [`CratesSort::Relevance`].
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub descending: bool,
+  /// Page to get, starting at 1. Defaults to 1.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub page: Option<u32>,
+  /// Number of crates per page. Defaults to [`DEFAULT_PER_PAGE`], and is capped at [`MAX_PER_PAGE`].
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub per_page: Option<u32>,
 }
 
 impl CratesQuery {
   #[inline]
   pub fn from_followed(followed: bool) -> Self { Self { followed: Some(followed), ..Self::default() } }
+
+  /// Gets the page to get, starting at 1.
+  #[inline]
+  pub fn page(&self) -> u32 { self.page.unwrap_or(1).max(1) }
+
+  /// Gets the number of crates per page.
+  #[inline]
+  pub fn per_page(&self) -> u32 { self.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE) }
+
+  /// Returns whether results of this query are paginated. Queries for only followed crates are not paginated unless
+  /// [`page`](Self::page) or [`per_page`](Self::per_page) are set, as the number of followed crates is bounded and
+  /// clients keep all followed crates.
+  #[inline]
+  pub fn is_paginated(&self) -> bool {
+    self.followed != Some(true) || self.page.is_some() || self.per_page.is_some()
+  }
+}
+
+/// Position of a page of crates in all crates matching a [`CratesQuery`].
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub struct PageInfo {
+  /// Page number, starting at 1.
+  pub page: u32,
+  pub per_page: u32,
+  /// Total number of crates matching the query, across all pages.
+  pub total_count: u64,
+}
+
+impl PageInfo {
+  /// Gets the total number of pages, which is at least 1.
+  #[inline]
+  pub fn page_count(&self) -> u32 {
+    let per_page = self.per_page.max(1) as u64;
+    self.total_count.div_ceil(per_page).clamp(1, u32::MAX as u64) as u32
+  }
+
+  #[inline]
+  pub fn has_previous(&self) -> bool { self.page > 1 }
+
+  #[inline]
+  pub fn has_next(&self) -> bool { self.page < self.page_count() }
+}
+
+/// Page of crates matching a [`CratesQuery`].
+#[derive(Default, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CratesPage {
+  pub crates: Vec<FullCrate>,
+  pub page_info: PageInfo,
+}
+
+impl Display for CratesQuery {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match &self.name {
+      Some(name) if !name.is_empty() => write!(f, "{name}")?,
+      _ => f.write_str("All crates")?,
+    }
+    match self.followed {
+      Some(true) => f.write_str(" (followed)"),
+      Some(false) => f.write_str(" (not followed)"),
+      None => Ok(()),
+    }
+  }
+}
+
+/// A crates query saved under a name.
+#[derive(Default, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub struct SavedSearch {
+  pub id: i32,
+  pub name: String,
+  pub query: CratesQuery,
+}
+
+impl Display for SavedSearch {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result { f.write_str(&self.name) }
+}
+
+/// A crates query to save under a name.
+#[derive(Default, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub struct NewSavedSearch {
+  pub name: String,
+  pub query: CratesQuery,
 }
 
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 pub struct CratesQueryConfig {
+  /// Whether to show the followed facet and allow switching between followed and not followed crates, for example
+  /// with [`QueryMessage::SetFollowed`](crate::query::QueryMessage::SetFollowed).
   pub show_followed: bool,
 }
 impl Default for CratesQueryConfig {
@@ -151,6 +393,30 @@ impl Query for CratesQuery {
         .transpose().unwrap_or_else(|f| panic!("facet {:?} at index {} is not a string", f, i)),
       _ => panic!("facet index {} is out of bounds for `CratesQuery`", index),
     }
+    // Results change when facets change, so go back to the first page.
+    self.page = None;
+  }
+
+  #[inline]
+  fn set_followed(&mut self, config: &Self::Config, followed: bool) {
+    if config.show_followed {
+      self.followed = Some(followed);
+      self.page = None;
+    }
+  }
+
+  type SortField = CratesSort;
+
+  #[inline]
+  fn sort(&self) -> Option<Sort<CratesSort>> {
+    Some(Sort::new(self.sort_by, self.descending))
+  }
+
+  #[inline]
+  fn set_sort(&mut self, _config: &Self::Config, sort: Sort<CratesSort>) {
+    self.sort_by = sort.field;
+    self.descending = sort.descending;
+    self.page = None;
   }
 }
 
@@ -161,6 +427,14 @@ pub enum CrateError {
   NotLoggedIn,
   #[error("Crate was not found")]
   NotFound,
+  #[error("Cannot follow more than {0} crates")]
+  FollowLimitReached(u32),
+  #[error("A saved search with that name already exists")]
+  SavedSearchNameTaken,
+  #[error("Server is under maintenance; try again in {0} seconds")]
+  UnderMaintenance(u32),
+  #[error("Server is temporarily unavailable; try again later")]
+  Unavailable,
   #[error("Internal server error")]
   Internal,
 }
@@ -177,8 +451,98 @@ pub mod http_status_code {
       match self {
         Self::NotLoggedIn => StatusCode::FORBIDDEN,
         Self::NotFound => StatusCode::NOT_FOUND,
+        Self::FollowLimitReached(_) => StatusCode::CONFLICT,
+        Self::SavedSearchNameTaken => StatusCode::CONFLICT,
+        Self::UnderMaintenance(_) => StatusCode::SERVICE_UNAVAILABLE,
+        Self::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
         Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
       }
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use crate::query::QueryMessage;
+
+  use super::*;
+
+  #[test]
+  fn releases_url_of_known_hosts() {
+    assert_eq!(releases_url("https://github.com/serde-rs/serde"), "https://github.com/serde-rs/serde/releases");
+    assert_eq!(releases_url("https://github.com/serde-rs/serde/"), "https://github.com/serde-rs/serde/releases");
+    assert_eq!(releases_url("https://github.com/serde-rs/serde.git"), "https://github.com/serde-rs/serde/releases");
+    assert_eq!(releases_url("http://www.github.com/tokio-rs/tokio/tree/master/tokio"), "https://github.com/tokio-rs/tokio/releases");
+    assert_eq!(releases_url("git+https://github.com/rust-lang/regex"), "https://github.com/rust-lang/regex/releases");
+    assert_eq!(releases_url("git@github.com:rust-lang/log.git"), "https://github.com/rust-lang/log/releases");
+    assert_eq!(releases_url(" https://GitHub.com/dtolnay/anyhow "), "https://github.com/dtolnay/anyhow/releases");
+    assert_eq!(releases_url("https://gitlab.com/owner/project"), "https://gitlab.com/owner/project/-/releases");
+    assert_eq!(releases_url("https://codeberg.org/owner/project"), "https://codeberg.org/owner/project/releases");
+  }
+
+  #[test]
+  fn releases_url_falls_back_to_repository() {
+    assert_eq!(releases_url("https://git.example.com/owner/project"), "https://git.example.com/owner/project");
+    assert_eq!(releases_url("https://github.com/serde-rs"), "https://github.com/serde-rs");
+  }
+
+  #[test]
+  fn crate_releases_url() {
+    let krate = |repository: Option<&str>| Crate { repository: repository.map(str::to_string), ..Crate::default() };
+    assert_eq!(krate(Some("https://github.com/serde-rs/serde")).releases_url().as_deref(), Some("https://github.com/serde-rs/serde/releases"));
+    assert_eq!(krate(Some("  ")).releases_url(), None);
+    assert_eq!(krate(None).releases_url(), None);
+  }
+
+  #[test]
+  fn set_followed_is_ignored_when_followed_is_not_shown() {
+    let config = CratesQueryConfig { show_followed: false };
+    let mut query = CratesQuery { page: Some(3), ..CratesQuery::default() };
+    QueryMessage::SetFollowed(true).update_query(&mut query, &config);
+    assert_eq!(query.followed, None);
+    assert_eq!(query.page, Some(3));
+  }
+
+  #[test]
+  fn set_followed_switches_followed_and_resets_page() {
+    let config = CratesQueryConfig { show_followed: true };
+    let mut query = CratesQuery { page: Some(3), ..CratesQuery::default() };
+    QueryMessage::SetFollowed(true).update_query(&mut query, &config);
+    assert_eq!(query.followed, Some(true));
+    assert_eq!(query.page, None);
+    QueryMessage::SetFollowed(false).update_query(&mut query, &config);
+    assert_eq!(query.followed, Some(false));
+  }
+
+  #[test]
+  fn large_crate_id_round_trips() {
+    let id = CrateId(i32::MAX);
+    assert_eq!(CrateId::from(i32::from(id)), id);
+
+    let json = serde_json::to_string(&id).unwrap();
+    assert_eq!(json, i32::MAX.to_string());
+    assert_eq!(serde_json::from_str::<CrateId>(&json).unwrap(), id);
+  }
+
+  #[cfg(feature = "csv")]
+  #[test]
+  fn full_crate_csv() {
+    let krate = Crate {
+      id: CrateId(1),
+      name: "serde".to_string(),
+      updated_at: DateTime::UNIX_EPOCH,
+      downloads: 1_234_567,
+      description: "A serialization framework, for \"Rust\"".to_string(),
+      ..Crate::default()
+    };
+    let default_version = CrateVersion { number: "1.0.0".to_string(), ..CrateVersion::default() };
+    let full_crates = [FullCrate { krate, default_version }];
+
+    let mut csv = Vec::new();
+    crate::table::write_csv(&full_crates, &mut csv).unwrap();
+    assert_eq!(String::from_utf8(csv).unwrap(), "\
+Id,Name,Updated At,Latest Version,Downloads,Description
+1,serde,1970-01-01T00:00:00+00:00,1.0.0,1234567,\"A serialization framework, for \"\"Rust\"\"\"
+");
+  }
+}
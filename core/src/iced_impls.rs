@@ -1,16 +1,18 @@
+use std::hash::Hash;
+
 use iced::{Element, Font};
 use iced::advanced::Renderer;
 use iced::alignment::{Alignment, Horizontal, Vertical};
-use iced::widget::Row;
+use iced::widget::{container, Row};
 
 use iced_builder::WidgetBuilder;
 use iced_virtual::constrained_row::Constraint;
 use iced_virtual::table::Table;
 
 use crate::action::{Action, ActionLayout, ActionStyle, ActionWithDef};
-use crate::query::{FacetRef, FacetType, Query, QueryMessage};
+use crate::query::{FacetRef, FacetType, Query, QueryMessage, Sort, SortField};
 use crate::service::{Catalog, DataActions, Service, ServiceActions};
-use crate::table::AsTableRow;
+use crate::table::{AsTableRow, ColumnSelection, KeyedTableRow, RowSelection, SelectionMessage};
 
 trait IntoElement<'a, M, T, R> {
   fn into_element(self) -> Element<'a, M, T, R>;
@@ -71,26 +73,77 @@ impl<'a, A: Action + 'a> From<ActionWithDef<'a, A>> for Element<'a, A::Request>
       ActionStyle::Success => button.success_style(),
       ActionStyle::Danger => button.danger_style(),
     };
-    button.add()
+    let button = button.add();
+
+    if let Some(tooltip) = definition.tooltip {
+      WidgetBuilder::stack()
+        .add_element(button)
+        .tooltip(tooltip).style(container::rounded_box).add()
+        .take()
+    } else {
+      button
+    }
+  }
+}
+
+/// Converts `action` into an element, converting its request into a message of type [M] with `map_request`, or with
+/// `map_confirm_request` along with the confirmation message if the action [must be confirmed](crate::action::ActionDef::confirm).
+fn map_action_element<'a, A: Action + 'a, M: 'a>(
+  action: ActionWithDef<'a, A>,
+  map_request: impl (Fn(A::Request) -> M) + 'a,
+  map_confirm_request: impl (Fn(&'static str, A::Request) -> M) + 'a,
+) -> Element<'a, M> {
+  match action.definition.confirm {
+    Some(message) => action.into_element().map(move |request| map_confirm_request(message, request)),
+    None => action.into_element().map(map_request),
   }
 }
 
 /// Creates a table view for `service`, showing a `header` with `custom_buttons` and service actions, the query from the
-/// service, and a table with the service's data.
+/// service, and a table with the service's data in the columns selected by `column_selection`.
 ///
 /// Requests are converted to messages of type [M] with `map_request`, enabling `custom_buttons` to send custom messages.
+/// Requests of actions that must be confirmed are converted with `map_confirm_request` instead.
 /// Query messages are converted with `map_query_message` into [M].
 pub fn as_full_table<'a, S: Service + Catalog<Data: AsTableRow>, A: ServiceActions<S> + DataActions<S>, M: 'a>(
   service: &'a S,
   actions: &'a A,
   header: Option<&'a str>,
   custom_buttons: impl IntoIterator<Item=Element<'a, M>>,
+  column_selection: &ColumnSelection,
   map_request: impl (Fn(S::Request) -> M) + 'a + Copy,
+  map_confirm_request: impl (Fn(&'static str, S::Request) -> M) + 'a + Copy,
   //map_query_message: impl (Fn(QueryMessage) -> M) + 'a + Copy,
 ) -> Element<'a, M> {
-  let header = as_table_header(service, actions, header, custom_buttons, map_request);
+  let header = as_table_header(service, actions, header, custom_buttons, map_request, map_confirm_request);
   let query = as_table_query(service).map(move |q| map_request(service.request_update(q)));
-  let table = as_table(service, actions, map_request);
+  let table = as_table(service, actions, column_selection, map_request, map_confirm_request);
+  full_table(header, query, table)
+}
+
+/// Creates a table view like [`as_full_table`], but with a [selectable](as_selectable_table) table. Selection changes
+/// are converted to messages of type [M] with `on_select`.
+pub fn as_full_selectable_table<'a, S, A, M: 'a>(
+  service: &'a S,
+  actions: &'a A,
+  header: Option<&'a str>,
+  custom_buttons: impl IntoIterator<Item=Element<'a, M>>,
+  column_selection: &ColumnSelection,
+  selection: &'a RowSelection<<S::Data as KeyedTableRow>::Key>,
+  on_select: impl (Fn(SelectionMessage<<S::Data as KeyedTableRow>::Key>) -> M) + 'a + Copy,
+  map_request: impl (Fn(S::Request) -> M) + 'a + Copy,
+  map_confirm_request: impl (Fn(&'static str, S::Request) -> M) + 'a + Copy,
+) -> Element<'a, M> where
+  S: Service + Catalog<Data: KeyedTableRow>,
+  A: ServiceActions<S> + DataActions<S>,
+{
+  let header = as_table_header(service, actions, header, custom_buttons, map_request, map_confirm_request);
+  let query = as_table_query(service).map(move |q| map_request(service.request_update(q)));
+  let table = as_selectable_table(service, actions, column_selection, selection, on_select, map_request, map_confirm_request);
+  full_table(header, query, table)
+}
+
+fn full_table<'a, M: 'a>(header: Option<Element<'a, M>>, query: Element<'a, M>, table: Element<'a, M>) -> Element<'a, M> {
   let mut wb = WidgetBuilder::heap_with_capacity(3 + if header.is_some() { 2 } else { 0 });
   if let Some(header) = header {
     wb = wb
@@ -107,15 +160,17 @@ pub fn as_full_table<'a, S: Service + Catalog<Data: AsTableRow>, A: ServiceActio
 /// Creates a table header for `service`, showing a `header` with `custom_buttons` and service actions.
 ///
 /// Requests are converted to messages of type [M] with `map_request`, enabling `custom_buttons` to send custom messages.
+/// Requests of actions that must be confirmed are converted with `map_confirm_request` instead.
 pub fn as_table_header<'a, S: Service, A: ServiceActions<S>, M: 'a>(
   service: &'a S,
   actions: &'a A,
   header: Option<&'a str>,
   custom_buttons: impl IntoIterator<Item=Element<'a, M>>,
   map_request: impl (Fn(S::Request) -> M) + 'a + Copy,
+  map_confirm_request: impl (Fn(&'static str, S::Request) -> M) + 'a + Copy,
 ) -> Option<Element<'a, M>> {
   let action_buttons = actions.actions_with_definitions(service)
-    .map(|action| action.into_element().map(map_request));
+    .map(|action| map_action_element(action, map_request, map_confirm_request));
   let buttons: Vec<_> = custom_buttons.into_iter().chain(action_buttons).collect();
 
   let mut header_builder = WidgetBuilder::heap_with_capacity(3);
@@ -142,21 +197,65 @@ pub fn as_table_query<S: Catalog>(service: &S) -> Element<QueryMessage> {
   view_query(service.query(), service.query_config())
 }
 
-/// Creates a table showing `service`'s data. Requests are converted to a message of type [M] with `map_request`.
+/// Creates a table showing `service`'s data in the columns selected by `column_selection`. Requests are converted to a
+/// message of type [M] with `map_request`, or with `map_confirm_request` for actions that must be confirmed.
 pub fn as_table<'a, S: Service + Catalog<Data: AsTableRow>, A: DataActions<S>, M: 'a>(
   service: &'a S,
   actions: &'a A,
+  column_selection: &ColumnSelection,
+  map_request: impl (Fn(S::Request) -> M) + 'a + Copy,
+  map_confirm_request: impl (Fn(&'static str, S::Request) -> M) + 'a + Copy,
+) -> Element<'a, M> {
+  build_table(service, actions, column_selection, map_request, map_confirm_request, None)
+}
+
+/// Creates a table like [`as_table`], with a leading column of checkboxes that select rows in `selection`. Selection
+/// changes are converted to messages of type [M] with `on_select`.
+pub fn as_selectable_table<'a, S: Service + Catalog<Data: KeyedTableRow>, A: DataActions<S>, M: 'a>(
+  service: &'a S,
+  actions: &'a A,
+  column_selection: &ColumnSelection,
+  selection: &'a RowSelection<<S::Data as KeyedTableRow>::Key>,
+  on_select: impl (Fn(SelectionMessage<<S::Data as KeyedTableRow>::Key>) -> M) + 'a + Copy,
   map_request: impl (Fn(S::Request) -> M) + 'a + Copy,
+  map_confirm_request: impl (Fn(&'static str, S::Request) -> M) + 'a + Copy,
 ) -> Element<'a, M> {
-  let cell_to_element = move |row, col| -> Option<Element<M>> {
+  let selection_cell = move |data: &S::Data| -> Element<'a, M> {
+    let key = data.key();
+    WidgetBuilder::once()
+      .checkbox("", selection.is_selected(&key))
+      .on_toggle(move |selected| on_select(SelectionMessage::SetSelected(key, selected)))
+      .add()
+  };
+  build_table(service, actions, column_selection, map_request, map_confirm_request, Some(Box::new(selection_cell)))
+}
+
+fn build_table<'a, S: Service + Catalog<Data: AsTableRow>, A: DataActions<S>, M: 'a>(
+  service: &'a S,
+  actions: &'a A,
+  column_selection: &ColumnSelection,
+  map_request: impl (Fn(S::Request) -> M) + 'a + Copy,
+  map_confirm_request: impl (Fn(&'static str, S::Request) -> M) + 'a + Copy,
+  leading_cell: Option<Box<dyn Fn(&S::Data) -> Element<'a, M> + 'a>>,
+) -> Element<'a, M> {
+  let column_indices = column_selection.visible_column_indices::<S::Data>();
+  let column_count = column_indices.len();
+  let leading_column_count = if leading_cell.is_some() { 1 } else { 0 };
+  let cell_to_element = move |row, col: usize| -> Option<Element<M>> {
     let Some(krate) = service.get(row) else { return None; };
-    if let Some(text) = krate.cell(col as u8) {
+    let col = match &leading_cell {
+      Some(leading_cell) if col == 0 => return Some(leading_cell(krate)),
+      Some(_) => col - 1,
+      None => col,
+    };
+    if let Some(column_index) = column_indices.get(col) {
+      let text = krate.cell(*column_index)?;
       return Some(WidgetBuilder::once().add_text(text))
     }
 
-    let action_index = col - S::Data::COLUMNS.len();
+    let action_index = col - column_count;
     let element = if let Some(action) = actions.data_action_with_definition(service, action_index, krate) {
-      action.into_element().map(map_request)
+      map_action_element(action, map_request, map_confirm_request)
     } else {
       return None
     };
@@ -164,12 +263,14 @@ pub fn as_table<'a, S: Service + Catalog<Data: AsTableRow>, A: DataActions<S>, M
   };
 
   let data_actions = actions.data_action_definitions(service);
-  let column_count = S::Data::COLUMNS.len() + data_actions.len();
-  let mut table = Table::with_capacity(column_count, cell_to_element)
+  let mut table = Table::with_capacity(leading_column_count + column_count + data_actions.len(), cell_to_element)
     .spacing(1.0)
     .body_row_height(24.0)
     .body_row_count(service.len());
-  for column in S::Data::COLUMNS {
+  if leading_column_count > 0 {
+    table = table.push(0.2, "");
+  }
+  for column in S::Data::COLUMNS.iter().filter(|column| column_selection.is_visible(column)) {
     table = table.push(Constraint::new(column.width_fill_portion, column.horizontal_alignment.into(), column.vertical_alignment.into()), column.header)
   }
   for action_def in data_actions {
@@ -179,10 +280,54 @@ pub fn as_table<'a, S: Service + Catalog<Data: AsTableRow>, A: DataActions<S>, M
     };
     table = table.push(column_constraint, "");
   }
+  if let Some(reorder_request) = actions.data_reorder_request(service) {
+    table = table.on_reorder(move |from, to| map_request(reorder_request(from, to)));
+  }
 
   table.into_element()
 }
 
+/// Creates selection controls for rows with `keys`: a checkbox that selects all or none of the rows, and a button that
+/// inverts the selection. Selection changes are converted to messages of type [M] with `on_select`.
+pub fn view_selection_controls<'a, K: Copy + Eq + Hash, M: 'a>(
+  selection: &RowSelection<K>,
+  keys: impl IntoIterator<Item=K>,
+  on_select: impl Fn(SelectionMessage<K>) -> M + 'a + Copy,
+) -> Element<'a, M> {
+  let all_selected = selection.is_all_selected(keys);
+  let label = if selection.is_empty() {
+    "Select All".to_string()
+  } else {
+    format!("Select All ({} selected)", selection.len())
+  };
+  WidgetBuilder::stack()
+    .checkbox(label, all_selected)
+    .on_toggle(move |select_all| on_select(if select_all { SelectionMessage::SelectAll } else { SelectionMessage::SelectNone }))
+    .add()
+    .button("Invert Selection").secondary_style().on_press(move || on_select(SelectionMessage::Invert)).add()
+    .row().spacing(5.0).align_center().add()
+    .take()
+}
+
+/// Creates a column chooser with a toggler for each column of `T`, showing whether it is visible in `column_selection`.
+/// Toggling a column is converted to a message of type [M] with `on_toggle`, with the column index and its new
+/// visibility.
+pub fn view_column_chooser<'a, T: AsTableRow, M: 'a>(
+  column_selection: &ColumnSelection,
+  on_toggle: impl Fn(u8, bool) -> M + 'a + Copy,
+) -> Element<'a, M> {
+  let mut builder = WidgetBuilder::heap_with_capacity(T::COLUMNS.len());
+  for (column_index, column) in T::COLUMNS.iter().enumerate() {
+    let column_index = column_index as u8;
+    builder = builder.toggler(Some(column.header), column_selection.is_visible(column), move |visible| on_toggle(column_index, visible))
+      .width_shrink()
+      .add();
+  }
+  builder
+    .row().spacing(10.0).align_center().add()
+    .take()
+}
+
 pub fn view_query<'a, Q: Query>(query: &'a Q, config: &Q::Config) -> Element<'a, QueryMessage> {
   let mut num_facets: usize = 0;
   for index in 0..Q::FACET_DEFS.len() as u8 {
@@ -191,8 +336,10 @@ pub fn view_query<'a, Q: Query>(query: &'a Q, config: &Q::Config) -> Element<'a,
     }
   }
 
-  // Label text element + actual element + space element between elements.
-  let capacity = num_facets * 2 + num_facets.saturating_sub(1);
+  // Label text element + actual element + space element between elements, plus space element + label text element +
+  // pick list + descending toggler for sorting.
+  let sort = query.sort();
+  let capacity = num_facets * 2 + num_facets.saturating_sub(1) + if sort.is_some() { 4 } else { 0 };
   let mut builder = WidgetBuilder::heap_with_capacity(capacity);
 
   let mut first = true;
@@ -236,6 +383,21 @@ pub fn view_query<'a, Q: Query>(query: &'a Q, config: &Q::Config) -> Element<'a,
     }
   }
 
+  if let Some(sort) = sort {
+    let labels: Vec<&'static str> = Q::SortField::ALL.iter().map(SortField::label).collect();
+    let Sort { field, descending } = sort;
+    let select_fn = move |label: &'static str| {
+      let field = Q::SortField::ALL.iter().copied().find(|f| f.label() == label).unwrap_or(field);
+      QueryMessage::set_sort(Sort::new(field, descending))
+    };
+    let toggle_fn = move |descending| QueryMessage::set_sort(Sort::new(field, descending));
+    builder = builder
+      .space().width(5.0).add()
+      .text("Sort:").add()
+      .pick_list(labels, Some(field.label()), select_fn).add()
+      .toggler(Some("Descending"), descending, toggle_fn).spacing(5).width_shrink().add();
+  }
+
   builder
     .row().spacing(5.0).align_center().fill_width().add()
     .take()
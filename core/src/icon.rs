@@ -0,0 +1,63 @@
+/// Named icons, mapping to glyphs of the [Bootstrap icon](https://icons.getbootstrap.com/) font.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Icon {
+  /// Clockwise arrow.
+  Refresh,
+  /// Box with an arrow pointing out of it to the upper right.
+  OpenExternal,
+  /// Trash can.
+  Delete,
+  /// Sun.
+  Light,
+  /// Moon.
+  Dark,
+  /// Half-filled circle.
+  System,
+}
+
+impl Icon {
+  /// Name of the font that contains the icon glyphs.
+  pub const FONT_NAME: &'static str = "bootstrap-icons";
+
+  /// All icons.
+  pub const ALL: &'static [Icon] = &[Icon::Refresh, Icon::OpenExternal, Icon::Delete, Icon::Light, Icon::Dark, Icon::System];
+
+  /// Returns the glyph of this icon in the [icon font](Self::FONT_NAME).
+  #[inline]
+  pub const fn glyph(self) -> &'static str {
+    match self {
+      Icon::Refresh => "\u{F116}",
+      Icon::OpenExternal => "\u{F1C5}",
+      Icon::Delete => "\u{F5DE}",
+      Icon::Light => "\u{F5A2}",
+      Icon::Dark => "\u{F496}",
+      Icon::System => "\u{F288}",
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashSet;
+
+  use super::*;
+
+  #[test]
+  fn each_icon_resolves_to_a_glyph() {
+    for icon in Icon::ALL {
+      let glyph = icon.glyph();
+      assert!(!glyph.is_empty(), "{icon:?} has an empty glyph");
+      let mut chars = glyph.chars();
+      let c = chars.next().unwrap();
+      assert!(chars.next().is_none(), "{icon:?} glyph {glyph:?} is not a single character");
+      // Icon fonts map their glyphs into the Unicode private use area.
+      assert!(('\u{E000}'..='\u{F8FF}').contains(&c), "{icon:?} glyph {c:?} is not in the private use area");
+    }
+  }
+
+  #[test]
+  fn icons_have_distinct_glyphs() {
+    let glyphs: HashSet<_> = Icon::ALL.iter().map(|icon| icon.glyph()).collect();
+    assert_eq!(glyphs.len(), Icon::ALL.len());
+  }
+}
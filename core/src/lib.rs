@@ -4,9 +4,11 @@ pub mod crates;
 pub mod users;
 
 pub mod action;
+pub mod icon;
 pub mod service;
 pub mod query;
 pub mod table;
+pub mod theme;
 
 #[cfg(feature = "iced")]
 pub mod iced_impls;
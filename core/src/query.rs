@@ -8,6 +8,64 @@ pub trait Query {
   fn is_empty(&self, config: &Self::Config) -> bool;
   fn facet(&self, config: &Self::Config, index: u8) -> Option<FacetRef>;
   fn set_facet(&mut self, config: &Self::Config, index: u8, facet: Option<Facet>);
+
+  /// Sets whether to only query followed (`true`) or not followed (`false`) items. Does nothing if `config` does not
+  /// enable switching between followed and not followed items, or if this query does not support that.
+  #[inline]
+  fn set_followed(&mut self, _config: &Self::Config, _followed: bool) {}
+
+  /// Field this query can be sorted by. Use [`NoSortField`] if this query cannot be sorted.
+  type SortField: SortField;
+  /// Gets how this query is sorted, or `None` if this query cannot be sorted.
+  #[inline]
+  fn sort(&self) -> Option<Sort<Self::SortField>> { None }
+  /// Sorts this query by `sort`. Does nothing if this query cannot be sorted.
+  #[inline]
+  fn set_sort(&mut self, _config: &Self::Config, _sort: Sort<Self::SortField>) {}
+}
+
+
+/// Field that a [`Query`] can be sorted by.
+pub trait SortField: Copy + Eq + 'static {
+  /// All fields, in the order they should be presented in.
+  const ALL: &'static [Self];
+  /// Gets the label of this field.
+  fn label(&self) -> &'static str;
+
+  /// Gets the index of this field into [`ALL`](Self::ALL).
+  #[inline]
+  fn index(&self) -> u8 {
+    Self::ALL.iter().position(|f| f == self).unwrap_or_default() as u8
+  }
+  /// Gets the field at `index` into [`ALL`](Self::ALL), or `None` if `index` is out of bounds.
+  #[inline]
+  fn from_index(index: u8) -> Option<Self> {
+    Self::ALL.get(index as usize).copied()
+  }
+}
+
+/// [Sort field](SortField) for queries that cannot be sorted.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum NoSortField {}
+impl SortField for NoSortField {
+  const ALL: &'static [Self] = &[];
+  #[inline]
+  fn label(&self) -> &'static str { match *self {} }
+}
+
+/// Sort specification: sort by `field`, in descending order if `descending`.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Sort<F> {
+  pub field: F,
+  pub descending: bool,
+}
+impl<F> Sort<F> {
+  #[inline]
+  pub fn new(field: F, descending: bool) -> Self { Self { field, descending } }
+  #[inline]
+  pub fn ascending(field: F) -> Self { Self::new(field, false) }
+  #[inline]
+  pub fn descending(field: F) -> Self { Self::new(field, true) }
 }
 
 
@@ -93,7 +151,14 @@ pub enum QueryMessage {
   FacetChange {
     index: u8,
     new_facet: Option<Facet>,
-  }
+  },
+  /// Switch between followed (`true`) and not followed (`false`) items, if enabled in the query config.
+  SetFollowed(bool),
+  /// Sort by the field at `index` into [`SortField::ALL`] of [`Query::SortField`], in descending order if `descending`.
+  SetSort {
+    index: u8,
+    descending: bool,
+  },
 }
 impl QueryMessage {
   #[inline]
@@ -109,12 +174,25 @@ impl QueryMessage {
     Self::facet_change(facet_index, Some(Facet::String(string)))
   }
 
+  #[inline]
+  pub fn set_sort<F: SortField>(sort: Sort<F>) -> Self {
+    Self::SetSort { index: sort.field.index(), descending: sort.descending }
+  }
+
   #[inline]
   pub fn update_query<Q: Query>(self, query: &mut Q, config: &Q::Config) {
     match self {
       QueryMessage::FacetChange { index, new_facet } => {
         query.set_facet(config, index, new_facet);
       }
+      QueryMessage::SetFollowed(followed) => {
+        query.set_followed(config, followed);
+      }
+      QueryMessage::SetSort { index, descending } => {
+        if let Some(field) = Q::SortField::from_index(index) {
+          query.set_sort(config, Sort::new(field, descending));
+        }
+      }
     }
   }
 }
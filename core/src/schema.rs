@@ -1,5 +1,12 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    crate_refreshes (crate_id) {
+        crate_id -> Int4,
+        refreshed_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     crate_versions (id) {
         id -> Int4,
@@ -20,6 +27,7 @@ diesel::table! {
         repository -> Nullable<Varchar>,
         downloads -> Int8,
         default_version_id -> Int4,
+        registry -> Varchar,
     }
 }
 
@@ -27,6 +35,7 @@ diesel::table! {
     favorite_crates (user_id, crate_id) {
         user_id -> Int4,
         crate_id -> Int4,
+        refresh_interval_secs -> Nullable<Int4>,
     }
 }
 
@@ -37,6 +46,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    saved_searches (id) {
+        id -> Int4,
+        user_id -> Int4,
+        name -> Varchar,
+        followed -> Nullable<Bool>,
+        crate_name -> Nullable<Varchar>,
+    }
+}
+
 diesel::table! {
     users (id) {
         id -> Int4,
@@ -45,11 +64,15 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(crate_refreshes -> crates (crate_id));
 diesel::joinable!(favorite_crates -> crates (crate_id));
 diesel::joinable!(favorite_crates -> users (user_id));
+diesel::joinable!(saved_searches -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(favorite_crates, crates, crate_versions);
+diesel::allow_tables_to_appear_in_same_query!(favorite_crates, crate_refreshes);
 diesel::allow_tables_to_appear_in_same_query!(favorite_crates, users);
+diesel::allow_tables_to_appear_in_same_query!(saved_searches, users);
 
 // diesel::allow_tables_to_appear_in_same_query!(
 //     crate_versions,
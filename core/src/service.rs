@@ -2,6 +2,8 @@ use std::future::Future;
 
 use crate::action::{Action, ActionDef, ActionWithDef};
 use crate::query::{Query, QueryMessage};
+#[cfg(feature = "csv")]
+use crate::table::AsTableRow;
 use crate::util::maybe_send::MaybeSend;
 
 /// Service that sends requests and processes responses.
@@ -56,6 +58,15 @@ pub trait Catalog: Service {
 
   fn iter(&self) -> impl Iterator<Item=&Self::Data>;
 
+  /// Writes all data as CSV to `writer`, with a column per [table column](AsTableRow::COLUMNS) of the data.
+  ///
+  /// Requires [`AsTableRow`] instead of `Serialize`, so that the CSV has the same columns as the table shown to users,
+  /// and because CSV cannot represent the nested structure that data such as [`FullCrate`](crate::crates::FullCrate)
+  /// serializes to.
+  #[cfg(feature = "csv")]
+  fn export_csv(&self, writer: impl std::io::Write) -> csv::Result<()> where Self::Data: AsTableRow {
+    crate::table::write_csv(self.iter(), writer)
+  }
 
   type Query: Query;
 
@@ -108,6 +119,11 @@ pub trait DataActions<S: Service + Catalog> {
 
   fn data_action<'d>(&self, service: &S, index: usize, data: &'d S::Data) -> Option<impl Action<Request=S::Request> + 'd>;
 
+  /// Gets the function that creates a request to move data at index `from` to index `to`, or `None` if data cannot be
+  /// reordered.
+  #[inline]
+  fn data_reorder_request(&self, _service: &S) -> Option<fn(usize, usize) -> S::Request> { None }
+
   fn data_action_with_definition<'d>(&self, service: &S, index: usize, data: &'d S::Data) -> Option<ActionWithDef<impl Action<Request=S::Request> + 'd>> {
     match (self.data_action_definitions(service).get(index), self.data_action(service, index, data)) {
       (Some(definition), Some(action)) => Some(ActionWithDef { definition, action }),
@@ -1,4 +1,11 @@
 use std::borrow::Cow;
+use std::collections::{BTreeSet, HashSet};
+use std::hash::Hash;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::util::format_count;
 
 /// Horizontal and vertical alignment.
 #[derive(Default, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
@@ -19,17 +26,24 @@ pub struct ColumnDef {
   pub width_fill_portion: f32,
   pub horizontal_alignment: Alignment,
   pub vertical_alignment: Alignment,
+  pub format: CellFormat,
 }
 
 impl ColumnDef {
   #[inline]
   pub const fn new(header: &'static str, width_fill_portion: f32, horizontal_alignment: Alignment, vertical_alignment: Alignment) -> Self {
-    Self { header, width_fill_portion, horizontal_alignment, vertical_alignment }
+    Self { header, width_fill_portion, horizontal_alignment, vertical_alignment, format: CellFormat::Text }
   }
 
   #[inline]
   pub const fn with_default_alignment(header: &'static str, width_fill_portion: f32) -> Self {
-    Self { header, width_fill_portion, horizontal_alignment: Alignment::Start, vertical_alignment: Alignment::Start }
+    Self::new(header, width_fill_portion, Alignment::Start, Alignment::Start)
+  }
+
+  /// Sets the format of cells in this column to `format`.
+  #[inline]
+  pub const fn with_format(self, format: CellFormat) -> Self {
+    Self { format, ..self }
   }
 }
 
@@ -37,5 +51,439 @@ impl ColumnDef {
 pub trait AsTableRow {
   const COLUMNS: &'static [ColumnDef];
 
-  fn cell(&self, column_index: u8) -> Option<Cow<str>>;
+  /// Gets the value of the cell at `column_index`, or `None` if there is no cell at that index.
+  fn cell_value(&self, column_index: u8) -> Option<CellValue>;
+
+  /// Gets the text of the cell at `column_index`, formatted with the [format](ColumnDef::format) of its column, or
+  /// `None` if there is no cell at that index.
+  fn cell(&self, column_index: u8) -> Option<Cow<str>> {
+    let format = Self::COLUMNS.get(column_index as usize)?.format;
+    let value = self.cell_value(column_index)?;
+    Some(format.format(value, Utc::now()))
+  }
+}
+
+/// Table row with a key that identifies it regardless of its position in the table, such as an ID.
+pub trait KeyedTableRow: AsTableRow {
+  type Key: Copy + Eq + Hash;
+
+  fn key(&self) -> Self::Key;
+}
+
+/// Message that changes a [`RowSelection`].
+#[derive(Clone, Debug)]
+pub enum SelectionMessage<K> {
+  /// Select or deselect the row with the key.
+  SetSelected(K, bool),
+  /// Select all rows.
+  SelectAll,
+  /// Deselect all rows.
+  SelectNone,
+  /// Select the rows that are not selected, and deselect the rows that are.
+  Invert,
+}
+
+/// Selection of table rows for batch operations. Rows are identified by their [key](KeyedTableRow::key), so that the
+/// same rows stay selected when rows are sorted or filtered.
+#[derive(Clone, Debug)]
+pub struct RowSelection<K> {
+  selected: HashSet<K>,
+}
+
+impl<K> Default for RowSelection<K> {
+  #[inline]
+  fn default() -> Self { Self { selected: HashSet::new() } }
+}
+
+impl<K: Copy + Eq + Hash> RowSelection<K> {
+  #[inline]
+  pub fn is_selected(&self, key: &K) -> bool { self.selected.contains(key) }
+  #[inline]
+  pub fn len(&self) -> usize { self.selected.len() }
+  #[inline]
+  pub fn is_empty(&self) -> bool { self.selected.is_empty() }
+
+  pub fn set_selected(&mut self, key: K, selected: bool) {
+    if selected {
+      self.selected.insert(key);
+    } else {
+      self.selected.remove(&key);
+    }
+  }
+
+  /// Selects all rows with `keys`, keeping rows that are already selected.
+  pub fn select_all(&mut self, keys: impl IntoIterator<Item=K>) {
+    self.selected.extend(keys);
+  }
+
+  /// Deselects all rows.
+  #[inline]
+  pub fn select_none(&mut self) { self.selected.clear(); }
+
+  /// Inverts the selection of the rows with `keys`.
+  pub fn invert(&mut self, keys: impl IntoIterator<Item=K>) {
+    for key in keys {
+      if !self.selected.remove(&key) {
+        self.selected.insert(key);
+      }
+    }
+  }
+
+  /// Returns whether all rows with `keys` are selected, and there is at least one such row.
+  pub fn is_all_selected(&self, keys: impl IntoIterator<Item=K>) -> bool {
+    let mut keys = keys.into_iter().peekable();
+    keys.peek().is_some() && keys.all(|key| self.selected.contains(&key))
+  }
+
+  /// Gets the selected keys of `keys`, in the order of `keys`.
+  pub fn selected_in(&self, keys: impl IntoIterator<Item=K>) -> Vec<K> {
+    keys.into_iter().filter(|key| self.selected.contains(key)).collect()
+  }
+
+  /// Deselects rows whose key is not in `keys`, for example because they were removed.
+  pub fn retain(&mut self, keys: impl IntoIterator<Item=K>) {
+    let keys: HashSet<K> = keys.into_iter().collect();
+    self.selected.retain(|key| keys.contains(key));
+  }
+
+  /// Updates the selection from `message`, where `keys` are the keys of the rows currently shown.
+  pub fn update(&mut self, message: SelectionMessage<K>, keys: impl IntoIterator<Item=K>) {
+    match message {
+      SelectionMessage::SetSelected(key, selected) => self.set_selected(key, selected),
+      SelectionMessage::SelectAll => self.select_all(keys),
+      SelectionMessage::SelectNone => self.select_none(),
+      SelectionMessage::Invert => self.invert(keys),
+    }
+  }
+}
+
+/// Selection of table columns to display. Columns are identified by their header, and are visible unless hidden, so
+/// that columns added later are visible by default.
+#[derive(Default, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ColumnSelection {
+  hidden: BTreeSet<String>,
+}
+
+impl ColumnSelection {
+  #[inline]
+  pub fn is_visible(&self, column: &ColumnDef) -> bool { !self.hidden.contains(column.header) }
+
+  pub fn set_visible(&mut self, column: &ColumnDef, visible: bool) {
+    if visible {
+      self.hidden.remove(column.header);
+    } else {
+      self.hidden.insert(column.header.to_string());
+    }
+  }
+
+  /// Gets the indices of the columns of `T` that are visible.
+  pub fn visible_column_indices<T: AsTableRow>(&self) -> Vec<u8> {
+    T::COLUMNS.iter()
+      .enumerate()
+      .filter(|(_, column)| self.is_visible(column))
+      .map(|(i, _)| i as u8)
+      .collect()
+  }
+}
+
+/// Renders `rows` as a GitHub-flavored markdown table with the columns at `column_indices`, using the headers,
+/// alignments, and formats of those columns. Pipe characters in cells are escaped and line breaks are replaced with
+/// spaces, so that cells cannot break the table layout.
+pub fn as_markdown_table<'a, T: AsTableRow + 'a>(rows: impl IntoIterator<Item=&'a T>, column_indices: &[u8]) -> String {
+  let columns: Vec<_> = column_indices.iter()
+    .filter_map(|i| T::COLUMNS.get(*i as usize).map(|column| (*i, column)))
+    .collect();
+  let mut markdown = String::new();
+
+  markdown.push('|');
+  for (_, column) in &columns {
+    markdown.push_str(&format!(" {} |", escape_markdown_cell(column.header)));
+  }
+  markdown.push_str("\n|");
+  for (_, column) in &columns {
+    let alignment = match column.horizontal_alignment {
+      Alignment::Start => " :--- |",
+      Alignment::Center => " :---: |",
+      Alignment::End => " ---: |",
+    };
+    markdown.push_str(alignment);
+  }
+  markdown.push('\n');
+
+  let now = Utc::now();
+  for row in rows {
+    markdown.push('|');
+    for (column_index, column) in &columns {
+      let text = row.cell_value(*column_index)
+        .map(|value| column.format.format(value, now))
+        .unwrap_or_default();
+      markdown.push_str(&format!(" {} |", escape_markdown_cell(&text)));
+    }
+    markdown.push('\n');
+  }
+
+  markdown
+}
+
+fn escape_markdown_cell(text: &str) -> String {
+  text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// Writes `rows` as CSV to `writer`, with a header record of all column headers followed by a record per row. Cells are
+/// written as raw values instead of being formatted with their column format, with date times in RFC 3339 format, so that
+/// the CSV can be processed further.
+#[cfg(feature = "csv")]
+pub fn write_csv<'a, T: AsTableRow + 'a>(rows: impl IntoIterator<Item=&'a T>, writer: impl std::io::Write) -> csv::Result<()> {
+  let mut writer = csv::Writer::from_writer(writer);
+  writer.write_record(T::COLUMNS.iter().map(|column| column.header))?;
+  for row in rows {
+    let record = (0..T::COLUMNS.len() as u8).map(|column_index| match row.cell_value(column_index) {
+      Some(CellValue::Text(text)) => text.into_owned(),
+      Some(CellValue::Integer(integer)) => integer.to_string(),
+      Some(CellValue::DateTime(date_time)) => date_time.to_rfc3339(),
+      None => String::new(),
+    });
+    writer.write_record(record)?;
+  }
+  writer.flush()?;
+  Ok(())
+}
+
+/// Value of a table cell, formatted into text with the [format](CellFormat) of its column.
+#[derive(Clone, Debug)]
+pub enum CellValue<'a> {
+  Text(Cow<'a, str>),
+  Integer(i64),
+  DateTime(DateTime<Utc>),
+}
+
+impl<'a> From<&'a str> for CellValue<'a> {
+  #[inline]
+  fn from(text: &'a str) -> Self { Self::Text(Cow::Borrowed(text)) }
+}
+impl<'a> From<&'a String> for CellValue<'a> {
+  #[inline]
+  fn from(text: &'a String) -> Self { Self::Text(Cow::Borrowed(text)) }
+}
+impl From<String> for CellValue<'_> {
+  #[inline]
+  fn from(text: String) -> Self { Self::Text(Cow::Owned(text)) }
+}
+impl From<i64> for CellValue<'_> {
+  #[inline]
+  fn from(integer: i64) -> Self { Self::Integer(integer) }
+}
+impl From<i32> for CellValue<'_> {
+  #[inline]
+  fn from(integer: i32) -> Self { Self::Integer(integer as i64) }
+}
+impl From<DateTime<Utc>> for CellValue<'_> {
+  #[inline]
+  fn from(date_time: DateTime<Utc>) -> Self { Self::DateTime(date_time) }
+}
+
+/// How the [value](CellValue) of a table cell is formatted into text.
+#[derive(Default, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum CellFormat {
+  /// Format as plain text: integers as is and date times as `YYYY-MM-DD`.
+  #[default]
+  Text,
+  /// Format integers with thousands separators, e.g., `1,234,567`.
+  Count,
+  /// Format date times relative to now, e.g., `3 days ago`.
+  RelativeTime,
+  /// Format integers as byte sizes with binary units, e.g., `1.5 KiB`.
+  ByteSize,
+}
+
+impl CellFormat {
+  /// Formats `value` into text, formatting relative times relative to `now`.
+  pub fn format(self, value: CellValue, now: DateTime<Utc>) -> Cow<str> {
+    match (self, value) {
+      (_, CellValue::Text(text)) => text,
+      (CellFormat::Count, CellValue::Integer(integer)) => Cow::Owned(format_count(integer)),
+      (CellFormat::ByteSize, CellValue::Integer(integer)) => Cow::Owned(format_byte_size(integer)),
+      (_, CellValue::Integer(integer)) => Cow::Owned(integer.to_string()),
+      (CellFormat::RelativeTime, CellValue::DateTime(date_time)) => Cow::Owned(format_relative_time(date_time, now)),
+      (_, CellValue::DateTime(date_time)) => Cow::Owned(date_time.format("%Y-%m-%d").to_string()),
+    }
+  }
+}
+
+fn format_byte_size(bytes: i64) -> String {
+  const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+  if bytes.unsigned_abs() < 1024 {
+    return format!("{bytes} B");
+  }
+  let mut size = bytes as f64 / 1024.0;
+  let mut unit = UNITS[0];
+  for next_unit in &UNITS[1..] {
+    if size.abs() < 1024.0 {
+      break;
+    }
+    size /= 1024.0;
+    unit = next_unit;
+  }
+  format!("{size:.1} {unit}")
+}
+
+fn format_relative_time(date_time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+  let delta = now.signed_duration_since(date_time);
+  let seconds = delta.num_seconds().unsigned_abs();
+  let (amount, unit) = match seconds {
+    0..=59 => return "just now".to_string(),
+    60..=3_599 => (seconds / 60, "minute"),
+    3_600..=86_399 => (seconds / 3_600, "hour"),
+    86_400..=2_591_999 => (seconds / 86_400, "day"),
+    2_592_000..=31_535_999 => (seconds / 2_592_000, "month"),
+    _ => (seconds / 31_536_000, "year"),
+  };
+  let plural = if amount == 1 { "" } else { "s" };
+  if delta.num_seconds() < 0 {
+    format!("in {amount} {unit}{plural}")
+  } else {
+    format!("{amount} {unit}{plural} ago")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::TimeDelta;
+
+  use super::*;
+
+  struct Row {
+    name: &'static str,
+    downloads: i64,
+  }
+
+  impl AsTableRow for Row {
+    const COLUMNS: &'static [ColumnDef] = &[
+      ColumnDef::with_default_alignment("Name | Alias", 1.0),
+      ColumnDef::new("Downloads", 1.0, Alignment::End, Alignment::Start).with_format(CellFormat::Count),
+    ];
+
+    fn cell_value(&self, column_index: u8) -> Option<CellValue> {
+      let value = match column_index {
+        0 => self.name.into(),
+        1 => self.downloads.into(),
+        _ => return None,
+      };
+      Some(value)
+    }
+  }
+
+  #[test]
+  fn markdown_table() {
+    let rows = [Row { name: "serde", downloads: 1_234_567 }, Row { name: "tokio", downloads: 0 }];
+    let markdown = as_markdown_table(&rows, &[0, 1]);
+    assert_eq!(markdown, "\
+| Name \\| Alias | Downloads |
+| :--- | ---: |
+| serde | 1,234,567 |
+| tokio | 0 |
+");
+  }
+
+  #[test]
+  fn markdown_table_escapes_special_characters() {
+    let rows = [
+      Row { name: "a|b", downloads: 1 },
+      Row { name: "line\nbreak\r\nhere", downloads: 2 },
+      Row { name: "*not* `code` | <b>", downloads: 3 },
+    ];
+    let markdown = as_markdown_table(&rows, &[0]);
+    let lines: Vec<_> = markdown.lines().skip(2).collect();
+    assert_eq!(lines, [
+      "| a\\|b |",
+      "| line break  here |",
+      "| *not* `code` \\| <b> |",
+    ]);
+  }
+
+  #[test]
+  fn markdown_table_skips_unknown_columns() {
+    let rows = [Row { name: "serde", downloads: 1 }];
+    let markdown = as_markdown_table(&rows, &[1, 7]);
+    assert_eq!(markdown, "| Downloads |\n| ---: |\n| 1 |\n");
+  }
+
+  #[test]
+  fn select_all_then_filter_keeps_selected_keys() {
+    let mut selection = RowSelection::default();
+    selection.update(SelectionMessage::SelectAll, [1, 2, 3, 4]);
+    assert_eq!(selection.selected_in([2, 4]), [2, 4]);
+    assert!(selection.is_all_selected([2, 4]));
+
+    selection.retain([4, 2]);
+    assert_eq!(selection.len(), 2);
+    assert_eq!(selection.selected_in([1, 2, 3, 4]), [2, 4]);
+  }
+
+  #[test]
+  fn select_all_after_filter_only_selects_shown_rows() {
+    let mut selection = RowSelection::default();
+    selection.update(SelectionMessage::SetSelected(1, true), [1, 2, 3, 4]);
+    selection.update(SelectionMessage::SelectAll, [3, 4]);
+    assert_eq!(selection.selected_in([1, 2, 3, 4]), [1, 3, 4]);
+
+    selection.update(SelectionMessage::Invert, [3, 4]);
+    assert_eq!(selection.selected_in([1, 2, 3, 4]), [1]);
+    selection.update(SelectionMessage::SelectNone, [3, 4]);
+    assert!(selection.is_empty());
+  }
+
+  #[test]
+  fn column_selection_round_trips() {
+    let mut column_selection = ColumnSelection::default();
+    column_selection.set_visible(&Row::COLUMNS[1], false);
+    let json = serde_json::to_string(&column_selection).unwrap();
+    let deserialized: ColumnSelection = serde_json::from_str(&json).unwrap();
+    assert_eq!(deserialized, column_selection);
+    assert!(deserialized.is_visible(&Row::COLUMNS[0]));
+    assert!(!deserialized.is_visible(&Row::COLUMNS[1]));
+  }
+
+  #[test]
+  fn hidden_columns_are_not_rendered() {
+    let mut column_selection = ColumnSelection::default();
+    assert_eq!(column_selection.visible_column_indices::<Row>(), [0, 1]);
+
+    column_selection.set_visible(&Row::COLUMNS[0], false);
+    let column_indices = column_selection.visible_column_indices::<Row>();
+    assert_eq!(column_indices, [1]);
+    let rows = [Row { name: "serde", downloads: 1 }];
+    assert_eq!(as_markdown_table(&rows, &column_indices), "| Downloads |\n| ---: |\n| 1 |\n");
+
+    column_selection.set_visible(&Row::COLUMNS[0], true);
+    assert_eq!(column_selection.visible_column_indices::<Row>(), [0, 1]);
+  }
+
+  #[test]
+  fn byte_size_format() {
+    let format = |bytes: i64| CellFormat::ByteSize.format(bytes.into(), DateTime::UNIX_EPOCH).into_owned();
+    assert_eq!(format(0), "0 B");
+    assert_eq!(format(1023), "1023 B");
+    assert_eq!(format(1024), "1.0 KiB");
+    assert_eq!(format(1536), "1.5 KiB");
+    assert_eq!(format(1024 * 1024), "1.0 MiB");
+    assert_eq!(format(-1023), "-1023 B");
+    assert_eq!(format(-2048), "-2.0 KiB");
+  }
+
+  #[test]
+  fn relative_time_format() {
+    let now = DateTime::UNIX_EPOCH + TimeDelta::days(1000);
+    let format = |delta: TimeDelta| CellFormat::RelativeTime.format((now - delta).into(), now).into_owned();
+    assert_eq!(format(TimeDelta::seconds(0)), "just now");
+    assert_eq!(format(TimeDelta::seconds(59)), "just now");
+    assert_eq!(format(TimeDelta::seconds(-59)), "just now");
+    assert_eq!(format(TimeDelta::minutes(1)), "1 minute ago");
+    assert_eq!(format(TimeDelta::minutes(2)), "2 minutes ago");
+    assert_eq!(format(TimeDelta::hours(1)), "1 hour ago");
+    assert_eq!(format(TimeDelta::days(3)), "3 days ago");
+    assert_eq!(format(TimeDelta::days(365)), "1 year ago");
+    assert_eq!(format(TimeDelta::hours(-1)), "in 1 hour");
+    assert_eq!(format(TimeDelta::days(-2)), "in 2 days");
+  }
 }
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+/// Light/dark theme mode of an application.
+#[derive(Default, Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub enum ThemeMode {
+  Light,
+  Dark,
+  /// Follow the light/dark mode of the system.
+  #[default]
+  System,
+}
+
+impl ThemeMode {
+  /// Returns whether this mode is dark, where `system_dark` is whether the system is in dark mode.
+  #[inline]
+  pub fn is_dark(self, system_dark: bool) -> bool {
+    match self {
+      ThemeMode::Light => false,
+      ThemeMode::Dark => true,
+      ThemeMode::System => system_dark,
+    }
+  }
+
+  /// Returns the opposite of this mode, resolving [`ThemeMode::System`] with `system_dark` first.
+  #[inline]
+  pub fn toggle(self, system_dark: bool) -> Self {
+    if self.is_dark(system_dark) { ThemeMode::Light } else { ThemeMode::Dark }
+  }
+
+  /// Returns the next mode in the `Light -> Dark -> System -> Light` cycle.
+  #[inline]
+  pub fn cycle(self) -> Self {
+    match self {
+      ThemeMode::Light => ThemeMode::Dark,
+      ThemeMode::Dark => ThemeMode::System,
+      ThemeMode::System => ThemeMode::Light,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn cycle_visits_all_modes() {
+    assert_eq!(ThemeMode::Light.cycle(), ThemeMode::Dark);
+    assert_eq!(ThemeMode::Dark.cycle(), ThemeMode::System);
+    assert_eq!(ThemeMode::System.cycle(), ThemeMode::Light);
+  }
+
+  #[test]
+  fn toggle_resolves_system_mode() {
+    for system_dark in [false, true] {
+      assert_eq!(ThemeMode::Light.toggle(system_dark), ThemeMode::Dark);
+      assert_eq!(ThemeMode::Dark.toggle(system_dark), ThemeMode::Light);
+    }
+    assert_eq!(ThemeMode::System.toggle(false), ThemeMode::Dark);
+    assert_eq!(ThemeMode::System.toggle(true), ThemeMode::Light);
+  }
+
+  #[test]
+  fn is_dark_follows_system_only_in_system_mode() {
+    assert!(!ThemeMode::Light.is_dark(true));
+    assert!(ThemeMode::Dark.is_dark(false));
+    assert!(ThemeMode::System.is_dark(true));
+    assert!(!ThemeMode::System.is_dark(false));
+  }
+}
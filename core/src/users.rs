@@ -1,10 +1,54 @@
-use std::fmt::{Debug, Formatter};
+use std::fmt::{Debug, Display, Formatter};
 use std::fmt;
 
 use dotenvy_macro::dotenv;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+/// ID of a user. Serialized as a bare integer, and stored as an `integer` in the database.
+#[cfg_attr(feature = "diesel", derive(diesel::expression::AsExpression, diesel::deserialize::FromSqlRow), diesel(sql_type = diesel::sql_types::Int4))]
+#[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+#[repr(transparent)]
+pub struct UserId(pub i32);
+
+impl From<i32> for UserId {
+  #[inline]
+  fn from(id: i32) -> Self { Self(id) }
+}
+impl From<UserId> for i32 {
+  #[inline]
+  fn from(id: UserId) -> Self { id.0 }
+}
+impl Display for UserId {
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { Display::fmt(&self.0, f) }
+}
+
+#[cfg(feature = "diesel")]
+mod user_id_sql {
+  use diesel::deserialize::{self, FromSql};
+  use diesel::pg::{Pg, PgValue};
+  use diesel::serialize::{self, Output, ToSql};
+  use diesel::sql_types::Int4;
+
+  use super::UserId;
+
+  impl ToSql<Int4, Pg> for UserId {
+    #[inline]
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+      <i32 as ToSql<Int4, Pg>>::to_sql(&self.0, out)
+    }
+  }
+  impl FromSql<Int4, Pg> for UserId {
+    #[inline]
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+      <i32 as FromSql<Int4, Pg>>::from_sql(bytes).map(UserId)
+    }
+  }
+}
+
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct UserCredentials {
   pub name: String,
@@ -57,3 +101,18 @@ pub mod http_status_code {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::UserId;
+
+  #[test]
+  fn large_user_id_round_trips() {
+    let id = UserId(i32::MAX);
+    assert_eq!(UserId::from(i32::from(id)), id);
+
+    let json = serde_json::to_string(&id).unwrap();
+    assert_eq!(json, i32::MAX.to_string());
+    assert_eq!(serde_json::from_str::<UserId>(&json).unwrap(), id);
+  }
+}
@@ -0,0 +1,44 @@
+/// Formats `count` with `,` as thousands separator, e.g., `1234567` as `1,234,567`, and `-1234` as `-1,234`.
+pub fn format_count(count: i64) -> String {
+  let digits = count.unsigned_abs().to_string();
+  let mut text = String::with_capacity(digits.len() + digits.len() / 3 + 1);
+  if count < 0 {
+    text.push('-');
+  }
+  for (i, digit) in digits.chars().enumerate() {
+    if i > 0 && (digits.len() - i) % 3 == 0 {
+      text.push(',');
+    }
+    text.push(digit);
+  }
+  text
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn format_count_groups_thousands() {
+    assert_eq!(format_count(0), "0");
+    assert_eq!(format_count(7), "7");
+    assert_eq!(format_count(999), "999");
+    assert_eq!(format_count(1_000), "1,000");
+    assert_eq!(format_count(9_999), "9,999");
+    assert_eq!(format_count(10_000), "10,000");
+    assert_eq!(format_count(100_000), "100,000");
+    assert_eq!(format_count(999_999), "999,999");
+    assert_eq!(format_count(1_000_000), "1,000,000");
+    assert_eq!(format_count(1_234_567_890), "1,234,567,890");
+    assert_eq!(format_count(i64::MAX), "9,223,372,036,854,775,807");
+  }
+
+  #[test]
+  fn format_count_negative() {
+    assert_eq!(format_count(-1), "-1");
+    assert_eq!(format_count(-999), "-999");
+    assert_eq!(format_count(-1_000), "-1,000");
+    assert_eq!(format_count(-1_000_000), "-1,000,000");
+    assert_eq!(format_count(i64::MIN), "-9,223,372,036,854,775,808");
+  }
+}
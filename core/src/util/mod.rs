@@ -1,6 +1,12 @@
+pub mod format;
 pub mod future;
 pub mod maybe_send;
+pub mod request_id;
+pub mod retry;
 #[cfg(feature = "time")]
 pub mod time;
 #[cfg(feature = "http_status_code")]
 pub mod http_status_code;
+
+pub use format::format_count;
+pub use retry::RetryPolicy;
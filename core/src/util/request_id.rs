@@ -0,0 +1,3 @@
+/// Name of the HTTP header that carries the ID of a request, used to correlate client and server logs. The server
+/// echoes this header in its response, and reuses the ID if the client sent one.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
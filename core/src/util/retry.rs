@@ -0,0 +1,161 @@
+use std::hash::{BuildHasher, RandomState};
+use std::time::Duration;
+
+/// Policy for retrying failed operations with exponential backoff and jitter.
+///
+/// The backoff before retry `n` (starting at 0) is `base_backoff * 2^n`, capped at `max_backoff`, and then randomly
+/// varied by up to `jitter` (a fraction in `0.0..=1.0`) in both directions, while still being capped at `max_backoff`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct RetryPolicy {
+  /// Maximum number of retries after the initial attempt. `0` disables retrying.
+  pub max_retries: u32,
+  /// Backoff before the first retry.
+  pub base_backoff: Duration,
+  /// Maximum backoff between retries.
+  pub max_backoff: Duration,
+  /// Fraction in `0.0..=1.0` by which backoffs are randomly varied.
+  pub jitter: f64,
+}
+impl Default for RetryPolicy {
+  #[inline]
+  fn default() -> Self {
+    Self::new(3, Duration::from_millis(250), Duration::from_secs(10), 0.2)
+  }
+}
+impl RetryPolicy {
+  /// Policy that never retries.
+  pub const NONE: Self = Self::new(0, Duration::ZERO, Duration::ZERO, 0.0);
+
+  #[inline]
+  pub const fn new(max_retries: u32, base_backoff: Duration, max_backoff: Duration, jitter: f64) -> Self {
+    Self { max_retries, base_backoff, max_backoff, jitter }
+  }
+
+  #[inline]
+  pub const fn with_max_retries(mut self, max_retries: u32) -> Self {
+    self.max_retries = max_retries;
+    self
+  }
+  #[inline]
+  pub const fn with_base_backoff(mut self, base_backoff: Duration) -> Self {
+    self.base_backoff = base_backoff;
+    self
+  }
+  #[inline]
+  pub const fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+    self.max_backoff = max_backoff;
+    self
+  }
+  #[inline]
+  pub const fn with_jitter(mut self, jitter: f64) -> Self {
+    self.jitter = jitter;
+    self
+  }
+
+  /// Returns the backoff before retry `retry` (starting at 0) without jitter.
+  pub fn backoff(&self, retry: u32) -> Duration {
+    let factor = 1u32.checked_shl(retry).unwrap_or(u32::MAX);
+    self.base_backoff.saturating_mul(factor).min(self.max_backoff)
+  }
+
+  /// Returns the backoff before retry `retry` (starting at 0) with jitter, where `random` is a value in `0.0..=1.0`
+  /// that selects where in the jitter range the backoff ends up. The result is always in
+  /// `backoff * (1 - jitter)..=backoff * (1 + jitter)`, capped at `max_backoff`.
+  pub fn backoff_with_jitter(&self, retry: u32, random: f64) -> Duration {
+    let backoff = self.backoff(retry);
+    let jitter = self.jitter.clamp(0.0, 1.0);
+    let random = random.clamp(0.0, 1.0);
+    let factor = 1.0 - jitter + 2.0 * jitter * random;
+    backoff.mul_f64(factor).min(self.max_backoff)
+  }
+
+  /// Returns an iterator over the backoffs before each retry, without jitter.
+  pub fn backoffs(&self) -> impl Iterator<Item=Duration> + '_ {
+    (0..self.max_retries).map(|retry| self.backoff(retry))
+  }
+
+  /// Returns the backoff before retry `retry` (starting at 0) with random jitter.
+  #[inline]
+  pub fn jittered_backoff(&self, retry: u32) -> Duration {
+    self.backoff_with_jitter(retry, random_unit())
+  }
+
+  /// Runs `operation` until it succeeds, until it fails with an error for which `should_retry` returns `false`, or
+  /// until the maximum number of retries is reached. Sleeps for a jittered backoff between attempts.
+  #[cfg(feature = "time")]
+  pub async fn retry<T, E, F: std::future::Future<Output=Result<T, E>>>(
+    &self,
+    mut operation: impl FnMut() -> F,
+    should_retry: impl Fn(&E) -> bool,
+  ) -> Result<T, E> {
+    let mut retry = 0;
+    loop {
+      match operation().await {
+        Err(e) if retry < self.max_retries && should_retry(&e) => {
+          super::time::sleep(self.jittered_backoff(retry)).await;
+          retry += 1;
+        }
+        result => return result,
+      }
+    }
+  }
+}
+
+/// Returns a pseudo-random value in `0.0..=1.0`, good enough for jitter but not for anything else. Every
+/// `RandomState` is created with different keys, so hashing a constant produces a different value on each call.
+fn random_unit() -> f64 {
+  let random = RandomState::new().hash_one(0u8);
+  (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const POLICY: RetryPolicy = RetryPolicy::new(6, Duration::from_millis(100), Duration::from_secs(2), 0.25);
+
+  #[test]
+  fn backoff_doubles_until_capped() {
+    let backoffs: Vec<_> = POLICY.backoffs().collect();
+    assert_eq!(backoffs, [
+      Duration::from_millis(100),
+      Duration::from_millis(200),
+      Duration::from_millis(400),
+      Duration::from_millis(800),
+      Duration::from_millis(1600),
+      Duration::from_secs(2),
+    ]);
+    // Shifting past the width of the factor saturates instead of overflowing.
+    assert_eq!(POLICY.backoff(31), Duration::from_secs(2));
+    assert_eq!(POLICY.backoff(32), Duration::from_secs(2));
+    assert_eq!(POLICY.backoff(u32::MAX), Duration::from_secs(2));
+  }
+
+  #[test]
+  fn jitter_stays_within_bounds() {
+    assert_eq!(POLICY.backoff_with_jitter(2, 0.0), Duration::from_millis(300));
+    assert_eq!(POLICY.backoff_with_jitter(2, 0.5), Duration::from_millis(400));
+    assert_eq!(POLICY.backoff_with_jitter(2, 1.0), Duration::from_millis(500));
+    // Out-of-range random values are clamped.
+    assert_eq!(POLICY.backoff_with_jitter(2, -1.0), Duration::from_millis(300));
+    assert_eq!(POLICY.backoff_with_jitter(2, 2.0), Duration::from_millis(500));
+    // Jitter never exceeds the maximum backoff.
+    assert_eq!(POLICY.backoff_with_jitter(4, 1.0), Duration::from_secs(2));
+
+    for retry in 0..POLICY.max_retries {
+      let backoff = POLICY.backoff(retry);
+      for _ in 0..100 {
+        let jittered = POLICY.jittered_backoff(retry);
+        assert!(jittered >= backoff.mul_f64(0.75), "{jittered:?} is less than 75% of {backoff:?}");
+        assert!(jittered <= backoff.mul_f64(1.25).min(POLICY.max_backoff), "{jittered:?} is more than 125% of {backoff:?}");
+      }
+    }
+  }
+
+  #[test]
+  fn no_jitter_and_no_retries() {
+    let policy = POLICY.with_jitter(0.0);
+    assert_eq!(policy.jittered_backoff(1), Duration::from_millis(200));
+    assert_eq!(RetryPolicy::NONE.backoffs().count(), 0);
+  }
+}
@@ -1,3 +1,6 @@
+use std::future::{Future, poll_fn};
+use std::pin::pin;
+use std::task::Poll;
 use std::time::Duration;
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -13,3 +16,20 @@ pub async fn sleep(duration: Duration) {
 pub async fn sleep(duration: Duration) {
   gloo_timers::future::sleep(duration).await;
 }
+
+/// Error returned by [`timeout`] when the future did not complete in time.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Elapsed;
+
+/// Awaits `future`, or returns `Err(Elapsed)` if it does not complete within `duration`. The future is dropped on
+/// timeout.
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Result<F::Output, Elapsed> {
+  let mut future = pin!(future);
+  let mut sleep = pin!(sleep(duration));
+  poll_fn(|cx| {
+    if let Poll::Ready(output) = future.as_mut().poll(cx) {
+      return Poll::Ready(Ok(output));
+    }
+    sleep.as_mut().poll(cx).map(|_| Err(Elapsed))
+  }).await
+}
@@ -33,6 +33,14 @@ impl<E> HeapList<E> {
       HeapList::Many(vec) => vec.len(),
     }
   }
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    match self {
+      HeapList::Zero => true,
+      HeapList::One(_, _) => false,
+      HeapList::Many(vec) => vec.is_empty(),
+    }
+  }
 
   #[inline]
   pub fn reserve(&mut self, additional: usize) {
@@ -43,6 +51,15 @@ impl<E> HeapList<E> {
     }
   }
 
+  #[inline]
+  pub fn clear(&mut self) {
+    match self {
+      HeapList::Zero => {}
+      HeapList::One(_, _) => *self = HeapList::Zero, // Nothing allocated yet, so there is no capacity to retain.
+      HeapList::Many(ref mut vec) => vec.clear(),
+    }
+  }
+
   #[inline]
   fn add(self, new_element: E) -> Self {
     match self {
@@ -65,6 +82,14 @@ impl<E> HeapList<E> {
     }
   }
   #[inline]
+  fn add_all(self, new_elements: impl IntoIterator<Item=E>) -> Self {
+    let (mut vec, reserve_additional) = self.unwrap();
+    let new_elements = new_elements.into_iter();
+    vec.reserve(reserve_additional.max(new_elements.size_hint().0));
+    vec.extend(new_elements);
+    HeapList::Many(vec)
+  }
+  #[inline]
   fn unwrap(self) -> (Vec<E>, usize) {
     match self {
       HeapList::Zero => (vec![], 0),
@@ -92,6 +117,13 @@ impl<E: El> StateAppend for HeapList<E> {
   }
 }
 
+impl<E: El> HeapList<E> {
+  #[inline]
+  pub(crate) fn append_all(self, into_elements: impl IntoIterator<Item=impl Into<E>>) -> WidgetBuilder<Self> {
+    WidgetBuilder(self.add_all(into_elements.into_iter().map(Into::into)))
+  }
+}
+
 impl<E: El> StateReduce for HeapList<E> {
   type ReduceOutput = WidgetBuilder<Self>;
   fn reduce(self, reduce_fn: impl FnOnce(Vec<E>) -> E) -> Self::ReduceOutput {
@@ -1,27 +1,36 @@
 use std::borrow::Cow;
 use std::marker::PhantomData;
+use std::ops::RangeInclusive;
 
 use iced::advanced::text::Renderer as TextRenderer;
-use iced::{Element, Pixels};
+use iced::{Color, Element, Pixels};
 use iced::advanced::Renderer;
-use iced::widget::{button, container, Rule, rule, scrollable, Space, Text, text, text_input, toggler};
+use iced::widget::{button, checkbox, container, pick_list, Rule, rule, scrollable, slider, Space, Text, text, text_editor, text_input, toggler};
+use num_traits::FromPrimitive;
 
-use internal::state::{Elem, ElemM, StateAppend, StateMap, StateReduce, StateTake, StateTakeAll};
+use internal::state::{El, Elem, ElemM, StateAppend, StateMap, StateReduce, StateTake, StateTakeAll};
 use internal::state::heap::HeapList;
 use internal::state::stack::Nil;
 use widget::button::ButtonBuilder;
 use widget::column::ColumnBuilder;
 use widget::container::ContainerBuilder;
 use widget::element::ElementBuilder;
+use widget::keyed_column::KeyedColumnBuilder;
+use widget::mouse_area::MouseAreaBuilder;
+use widget::pick_list::PickListBuilder;
 use widget::row::RowBuilder;
 use widget::rule::RuleBuilder;
 use widget::scrollable::ScrollableBuilder;
+use widget::slider::SliderBuilder;
 use widget::space::SpaceBuilder;
 use widget::text::TextBuilder;
+use widget::text_editor::TextEditorBuilder;
 use widget::text_input::TextInputBuilder;
 use crate::internal::state::stack::Cons;
 
+use crate::widget::checkbox::CheckboxBuilder;
 use crate::widget::toggler::TogglerBuilder;
+use crate::widget::tooltip::TooltipBuilder;
 
 pub mod widget;
 mod internal;
@@ -155,6 +164,14 @@ impl<S: StateAppend> WidgetBuilder<S> {
     TextInputBuilder::new(self.0, placeholder, value)
   }
 
+  /// Build a multi-line [`TextEditor`](iced::widget::TextEditor) widget editing `content`.
+  pub fn text_editor<'a>(self, content: &'a text_editor::Content<S::Renderer>) -> TextEditorBuilder<'a, S> where
+    S::Renderer: TextRenderer,
+    S::Theme: text_editor::Catalog
+  {
+    TextEditorBuilder::new(self.0, content)
+  }
+
   /// Build a [`Button`](iced::widget::Button) widget from `content`.
   pub fn button<'a, C>(self, content: C) -> ButtonBuilder<'a, S, C> where
     S::Theme: button::Catalog
@@ -176,6 +193,48 @@ impl<S: StateAppend> WidgetBuilder<S> {
     TogglerBuilder::new(self.0, label, is_toggled, toggle_fn)
   }
 
+  /// Build a [`Checkbox`](iced::widget::Checkbox) widget from a `label` and whether it `is_checked`. Set
+  /// [`on_toggle`](CheckboxBuilder::on_toggle) to enable the checkbox.
+  pub fn checkbox<'a>(
+    self,
+    label: impl Into<String>,
+    is_checked: bool,
+  ) -> CheckboxBuilder<'a, S> where
+    S::Renderer: TextRenderer,
+    S::Theme: checkbox::Catalog,
+  {
+    CheckboxBuilder::new(self.0, label, is_checked)
+  }
+
+  /// Build a [`PickList`](iced::widget::PickList) widget from `options`, the `selected` option, and an `on_select`
+  /// function that produces a message when an option is selected.
+  pub fn pick_list<'a, T>(
+    self,
+    options: impl Into<Cow<'a, [T]>>,
+    selected: Option<T>,
+    on_select: impl Fn(T) -> S::Message + 'a,
+  ) -> PickListBuilder<'a, S, T> where
+    T: ToString + Clone + PartialEq + 'a,
+    S::Renderer: TextRenderer,
+    S::Theme: pick_list::Catalog,
+  {
+    PickListBuilder::new(self.0, options, selected, on_select)
+  }
+
+  /// Build a [`Slider`](iced::widget::Slider) widget from a `range` of values, the current `value`, and an `on_change`
+  /// function that produces a message when the slider is dragged.
+  pub fn slider<'a, T>(
+    self,
+    range: RangeInclusive<T>,
+    value: T,
+    on_change: impl Fn(T) -> S::Message + 'a,
+  ) -> SliderBuilder<'a, S, T> where
+    T: Copy + From<u8> + PartialOrd + Into<f64> + FromPrimitive,
+    S::Theme: slider::Catalog,
+  {
+    SliderBuilder::new(self.0, range, value, on_change)
+  }
+
 
   /// Build an [`Element`](iced::Element) from `element`.
   pub fn element<'a, M>(self, element: impl Into<ElemM<'a, S, M>>) -> ElementBuilder<'a, S, M> {
@@ -196,6 +255,15 @@ impl<S: StateReduce> WidgetBuilder<S> {
     ColumnBuilder::new(self.0)
   }
 
+  /// Build a [keyed `Column`](iced::widget::keyed::Column) widget that will consume all elements in this builder,
+  /// keying each element with the key at the same position in `keys`. Keyed columns preserve the state of their
+  /// children (e.g., text input focus) when children are reordered, inserted, or removed.
+  ///
+  /// The number of `keys` must match the number of elements in this builder.
+  pub fn keyed_column<K: Copy + PartialEq>(self, keys: impl Into<Vec<K>>) -> KeyedColumnBuilder<S, K> {
+    KeyedColumnBuilder::new(self.0, keys.into())
+  }
+
   /// Build a [`Row`](iced::widget::Column) widget that will consume all elements in this builder.
   pub fn row(self) -> RowBuilder<S> {
     RowBuilder::new(self.0)
@@ -220,6 +288,24 @@ impl<S: StateMap> WidgetBuilder<S> {
   {
     ContainerBuilder::new(self.0)
   }
+
+  /// Build a [`Tooltip`](iced::widget::Tooltip) widget showing `text` that will consume the last element in this
+  /// builder as its content.
+  ///
+  /// Can only be called when this builder has at least one element.
+  pub fn tooltip<'a>(self, text: impl Into<Cow<'a, str>>) -> TooltipBuilder<'a, S> where
+    S::Theme: container::Catalog,
+  {
+    TooltipBuilder::new(self.0, text.into())
+  }
+
+  /// Build a [`MouseArea`](iced::widget::MouseArea) widget that will consume the last element in this builder as its
+  /// content, making it respond to mouse presses and hovering.
+  ///
+  /// Can only be called when this builder has at least one element.
+  pub fn mouse_area(self) -> MouseAreaBuilder<S> {
+    MouseAreaBuilder::new(self.0)
+  }
 }
 
 impl<S: StateTakeAll> WidgetBuilder<S> {
@@ -227,6 +313,19 @@ impl<S: StateTakeAll> WidgetBuilder<S> {
   pub fn take_all(self) -> Vec<S::Element> {
     self.0.take_all()
   }
+
+  /// Take a [`Vec`] with all element out of this builder, with each element [explaining](Element::explain) its layout
+  /// by drawing the bounds of its widgets in `color`. For debugging layouts.
+  pub fn take_all_explained<'a>(self, color: impl Into<Color>) -> Vec<S::Element> where
+    S::Element: Into<Elem<'a, S>>,
+    Elem<'a, S>: Into<S::Element>,
+    S::Message: 'a,
+    S::Theme: 'a,
+    S::Renderer: Renderer + 'a,
+  {
+    let color = color.into();
+    self.0.take_all().into_iter().map(|element| element.into().explain(color).into()).collect()
+  }
 }
 
 impl<S: StateTake> WidgetBuilder<S> {
@@ -236,6 +335,20 @@ impl<S: StateTake> WidgetBuilder<S> {
   pub fn take(self) -> S::Element {
     self.0.take()
   }
+
+  /// Take the single element out of this builder, [explaining](Element::explain) its layout by drawing the bounds of
+  /// its widgets in `color`. For debugging layouts; replace with [`take`](Self::take) when done.
+  ///
+  /// Can only be called when this builder has exactly one element.
+  pub fn debug_explain<'a>(self, color: impl Into<Color>) -> S::Element where
+    S::Element: Into<Elem<'a, S>>,
+    Elem<'a, S>: Into<S::Element>,
+    S::Message: 'a,
+    S::Theme: 'a,
+    S::Renderer: Renderer + 'a,
+  {
+    self.0.take().into().explain(color).into()
+  }
 }
 
 impl<E> WidgetBuilder<HeapList<E>> {
@@ -246,11 +359,12 @@ impl<E> WidgetBuilder<HeapList<E>> {
     self.0.len()
   }
 
-  /// Return true if there are no elements in this builder.
+  /// Return true if there are no elements in this builder. Use this to check whether a [`Scrollable`] or
+  /// [`Container`] can be built, as building those from an empty builder panics.
   ///
   /// Can only be called when this is a heap-allocated builder.
   pub fn is_empty(&self) -> bool {
-    self.len() == 0
+    self.0.is_empty()
   }
 
   /// Reserve space for `additional` elements.
@@ -260,6 +374,45 @@ impl<E> WidgetBuilder<HeapList<E>> {
     self.0.reserve(additional);
     self
   }
+
+  /// Removes all elements from this builder, retaining its allocated capacity. Useful when reusing a builder in a loop.
+  ///
+  /// Can only be called when this is a heap-allocated builder.
+  pub fn clear(&mut self) {
+    self.0.clear();
+  }
+}
+
+impl<E: El> WidgetBuilder<HeapList<E>> {
+  /// Adds all `elements` to this builder, in order.
+  ///
+  /// Can only be called when this is a heap-allocated builder, as it adds a number of elements only known at run-time.
+  pub fn add_elements<'a, I>(self, elements: I) -> Self where
+    I: IntoIterator<Item=Elem<'a, HeapList<E>>>,
+    Elem<'a, HeapList<E>>: Into<E>,
+  {
+    self.0.append_all(elements)
+  }
+
+  /// Calls `f` with this builder to add elements if `condition` is true, otherwise returns this builder unchanged.
+  ///
+  /// Can only be called when this is a heap-allocated builder, as both branches must produce the same type. With a
+  /// stack-allocated builder, adding an element changes its type.
+  pub fn add_if(self, condition: bool, f: impl FnOnce(Self) -> Self) -> Self {
+    if condition { f(self) } else { self }
+  }
+
+  /// Adds `element` to this builder if it is `Some`, otherwise returns this builder unchanged.
+  ///
+  /// Can only be called when this is a heap-allocated builder, for the same reason as [`add_if`](Self::add_if).
+  pub fn add_maybe<'a>(self, element: Option<impl Into<Elem<'a, HeapList<E>>>>) -> Self where
+    Elem<'a, HeapList<E>>: Into<E>,
+  {
+    match element {
+      Some(element) => self.add_element(element),
+      None => self,
+    }
+  }
 }
 
 
@@ -272,3 +425,28 @@ impl<'a, M, T, R: Renderer> ElementExt for Element<'a, M, T, R> {
     WidgetBuilder::stack().add_element(self)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use iced::{Renderer, Theme};
+
+  use super::*;
+
+  type TestElement = Element<'static, (), Theme, Renderer>;
+
+  #[test]
+  fn add_elements_builds_column_with_all_elements() {
+    let elements: Vec<TestElement> = (0..100).map(|i| text(i).into()).collect();
+    let builder = WidgetBuilder::heap().add_text("header").add_elements(elements);
+    assert_eq!(builder.len(), 101);
+
+    let column: TestElement = builder.column().add().take();
+    assert_eq!(column.as_widget().children().len(), 101);
+  }
+
+  #[test]
+  fn add_elements_with_no_elements() {
+    let builder = WidgetBuilder::<HeapList<TestElement>>::heap().add_elements(Vec::new());
+    assert!(builder.is_empty());
+  }
+}
@@ -2,10 +2,17 @@ pub mod space;
 pub mod rule;
 pub mod text;
 pub mod text_input;
+pub mod text_editor;
 pub mod button;
 pub mod toggler;
+pub mod checkbox;
+pub mod pick_list;
+pub mod slider;
 pub mod element;
 pub mod column;
+pub mod keyed_column;
 pub mod row;
 pub mod scrollable;
 pub mod container;
+pub mod tooltip;
+pub mod mouse_area;
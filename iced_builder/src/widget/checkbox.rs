@@ -0,0 +1,113 @@
+use iced::{Length, Pixels};
+use iced::advanced::text::{LineHeight, Renderer as TextRenderer, Shaping};
+use iced::widget::{Checkbox, checkbox};
+
+use crate::internal::state::StateAppend;
+
+/// Builder for a [`Checkbox`] widget.
+#[must_use]
+pub struct CheckboxBuilder<'a, S: StateAppend> where
+  S::Renderer: TextRenderer,
+  S::Theme: checkbox::Catalog,
+{
+  state: S,
+  checkbox: Checkbox<'a, S::Message, S::Theme, S::Renderer>
+}
+
+impl<'a, S: StateAppend> CheckboxBuilder<'a, S> where
+  S::Renderer: TextRenderer,
+  S::Theme: checkbox::Catalog,
+{
+  pub(crate) fn new(
+    state: S,
+    label: impl Into<String>,
+    is_checked: bool,
+  ) -> Self {
+    Self {
+      state,
+      checkbox: Checkbox::new(label, is_checked),
+    }
+  }
+
+
+  /// Sets the function that produces a message when the checkbox is toggled. The checkbox is disabled if this is not
+  /// set.
+  pub fn on_toggle(mut self, toggle_fn: impl 'a + Fn(bool) -> S::Message) -> Self {
+    self.checkbox = self.checkbox.on_toggle(toggle_fn);
+    self
+  }
+
+
+  /// Sets the size of the checkbox.
+  pub fn size(mut self, size: impl Into<Pixels>) -> Self {
+    self.checkbox = self.checkbox.size(size);
+    self
+  }
+
+  /// Sets the width of the checkbox.
+  pub fn width(mut self, width: impl Into<Length>) -> Self {
+    self.checkbox = self.checkbox.width(width);
+    self
+  }
+
+  /// Sets the width of the checkbox to [`Length::Shrink`].
+  pub fn width_shrink(self) -> Self {
+    self.width(Length::Shrink)
+  }
+
+
+  /// Sets the size of the checkbox's label.
+  pub fn label_size(mut self, size: impl Into<Pixels>) -> Self {
+    self.checkbox = self.checkbox.text_size(size);
+    self
+  }
+
+  /// Sets the [`LineHeight`] of the checkbox's label.
+  pub fn label_line_height(mut self, line_height: impl Into<LineHeight>) -> Self {
+    self.checkbox = self.checkbox.text_line_height(line_height);
+    self
+  }
+
+  /// Sets the [`Shaping`] strategy of the checkbox's label.
+  pub fn label_shaping(mut self, shaping: Shaping) -> Self {
+    self.checkbox = self.checkbox.text_shaping(shaping);
+    self
+  }
+
+  /// Sets the [`Font`] of the checkbox's label.
+  ///
+  /// [`Font`]: S::Renderer::Font
+  pub fn label_font(mut self, font: impl Into<<S::Renderer as TextRenderer>::Font>) -> Self {
+    self.checkbox = self.checkbox.font(font);
+    self
+  }
+
+  /// Sets the spacing between the checkbox and its label.
+  pub fn spacing(mut self, spacing: impl Into<Pixels>) -> Self {
+    self.checkbox = self.checkbox.spacing(spacing);
+    self
+  }
+
+
+  /// Sets the `styler` function of the checkbox.
+  pub fn style(mut self, styler: impl Fn(&S::Theme, checkbox::Status) -> checkbox::Style + 'a) -> Self where
+    <S::Theme as checkbox::Catalog>::Class<'a>: From<checkbox::StyleFn<'a, S::Theme>>
+  {
+    self.checkbox = self.checkbox.style(styler);
+    self
+  }
+
+  /// Sets the `class` of the checkbox.
+  pub fn class(mut self, class: impl Into<<S::Theme as checkbox::Catalog>::Class<'a>>) -> Self {
+    self.checkbox = self.checkbox.class(class);
+    self
+  }
+
+
+  /// Adds the [`Checkbox`] widget to the builder and returns the builder.
+  pub fn add(self) -> S::AddOutput where
+    Checkbox<'a, S::Message, S::Theme, S::Renderer>: Into<S::Element>
+  {
+    self.state.append(self.checkbox)
+  }
+}
@@ -46,6 +46,37 @@ impl<S: StateReduce> ColumnBuilder<S> {
     self
   }
 
+  /// Sets the top padding of the [`Column`], keeping the padding of other sides.
+  pub fn padding_top(mut self, top: impl Into<Pixels>) -> Self {
+    self.padding.top = top.into().0;
+    self
+  }
+
+  /// Sets the bottom padding of the [`Column`], keeping the padding of other sides.
+  pub fn padding_bottom(mut self, bottom: impl Into<Pixels>) -> Self {
+    self.padding.bottom = bottom.into().0;
+    self
+  }
+
+  /// Sets the left padding of the [`Column`], keeping the padding of other sides.
+  pub fn padding_left(mut self, left: impl Into<Pixels>) -> Self {
+    self.padding.left = left.into().0;
+    self
+  }
+
+  /// Sets the right padding of the [`Column`], keeping the padding of other sides.
+  pub fn padding_right(mut self, right: impl Into<Pixels>) -> Self {
+    self.padding.right = right.into().0;
+    self
+  }
+
+  /// Sets the left and right padding of the [`Column`] to `horizontal`, and the top and bottom padding to `vertical`.
+  pub fn padding_xy(mut self, horizontal: impl Into<Pixels>, vertical: impl Into<Pixels>) -> Self {
+    let (horizontal, vertical) = (horizontal.into().0, vertical.into().0);
+    self.padding = Padding { top: vertical, right: horizontal, bottom: vertical, left: horizontal };
+    self
+  }
+
 
   /// Sets the width of the [`Column`].
   pub fn width(mut self, width: impl Into<Length>) -> Self {
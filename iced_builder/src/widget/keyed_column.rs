@@ -0,0 +1,113 @@
+use iced::{Alignment, Element, Length, Padding, Pixels};
+use iced::widget::keyed;
+
+use crate::internal::state::StateReduce;
+
+/// Builder for a [keyed `Column`](keyed::Column) widget.
+#[must_use]
+pub struct KeyedColumnBuilder<S, K> {
+  state: S,
+  keys: Vec<K>,
+  spacing: f32,
+  padding: Padding,
+  width: Length,
+  height: Length,
+  max_width: f32,
+  align_items: Alignment,
+}
+
+impl<S: StateReduce, K: Copy + PartialEq> KeyedColumnBuilder<S, K> {
+  pub(crate) fn new(state: S, keys: Vec<K>) -> Self {
+    Self {
+      state,
+      keys,
+      spacing: 0.0,
+      padding: Padding::ZERO,
+      width: Length::Shrink,
+      height: Length::Shrink,
+      max_width: f32::INFINITY,
+      align_items: Alignment::Start,
+    }
+  }
+
+
+  /// Sets the vertical spacing _between_ elements.
+  pub fn spacing(mut self, amount: impl Into<Pixels>) -> Self {
+    self.spacing = amount.into().0;
+    self
+  }
+
+  /// Sets the [`Padding`] of the [`keyed::Column`].
+  pub fn padding(mut self, padding: impl Into<Padding>) -> Self {
+    self.padding = padding.into();
+    self
+  }
+
+
+  /// Sets the width of the [`keyed::Column`].
+  pub fn width(mut self, width: impl Into<Length>) -> Self {
+    self.width = width.into();
+    self
+  }
+
+  /// Sets the height of the [`keyed::Column`].
+  pub fn height(mut self, height: impl Into<Length>) -> Self {
+    self.height = height.into();
+    self
+  }
+
+  /// Sets the maximum width of the [`keyed::Column`].
+  pub fn max_width(mut self, max_width: impl Into<Pixels>) -> Self {
+    self.max_width = max_width.into().0;
+    self
+  }
+
+  /// Sets the width of the [`keyed::Column`] to [`Length::Fill`].
+  pub fn fill_width(self) -> Self {
+    self.width(Length::Fill)
+  }
+
+  /// Sets the height of the [`keyed::Column`] to [`Length::Fill`].
+  pub fn fill_height(self) -> Self {
+    self.height(Length::Fill)
+  }
+
+  /// Sets the width and height of the [`keyed::Column`] to [`Length::Fill`].
+  pub fn fill(self) -> Self {
+    self.fill_width().fill_height()
+  }
+
+
+  /// Sets the horizontal alignment of the contents of the [`keyed::Column`] .
+  pub fn align_items(mut self, align: Alignment) -> Self {
+    self.align_items = align;
+    self
+  }
+
+  /// Sets the horizontal alignment of the contents of the [`keyed::Column`] to [`Alignment::Center`].
+  pub fn align_center(self) -> Self {
+    self.align_items(Alignment::Center)
+  }
+
+
+  /// Takes all current elements out of the builder, creates the [`keyed::Column`] with those elements keyed by the
+  /// keys in the same position, then adds the column to the builder and returns the builder.
+  ///
+  /// Panics if the number of keys does not match the number of elements.
+  pub fn add<'a>(self) -> S::ReduceOutput where
+    Vec<S::Element>: IntoIterator<Item=Element<'a, S::Message, S::Theme, S::Renderer>>, // For `keyed::Column::with_children`
+    keyed::Column<'a, K, S::Message, S::Theme, S::Renderer>: Into<S::Element>, // For `.into()`
+  {
+    self.state.reduce(|vec| {
+      assert_eq!(self.keys.len(), vec.len(), "number of keys must match the number of elements in a keyed column");
+      keyed::Column::with_children(self.keys.into_iter().zip(vec))
+        .spacing(self.spacing)
+        .padding(self.padding)
+        .width(self.width)
+        .height(self.height)
+        .max_width(self.max_width)
+        .align_items(self.align_items)
+        .into()
+    })
+  }
+}
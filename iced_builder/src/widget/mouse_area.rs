@@ -0,0 +1,100 @@
+use iced::advanced::Renderer;
+use iced::mouse::Interaction;
+use iced::widget::MouseArea;
+
+use crate::internal::state::{Elem, StateMap};
+
+/// Builder for a [`MouseArea`] widget.
+#[must_use]
+pub struct MouseAreaBuilder<S: StateMap> {
+  state: S,
+  on_press: Option<S::Message>,
+  on_release: Option<S::Message>,
+  on_right_press: Option<S::Message>,
+  on_enter: Option<S::Message>,
+  on_exit: Option<S::Message>,
+  interaction: Option<Interaction>,
+}
+impl<S: StateMap> MouseAreaBuilder<S> {
+  pub(crate) fn new(state: S) -> Self {
+    Self {
+      state,
+      on_press: None,
+      on_release: None,
+      on_right_press: None,
+      on_enter: None,
+      on_exit: None,
+      interaction: None,
+    }
+  }
+
+
+  /// Sets the message to produce when the left mouse button is pressed over the [`MouseArea`].
+  pub fn on_press(mut self, message: S::Message) -> Self {
+    self.on_press = Some(message);
+    self
+  }
+
+  /// Sets the message to produce when the left mouse button is released over the [`MouseArea`].
+  pub fn on_release(mut self, message: S::Message) -> Self {
+    self.on_release = Some(message);
+    self
+  }
+
+  /// Sets the message to produce when the right mouse button is pressed over the [`MouseArea`].
+  pub fn on_right_press(mut self, message: S::Message) -> Self {
+    self.on_right_press = Some(message);
+    self
+  }
+
+  /// Sets the message to produce when the mouse cursor enters the [`MouseArea`].
+  pub fn on_enter(mut self, message: S::Message) -> Self {
+    self.on_enter = Some(message);
+    self
+  }
+
+  /// Sets the message to produce when the mouse cursor exits the [`MouseArea`].
+  pub fn on_exit(mut self, message: S::Message) -> Self {
+    self.on_exit = Some(message);
+    self
+  }
+
+  /// Sets the mouse [`Interaction`] (cursor) shown when hovering over the [`MouseArea`].
+  pub fn interaction(mut self, interaction: Interaction) -> Self {
+    self.interaction = Some(interaction);
+    self
+  }
+
+
+  /// Takes the last element out of the builder, creates the [`MouseArea`] with that element as content, then adds the
+  /// mouse area to the builder and returns the builder.
+  pub fn add<'a>(self) -> S::MapOutput where
+    S::Message: Clone,
+    S::Renderer: Renderer,
+    S::Element: Into<Elem<'a, S>>, // For `MouseArea::new`
+    MouseArea<'a, S::Message, S::Theme, S::Renderer>: Into<S::Element>, // For `mouse_area.into()`
+  {
+    self.state.map_last(|content| {
+      let mut mouse_area = MouseArea::new(content);
+      if let Some(message) = self.on_press {
+        mouse_area = mouse_area.on_press(message);
+      }
+      if let Some(message) = self.on_release {
+        mouse_area = mouse_area.on_release(message);
+      }
+      if let Some(message) = self.on_right_press {
+        mouse_area = mouse_area.on_right_press(message);
+      }
+      if let Some(message) = self.on_enter {
+        mouse_area = mouse_area.on_enter(message);
+      }
+      if let Some(message) = self.on_exit {
+        mouse_area = mouse_area.on_exit(message);
+      }
+      if let Some(interaction) = self.interaction {
+        mouse_area = mouse_area.interaction(interaction);
+      }
+      mouse_area.into()
+    })
+  }
+}
@@ -0,0 +1,84 @@
+use std::borrow::Cow;
+
+use iced::{Length, Padding, Pixels};
+use iced::advanced::text::Renderer as TextRenderer;
+use iced::widget::{pick_list, PickList};
+
+use crate::internal::state::StateAppend;
+
+/// Builder for a [`PickList`] widget.
+#[must_use]
+pub struct PickListBuilder<'a, S: StateAppend, T> where
+  T: ToString + Clone + PartialEq + 'a,
+  S::Renderer: TextRenderer,
+  S::Theme: pick_list::Catalog,
+{
+  state: S,
+  pick_list: PickList<'a, T, Cow<'a, [T]>, T, S::Message, S::Theme, S::Renderer>,
+}
+
+impl<'a, S: StateAppend, T> PickListBuilder<'a, S, T> where
+  T: ToString + Clone + PartialEq + 'a,
+  S::Renderer: TextRenderer,
+  S::Theme: pick_list::Catalog,
+{
+  pub(crate) fn new(
+    state: S,
+    options: impl Into<Cow<'a, [T]>>,
+    selected: Option<T>,
+    on_select: impl Fn(T) -> S::Message + 'a,
+  ) -> Self {
+    Self {
+      state,
+      pick_list: PickList::new(options.into(), selected, on_select),
+    }
+  }
+
+
+  /// Sets the placeholder of the pick list, shown when no option is selected.
+  pub fn placeholder(mut self, placeholder: impl Into<String>) -> Self {
+    self.pick_list = self.pick_list.placeholder(placeholder);
+    self
+  }
+
+  /// Sets the width of the pick list.
+  pub fn width(mut self, width: impl Into<Length>) -> Self {
+    self.pick_list = self.pick_list.width(width);
+    self
+  }
+
+  /// Sets the [`Padding`] of the pick list.
+  pub fn padding(mut self, padding: impl Into<Padding>) -> Self {
+    self.pick_list = self.pick_list.padding(padding);
+    self
+  }
+
+  /// Sets the text size of the pick list.
+  pub fn text_size(mut self, size: impl Into<Pixels>) -> Self {
+    self.pick_list = self.pick_list.text_size(size);
+    self
+  }
+
+
+  /// Sets the `styler` function of the pick list.
+  pub fn style(mut self, styler: impl Fn(&S::Theme, pick_list::Status) -> pick_list::Style + 'a) -> Self where
+    <S::Theme as pick_list::Catalog>::Class<'a>: From<pick_list::StyleFn<'a, S::Theme>>
+  {
+    self.pick_list = self.pick_list.style(styler);
+    self
+  }
+
+  /// Sets the `class` of the pick list.
+  pub fn class(mut self, class: impl Into<<S::Theme as pick_list::Catalog>::Class<'a>>) -> Self {
+    self.pick_list = self.pick_list.class(class);
+    self
+  }
+
+
+  /// Adds the [`PickList`] widget to the builder and returns the builder.
+  pub fn add(self) -> S::AddOutput where
+    PickList<'a, T, Cow<'a, [T]>, T, S::Message, S::Theme, S::Renderer>: Into<S::Element>
+  {
+    self.state.append(self.pick_list)
+  }
+}
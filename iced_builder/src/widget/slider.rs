@@ -0,0 +1,81 @@
+use std::ops::RangeInclusive;
+
+use iced::{Length, Pixels};
+use iced::widget::{Slider, slider};
+use num_traits::FromPrimitive;
+
+use crate::internal::state::StateAppend;
+
+/// Builder for a [`Slider`] widget.
+#[must_use]
+pub struct SliderBuilder<'a, S: StateAppend, T> where
+  S::Theme: slider::Catalog,
+{
+  state: S,
+  slider: Slider<'a, T, S::Message, S::Theme>,
+}
+
+impl<'a, S: StateAppend, T> SliderBuilder<'a, S, T> where
+  T: Copy + From<u8> + PartialOrd + Into<f64> + FromPrimitive,
+  S::Theme: slider::Catalog,
+{
+  pub(crate) fn new(
+    state: S,
+    range: RangeInclusive<T>,
+    value: T,
+    on_change: impl Fn(T) -> S::Message + 'a,
+  ) -> Self {
+    Self {
+      state,
+      slider: Slider::new(range, value, on_change),
+    }
+  }
+
+
+  /// Sets the step size of the slider.
+  pub fn step(mut self, step: impl Into<T>) -> Self {
+    self.slider = self.slider.step(step);
+    self
+  }
+
+  /// Sets the message that is produced when the mouse is released after dragging the slider.
+  pub fn on_release(mut self, on_release: S::Message) -> Self {
+    self.slider = self.slider.on_release(on_release);
+    self
+  }
+
+  /// Sets the width of the slider.
+  pub fn width(mut self, width: impl Into<Length>) -> Self {
+    self.slider = self.slider.width(width);
+    self
+  }
+
+  /// Sets the height of the slider.
+  pub fn height(mut self, height: impl Into<Pixels>) -> Self {
+    self.slider = self.slider.height(height);
+    self
+  }
+
+
+  /// Sets the `styler` function of the slider.
+  pub fn style(mut self, styler: impl Fn(&S::Theme, slider::Status) -> slider::Style + 'a) -> Self where
+    <S::Theme as slider::Catalog>::Class<'a>: From<slider::StyleFn<'a, S::Theme>>
+  {
+    self.slider = self.slider.style(styler);
+    self
+  }
+
+  /// Sets the `class` of the slider.
+  pub fn class(mut self, class: impl Into<<S::Theme as slider::Catalog>::Class<'a>>) -> Self {
+    self.slider = self.slider.class(class);
+    self
+  }
+
+
+  /// Adds the [`Slider`] widget to the builder and returns the builder.
+  pub fn add(self) -> S::AddOutput where
+    Slider<'a, T, S::Message, S::Theme>: Into<S::Element>
+  {
+    self.state.append(self.slider)
+  }
+}
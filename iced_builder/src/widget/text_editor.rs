@@ -0,0 +1,77 @@
+use iced::{Length, Padding};
+use iced::advanced::text::highlighter::PlainText;
+use iced::advanced::text::Renderer as TextRenderer;
+use iced::widget::{text_editor, TextEditor};
+
+use crate::internal::state::StateAppend;
+
+/// Builder for a [`TextEditor`] widget.
+#[must_use]
+pub struct TextEditorBuilder<'a, S: StateAppend> where
+  S::Renderer: TextRenderer,
+  S::Theme: text_editor::Catalog,
+{
+  state: S,
+  text_editor: TextEditor<'a, PlainText, S::Message, S::Theme, S::Renderer>,
+}
+
+impl<'a, S: StateAppend> TextEditorBuilder<'a, S> where
+  S::Renderer: TextRenderer,
+  S::Theme: text_editor::Catalog,
+{
+  pub(crate) fn new(state: S, content: &'a text_editor::Content<S::Renderer>) -> Self {
+    Self {
+      state,
+      text_editor: TextEditor::new(content),
+    }
+  }
+
+
+  /// Sets the function that produces a message when an [`Action`](text_editor::Action) is performed in the text
+  /// editor. The text editor is read-only if this is not set.
+  pub fn on_action(mut self, on_action: impl Fn(text_editor::Action) -> S::Message + 'a) -> Self {
+    self.text_editor = self.text_editor.on_action(on_action);
+    self
+  }
+
+  /// Sets the font of the text editor.
+  pub fn font(mut self, font: impl Into<<S::Renderer as TextRenderer>::Font>) -> Self {
+    self.text_editor = self.text_editor.font(font);
+    self
+  }
+
+  /// Sets the [`Padding`] of the text editor.
+  pub fn padding(mut self, padding: impl Into<Padding>) -> Self {
+    self.text_editor = self.text_editor.padding(padding);
+    self
+  }
+
+  /// Sets the height of the text editor.
+  pub fn height(mut self, height: impl Into<Length>) -> Self {
+    self.text_editor = self.text_editor.height(height);
+    self
+  }
+
+
+  /// Sets the `styler` function of the text editor.
+  pub fn style(mut self, styler: impl Fn(&S::Theme, text_editor::Status) -> text_editor::Style + 'a) -> Self where
+    <S::Theme as text_editor::Catalog>::Class<'a>: From<text_editor::StyleFn<'a, S::Theme>>
+  {
+    self.text_editor = self.text_editor.style(styler);
+    self
+  }
+
+  /// Sets the `class` of the text editor.
+  pub fn class(mut self, class: impl Into<<S::Theme as text_editor::Catalog>::Class<'a>>) -> Self {
+    self.text_editor = self.text_editor.class(class);
+    self
+  }
+
+
+  /// Adds the [`TextEditor`] widget to the builder and returns the builder.
+  pub fn add(self) -> S::AddOutput where
+    TextEditor<'a, PlainText, S::Message, S::Theme, S::Renderer>: Into<S::Element>
+  {
+    self.state.append(self.text_editor)
+  }
+}
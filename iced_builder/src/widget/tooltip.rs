@@ -0,0 +1,97 @@
+use std::borrow::Cow;
+
+use iced::Pixels;
+use iced::advanced::text::Renderer as TextRenderer;
+use iced::widget::{container, Text, text, Tooltip};
+use iced::widget::tooltip::Position;
+
+use crate::internal::state::{Elem, StateMap};
+
+/// Builder for a [`Tooltip`] widget.
+#[must_use]
+pub struct TooltipBuilder<'a, S: StateMap> where
+  S::Theme: container::Catalog
+{
+  state: S,
+  text: Cow<'a, str>,
+  position: Position,
+  gap: f32,
+  padding: f32,
+  snap_within_viewport: bool,
+  class: <S::Theme as container::Catalog>::Class<'a>,
+}
+impl<'a, S: StateMap> TooltipBuilder<'a, S> where
+  S::Theme: container::Catalog
+{
+  pub(crate) fn new(state: S, text: Cow<'a, str>) -> Self {
+    Self {
+      state,
+      text,
+      position: Position::Top,
+      gap: 0.0,
+      padding: 5.0,
+      snap_within_viewport: true,
+      class: <S::Theme as container::Catalog>::default(),
+    }
+  }
+
+
+  /// Sets the [`Position`] of the [`Tooltip`] relative to its content.
+  pub fn position(mut self, position: Position) -> Self {
+    self.position = position;
+    self
+  }
+
+  /// Sets the gap between the content and the [`Tooltip`].
+  pub fn gap(mut self, gap: impl Into<Pixels>) -> Self {
+    self.gap = gap.into().0;
+    self
+  }
+
+  /// Sets the padding of the [`Tooltip`].
+  pub fn padding(mut self, padding: impl Into<Pixels>) -> Self {
+    self.padding = padding.into().0;
+    self
+  }
+
+  /// Sets whether the [`Tooltip`] is snapped within the viewport.
+  pub fn snap_within_viewport(mut self, snap: bool) -> Self {
+    self.snap_within_viewport = snap;
+    self
+  }
+
+
+  /// Sets the `styler` function of the [`Tooltip`].
+  pub fn style(mut self, styler: impl Fn(&S::Theme) -> container::Style + 'a) -> Self where
+    <S::Theme as container::Catalog>::Class<'a>: From<container::StyleFn<'a, S::Theme>>,
+  {
+    self.class = (Box::new(styler) as container::StyleFn<'a, S::Theme>).into();
+    self
+  }
+
+  /// Sets the `class` of the [`Tooltip`].
+  pub fn class(mut self, class: impl Into<<S::Theme as container::Catalog>::Class<'a>>) -> Self {
+    self.class = class.into();
+    self
+  }
+
+
+  /// Takes the last element out of the builder, creates the [`Tooltip`] with that element as content, then adds the
+  /// tooltip to the builder and returns the builder.
+  pub fn add(self) -> S::MapOutput where
+    S::Renderer: TextRenderer,
+    S::Theme: text::Catalog,
+    S::Element: Into<Elem<'a, S>>, // For `Tooltip::new`
+    Text<'a, S::Theme, S::Renderer>: Into<Elem<'a, S>>, // For `Tooltip::new`
+    Tooltip<'a, S::Message, S::Theme, S::Renderer>: Into<S::Element>, // For `tooltip.into()`
+  {
+    self.state.map_last(|content| {
+      Tooltip::new(content, Text::new(self.text), self.position)
+        .gap(self.gap)
+        .padding(self.padding)
+        .snap_within_viewport(self.snap_within_viewport)
+        .class(self.class)
+        .into()
+    })
+  }
+}
@@ -1,28 +1,48 @@
-use iced::{Alignment, Element, Event, Length, Point, Rectangle, Size, Vector};
+use iced::{Alignment, Element, Event, Length, mouse, Point, Rectangle, Size, Vector};
 use iced::advanced::{Clipboard, Layout, overlay, Renderer, renderer, Shell, Widget};
 use iced::advanced::layout::{Limits, Node};
-use iced::advanced::widget::{Operation, Tree};
+use iced::advanced::widget::{Operation, tree, Tree};
 use iced::event::Status;
 use iced::mouse::{Cursor, Interaction};
 
+/// Width of an element in a [constrained row](ConstrainedRow).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Width {
+  /// Fill a portion of the width that remains after subtracting fixed widths and spacing, relative to the fill portions
+  /// of other elements.
+  FillPortion(f32),
+  /// Fixed width in pixels.
+  Fixed(f32),
+}
+
 /// A constraint to apply to an element in a [constrained row](ConstrainedRow).
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Constraint {
-  width_fill_portion: f32,
+  width: Width,
   horizontal_alignment: Alignment,
   vertical_alignment: Alignment,
 }
 
 impl Constraint {
   pub fn new(width_fill_portion: f32, horizontal_alignment: Alignment, vertical_alignment: Alignment) -> Self {
-    Self { width_fill_portion, horizontal_alignment, vertical_alignment }
+    Self { width: Width::FillPortion(width_fill_portion), horizontal_alignment, vertical_alignment }
   }
+  /// Creates a constraint with a fixed `width` in pixels.
+  pub fn fixed(width: f32, horizontal_alignment: Alignment, vertical_alignment: Alignment) -> Self {
+    Self { width: Width::Fixed(width), horizontal_alignment, vertical_alignment }
+  }
+
+  #[inline]
+  pub fn width(&self) -> Width { self.width }
+  /// Returns `true` if this constraint has a [fixed width](Width::Fixed).
+  #[inline]
+  pub fn is_fixed(&self) -> bool { matches!(self.width, Width::Fixed(_)) }
 }
 
 impl Default for Constraint {
   fn default() -> Self {
     Self {
-      width_fill_portion: 1.0,
+      width: Width::FillPortion(1.0),
       horizontal_alignment: Alignment::Start,
       vertical_alignment: Alignment::Center
     }
@@ -31,7 +51,7 @@ impl Default for Constraint {
 
 impl From<f32> for Constraint {
   fn from(width_fill_portion: f32) -> Self {
-    Self { width_fill_portion, ..Self::default() }
+    Self { width: Width::FillPortion(width_fill_portion), ..Self::default() }
   }
 }
 
@@ -41,6 +61,15 @@ impl From<u32> for Constraint {
   }
 }
 
+impl From<Width> for Constraint {
+  fn from(width: Width) -> Self {
+    Self { width, ..Self::default() }
+  }
+}
+
+
+/// Half the width of the region around a column boundary in which a column can be resized by dragging.
+const RESIZE_GRIP_HALF_WIDTH: f32 = 4.0;
 
 /// A row where [constraints](Constraint) are applied to each element in the row.
 pub struct ConstrainedRow<'a, M, T, R> {
@@ -48,6 +77,9 @@ pub struct ConstrainedRow<'a, M, T, R> {
   height: f32,
   constraints: Vec<Constraint>,
   elements: Vec<Element<'a, M, T, R>>,
+  on_resize: Option<Box<dyn Fn(usize, f32) -> M + 'a>>,
+  min_resize_width: f32,
+  max_resize_width: f32,
 }
 
 impl<'a, M, T, R> ConstrainedRow<'a, M, T, R> {
@@ -72,6 +104,9 @@ impl<'a, M, T, R> ConstrainedRow<'a, M, T, R> {
       height: 24.0,
       constraints,
       elements,
+      on_resize: None,
+      min_resize_width: 16.0,
+      max_resize_width: f32::INFINITY,
     }
   }
 
@@ -95,12 +130,90 @@ impl<'a, M, T, R> ConstrainedRow<'a, M, T, R> {
   }
 
 
+  /// Enables resizing columns by dragging the boundary between two elements. While the boundary at the right of column
+  /// `index` is dragged, its constraint is changed to a [fixed width](Width::Fixed), and the message created by
+  /// `on_resize(index, width)` is published. The new width should be passed back as a constraint in the next view.
+  pub fn on_resize(mut self, on_resize: impl Fn(usize, f32) -> M + 'a) -> Self {
+    self.on_resize = Some(Box::new(on_resize));
+    self
+  }
+
+  /// Sets the minimum and maximum width that columns can be resized to. Defaults to `16.0` and [`f32::INFINITY`].
+  pub fn resize_width_limits(mut self, min_width: f32, max_width: f32) -> Self {
+    self.min_resize_width = min_width;
+    self.max_resize_width = max_width;
+    self
+  }
+
+
   /// Appends `constraint` and `element` to the constraints and elements of the row.
   pub fn push(mut self, constraint: impl Into<Constraint>, element: impl Into<Element<'a, M, T, R>>) -> Self {
     self.constraints.push(constraint.into());
     self.elements.push(element.into());
     self
   }
+
+
+  /// Gets the total width of this row if all its constraints have [fixed widths](Width::Fixed), or `None` otherwise.
+  pub fn fixed_width(&self) -> Option<f32> {
+    if self.constraints.is_empty() || !self.constraints.iter().all(Constraint::is_fixed) {
+      return None;
+    }
+    let total_fixed_width: f32 = self.constraints.iter().map(|c| match c.width {
+      Width::Fixed(w) => w,
+      Width::FillPortion(_) => 0.0,
+    }).sum();
+    Some(total_fixed_width + self.spacing * self.constraints.len().saturating_sub(1) as f32)
+  }
+
+  /// Gets the width of each column when this row is laid out with `max_width`.
+  fn column_widths(&self, max_width: f32) -> impl Iterator<Item=f32> + '_ {
+    let total_spacing = self.spacing * self.constraints.len().saturating_sub(1) as f32;
+    let (total_fill_portion, total_fixed_width) = self.constraints.iter()
+      .fold((0.0, 0.0), |(fill_portion, fixed_width), c| match c.width {
+        Width::FillPortion(p) => (fill_portion + p, fixed_width),
+        Width::Fixed(w) => (fill_portion, fixed_width + w),
+      });
+    // Fill portions share the width that remains after fixed widths and spacing. With unbounded width (e.g., inside a
+    // horizontal scrollable) there is no remaining width to share.
+    let available_width = max_width - total_spacing - total_fixed_width;
+    let available_width = if available_width.is_finite() { available_width.max(0.0) } else { 0.0 };
+    self.constraints.iter().map(move |c| match c.width {
+      Width::FillPortion(p) => (p / total_fill_portion) * available_width,
+      Width::Fixed(w) => w,
+    })
+  }
+
+  /// Gets the index of the column whose right boundary has a resize grip at `x` position relative to this row, along
+  /// with the current width of that column. Only boundaries _between_ columns have grips.
+  fn resize_grip_at(&self, x: f32, max_width: f32) -> Option<(usize, f32)> {
+    let last_col = self.constraints.len().checked_sub(1)?;
+    let mut boundary = 0.0;
+    for (col, width) in self.column_widths(max_width).enumerate().take(last_col) {
+      boundary += width;
+      let grip_center = boundary + self.spacing / 2.0;
+      if (x - grip_center).abs() <= RESIZE_GRIP_HALF_WIDTH {
+        return Some((col, width));
+      }
+      boundary += self.spacing;
+    }
+    None
+  }
+}
+
+/// Column that is being resized by dragging its right boundary.
+#[derive(Copy, Clone)]
+struct ColumnResize {
+  col: usize,
+  /// Absolute x-position of the cursor when the drag started.
+  start_x: f32,
+  /// Width of the column when the drag started.
+  start_width: f32,
+}
+
+#[derive(Default)]
+struct State {
+  resize: Option<ColumnResize>,
 }
 
 impl<'a, M, T, R> Into<Element<'a, M, T, R>> for ConstrainedRow<'a, M, T, R> where
@@ -114,6 +227,13 @@ impl<'a, M, T, R> Into<Element<'a, M, T, R>> for ConstrainedRow<'a, M, T, R> whe
 }
 
 impl<'a, M, T, R: Renderer> Widget<M, T, R> for ConstrainedRow<'a, M, T, R> {
+  fn tag(&self) -> tree::Tag {
+    tree::Tag::of::<State>()
+  }
+  fn state(&self) -> tree::State {
+    tree::State::new(State::default())
+  }
+
   fn children(&self) -> Vec<Tree> {
     self.elements.iter().map(Tree::new).collect()
   }
@@ -121,19 +241,21 @@ impl<'a, M, T, R: Renderer> Widget<M, T, R> for ConstrainedRow<'a, M, T, R> {
     tree.diff_children(&self.elements);
   }
 
-  fn size(&self) -> Size<Length> { Size::new(Length::Fill, Length::Fixed(self.height)) }
+  fn size(&self) -> Size<Length> {
+    let width = self.fixed_width().map(Length::Fixed).unwrap_or(Length::Fill);
+    Size::new(width, Length::Fixed(self.height))
+  }
   fn layout(&self, tree: &mut Tree, renderer: &R, limits: &Limits) -> Node {
     let limits = limits.max_height(self.height);
-    let max = limits.max();
-
-    let cells = self.elements.len();
-    let total_fill_portion: f32 = self.constraints.iter().map(|c| c.width_fill_portion).sum();
-    let available_width = max.width - (self.spacing * cells.saturating_sub(1) as f32);
+    let mut max = limits.max();
+    if let Some(fixed_width) = self.fixed_width() {
+      max.width = fixed_width;
+    }
 
-    let mut nodes = Vec::with_capacity(cells);
+    let mut nodes = Vec::with_capacity(self.elements.len());
     let mut x = 0.0;
-    for ((element, constraint), tree) in self.elements.iter().zip(&self.constraints).zip(&mut tree.children) {
-      let width = (constraint.width_fill_portion / total_fill_portion) * available_width;
+    let widths = self.column_widths(max.width);
+    for (((element, constraint), tree), width) in self.elements.iter().zip(&self.constraints).zip(&mut tree.children).zip(widths) {
       let element_limits = limits.max_width(width);
       let node = element.as_widget()
         .layout(tree, renderer, &element_limits)
@@ -169,9 +291,52 @@ impl<'a, M, T, R: Renderer> Widget<M, T, R> for ConstrainedRow<'a, M, T, R> {
     shell: &mut Shell<'_, M>,
     viewport: &Rectangle,
   ) -> Status {
+    if let Some(on_resize) = &self.on_resize {
+      let state = tree.state.downcast_mut::<State>();
+      if let Some(resize) = state.resize {
+        // Children do not receive events while a column is being resized.
+        match event {
+          Event::Mouse(mouse::Event::CursorMoved { position }) => {
+            let width = (resize.start_width + position.x - resize.start_x)
+              .clamp(self.min_resize_width, self.max_resize_width);
+            if let Some(constraint) = self.constraints.get_mut(resize.col) {
+              if constraint.width != Width::Fixed(width) {
+                constraint.width = Width::Fixed(width);
+                shell.invalidate_layout();
+                shell.publish(on_resize(resize.col, width));
+              }
+            }
+          }
+          Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+            state.resize = None;
+          }
+          _ => {}
+        }
+        return Status::Captured;
+      }
+      if let Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) = event {
+        if let Some(position) = cursor.position_in(layout.bounds()) {
+          if let Some((col, start_width)) = self.resize_grip_at(position.x, layout.bounds().width) {
+            let start_x = layout.position().x + position.x;
+            state.resize = Some(ColumnResize { col, start_x, start_width });
+            return Status::Captured;
+          }
+        }
+      }
+    }
     crate::child::on_event(&mut self.elements, tree, event, layout, cursor, renderer, clipboard, shell, viewport)
   }
   fn mouse_interaction(&self, tree: &Tree, layout: Layout, cursor: Cursor, viewport: &Rectangle, renderer: &R) -> Interaction {
+    if self.on_resize.is_some() {
+      if tree.state.downcast_ref::<State>().resize.is_some() {
+        return Interaction::ResizingHorizontally;
+      }
+      if let Some(position) = cursor.position_in(layout.bounds()) {
+        if self.resize_grip_at(position.x, layout.bounds().width).is_some() {
+          return Interaction::ResizingHorizontally;
+        }
+      }
+    }
     crate::child::mouse_interaction(&self.elements, tree, layout, cursor, viewport, renderer)
   }
   fn operate(&self, tree: &mut Tree, layout: Layout, renderer: &R, operation: &mut dyn Operation<()>) {
@@ -1,24 +1,34 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Range;
+use std::rc::Rc;
+use std::time::Duration;
 
-use iced::{Element, Event, Length, Point, Rectangle, Size, touch};
+use iced::{Color, Element, Event, Length, mouse, Point, Rectangle, Size, touch, window};
 use iced::advanced::{Clipboard, Layout, Renderer, renderer, Shell, Widget};
 use iced::advanced::layout::{Limits, Node};
 use iced::advanced::widget::{Operation, tree, Tree};
 use iced::event::Status;
 use iced::mouse::{Cursor, Interaction};
+use iced::time::Instant;
 
 pub struct Body<'a, M, T, R, F> {
   spacing: f32,
   column_count: usize,
   row_height: f32,
-  row_height_plus_spacing: f32,
+  row_height_fn: Option<(u64, Box<dyn Fn(usize) -> f32 + 'a>)>,
   row_count: usize,
-  last_row_index: usize,
   cell_to_element: F,
   phantom_row: Element<'a, M, T, R>,
   element_state: RefCell<ElementState<'a, M, T, R>>,
+  on_reorder: Option<Box<dyn Fn(usize, usize) -> M + 'a>>,
+  selected_rows: Option<&'a HashSet<usize>>,
+  selected_color: Option<Color>,
+  on_select: Option<Box<dyn Fn(usize) -> M + 'a>>,
+  hovered_color: Option<Color>,
+  stripe_color: Option<Color>,
+  on_double_click: Option<Box<dyn Fn(usize) -> M + 'a>>,
+  double_click_threshold: Duration,
 }
 
 impl<'a, M, T, R, F> Body<'a, M, T, R, F> {
@@ -34,14 +44,81 @@ impl<'a, M, T, R, F> Body<'a, M, T, R, F> {
       spacing,
       column_count,
       row_height,
-      row_height_plus_spacing: row_height + spacing,
+      row_height_fn: None,
       row_count,
-      last_row_index: row_count.saturating_sub(1),
       cell_to_element,
       phantom_row,
-      element_state: Default::default()
+      element_state: Default::default(),
+      on_reorder: None,
+      selected_rows: None,
+      selected_color: None,
+      on_select: None,
+      hovered_color: None,
+      stripe_color: None,
+      on_double_click: None,
+      double_click_threshold: Duration::from_millis(500),
     }
   }
+
+  /// Sets the function that gets the height of a row by its index, for rows with variable heights, along with an id that
+  /// identifies the function. Cached row offsets are recalculated when the id changes. All rows have the same height if
+  /// this is `None`.
+  pub fn row_height_fn(mut self, row_height_fn: Option<(u64, Box<dyn Fn(usize) -> f32 + 'a>)>) -> Self {
+    self.row_height_fn = row_height_fn;
+    self
+  }
+
+  /// Sets the function that creates a message when a row is dragged from index `from` and dropped at index `to`.
+  /// Dragging rows is disabled if this is `None`.
+  pub fn on_reorder(mut self, on_reorder: Option<Box<dyn Fn(usize, usize) -> M + 'a>>) -> Self {
+    self.on_reorder = on_reorder;
+    self
+  }
+
+  /// Sets the indices of rows to highlight as selected. No rows are highlighted if this is `None`.
+  pub fn selected_rows(mut self, selected_rows: Option<&'a HashSet<usize>>) -> Self {
+    self.selected_rows = selected_rows;
+    self
+  }
+
+  /// Sets the background color of selected rows. If this is `None`, a translucent version of the text color is used.
+  pub fn selected_color(mut self, selected_color: Option<Color>) -> Self {
+    self.selected_color = selected_color;
+    self
+  }
+
+  /// Sets the function that creates a message when row `index` is double-clicked outside of any cell widget that
+  /// captures the click. The second click of a double click does not create a selection message.
+  pub fn on_double_click(mut self, on_double_click: Option<Box<dyn Fn(usize) -> M + 'a>>) -> Self {
+    self.on_double_click = on_double_click;
+    self
+  }
+
+  /// Sets the maximum time between two clicks on the same row for them to count as a double click.
+  pub fn double_click_threshold(mut self, double_click_threshold: Duration) -> Self {
+    self.double_click_threshold = double_click_threshold;
+    self
+  }
+
+  /// Sets the background color of the row under the cursor. If this is `None`, a faint version of the text color is used.
+  pub fn hovered_color(mut self, hovered_color: Option<Color>) -> Self {
+    self.hovered_color = hovered_color;
+    self
+  }
+
+  /// Sets the background color of odd rows, alternating with the default background of even rows. Rows are not striped
+  /// if this is `None`.
+  pub fn stripe_color(mut self, stripe_color: Option<Color>) -> Self {
+    self.stripe_color = stripe_color;
+    self
+  }
+
+  /// Sets the function that creates a message when row `index` is clicked outside of any cell widget that captures the
+  /// click. Selecting rows is disabled if this is `None`.
+  pub fn on_select(mut self, on_select: Option<Box<dyn Fn(usize) -> M + 'a>>) -> Self {
+    self.on_select = on_select;
+    self
+  }
 }
 
 
@@ -75,6 +152,159 @@ impl<'a, M, T, R> ElementState<'a, M, T, R> {
 struct TreeState {
   trees: HashMap<(usize, usize), Tree>,
   previous_rows: Range<usize>,
+  drag: Option<RowDrag>,
+  hovered_row: Option<usize>,
+  last_click: Option<RowClick>,
+  row_offsets: Option<RowOffsets>,
+}
+
+/// A click on a row, used to detect double clicks.
+#[derive(Copy, Clone)]
+struct RowClick {
+  row: usize,
+  time: Instant,
+}
+impl RowClick {
+  /// Checks whether a click on `row` at `time` forms a double click with this previous click.
+  fn is_double_click(&self, row: usize, time: Instant, threshold: Duration) -> bool {
+    self.row == row && time.saturating_duration_since(self.time) <= threshold
+  }
+}
+
+/// Cached prefix-sum of variable row heights.
+struct RowOffsets {
+  /// Row count the offsets were calculated for.
+  row_count: usize,
+  /// Spacing the offsets were calculated for.
+  spacing: f32,
+  /// Id of the row height function the offsets were calculated with.
+  row_height_fn_id: u64,
+  /// `offsets[row]` is the y-position of `row`, and `offsets[row_count]` is the total height of all rows plus spacing.
+  offsets: Rc<[f32]>,
+}
+impl RowOffsets {
+  /// Calculates the offsets of `row_count` rows with `spacing` between them, getting row heights from `row_height_fn`.
+  fn calculate(row_count: usize, spacing: f32, row_height_fn: impl Fn(usize) -> f32) -> Rc<[f32]> {
+    let mut offsets = Vec::with_capacity(row_count + 1);
+    let mut y = 0.0;
+    offsets.push(y);
+    for row in 0..row_count {
+      y += row_height_fn(row) + spacing;
+      offsets.push(y);
+    }
+    offsets.into()
+  }
+}
+
+/// Vertical geometry of the rows of a table body.
+#[derive(Clone)]
+struct RowGeometry {
+  spacing: f32,
+  row_count: usize,
+  heights: RowHeights,
+}
+
+/// Heights of the rows of a table body.
+#[derive(Clone)]
+enum RowHeights {
+  /// All rows have the same height.
+  Uniform { row_height: f32, row_height_plus_spacing: f32 },
+  /// Rows have variable heights, with offsets from [`RowOffsets`].
+  Variable { offsets: Rc<[f32]> },
+}
+
+impl RowGeometry {
+  /// Creates the geometry of `row_count` rows that all have `row_height`, with `spacing` between them.
+  #[inline]
+  fn uniform(spacing: f32, row_count: usize, row_height: f32) -> Self {
+    let heights = RowHeights::Uniform { row_height, row_height_plus_spacing: row_height + spacing };
+    Self { spacing, row_count, heights }
+  }
+
+  /// Creates the geometry of `row_count` rows with variable heights, positioned at `offsets` from [`RowOffsets`].
+  #[inline]
+  fn variable(spacing: f32, row_count: usize, offsets: Rc<[f32]>) -> Self {
+    Self { spacing, row_count, heights: RowHeights::Variable { offsets } }
+  }
+
+  /// Gets the y-position of `row` relative to the table body. `row` may be `row_count`, returning the total height of all
+  /// rows plus spacing.
+  #[inline]
+  fn top(&self, row: usize) -> f32 {
+    match &self.heights {
+      RowHeights::Uniform { row_height_plus_spacing, .. } => row as f32 * row_height_plus_spacing,
+      RowHeights::Variable { offsets } => offsets[row],
+    }
+  }
+
+  /// Gets the height of `row`.
+  #[inline]
+  fn height(&self, row: usize) -> f32 {
+    match &self.heights {
+      RowHeights::Uniform { row_height, .. } => *row_height,
+      RowHeights::Variable { offsets } => offsets[row + 1] - offsets[row] - self.spacing,
+    }
+  }
+
+  /// Gets the total height of all rows, including spacing between rows.
+  #[inline]
+  fn total_height(&self) -> f32 {
+    (self.top(self.row_count) - self.spacing).max(0.0)
+  }
+
+  /// Gets the index of the row whose area, including its spacing below, contains `y`. Does not check bounds: may return
+  /// `row_count` or higher when `y` is past the last row.
+  fn index_at(&self, y: f32) -> usize {
+    match &self.heights {
+      RowHeights::Uniform { row_height_plus_spacing, .. } => (y / row_height_plus_spacing).max(0.0) as usize,
+      RowHeights::Variable { offsets } => offsets.partition_point(|offset| *offset <= y).saturating_sub(1),
+    }
+  }
+
+  /// Gets the row for `y` position relative to the table body, or `None` if there is no row at `y`.
+  fn row_at(&self, y: f32) -> Option<usize> {
+    if y < 0.0 { return None; } // Out of bounds
+    let row = self.index_at(y);
+    if row >= self.row_count {
+      None // Out of bounds
+    } else if y > self.top(row) + self.height(row) {
+      None // On row spacing
+    } else {
+      Some(row)
+    }
+  }
+
+  /// Gets the index (in `0..=row_count`) of the row boundary closest to `y` position relative to the table body.
+  fn insert_index_at(&self, y: f32) -> usize {
+    if y <= 0.0 || self.row_count == 0 { return 0; }
+    let row = self.index_at(y).min(self.row_count - 1);
+    let top = self.top(row);
+    let middle = top + (self.top(row + 1) - top) / 2.0;
+    if y > middle { row + 1 } else { row }
+  }
+
+  /// Gets the rows that are (partially) visible in a viewport at `y` position relative to the table body with `height`.
+  fn visible(&self, y: f32, height: f32) -> Range<usize> {
+    if self.row_count == 0 { return 0..0; }
+    let start = self.index_at(y.max(0.0)).min(self.row_count - 1); // Can't start past last row.
+    let end = self.index_at(y + height).saturating_add(1).min(self.row_count); // Can't be longer than number of rows.
+    start..end
+  }
+}
+
+/// A row that is being dragged.
+#[derive(Copy, Clone)]
+struct RowDrag {
+  /// Index of the dragged row.
+  from: usize,
+  /// Index (in `0..=row_count`) of the row boundary the dragged row would be inserted at when dropped.
+  insert_at: usize,
+}
+impl RowDrag {
+  /// Gets the index the dragged row ends up at after removing it from `from` and inserting it at `insert_at`.
+  fn to(&self) -> usize {
+    if self.insert_at > self.from { self.insert_at - 1 } else { self.insert_at }
+  }
 }
 impl TreeState {
   pub fn get_or_insert<'a, M, T, R: Renderer>(&mut self, row: usize, col: usize, element: &Element<'a, M, T, R>) -> &mut Tree {
@@ -116,12 +346,14 @@ impl<'a, F, M, T, R> Widget<M, T, R> for Body<'a, M, T, R, F> where
   }
 
   fn layout(&self, tree: &mut Tree, renderer: &R, limits: &Limits) -> Node {
-    let max_height = self.row_count as f32 * self.row_height + self.row_count.saturating_sub(1) as f32 * self.spacing;
-    let limits = limits.max_height(max_height);
+    let geometry = self.geometry(&mut tree.state.downcast_ref::<RefCell<TreeState>>().borrow_mut());
+    let limits = limits.max_height(geometry.total_height());
     // The phantom row lays out the cells of a single row. We will re-use that layout for every row in the table body,
-    // but corrects its y-position to correspond to the actual row.
+    // but corrects its y-position and height to correspond to the actual row.
     let node = self.phantom_row.as_widget().layout(&mut tree.children[0], renderer, &limits.height(self.row_height));
-    Node::with_children(limits.max(), vec![node])
+    // Take the width of the phantom row, which is narrower than the maximum width when all columns have fixed widths.
+    let size = Size::new(node.size().width, limits.max().height);
+    Node::with_children(size, vec![node])
   }
 
 
@@ -141,28 +373,13 @@ impl<'a, F, M, T, R> Widget<M, T, R> for Body<'a, M, T, R, F> where
 
     let mut element_state = self.element_state.borrow_mut();
     let mut tree_state = tree.state.downcast_ref::<RefCell<TreeState>>().borrow_mut();
+    let geometry = self.geometry(&mut tree_state);
 
     let absolute_y = layout.position().y;
     let y = viewport.y - absolute_y;
 
     // Calculate visible rows.
-    let rows = {
-      let start = y / self.row_height_plus_spacing;
-      let start = start.max(0.0); // Can't start on negative row.
-      let start_floored = start.floor(); // Use floor so partial rows are visible.
-      let floored_amount = start - start_floored; // Store how much we floored off for length calculation.
-      let start = start_floored as usize;
-      let start = start.min(self.last_row_index); // Can't start past last row.
-
-      // Use floored amount to account for extra space at the bottom in which an additional row can be visible.
-      let additional_height = floored_amount * self.row_height_plus_spacing;
-      let length = (viewport.height + additional_height) / self.row_height_plus_spacing;
-      let length = length.ceil() as usize; // Use ceil so partial rows are visible.
-
-      let end = start + length;
-      let end = end.min(self.row_count); // Can't be longer than number of rows.
-      start..end
-    };
+    let rows = geometry.visible(y, viewport.height);
 
     // Remove trees and elements from rows that are no longer visible.
     let previous_rows = tree_state.previous_rows.clone();
@@ -179,6 +396,25 @@ impl<'a, F, M, T, R> Widget<M, T, R> for Body<'a, M, T, R, F> where
       }
     }
 
+    // Draw backgrounds of striped, hovered, and selected rows before their cells, so that cells are drawn on top.
+    if let Some(color) = self.stripe_color {
+      for row in rows.clone().filter(|row| is_striped(*row)) {
+        Self::fill_row(renderer, layout, &geometry, row, color);
+      }
+    }
+    if let (Some(row), None) = (tree_state.hovered_row, tree_state.drag) {
+      if rows.contains(&row) {
+        let color = self.hovered_color.unwrap_or(Color { a: 0.05, ..style.text_color });
+        Self::fill_row(renderer, layout, &geometry, row, color);
+      }
+    }
+    if let Some(selected_rows) = self.selected_rows {
+      let color = self.selected_color.unwrap_or(Color { a: 0.15, ..style.text_color });
+      for row in rows.clone().filter(|row| selected_rows.contains(row)) {
+        Self::fill_row(renderer, layout, &geometry, row, color);
+      }
+    }
+
     // Draw all table cells.
     for row in rows.clone() {
       for (col, cell_bounds) in (0..self.column_count).zip(Self::get_cell_bounds(layout)) {
@@ -186,6 +422,7 @@ impl<'a, F, M, T, R> Widget<M, T, R> for Body<'a, M, T, R, F> where
           row,
           col,
           cell_bounds,
+          &geometry,
           absolute_y,
           renderer,
           &mut element_state,
@@ -203,6 +440,16 @@ impl<'a, F, M, T, R> Widget<M, T, R> for Body<'a, M, T, R, F> where
       }
     }
 
+    // Draw drop indicator at the row boundary the dragged row would be inserted at.
+    if let Some(drag) = tree_state.drag {
+      if drag.to() != drag.from {
+        let thickness = 2.0;
+        let y = absolute_y + geometry.top(drag.insert_at) - (self.spacing + thickness) / 2.0;
+        let bounds = Rectangle::new(Point::new(layout.position().x, y), Size::new(layout.bounds().width, thickness));
+        renderer.fill_quad(renderer::Quad { bounds, ..renderer::Quad::default() }, style.text_color);
+      }
+    }
+
     // Store current row indices.
     tree_state.previous_rows = rows;
   }
@@ -235,29 +482,101 @@ impl<'a, F, M, T, R> Widget<M, T, R> for Body<'a, M, T, R, F> where
       _ => None, // TODO: propagate other events?
     };
 
-    if let Some(event_position) = event_position {
-      let absolute_position = layout.position();
-      let position = relative_to(event_position, absolute_position);
-      let mut element_state = self.element_state.borrow_mut();
-      let mut tree_state = tree.state.downcast_ref::<RefCell<TreeState>>().borrow_mut();
-      if let Some(cell) = self.cell_at_position(
-        position,
-        layout,
-        absolute_position.y,
+    let mut tree_state = tree.state.downcast_ref::<RefCell<TreeState>>().borrow_mut();
+    let geometry = self.geometry(&mut tree_state);
+
+    // Track the row under the cursor, also when the cursor leaves the table.
+    if let Event::Mouse(_) = event {
+      let hovered_row = cursor.position_in(layout.bounds()).and_then(|position| geometry.row_at(position.y));
+      if tree_state.hovered_row != hovered_row {
+        tree_state.hovered_row = hovered_row;
+        shell.request_redraw(window::RedrawRequest::NextFrame);
+      }
+    }
+
+    let Some(event_position) = event_position else {
+      return Status::Ignored;
+    };
+
+    let absolute_position = layout.position();
+    let position = relative_to(event_position, absolute_position);
+    let mut element_state = self.element_state.borrow_mut();
+
+    if let (Some(on_reorder), Some(mut drag)) = (&self.on_reorder, tree_state.drag) {
+      // Cells do not receive events while a row is being dragged.
+      match event {
+        Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+          let insert_at = geometry.insert_index_at(position.y);
+          if drag.insert_at != insert_at {
+            drag.insert_at = insert_at;
+            tree_state.drag = Some(drag);
+            shell.request_redraw(window::RedrawRequest::NextFrame);
+          }
+        }
+        Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+          tree_state.drag = None;
+          let to = drag.to();
+          if drag.from != to {
+            shell.publish(on_reorder(drag.from, to));
+          }
+          shell.request_redraw(window::RedrawRequest::NextFrame);
+        }
+        _ => {}
+      }
+      return Status::Captured;
+    }
+
+    if let Some(cell) = self.cell_at_position(
+      position,
+      layout,
+      &geometry,
+      absolute_position.y,
+      renderer,
+      &mut element_state,
+      &mut tree_state
+    ) {
+      let status = cell.element.as_widget_mut().on_event(
+        cell.tree,
+        event.clone(),
+        Layout::new(&cell.node),
+        cursor,
         renderer,
-        &mut element_state,
-        &mut tree_state
-      ) {
-        return cell.element.as_widget_mut().on_event(
-          cell.tree,
-          event,
-          Layout::new(&cell.node),
-          cursor,
-          renderer,
-          clipboard,
-          shell,
-          viewport
-        );
+        clipboard,
+        shell,
+        viewport
+      );
+      if status == Status::Captured {
+        return status;
+      }
+    }
+
+    // Select, double-click, and/or start dragging a row when it is pressed and the cell under the cursor did not capture
+    // the event, so that clicks on interactive widgets inside cells are not stolen.
+    let handles_press = self.on_select.is_some() || self.on_double_click.is_some() || self.on_reorder.is_some();
+    if handles_press && cursor.is_over(layout.bounds()) {
+      if let Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) = event {
+        if let Some(row) = geometry.row_at(position.y) {
+          let now = Instant::now();
+          let is_double_click = tree_state.last_click
+            .is_some_and(|click| click.is_double_click(row, now, self.double_click_threshold));
+          match (&self.on_double_click, is_double_click) {
+            (Some(on_double_click), true) => {
+              // The first click already selected the row; do not select it again.
+              tree_state.last_click = None;
+              shell.publish(on_double_click(row));
+            }
+            _ => {
+              tree_state.last_click = Some(RowClick { row, time: now });
+              if let Some(on_select) = &self.on_select {
+                shell.publish(on_select(row));
+              }
+            }
+          }
+          if self.on_reorder.is_some() {
+            tree_state.drag = Some(RowDrag { from: row, insert_at: row });
+          }
+          return Status::Captured;
+        }
       }
     }
 
@@ -270,9 +589,14 @@ impl<'a, F, M, T, R> Widget<M, T, R> for Body<'a, M, T, R, F> where
       let position = relative_to(cursor_position, absolute_position);
       let mut element_state = self.element_state.borrow_mut();
       let mut tree_state = tree.state.downcast_ref::<RefCell<TreeState>>().borrow_mut();
+      if tree_state.drag.is_some() {
+        return Interaction::Grabbing;
+      }
+      let geometry = self.geometry(&mut tree_state);
       if let Some(cell) = self.cell_at_position(
         position,
         layout,
+        &geometry,
         absolute_position.y,
         renderer,
         &mut element_state,
@@ -290,8 +614,31 @@ impl<'a, F, M, T, R> Widget<M, T, R> for Body<'a, M, T, R, F> where
     Interaction::default()
   }
 
-  fn operate(&self, _tree: &mut Tree, _layout: Layout, _renderer: &R, _operation: &mut dyn Operation<()>) {
-    // TODO: implement?
+  /// Propagates `operation` to the cells of rows that were visible when last drawn. Only those cells are materialized, so
+  /// cells of off-screen rows do not participate in operations such as focusing a widget or tab navigation.
+  fn operate(&self, tree: &mut Tree, layout: Layout, renderer: &R, operation: &mut dyn Operation<()>) {
+    let mut element_state = self.element_state.borrow_mut();
+    let mut tree_state = tree.state.downcast_ref::<RefCell<TreeState>>().borrow_mut();
+    let geometry = self.geometry(&mut tree_state);
+    let absolute_y = layout.position().y;
+    let rows = tree_state.previous_rows.clone();
+    operation.container(None, layout.bounds(), &mut |operation| {
+      for row in rows.clone() {
+        for (col, cell_bounds) in (0..self.column_count).zip(Self::get_cell_bounds(layout)) {
+          let cell = self.cell_at(
+            row,
+            col,
+            cell_bounds,
+            &geometry,
+            absolute_y,
+            renderer,
+            &mut element_state,
+            &mut tree_state
+          );
+          cell.element.as_widget().operate(cell.tree, Layout::new(&cell.node), renderer, operation);
+        }
+      }
+    });
   }
 }
 
@@ -304,12 +651,40 @@ struct Cell<'c, 'e, M, T, R> {
 impl<'a, F, M, T, R: Renderer> Body<'a, M, T, R, F> where
   F: Fn(usize, usize) -> Element<'a, M, T, R> + 'a
 {
+  /// Gets the row geometry of this table, updating the cached row offsets in `tree_state` if this table has variable row
+  /// heights and the row count, spacing, or row height function id changed.
+  fn geometry(&self, tree_state: &mut TreeState) -> RowGeometry {
+    if let Some((row_height_fn_id, row_height_fn)) = &self.row_height_fn {
+      let row_offsets = match &tree_state.row_offsets {
+        Some(row_offsets) if row_offsets.row_count == self.row_count && row_offsets.spacing == self.spacing &&
+          row_offsets.row_height_fn_id == *row_height_fn_id => row_offsets,
+        _ => tree_state.row_offsets.insert(RowOffsets {
+          row_count: self.row_count,
+          spacing: self.spacing,
+          row_height_fn_id: *row_height_fn_id,
+          offsets: RowOffsets::calculate(self.row_count, self.spacing, row_height_fn),
+        }),
+      };
+      RowGeometry::variable(self.spacing, self.row_count, row_offsets.offsets.clone())
+    } else {
+      RowGeometry::uniform(self.spacing, self.row_count, self.row_height)
+    }
+  }
+
+  /// Fills the background of `row` with `color`.
+  fn fill_row(renderer: &mut R, layout: Layout, geometry: &RowGeometry, row: usize, color: Color) {
+    let position = Point::new(layout.position().x, layout.position().y + geometry.top(row));
+    let bounds = Rectangle::new(position, Size::new(layout.bounds().width, geometry.height(row)));
+    renderer.fill_quad(renderer::Quad { bounds, ..renderer::Quad::default() }, color);
+  }
+
   /// Gets the cell at (`row`, `col`), with `cell_bounds` (retrieved from the layout of the phantom row).
   fn cell_at<'c>(
     &'c self,
     row: usize,
     col: usize,
     cell_bounds: Rectangle,
+    geometry: &RowGeometry,
     absolute_y: f32,
     renderer: &R,
     element_state: &'c mut ElementState<'a, M, T, R>,
@@ -318,10 +693,10 @@ impl<'a, F, M, T, R: Renderer> Body<'a, M, T, R, F> where
     let element = element_state.get_or_insert(row, col, &self.cell_to_element);
     let tree = tree_state.get_or_insert(row, col, element);
     tree.diff(element.as_widget());
-    let limits = Limits::new(Size::ZERO, cell_bounds.size());
+    let limits = Limits::new(Size::ZERO, Size::new(cell_bounds.width, geometry.height(row)));
     // Since `cell_bounds` is from the layout of the phantom row, it always has a y-position of 0.0. We move the node to
     // its correct y-position here.
-    let y = absolute_y + row as f32 * self.row_height_plus_spacing;
+    let y = absolute_y + geometry.top(row);
     let node = element.as_widget()
       .layout(tree, renderer, &limits)
       .move_to(Point::new(cell_bounds.x, y));
@@ -333,35 +708,20 @@ impl<'a, F, M, T, R: Renderer> Body<'a, M, T, R, F> where
     &'c self,
     position: Point,
     layout: Layout,
+    geometry: &RowGeometry,
     absolute_y: f32,
     renderer: &R,
     element_state: &'c mut ElementState<'a, M, T, R>,
     tree_state: &'c mut TreeState,
   ) -> Option<Cell<'c, 'a, M, T, R>> {
-    if let Some(row) = self.row_at(position.y) {
+    if let Some(row) = geometry.row_at(position.y) {
       if let Some((col, bounds)) = self.col_and_bounds_at(position.x, layout) {
-        return Some(self.cell_at(row, col, bounds, absolute_y, renderer, element_state, tree_state));
+        return Some(self.cell_at(row, col, bounds, geometry, absolute_y, renderer, element_state, tree_state));
       }
     }
     None
   }
 
-  /// Gets the row for `y` position relative to this table, or `None` if there is now row at `y`.
-  fn row_at(&self, y: f32) -> Option<usize> {
-    if y < 0.0 { return None; } // Out of bounds
-    let row = y / self.row_height_plus_spacing;
-    if y > (row.ceil() * self.row_height_plus_spacing) - self.spacing {
-      None // On row spacing
-    } else {
-      let row = row.floor() as usize;
-      if row > self.last_row_index {
-        None // Out of bounds
-      } else {
-        Some(row)
-      }
-    }
-  }
-
   /// Gets the column and bounds (retrieved from the layout of the phantom row) for `x` position relative to this table, or
   /// `None` if there is no column at `x`.
   fn col_and_bounds_at(&self, x: f32, layout: Layout) -> Option<(usize, Rectangle)> {
@@ -398,3 +758,268 @@ impl<'a, M, T, R, F> Into<Element<'a, M, T, R>> for Body<'a, M, T, R, F> where
 fn relative_to(point: Point, absolute: Point) -> Point {
   Point::new(point.x - absolute.x, point.y - absolute.y)
 }
+
+/// Checks whether `row` is striped: odd rows are striped, so that the first row has the default background.
+#[inline]
+fn is_striped(row: usize) -> bool {
+  row % 2 == 1
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn variable(spacing: f32, heights: &[f32]) -> RowGeometry {
+    let offsets = RowOffsets::calculate(heights.len(), spacing, |row| heights[row]);
+    RowGeometry::variable(spacing, heights.len(), offsets)
+  }
+
+  #[test]
+  fn visible_rows_of_empty_table() {
+    assert_eq!(RowGeometry::uniform(2.0, 0, 10.0).visible(0.0, 100.0), 0..0);
+    assert_eq!(RowGeometry::uniform(2.0, 0, 10.0).visible(50.0, 100.0), 0..0);
+    assert_eq!(variable(2.0, &[]).visible(0.0, 100.0), 0..0);
+  }
+
+  #[test]
+  fn visible_rows_include_partial_rows() {
+    // Rows are 12 apart: row 0 at 0..10, row 1 at 12..22, row 2 at 24..34, and so on.
+    let geometry = RowGeometry::uniform(2.0, 100, 10.0);
+    assert_eq!(geometry.visible(0.0, 24.0), 0..3);
+    assert_eq!(geometry.visible(5.0, 24.0), 0..3);
+    assert_eq!(geometry.visible(12.0, 12.0), 1..3);
+    // Viewport that ends in the last row, which is only partially visible.
+    assert_eq!(geometry.visible(1150.0, 40.0), 95..100);
+  }
+
+  #[test]
+  fn visible_rows_when_scrolled_past_end() {
+    let geometry = RowGeometry::uniform(2.0, 10, 10.0);
+    assert_eq!(geometry.total_height(), 118.0);
+    assert_eq!(geometry.visible(100.0, 100.0), 8..10);
+    assert_eq!(geometry.visible(1000.0, 100.0), 9..10);
+    assert_eq!(geometry.visible(-50.0, 60.0), 0..1);
+  }
+
+  #[test]
+  fn variable_row_heights() {
+    // Row 0 at 0..10, row 1 at 12..42, row 2 at 44..49.
+    let geometry = variable(2.0, &[10.0, 30.0, 5.0]);
+    assert_eq!(geometry.top(1), 12.0);
+    assert_eq!(geometry.top(2), 44.0);
+    assert_eq!(geometry.height(1), 30.0);
+    assert_eq!(geometry.total_height(), 49.0);
+    assert_eq!(geometry.row_at(5.0), Some(0));
+    assert_eq!(geometry.row_at(11.0), None);
+    assert_eq!(geometry.row_at(40.0), Some(1));
+    assert_eq!(geometry.row_at(45.0), Some(2));
+    assert_eq!(geometry.row_at(50.0), None);
+    assert_eq!(geometry.visible(0.0, 11.0), 0..1);
+    assert_eq!(geometry.visible(20.0, 5.0), 1..2);
+    assert_eq!(geometry.visible(20.0, 30.0), 1..3);
+  }
+
+  #[test]
+  fn variable_row_heights_match_uniform_row_heights() {
+    let uniform = RowGeometry::uniform(2.0, 50, 10.0);
+    let variable = variable(2.0, &[10.0; 50]);
+    assert_eq!(uniform.total_height(), variable.total_height());
+    for y in [0.0, 5.0, 11.0, 12.0, 300.0, 599.0, 650.0] {
+      assert_eq!(uniform.row_at(y), variable.row_at(y), "row at {y}");
+      assert_eq!(uniform.visible(y, 40.0), variable.visible(y, 40.0), "visible rows at {y}");
+      assert_eq!(uniform.insert_index_at(y), variable.insert_index_at(y), "insert index at {y}");
+    }
+  }
+
+  #[test]
+  fn row_at_hits_rows_but_not_spacing() {
+    // Rows are 12 apart: row 0 at 0..10, row 1 at 12..22, row 2 at 24..34.
+    let geometry = RowGeometry::uniform(2.0, 3, 10.0);
+    assert_eq!(geometry.row_at(-1.0), None);
+    assert_eq!(geometry.row_at(0.0), Some(0));
+    assert_eq!(geometry.row_at(10.0), Some(0));
+    assert_eq!(geometry.row_at(11.0), None);
+    assert_eq!(geometry.row_at(12.0), Some(1));
+    assert_eq!(geometry.row_at(21.9), Some(1));
+    assert_eq!(geometry.row_at(30.0), Some(2));
+    assert_eq!(geometry.row_at(35.0), None);
+    assert_eq!(geometry.row_at(100.0), None);
+  }
+
+  #[test]
+  fn row_at_without_spacing() {
+    let geometry = RowGeometry::uniform(0.0, 3, 10.0);
+    assert_eq!(geometry.row_at(9.9), Some(0));
+    assert_eq!(geometry.row_at(10.0), Some(1));
+    assert_eq!(geometry.row_at(29.9), Some(2));
+    assert_eq!(geometry.row_at(30.0), None);
+  }
+
+  #[test]
+  fn insert_index_with_spacing() {
+    // Rows are 12 apart: row 0 at 0..10, row 1 at 12..22, row 2 at 24..34. Boundaries are in the middle of each row and
+    // its spacing below.
+    let geometry = RowGeometry::uniform(2.0, 3, 10.0);
+    assert_eq!(geometry.insert_index_at(-5.0), 0);
+    assert_eq!(geometry.insert_index_at(3.0), 0); // Above the middle of row 0.
+    assert_eq!(geometry.insert_index_at(8.0), 1); // Below the middle of row 0.
+    assert_eq!(geometry.insert_index_at(11.0), 1); // On the spacing below row 0.
+    assert_eq!(geometry.insert_index_at(17.0), 1);
+    assert_eq!(geometry.insert_index_at(19.0), 2);
+    assert_eq!(geometry.insert_index_at(31.0), 3);
+    assert_eq!(geometry.insert_index_at(100.0), 3); // Past the last row.
+  }
+
+  #[test]
+  fn insert_index_without_spacing() {
+    let geometry = RowGeometry::uniform(0.0, 3, 10.0);
+    assert_eq!(geometry.insert_index_at(0.0), 0);
+    assert_eq!(geometry.insert_index_at(4.0), 0);
+    assert_eq!(geometry.insert_index_at(6.0), 1);
+    assert_eq!(geometry.insert_index_at(14.0), 1);
+    assert_eq!(geometry.insert_index_at(16.0), 2);
+    assert_eq!(geometry.insert_index_at(29.0), 3);
+    assert_eq!(geometry.insert_index_at(50.0), 3); // Past the last row.
+  }
+
+  #[test]
+  fn insert_index_of_empty_table() {
+    assert_eq!(RowGeometry::uniform(2.0, 0, 10.0).insert_index_at(5.0), 0);
+    assert_eq!(variable(2.0, &[]).insert_index_at(5.0), 0);
+  }
+
+  #[test]
+  fn insert_index_with_variable_row_heights() {
+    // Row 0 at 0..10, row 1 at 12..42, row 2 at 44..49.
+    let geometry = variable(2.0, &[10.0, 30.0, 5.0]);
+    assert_eq!(geometry.insert_index_at(26.0), 1);
+    assert_eq!(geometry.insert_index_at(30.0), 2);
+    assert_eq!(geometry.insert_index_at(48.0), 3);
+  }
+
+  /// Gets the index row `from` ends up at when dropped at `y` in a table with 3 rows of height 10 and `spacing`.
+  fn drop_at(spacing: f32, from: usize, y: f32) -> usize {
+    let geometry = RowGeometry::uniform(spacing, 3, 10.0);
+    RowDrag { from, insert_at: geometry.insert_index_at(y) }.to()
+  }
+
+  #[test]
+  fn drop_row_above_and_below() {
+    for spacing in [0.0, 2.0] {
+      let row_height_plus_spacing = 10.0 + spacing;
+      // Drop row 2 above row 0.
+      assert_eq!(drop_at(spacing, 2, 1.0), 0, "spacing {spacing}");
+      // Drop row 0 below row 1.
+      assert_eq!(drop_at(spacing, 0, row_height_plus_spacing + 9.0), 1, "spacing {spacing}");
+      // Drop row 2 between rows 0 and 1.
+      assert_eq!(drop_at(spacing, 2, row_height_plus_spacing + 1.0), 1, "spacing {spacing}");
+    }
+  }
+
+  #[test]
+  fn drop_row_onto_itself() {
+    for spacing in [0.0, 2.0] {
+      let row_height_plus_spacing = 10.0 + spacing;
+      // The boundaries directly above and below row 1 both keep it in place.
+      assert_eq!(drop_at(spacing, 1, row_height_plus_spacing + 1.0), 1, "spacing {spacing}");
+      assert_eq!(drop_at(spacing, 1, row_height_plus_spacing + 9.0), 1, "spacing {spacing}");
+    }
+  }
+
+  #[test]
+  fn drop_row_past_last_row() {
+    for spacing in [0.0, 2.0] {
+      assert_eq!(drop_at(spacing, 0, 1000.0), 2, "spacing {spacing}");
+      assert_eq!(drop_at(spacing, 2, 1000.0), 2, "spacing {spacing}");
+    }
+  }
+
+  #[test]
+  fn double_click_within_threshold_on_same_row() {
+    let threshold = Duration::from_millis(500);
+    let time = Instant::now();
+    let click = RowClick { row: 3, time };
+    assert!(click.is_double_click(3, time + Duration::from_millis(200), threshold));
+    assert!(click.is_double_click(3, time + threshold, threshold));
+    assert!(!click.is_double_click(3, time + Duration::from_millis(501), threshold));
+    assert!(!click.is_double_click(4, time + Duration::from_millis(200), threshold));
+  }
+
+  #[test]
+  fn double_click_with_click_before_previous_click() {
+    let time = Instant::now() + Duration::from_secs(1);
+    let click = RowClick { row: 3, time };
+    assert!(click.is_double_click(3, time - Duration::from_millis(100), Duration::from_millis(500)));
+  }
+
+  mod operate {
+    use iced::{Theme, widget};
+    use iced::advanced::widget::operation;
+    use iced::widget::text_input;
+
+    use super::*;
+
+    type TextInputState = text_input::State<<() as iced::advanced::text::Renderer>::Paragraph>;
+
+    fn text_input_id(row: usize, col: usize) -> text_input::Id {
+      text_input::Id::new(format!("cell-{row}-{col}"))
+    }
+
+    /// Creates a table body of 100 rows with 2 columns of text inputs, lays it out, and draws it in a viewport that shows
+    /// the first 5 rows.
+    fn drawn_body<'a>() -> (Element<'a, (), Theme, ()>, Tree, Node) {
+      let phantom_row = widget::Row::with_children(vec![
+        widget::Space::with_width(100.0).into(),
+        widget::Space::with_width(100.0).into(),
+      ]).spacing(2.0);
+      let cell_to_element = |row, col| -> Element<'a, (), Theme, ()> {
+        text_input("", "").id(text_input_id(row, col)).into()
+      };
+      let body: Element<(), Theme, ()> = Body::new(2.0, 2, 20.0, 100, cell_to_element, phantom_row.into()).into();
+      let mut tree = Tree::new(&body);
+      let node = body.as_widget().layout(&mut tree, &(), &Limits::new(Size::ZERO, Size::new(202.0, f32::INFINITY)));
+      let viewport = Rectangle::new(Point::ORIGIN, Size::new(202.0, 100.0));
+      let style = renderer::Style { text_color: Color::BLACK };
+      body.as_widget().draw(&tree, &mut (), &Theme::Light, &style, Layout::new(&node), Cursor::Unavailable, &viewport);
+      (body, tree, node)
+    }
+
+    fn is_focused(tree: &Tree, row: usize, col: usize) -> bool {
+      let tree_state = tree.state.downcast_ref::<RefCell<TreeState>>().borrow();
+      tree_state.trees.get(&(row, col))
+        .is_some_and(|tree| tree.state.downcast_ref::<TextInputState>().is_focused())
+    }
+
+    #[test]
+    fn focus_text_input_in_visible_cell() {
+      let (body, mut tree, node) = drawn_body();
+      let mut focus = operation::focusable::focus(text_input_id(2, 1).into());
+      body.as_widget().operate(&mut tree, Layout::new(&node), &(), &mut focus);
+
+      assert!(is_focused(&tree, 2, 1));
+      assert!(!is_focused(&tree, 2, 0));
+      assert!(!is_focused(&tree, 0, 0));
+    }
+
+    #[test]
+    fn focus_text_input_in_off_screen_cell_does_nothing() {
+      let (body, mut tree, node) = drawn_body();
+      let mut focus = operation::focusable::focus(text_input_id(50, 0).into());
+      body.as_widget().operate(&mut tree, Layout::new(&node), &(), &mut focus);
+
+      assert!(!is_focused(&tree, 50, 0));
+      assert!((0..5).all(|row| !is_focused(&tree, row, 0) && !is_focused(&tree, row, 1)));
+    }
+  }
+
+  #[test]
+  fn odd_rows_are_striped() {
+    assert!(!is_striped(0));
+    assert!(is_striped(1));
+    assert!(!is_striped(2));
+    assert!(is_striped(3));
+    // Parity depends on the row index, not on the position in the visible rows, so stripes do not shift when scrolling.
+    let striped: Vec<usize> = RowGeometry::uniform(2.0, 100, 10.0).visible(130.0, 40.0).filter(|row| is_striped(*row)).collect();
+    assert_eq!(striped, vec![11, 13]);
+  }
+}
@@ -1,6 +1,10 @@
-use iced::{Element, Length};
+use std::collections::HashSet;
+use std::time::Duration;
+
+use iced::{Color, Element, Length, Task};
 use iced::advanced::Renderer;
 use iced::widget::{Column, Scrollable, scrollable, Space};
+use iced::widget::scrollable::Direction;
 
 use crate::constrained_row::ConstrainedRow;
 use crate::constrained_row::Constraint;
@@ -20,8 +24,20 @@ pub struct Table<'a, M, T, R, F> {
   header_row_height: f32,
 
   body_row_height: f32,
+  body_row_height_fn: Option<(u64, Box<dyn Fn(usize) -> f32 + 'a>)>,
   body_row_count: usize,
   cell_to_element: F,
+  on_reorder: Option<Box<dyn Fn(usize, usize) -> M + 'a>>,
+  on_resize: Option<Box<dyn Fn(usize, f32) -> M + 'a>>,
+  selected_rows: Option<&'a HashSet<usize>>,
+  selected_color: Option<Color>,
+  on_select: Option<Box<dyn Fn(usize) -> M + 'a>>,
+  hovered_color: Option<Color>,
+  stripe_color: Option<Color>,
+  on_double_click: Option<Box<dyn Fn(usize) -> M + 'a>>,
+  double_click_threshold: Duration,
+  scrollable_id: Option<scrollable::Id>,
+  on_scroll: Option<Box<dyn Fn(scrollable::Viewport) -> M + 'a>>,
 }
 
 impl<'a, M, T, R, F> Table<'a, M, T, R, F> where
@@ -40,6 +56,9 @@ impl<'a, M, T, R, F> Table<'a, M, T, R, F> where
   ///
   /// If `column_constraints` is smaller or larger than `header_elements`, `column_constraints` will be resized to be
   /// the same size as `header_elements`, adding default constraints if needed.
+  ///
+  /// If all `column_constraints` have [fixed widths](crate::constrained_row::Width::Fixed), the table is as wide as the
+  /// sum of those widths, and scrolls horizontally (header and body together) when it does not fit.
   pub fn with_constraints_and_header_elements(
     mut column_constraints: Vec<Constraint>,
     header_elements: Vec<Element<'a, M, T, R>>,
@@ -56,8 +75,20 @@ impl<'a, M, T, R, F> Table<'a, M, T, R, F> where
       header_elements,
       header_row_height: row_height,
       body_row_height: row_height,
+      body_row_height_fn: None,
       body_row_count: 0,
       cell_to_element,
+      on_reorder: None,
+      on_resize: None,
+      selected_rows: None,
+      selected_color: None,
+      on_select: None,
+      hovered_color: None,
+      stripe_color: None,
+      on_double_click: None,
+      double_click_threshold: Duration::from_millis(500),
+      scrollable_id: None,
+      on_scroll: None,
     }
   }
   pub fn with_capacity(capacity: usize, cell_to_element: F) -> Self {
@@ -90,11 +121,93 @@ impl<'a, M, T, R, F> Table<'a, M, T, R, F> where
     self.body_row_height = height;
     self
   }
+  /// Sets the function that gets the height of a body row by its index, for rows with variable heights, such as rows with
+  /// multi-line text. Rows without such a function all have the [body row height](Self::body_row_height).
+  ///
+  /// Row positions are cached and only recalculated when the body row count, spacing, or `id` changes. Since closures are
+  /// recreated every view, `id` identifies the function instead: use a different `id` whenever row heights may change
+  /// without the body row count changing, for example a revision number of the data the heights are calculated from.
+  pub fn body_row_height_fn(mut self, id: u64, row_height_fn: impl Fn(usize) -> f32 + 'a) -> Self {
+    self.body_row_height_fn = Some((id, Box::new(row_height_fn)));
+    self
+  }
   pub fn body_row_count(mut self, body_row_count: usize) -> Self {
     self.body_row_count = body_row_count;
     self
   }
 
+  /// Enables dragging rows to reorder them. When a row is dragged from index `from` and dropped such that it should
+  /// end up at index `to`, the message created by `on_reorder(from, to)` is published. The table does not reorder rows
+  /// itself; `cell_to_element` and `body_row_count` should reflect the new order in the next view.
+  pub fn on_reorder(mut self, on_reorder: impl Fn(usize, usize) -> M + 'a) -> Self {
+    self.on_reorder = Some(Box::new(on_reorder));
+    self
+  }
+
+  /// Enables resizing columns by dragging the boundary between two header cells. While the boundary at the right of
+  /// column `index` is dragged, the message created by `on_resize(index, width)` is published. The table only changes
+  /// the width of the column in its header while dragging; pass a [fixed width](crate::constrained_row::Width::Fixed)
+  /// constraint for the column in the next view to resize the whole column.
+  pub fn on_resize(mut self, on_resize: impl Fn(usize, f32) -> M + 'a) -> Self {
+    self.on_resize = Some(Box::new(on_resize));
+    self
+  }
+
+  /// Highlights the rows with indices in `selected_rows` as selected.
+  pub fn selected_rows(mut self, selected_rows: &'a HashSet<usize>) -> Self {
+    self.selected_rows = Some(selected_rows);
+    self
+  }
+  /// Sets the background `color` of selected rows, instead of a translucent version of the text color.
+  pub fn selected_color(mut self, color: impl Into<Color>) -> Self {
+    self.selected_color = Some(color.into());
+    self
+  }
+  /// Sets the background `color` of the row under the cursor, instead of a faint version of the text color. Use
+  /// [`Color::TRANSPARENT`] to disable highlighting the hovered row.
+  pub fn hovered_color(mut self, color: impl Into<Color>) -> Self {
+    self.hovered_color = Some(color.into());
+    self
+  }
+  /// Stripes the table by drawing odd rows with background `color`, making it easier to follow wide rows.
+  pub fn striped(mut self, color: impl Into<Color>) -> Self {
+    self.stripe_color = Some(color.into());
+    self
+  }
+  /// Enables selecting rows by clicking them. When row `index` is clicked outside of any cell widget that captures the
+  /// click (such as a button), the message created by `on_select(index)` is published. The table does not keep track of
+  /// selected rows itself; pass them with [selected_rows](Self::selected_rows) in the next view.
+  pub fn on_select(mut self, on_select: impl Fn(usize) -> M + 'a) -> Self {
+    self.on_select = Some(Box::new(on_select));
+    self
+  }
+  /// Enables double-clicking rows, for example to open them. When row `index` is clicked twice within the
+  /// [double click threshold](Self::double_click_threshold) outside of any cell widget that captures the click, the
+  /// message created by `on_double_click(index)` is published. Only the first click of a double click publishes an
+  /// [on_select](Self::on_select) message.
+  pub fn on_double_click(mut self, on_double_click: impl Fn(usize) -> M + 'a) -> Self {
+    self.on_double_click = Some(Box::new(on_double_click));
+    self
+  }
+  /// Sets the maximum `threshold` between two clicks on the same row for them to count as a double click. Defaults to
+  /// 500 milliseconds.
+  pub fn double_click_threshold(mut self, threshold: Duration) -> Self {
+    self.double_click_threshold = threshold;
+    self
+  }
+
+  /// Sets the `id` of the scrollable that vertically scrolls the table body, for scrolling it programmatically.
+  pub fn scrollable_id(mut self, id: scrollable::Id) -> Self {
+    self.scrollable_id = Some(id);
+    self
+  }
+  /// Publishes the message created by `on_scroll(viewport)` when the table body is scrolled vertically, for example to
+  /// load more rows when scrolled near the end.
+  pub fn on_scroll(mut self, on_scroll: impl Fn(scrollable::Viewport) -> M + 'a) -> Self {
+    self.on_scroll = Some(Box::new(on_scroll));
+    self
+  }
+
   pub fn push(mut self, column_constraint: impl Into<Constraint>, header_element: impl Into<Element<'a, M, T, R>>) -> Self {
     self.column_constraints.push(column_constraint.into());
     self.header_elements.push(header_element.into());
@@ -102,6 +215,15 @@ impl<'a, M, T, R, F> Table<'a, M, T, R, F> where
   }
 }
 
+/// Creates a task that scrolls the body of the table with [scrollable id](Table::scrollable_id) `id` such that `row` is at
+/// the top of its viewport. `row_height` and `spacing` must be the [body row height](Table::body_row_height) and
+/// [spacing](Table::spacing) of the table, which must not have a [body row height function](Table::body_row_height_fn).
+pub fn scroll_to_row<M>(id: scrollable::Id, row: usize, row_height: f32, spacing: f32) -> Task<M> {
+  // Same as the y-position of a row in the table body with uniform row heights.
+  let y = row as f32 * (row_height + spacing);
+  scrollable::scroll_to(id, scrollable::AbsoluteOffset { x: 0.0, y })
+}
+
 impl<'a, F, M, T, R> Into<Element<'a, M, T, R>> for Table<'a, M, T, R, F> where
   M: 'a,
   T: scrollable::Catalog + 'a,
@@ -109,9 +231,13 @@ impl<'a, F, M, T, R> Into<Element<'a, M, T, R>> for Table<'a, M, T, R, F> where
   F: Fn(usize, usize) -> Option<Element<'a, M, T, R>> + 'a,
 {
   fn into(self) -> Element<'a, M, T, R> {
-    let header = ConstrainedRow::with_constraints_and_elements(self.column_constraints.clone(), self.header_elements)
+    let mut header = ConstrainedRow::with_constraints_and_elements(self.column_constraints.clone(), self.header_elements)
       .spacing(self.spacing)
       .height(self.header_row_height);
+    if let Some(on_resize) = self.on_resize {
+      header = header.on_resize(on_resize);
+    }
+    let fixed_width = header.fixed_width();
 
     let column_count = self.column_constraints.len();
     // Create a phantom row with space elements which the table body widget will use as a base to lay out rows.
@@ -121,15 +247,44 @@ impl<'a, F, M, T, R> Into<Element<'a, M, T, R>> for Table<'a, M, T, R, F> where
 
     let cell_to_element = move |row, col| (self.cell_to_element)(row, col)
       .unwrap_or_else(|| Space::new(Length::Fill, Length::Fill).into());
-    let body = Body::new(self.spacing, column_count, self.body_row_height, self.body_row_count, cell_to_element, phantom_row.into());
-    let body = Scrollable::new(body);
+    let body = Body::new(self.spacing, column_count, self.body_row_height, self.body_row_count, cell_to_element, phantom_row.into())
+      .row_height_fn(self.body_row_height_fn)
+      .on_reorder(self.on_reorder)
+      .selected_rows(self.selected_rows)
+      .selected_color(self.selected_color)
+      .on_select(self.on_select)
+      .hovered_color(self.hovered_color)
+      .stripe_color(self.stripe_color)
+      .on_double_click(self.on_double_click)
+      .double_click_threshold(self.double_click_threshold);
+    let mut body = Scrollable::new(body);
+    if let Some(id) = self.scrollable_id {
+      body = body.id(id);
+    }
+    if let Some(on_scroll) = self.on_scroll {
+      body = body.on_scroll(on_scroll);
+    }
 
-    Column::from_vec(vec![header.into(), body.into()])
-      .spacing(self.spacing)
-      .width(self.width)
-      .height(self.height)
-      .max_width(self.max_width)
-      .into()
+    let column = Column::from_vec(vec![header.into(), body.into()])
+      .spacing(self.spacing);
+    if let Some(fixed_width) = fixed_width {
+      // Scroll the header and body horizontally together, keeping the header aligned with the body. Vertical scrolling
+      // only applies to the body.
+      let column = column
+        .width(fixed_width)
+        .height(Length::Fill);
+      Scrollable::new(column)
+        .direction(Direction::Horizontal(Default::default()))
+        .width(self.width)
+        .height(self.height)
+        .into()
+    } else {
+      column
+        .width(self.width)
+        .height(self.height)
+        .max_width(self.max_width)
+        .into()
+    }
   }
 }
 
@@ -0,0 +1,68 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+#[cfg(test)]
+use std::sync::Mutex;
+
+#[cfg(test)]
+use chrono::TimeDelta;
+use chrono::{DateTime, Utc};
+
+/// Source of the current time, so that time-dependent logic such as staleness checks can be tested deterministically.
+pub trait Clock: Debug + Send + Sync + 'static {
+  /// Gets the current time.
+  fn now(&self) -> DateTime<Utc>;
+}
+
+/// Shareable [`Clock`].
+pub type SharedClock = Arc<dyn Clock>;
+
+
+/// [`Clock`] that returns the system time.
+#[derive(Default, Copy, Clone, Debug)]
+pub struct SystemClock;
+
+impl SystemClock {
+  #[inline]
+  pub fn shared() -> SharedClock { Arc::new(Self) }
+}
+
+impl Clock for SystemClock {
+  #[inline]
+  fn now(&self) -> DateTime<Utc> { Utc::now() }
+}
+
+
+/// [`Clock`] that only changes time when [set](Self::set) or [advanced](Self::advance). Clones share the same time.
+#[cfg(test)]
+#[derive(Clone, Debug)]
+pub struct MockClock {
+  now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+#[cfg(test)]
+impl MockClock {
+  #[inline]
+  pub fn new(now: DateTime<Utc>) -> Self { Self { now: Arc::new(Mutex::new(now)) } }
+
+  /// Sets the current time to `now`.
+  pub fn set(&self, now: DateTime<Utc>) {
+    *self.now.lock().unwrap_or_else(|e| e.into_inner()) = now;
+  }
+
+  /// Advances the current time by `delta`.
+  pub fn advance(&self, delta: TimeDelta) {
+    *self.now.lock().unwrap_or_else(|e| e.into_inner()) += delta;
+  }
+}
+
+#[cfg(test)]
+impl Default for MockClock {
+  #[inline]
+  fn default() -> Self { Self::new(DateTime::UNIX_EPOCH) }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+  #[inline]
+  fn now(&self) -> DateTime<Utc> { *self.now.lock().unwrap_or_else(|e| e.into_inner()) }
+}
@@ -0,0 +1,216 @@
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Deserialize;
+use thiserror::Error;
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+use att_core::app::tracing::FilterReloader;
+
+/// Server configuration file in JSON format. Values that are not set fall back to their environment variable or
+/// default.
+#[derive(Default, Clone, PartialEq, Debug, Deserialize)]
+#[serde(default)]
+pub struct ConfigFile {
+  /// Maximum number of crates a user can follow. Reloaded at runtime.
+  pub max_followed_crates: Option<u32>,
+  /// Number of seconds that responses which are not specific to a user may be cached. Reloaded at runtime.
+  pub crates_cache_max_age: Option<u32>,
+  /// Whether the server is in maintenance mode, rejecting requests that modify data. Reloaded at runtime.
+  pub maintenance_mode: Option<bool>,
+  /// Comma-separated log filter directives (e.g., `att_server=trace,hyper=info`) that replace the console and file log
+  /// filters. Reloaded at runtime.
+  pub log_filter: Option<String>,
+  /// URL of the database to connect to. Requires a restart; changes at runtime are ignored.
+  pub database_url: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+  #[error("Failed to read configuration file: {0}")]
+  Read(#[from] io::Error),
+  #[error("Failed to parse configuration file: {0}")]
+  Parse(#[from] serde_json::Error),
+  #[error("Failed to watch configuration file: {0}")]
+  Watch(#[from] notify::Error),
+}
+
+impl ConfigFile {
+  /// Reads the configuration file at `path`.
+  pub fn from_path(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+    let reader = BufReader::new(File::open(path)?);
+    let config_file = serde_json::from_reader(reader)?;
+    Ok(config_file)
+  }
+}
+
+
+/// Values that can be changed at runtime by editing the [configuration file](ConfigFile).
+#[derive(Clone, PartialEq, Debug)]
+pub struct Tunables {
+  pub max_followed_crates: Option<u32>,
+  pub crates_cache_max_age: u32,
+  pub maintenance_mode: bool,
+}
+
+impl Tunables {
+  /// Returns these tunables with the values that are set in `config_file` overriding them.
+  pub fn with_config_file(&self, config_file: &ConfigFile) -> Self {
+    Self {
+      max_followed_crates: config_file.max_followed_crates.or(self.max_followed_crates),
+      crates_cache_max_age: config_file.crates_cache_max_age.unwrap_or(self.crates_cache_max_age),
+      maintenance_mode: config_file.maintenance_mode.unwrap_or(self.maintenance_mode),
+    }
+  }
+}
+
+
+/// Watches the configuration file for changes, applying changed [`Tunables`] and log filters. Stops watching when
+/// dropped.
+pub struct ConfigWatcher {
+  _watcher: RecommendedWatcher,
+}
+
+impl ConfigWatcher {
+  /// Starts watching the configuration file at `path`, which was initially read as `config_file`. When it changes,
+  /// `base_tunables` overridden with values from the changed file are sent to `tunables`, and the log filter is
+  /// reloaded with `filter_reloader`.
+  pub fn new(
+    path: impl AsRef<Path>,
+    config_file: ConfigFile,
+    base_tunables: Tunables,
+    tunables: watch::Sender<Tunables>,
+    filter_reloader: FilterReloader,
+  ) -> Result<Self, ConfigError> {
+    // Watch the directory instead of the file, as editors often replace the file instead of modifying it.
+    let path = path.as_ref().canonicalize()?;
+    let directory = path.parent().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("/"));
+
+    let mut current = config_file;
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+      match result {
+        Ok(event) if (event.kind.is_create() || event.kind.is_modify()) && event.paths.contains(&path) => {
+          match ConfigFile::from_path(&path) {
+            Ok(config_file) => apply(&mut current, config_file, &base_tunables, &tunables, &filter_reloader),
+            Err(cause) => error!(%cause, "failed to reload configuration file; keeping current configuration: {cause:?}"),
+          }
+        }
+        Ok(_) => {}
+        Err(cause) => error!(%cause, "failed to watch configuration file: {cause:?}"),
+      }
+    })?;
+    watcher.watch(&directory, RecursiveMode::NonRecursive)?;
+
+    Ok(Self { _watcher: watcher })
+  }
+}
+
+/// Applies the changes from `current` to `new` that can be made at runtime, and warns about the ones that cannot.
+fn apply(
+  current: &mut ConfigFile,
+  mut new: ConfigFile,
+  base_tunables: &Tunables,
+  tunables: &watch::Sender<Tunables>,
+  filter_reloader: &FilterReloader,
+) {
+  if new.database_url != current.database_url {
+    warn!("ignoring changed database URL in configuration file; restart the server to apply it");
+    // Keep the database URL that is in use, so that we keep warning until it is changed back or the server restarts.
+    new.database_url = current.database_url.take();
+  }
+
+  let new_tunables = base_tunables.with_config_file(&new);
+  tunables.send_if_modified(|tunables| {
+    if *tunables == new_tunables { return false; }
+    info!(?new_tunables, "applied changed tunables from configuration file");
+    *tunables = new_tunables;
+    true
+  });
+
+  if new.log_filter != current.log_filter {
+    match &new.log_filter {
+      Some(log_filter) => {
+        filter_reloader.reload(log_filter);
+        info!(%log_filter, "applied changed log filter from configuration file");
+      }
+      None => warn!("log filter was removed from configuration file; restart the server to restore the default log filter"),
+    }
+  }
+
+  *current = new;
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{Arc, Mutex};
+  use std::time::Duration;
+
+  use super::*;
+
+  fn base_tunables() -> Tunables {
+    Tunables { max_followed_crates: None, crates_cache_max_age: 60, maintenance_mode: false }
+  }
+
+  /// Creates a filter reloader that records the directives it was reloaded with.
+  fn recording_filter_reloader() -> (FilterReloader, Arc<Mutex<Vec<String>>>) {
+    let reloaded = Arc::new(Mutex::new(Vec::new()));
+    let filter_reloader = {
+      let reloaded = reloaded.clone();
+      FilterReloader::new(move |directives| reloaded.lock().unwrap().push(directives.to_string()))
+    };
+    (filter_reloader, reloaded)
+  }
+
+  #[test]
+  fn changed_tunables_are_sent() {
+    let (tunables_tx, mut tunables_rx) = watch::channel(base_tunables());
+    let (filter_reloader, reloaded) = recording_filter_reloader();
+    let mut current = ConfigFile::default();
+
+    let new = ConfigFile { max_followed_crates: Some(10), log_filter: Some("att_server=trace".to_string()), ..ConfigFile::default() };
+    apply(&mut current, new.clone(), &base_tunables(), &tunables_tx, &filter_reloader);
+    assert!(tunables_rx.has_changed().unwrap());
+    assert_eq!(tunables_rx.borrow_and_update().max_followed_crates, Some(10));
+    assert_eq!(*reloaded.lock().unwrap(), ["att_server=trace"]);
+    assert_eq!(current, new);
+
+    // Applying the same file again changes nothing.
+    apply(&mut current, new, &base_tunables(), &tunables_tx, &filter_reloader);
+    assert!(!tunables_rx.has_changed().unwrap());
+    assert_eq!(reloaded.lock().unwrap().len(), 1);
+  }
+
+  #[test]
+  fn restart_only_changes_are_ignored() {
+    let (tunables_tx, mut tunables_rx) = watch::channel(base_tunables());
+    let (filter_reloader, reloaded) = recording_filter_reloader();
+    let database_url = Some("postgres://localhost/att".to_string());
+    let mut current = ConfigFile { database_url: database_url.clone(), ..ConfigFile::default() };
+
+    let new = ConfigFile { database_url: Some("postgres://elsewhere/att".to_string()), ..ConfigFile::default() };
+    apply(&mut current, new, &base_tunables(), &tunables_tx, &filter_reloader);
+    assert!(!tunables_rx.has_changed().unwrap());
+    assert_eq!(*tunables_rx.borrow_and_update(), base_tunables());
+    assert!(reloaded.lock().unwrap().is_empty());
+    assert_eq!(current.database_url, database_url);
+  }
+
+  #[tokio::test]
+  async fn watcher_sends_tunables_of_changed_file() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("config.json");
+    std::fs::write(&path, "{}").unwrap();
+    let (tunables_tx, mut tunables_rx) = watch::channel(base_tunables());
+    let (filter_reloader, _) = recording_filter_reloader();
+    let _watcher = ConfigWatcher::new(&path, ConfigFile::default(), base_tunables(), tunables_tx, filter_reloader).unwrap();
+
+    std::fs::write(&path, r#"{ "max_followed_crates": 5, "database_url": "postgres://elsewhere/att" }"#).unwrap();
+    tokio::time::timeout(Duration::from_secs(10), tunables_rx.changed()).await
+      .expect("changed configuration file should be applied")
+      .unwrap();
+    assert_eq!(*tunables_rx.borrow(), Tunables { max_followed_crates: Some(5), ..base_tunables() });
+  }
+}
@@ -1,35 +1,186 @@
+#[cfg(test)]
+use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::error::Error;
 use std::future::Future;
+use std::sync::Arc;
 use std::time::Duration;
 
-use crates_io_api::{AsyncClient, CrateResponse, CratesPage, CratesQuery, Sort};
+use crates_io_api::{CrateResponse, CratesPage};
 use futures::future::{BoxFuture, Fuse, FusedFuture};
 use futures::FutureExt;
-use tokio::sync::{mpsc, oneshot};
+use reqwest::{header, StatusCode};
+use serde::de::DeserializeOwned;
+use tokio::sync::{mpsc, Mutex, oneshot};
+use tokio::time::Instant;
 use tracing::{debug, info, instrument, trace};
+use url::Url;
 
 use att_core::crates::CrateError;
+use att_core::util::RetryPolicy;
 
 // Public API
 
+/// Base URL of the crates.io API.
+pub const DEFAULT_BASE_URL: &str = "https://crates.io/api/v1/";
+
 #[derive(Clone)]
 pub struct CratesIoClient {
   tx: mpsc::Sender<Request>
 }
 impl CratesIoClient {
-  pub fn new(user_agent: &str) -> Result<(Self, impl Future<Output=()>), Box<dyn Error>> {
-    let client = AsyncClient::new(user_agent, Duration::from_secs(1))?;
+  /// Creates a new crates.io client that sends requests to the API at `base_url`, identifying itself with
+  /// `user_agent`. Use [DEFAULT_BASE_URL] to send requests to crates.io. Requests that fail with a transient error are
+  /// retried according to `retry_policy`.
+  pub fn new(user_agent: &str, base_url: &str, retry_policy: RetryPolicy) -> Result<(Self, impl Future<Output=()>), Box<dyn Error>> {
+    let base_url = parse_base_url(base_url)?;
+    info!(%base_url, "using crates.io API");
+    let client = HttpApiClient::new(user_agent, base_url, Duration::from_secs(1), retry_policy)?;
+    Ok(Self::with_api_client(ApiClient::Http(client)))
+  }
+
+  /// Creates a new crates.io client that does not send any requests, but instead responds with the canned responses
+  /// of `mock`.
+  #[cfg(test)]
+  pub fn new_mock(mock: MockCratesIo) -> (Self, impl Future<Output=()>) {
+    Self::with_api_client(ApiClient::Mock(mock))
+  }
+
+  fn with_api_client(client: ApiClient) -> (Self, impl Future<Output=()>) {
     let (tx, rx) = mpsc::channel(64);
     let task = Task::new(rx, client).run();
-    Ok((Self { tx }, task))
+    (Self { tx }, task)
+  }
+}
+
+/// Canned crates.io responses for a [mock client](CratesIoClient::new_mock). Clones share the same responses, so
+/// responses can be changed after creating the client.
+///
+/// Requests for crates or search terms without a canned response fail with [ApiError::NotFound].
+#[cfg(test)]
+#[derive(Default, Clone)]
+pub struct MockCratesIo {
+  responses: Arc<std::sync::Mutex<MockResponses>>,
+}
+
+#[cfg(test)]
+#[derive(Default)]
+struct MockResponses {
+  crates: HashMap<String, Result<CrateResponse, StatusCode>>,
+  searches: HashMap<String, Result<CratesPage, StatusCode>>,
+  crate_request_count: usize,
+  on_crate_request: Option<Arc<dyn Fn(usize) + Send + Sync>>,
+}
+
+#[cfg(test)]
+impl MockCratesIo {
+  /// Respond to refreshing the crate with name `crate_name` with `response`.
+  pub fn set_crate(&self, crate_name: impl Into<String>, response: CrateResponse) {
+    self.responses().crates.insert(crate_name.into(), Ok(response));
+  }
+  /// Fail refreshing the crate with name `crate_name` with error `status`.
+  pub fn fail_crate(&self, crate_name: impl Into<String>, status: StatusCode) {
+    self.responses().crates.insert(crate_name.into(), Err(status));
+  }
+
+  /// Respond to searching for `search_term` with `response`.
+  pub fn set_search(&self, search_term: impl Into<String>, response: CratesPage) {
+    self.responses().searches.insert(search_term.into(), Ok(response));
+  }
+  /// Fail searching for `search_term` with error `status`.
+  pub fn fail_search(&self, search_term: impl Into<String>, status: StatusCode) {
+    self.responses().searches.insert(search_term.into(), Err(status));
+  }
+
+  /// Gets the number of crate refresh requests received so far.
+  pub fn crate_request_count(&self) -> usize {
+    self.responses().crate_request_count
+  }
+  /// Calls `f` with the number of crate refresh requests received so far, after receiving each crate refresh request.
+  pub fn on_crate_request(&self, f: impl Fn(usize) + Send + Sync + 'static) {
+    self.responses().on_crate_request = Some(Arc::new(f));
+  }
+
+  fn search(&self, search_term: &str) -> Result<CratesPage, ApiError> {
+    Self::respond(search_term, self.responses().searches.get(search_term))
+  }
+
+  fn get_crate(&self, crate_name: &str) -> Result<CrateResponse, ApiError> {
+    let (response, count, on_crate_request) = {
+      let mut responses = self.responses();
+      responses.crate_request_count += 1;
+      let response = Self::respond(crate_name, responses.crates.get(crate_name));
+      (response, responses.crate_request_count, responses.on_crate_request.clone())
+    };
+    // Call outside of the lock, so that `on_crate_request` can use this mock.
+    if let Some(on_crate_request) = on_crate_request {
+      on_crate_request(count);
+    }
+    response
+  }
+
+  fn respond<T: Clone>(key: &str, response: Option<&Result<T, StatusCode>>) -> Result<T, ApiError> {
+    match response {
+      Some(Ok(value)) => Ok(value.clone()),
+      Some(Err(StatusCode::NOT_FOUND)) | None => Err(ApiError::NotFound(key.to_string())),
+      Some(Err(status)) => Err(ApiError::Status(*status)),
+    }
+  }
+
+  fn responses(&self) -> std::sync::MutexGuard<'_, MockResponses> {
+    self.responses.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BaseUrlError {
+  #[error("Failed to parse crates.io API base URL: {0}")]
+  Parse(#[from] url::ParseError),
+  #[error("crates.io API base URL '{0}' must use the http or https scheme")]
+  InvalidScheme(Url),
+}
+
+/// Parses and validates crates.io API `base_url`, ensuring that it ends with a `/` so that API paths are joined onto
+/// it instead of replacing its last path segment.
+fn parse_base_url(base_url: &str) -> Result<Url, BaseUrlError> {
+  let mut base_url = Url::parse(base_url)?;
+  if !matches!(base_url.scheme(), "http" | "https") || base_url.cannot_be_a_base() {
+    return Err(BaseUrlError::InvalidScheme(base_url));
+  }
+  if !base_url.path().ends_with('/') {
+    let path = format!("{}/", base_url.path());
+    base_url.set_path(&path);
+  }
+  Ok(base_url)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+  #[error("HTTP request failed: {0}")]
+  Request(#[from] reqwest::Error),
+  #[error("Failed to create request URL: {0}")]
+  Url(#[from] url::ParseError),
+  #[error("Request failed with status code {0}")]
+  Status(StatusCode),
+  #[error("'{0}' was not found")]
+  NotFound(String),
+}
+impl ApiError {
+  /// Returns whether this error is transient, such as a connection failure, timeout, rate limit, or server error, and
+  /// the request may succeed when retried.
+  pub fn is_transient(&self) -> bool {
+    match self {
+      Self::Request(e) => e.is_connect() || e.is_timeout(),
+      Self::Status(status) => status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS,
+      _ => false,
+    }
   }
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum CratesIoClientError {
   #[error("Failed to execute request: {0}")]
-  CratesIoFail(#[from] crates_io_api::Error),
+  CratesIoFail(#[from] ApiError),
   #[error("Failed to receive response; request was cancelled or crates.io client is shutting down")]
   Cancelled,
   #[error("Failed to send request; crates.io client is shutting down")]
@@ -47,7 +198,7 @@ impl CratesIoClientError {
   #[inline]
   pub fn into_crate_error(self) -> CrateError {
     match self {
-      Self::CratesIoFail(crates_io_api::Error::NotFound(_)) => CrateError::NotFound,
+      Self::CratesIoFail(ApiError::NotFound(_)) => CrateError::NotFound,
       _ => CrateError::Internal,
     }
   }
@@ -75,7 +226,7 @@ impl CratesIoClient {
     self.send_receive(|tx| Request::Refresh(Refresh { crate_id, tx })).await
   }
 
-  async fn send_receive<T>(&self, make_request: impl FnOnce(oneshot::Sender<Result<T, crates_io_api::Error>>) -> Request) -> Result<T, CratesIoClientError> {
+  async fn send_receive<T>(&self, make_request: impl FnOnce(oneshot::Sender<Result<T, ApiError>>) -> Request) -> Result<T, CratesIoClientError> {
     let (tx, rx) = oneshot::channel();
     let request = make_request(tx);
     self.send(request).await?;
@@ -99,13 +250,13 @@ enum Request {
 
 struct Task {
   rx: mpsc::Receiver<Request>,
-  client: AsyncClient,
+  client: ApiClient,
   search: Fuse<BoxFuture<'static, ()>>,
   refresh: Fuse<BoxFuture<'static, ()>>,
   queue: VecDeque<Refresh>,
 }
 impl Task {
-  fn new(rx: mpsc::Receiver<Request>, client: AsyncClient) -> Self {
+  fn new(rx: mpsc::Receiver<Request>, client: ApiClient) -> Self {
     let task = Self {
       rx,
       client,
@@ -178,28 +329,185 @@ impl Task {
 
 struct Search {
   search_term: String,
-  tx: oneshot::Sender<Result<CratesPage, crates_io_api::Error>>,
+  tx: oneshot::Sender<Result<CratesPage, ApiError>>,
 }
 impl Search {
-  async fn run(self, client: AsyncClient) {
+  async fn run(self, client: ApiClient) {
     info!(search_term = self.search_term, "running crate search");
-    let query = CratesQuery::builder()
-      .search(self.search_term)
-      .sort(Sort::Relevance)
-      .build();
-    let response = client.crates(query).await;
+    let response = client.search(&self.search_term).await;
     let _ = self.tx.send(response); // Ignore error ok: do nothing if receiver was dropped.
   }
 }
 
 struct Refresh {
   crate_id: String,
-  tx: oneshot::Sender<Result<CrateResponse, crates_io_api::Error>>,
+  tx: oneshot::Sender<Result<CrateResponse, ApiError>>,
 }
 impl Refresh {
-  async fn run(self, client: AsyncClient) {
+  async fn run(self, client: ApiClient) {
     info!(crate_id = self.crate_id, "running crate refresh");
     let response = client.get_crate(&self.crate_id).await;
     let _ = self.tx.send(response); // Ignore error ok: do nothing if receiver was dropped.
   }
 }
+
+#[derive(Clone)]
+enum ApiClient {
+  Http(HttpApiClient),
+  #[cfg(test)]
+  Mock(MockCratesIo),
+}
+impl ApiClient {
+  async fn search(&self, search_term: &str) -> Result<CratesPage, ApiError> {
+    match self {
+      Self::Http(client) => client.search(search_term).await,
+      #[cfg(test)]
+      Self::Mock(mock) => mock.search(search_term),
+    }
+  }
+
+  async fn get_crate(&self, crate_name: &str) -> Result<CrateResponse, ApiError> {
+    match self {
+      Self::Http(client) => client.get_crate(crate_name).await,
+      #[cfg(test)]
+      Self::Mock(mock) => mock.get_crate(crate_name),
+    }
+  }
+}
+
+/// Rate-limited client for a crates.io-compatible API at `base_url`.
+#[derive(Clone)]
+struct HttpApiClient {
+  http_client: reqwest::Client,
+  base_url: Url,
+  rate_limit: Duration,
+  last_request_time: Arc<Mutex<Option<Instant>>>,
+  retry_policy: RetryPolicy,
+}
+impl HttpApiClient {
+  fn new(user_agent: &str, base_url: Url, rate_limit: Duration, retry_policy: RetryPolicy) -> Result<Self, Box<dyn Error>> {
+    let mut headers = header::HeaderMap::new();
+    headers.insert(header::USER_AGENT, header::HeaderValue::from_str(user_agent)?);
+    let http_client = reqwest::Client::builder()
+      .default_headers(headers)
+      .build()?;
+    Ok(Self { http_client, base_url, rate_limit, last_request_time: Default::default(), retry_policy })
+  }
+
+  async fn search(&self, search_term: &str) -> Result<CratesPage, ApiError> {
+    let mut url = self.base_url.join("crates")?;
+    url.query_pairs_mut()
+      .append_pair("q", search_term)
+      .append_pair("sort", "relevance");
+    self.get(url).await
+  }
+
+  async fn get_crate(&self, crate_name: &str) -> Result<CrateResponse, ApiError> {
+    let url = self.base_url.join("crates/")?.join(crate_name)?;
+    self.get(url).await
+  }
+
+  async fn get<T: DeserializeOwned>(&self, url: Url) -> Result<T, ApiError> {
+    self.retry_policy.retry(|| self.get_once(url.clone()), |e| {
+      let transient = e.is_transient();
+      if transient {
+        debug!(%url, "retrying crates.io request after transient error: {e:?}");
+      }
+      transient
+    }).await
+  }
+
+  async fn get_once<T: DeserializeOwned>(&self, url: Url) -> Result<T, ApiError> {
+    // Hold the lock while sending the request, so that concurrent requests also adhere to the rate limit.
+    let mut last_request_time = self.last_request_time.lock().await;
+    if let Some(last_request_time) = *last_request_time {
+      tokio::time::sleep_until(last_request_time + self.rate_limit).await;
+    }
+    let response = self.http_client.get(url.clone()).send().await;
+    *last_request_time = Some(Instant::now());
+    drop(last_request_time);
+
+    let response = response?;
+    match response.status() {
+      StatusCode::NOT_FOUND => return Err(ApiError::NotFound(url.to_string())),
+      status if !status.is_success() => return Err(ApiError::Status(status)),
+      _ => {}
+    }
+    let value = response.json().await?;
+    Ok(value)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn crates_page(total: u64) -> CratesPage {
+    serde_json::from_value(serde_json::json!({ "crates": [], "meta": { "total": total } })).unwrap()
+  }
+
+  #[tokio::test]
+  async fn search_responds_with_canned_page() {
+    let mock = MockCratesIo::default();
+    mock.set_search("serde", crates_page(42));
+    let (client, task) = CratesIoClient::new_mock(mock);
+    tokio::spawn(task);
+
+    let page = client.search("serde".to_string()).await.unwrap().unwrap();
+    assert_eq!(page.meta.total, 42);
+  }
+
+  #[tokio::test]
+  async fn refresh_unknown_crate_is_not_found() {
+    let mock = MockCratesIo::default();
+    mock.fail_crate("removed", StatusCode::NOT_FOUND);
+    let (client, task) = CratesIoClient::new_mock(mock);
+    tokio::spawn(task);
+
+    for crate_name in ["removed", "never-existed"] {
+      let error = client.refresh(crate_name.to_string()).await.unwrap_err();
+      assert!(matches!(error, CratesIoClientError::CratesIoFail(ApiError::NotFound(ref name)) if name == crate_name));
+      assert!(matches!(error.into_crate_error(), CrateError::NotFound));
+    }
+  }
+
+  #[tokio::test]
+  async fn refresh_server_error_is_transient() {
+    let mock = MockCratesIo::default();
+    mock.fail_crate("serde", StatusCode::SERVICE_UNAVAILABLE);
+    let (client, task) = CratesIoClient::new_mock(mock);
+    tokio::spawn(task);
+
+    let error = client.refresh("serde".to_string()).await.unwrap_err();
+    let CratesIoClientError::CratesIoFail(api_error) = error else { panic!("expected crates.io failure, got {error:?}") };
+    assert!(api_error.is_transient());
+  }
+
+  #[tokio::test]
+  async fn requests_are_sent_to_configured_base_url() {
+    let requested = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let router = {
+      let requested = requested.clone();
+      axum::Router::new().fallback(move |uri: axum::http::Uri| async move {
+        requested.lock().unwrap().push(uri.to_string());
+        StatusCode::NOT_FOUND
+      })
+    };
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+    // Base URL without trailing slash, which must still be joined onto instead of replaced.
+    let base_url = format!("http://{address}/mirror/api/v1");
+    let (client, task) = CratesIoClient::new("att test", &base_url, RetryPolicy::NONE).unwrap();
+    tokio::spawn(task);
+
+    let error = client.refresh("serde".to_string()).await.unwrap_err();
+    assert!(matches!(error, CratesIoClientError::CratesIoFail(ApiError::NotFound(ref url)) if url.starts_with(&base_url)));
+    client.search("serde".to_string()).await.unwrap_err();
+    assert_eq!(*requested.lock().unwrap(), [
+      "/mirror/api/v1/crates/serde",
+      "/mirror/api/v1/crates?q=serde&sort=relevance",
+    ]);
+  }
+}
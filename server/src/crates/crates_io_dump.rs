@@ -1,39 +1,93 @@
 use std::future::Future;
 use std::io;
-use std::path::PathBuf;
-use std::time::{Duration, SystemTimeError};
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::sync::Arc;
+use std::time::Duration;
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use db_dump::Loader;
-use futures::StreamExt;
+use futures::{Stream, StreamExt};
 use nohash_hasher::{BuildNoHashHasher, IntMap};
+use sha2::{Digest, Sha256};
 use thiserror::Error;
 use tokio::fs;
 use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, watch};
 use tokio::task::block_in_place;
-use tracing::{info, instrument};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, instrument};
 
-use att_core::crates::{Crate, CrateVersion};
+use att_core::crates::{Crate, CrateId, CrateVersion, CRATES_IO_REGISTRY};
+use att_core::util::RetryPolicy;
 use att_server_db::{DbError, DbPool};
 use att_server_db::crates::{CratesDb, ImportCrates};
 
+use crate::clock::SharedClock;
 use crate::job_scheduler::{Job, JobAction, JobResult};
 
 #[derive(Clone)]
 pub struct CratesIoDump {
   db_dump_file: PathBuf,
   db_pool: DbPool<CratesDb>,
+  clock: SharedClock,
+  cancel: CancellationToken,
+  update_interval: Duration,
+  progress: Arc<watch::Sender<ImportProgress>>,
+  update_lock: Arc<Mutex<()>>,
 }
 
 impl CratesIoDump {
-  pub fn new(db_dump_file: PathBuf, db_pool: DbPool<CratesDb>) -> Self {
-    Self { db_dump_file, db_pool }
+  /// Creates a new crates.io database dump updater/importer. Updating and importing stop when `cancel` is cancelled.
+  pub fn new(db_dump_file: PathBuf, db_pool: DbPool<CratesDb>, clock: SharedClock, cancel: CancellationToken) -> Self {
+    let (progress, _) = watch::channel(ImportProgress::default());
+    Self {
+      db_dump_file,
+      db_pool,
+      clock,
+      cancel,
+      update_interval: UPDATE_DURATION,
+      progress: Arc::new(progress),
+      update_lock: Default::default(),
+    }
+  }
+
+  /// Consider the database dump file outdated once it is older than `update_interval`.
+  #[inline]
+  pub fn with_update_interval(mut self, update_interval: Duration) -> Self {
+    self.update_interval = update_interval;
+    self
   }
+
+  /// Gets the interval after which the database dump file is considered outdated.
+  #[inline]
+  pub fn update_interval(&self) -> Duration { self.update_interval }
+
+  /// Subscribes to the progress of importing the database dump.
+  #[inline]
+  pub fn import_progress(&self) -> watch::Receiver<ImportProgress> { self.progress.subscribe() }
+}
+
+/// Number of rows read from a table of the database dump between progress reports.
+const PROGRESS_REPORT_ROWS: usize = 100_000;
+
+/// Progress of importing the crates.io database dump.
+#[derive(Default, Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ImportProgress {
+  /// Not importing.
+  #[default]
+  Idle,
+  /// Reading `rows` rows from `table` of the database dump so far. Reported every [`PROGRESS_REPORT_ROWS`] rows.
+  Reading { table: &'static str, rows: usize },
+  /// Writing `rows` rows of crates and versions into the database.
+  Writing { rows: usize },
 }
 
 
 // Scheduled job
 
+/// Default interval at which the database dump is updated.
 pub const UPDATE_DURATION: Duration = Duration::from_secs(60 * 60 * 24);
 
 pub struct UpdateCratesIoDumpJob {
@@ -44,16 +98,25 @@ impl UpdateCratesIoDumpJob {
   pub fn new(crates_io_dump: CratesIoDump) -> Self {
     Self { crates_io_dump }
   }
+
+  /// Subscribes to the progress of importing the database dump.
+  #[inline]
+  pub fn import_progress(&self) -> watch::Receiver<ImportProgress> { self.crates_io_dump.import_progress() }
 }
 
 impl Job for UpdateCratesIoDumpJob {
   async fn run(&self) -> JobResult {
-    let db_dump_file_updated = self.crates_io_dump.update_db_dump_file().await?;
-    let import_required = self.crates_io_dump.is_import_required().await?;
-    if db_dump_file_updated || import_required {
-      self.crates_io_dump.import_db_dump().await?;
+    match self.crates_io_dump.update_and_import().await {
+      Err(InternalError::Cancelled) => Ok(JobAction::Cancel),
+      Err(e) => Err(e.into()),
+      Ok(_) => Ok(JobAction::Continue),
     }
-    Ok(JobAction::Continue)
+  }
+
+  /// Retry soon after a failure, as downloading the database dump can fail due to transient network errors.
+  #[inline]
+  fn retry_policy(&self) -> RetryPolicy {
+    RetryPolicy::new(3, Duration::from_secs(60), Duration::from_secs(4 * 60), 0.2)
   }
 }
 
@@ -67,16 +130,41 @@ enum InternalError {
   #[error(transparent)]
   Io(#[from] io::Error),
   #[error(transparent)]
-  Time(#[from] SystemTimeError),
-  #[error(transparent)]
   HttpRequest(#[from] reqwest::Error),
   #[error(transparent)]
   Database(#[from] DbError),
+  #[error("Checksum of downloaded database dump {actual} does not match expected checksum {expected}")]
+  ChecksumMismatch { expected: String, actual: String },
+  #[error("Cancelled")]
+  Cancelled,
 }
 
 impl CratesIoDump {
+  /// Updates the database dump file and imports it if needed. Skipped if another update of this dump (or a clone of it)
+  /// is already running, as both would download into the same file and import the same data. Imports from other
+  /// processes are prevented from overlapping by an advisory lock in the database.
+  async fn update_and_import(&self) -> Result<(), InternalError> {
+    let Ok(_update_guard) = self.update_lock.try_lock() else {
+      info!("Skipped updating crates.io database dump, as another update is already running");
+      return Ok(());
+    };
+    let db_dump_file_updated = self.update_db_dump_file().await?;
+    let import_required = self.is_import_required().await?;
+    if db_dump_file_updated || import_required {
+      self.import_db_dump().await?;
+    }
+    Ok(())
+  }
+
   #[instrument(skip_all, err)]
   async fn import_db_dump(&self) -> Result<(), InternalError> {
+    let result = self.import_db_dump_with_progress().await;
+    self.progress.send_replace(ImportProgress::Idle);
+    result
+  }
+
+  async fn import_db_dump_with_progress(&self) -> Result<(), InternalError> {
+    self.check_cancelled()?;
     info!("Reading database dump");
 
     const EXPECTED_CRATE_COUNT: usize = 1024 * 512;
@@ -84,11 +172,22 @@ impl CratesIoDump {
     //let mut crate_id_to_index = IntMap::with_capacity_and_hasher(EXPECTED_CRATE_COUNT, BuildNoHashHasher::default());;
     let mut downloads = IntMap::with_capacity_and_hasher(EXPECTED_CRATE_COUNT, BuildNoHashHasher::default());
     let mut default_version_ids = IntMap::with_capacity_and_hasher(EXPECTED_CRATE_COUNT, BuildNoHashHasher::default());
+    // Report progress every `PROGRESS_REPORT_ROWS` rows. The loader streams rows from the archive, so progress is
+    // reported while reading rather than after loading the whole archive.
+    let report_progress = |table: &'static str, rows: &mut usize| {
+      *rows += 1;
+      if *rows % PROGRESS_REPORT_ROWS == 0 {
+        info!(table, rows, "Reading database dump");
+        self.progress.send_replace(ImportProgress::Reading { table, rows: *rows });
+      }
+    };
+    let (mut crate_rows, mut download_rows, mut version_rows, mut default_version_rows) = (0, 0, 0, 0);
 
     block_in_place(|| Loader::new()
       .crates(|row| {
+        report_progress("crates", &mut crate_rows);
         import_crates.crates.push(Crate {
-          id: row.id.0 as i32,
+          id: CrateId(row.id.0 as i32),
           name: row.name,
           updated_at: row.updated_at,
           created_at: row.created_at,
@@ -100,43 +199,64 @@ impl CratesIoDump {
           downloads: 0,
 
           default_version_id: 0,
+
+          registry: CRATES_IO_REGISTRY.to_string(),
         });
       })
       .crate_downloads(|row| {
+        report_progress("crate_downloads", &mut download_rows);
         downloads.insert(row.crate_id.0 as i32, row.downloads as i64);
       })
       .versions(|row| {
+        report_progress("versions", &mut version_rows);
         import_crates.versions.push(CrateVersion {
           id: row.id.0 as i32,
-          crate_id: row.crate_id.0 as i32,
+          crate_id: CrateId(row.crate_id.0 as i32),
           number: row.num.to_string(),
         });
       })
       .default_versions(|row| {
+        report_progress("default_versions", &mut default_version_rows);
         default_version_ids.insert(row.crate_id.0 as i32, row.version_id.0 as i32);
       })
       .load(&self.db_dump_file)
     )?;
 
     for krate in &mut import_crates.crates {
-      krate.downloads = *downloads.get(&krate.id).unwrap();
-      krate.default_version_id = *default_version_ids.get(&krate.id).unwrap();
+      krate.downloads = *downloads.get(&krate.id.0).unwrap();
+      krate.default_version_id = *default_version_ids.get(&krate.id.0).unwrap();
     }
 
-    info!("Importing database dump");
+    // Check before importing, as the import replaces all crates in a single transaction that cannot be cancelled.
+    self.check_cancelled()?;
+    let rows = import_crates.crates.len() + import_crates.versions.len();
+    info!(rows, "Importing database dump");
+    self.progress.send_replace(ImportProgress::Writing { rows });
     let inserted_rows = self.db_pool.query(move |db| db.import(import_crates))
       .await?;
-    info!(inserted_rows, "Imported database dump");
+    match inserted_rows {
+      Some(inserted_rows) => info!(inserted_rows, "Imported database dump"),
+      None => info!("Skipped importing database dump, as another import is already running"),
+    }
 
     Ok(())
   }
 
+  #[inline]
+  fn check_cancelled(&self) -> Result<(), InternalError> {
+    if self.cancel.is_cancelled() {
+      info!("Cancelled importing crates.io database dump");
+      return Err(InternalError::Cancelled);
+    }
+    Ok(())
+  }
+
   #[instrument(skip_all, err)]
   async fn is_import_required(&self) -> Result<bool, InternalError> {
     let last_imported_at = self.db_pool.query(move |db| db.get_last_imported_at())
       .await?;
     let import_required = if let Some(last_imported_at) = last_imported_at {
-      let delta = Utc::now() - last_imported_at;
+      let delta = self.clock.now() - last_imported_at;
       delta.num_days() > 0
     } else {
       true
@@ -147,10 +267,17 @@ impl CratesIoDump {
   #[instrument(skip_all, err)]
   fn update_db_dump_file(&self) -> impl Future<Output=Result<bool, InternalError>> {
     let db_dump_file = self.db_dump_file.clone();
+    let clock = self.clock.clone();
+    let cancel = self.cancel.clone();
+    let update_interval = self.update_interval;
 
     async move {
       let is_up_to_date = match fs::metadata(&db_dump_file).await {
-        Ok(metadata) => metadata.modified()?.elapsed()? < UPDATE_DURATION,
+        Ok(metadata) => {
+          let modified: DateTime<Utc> = metadata.modified()?.into();
+          // Modification times in the future fail to convert to a duration; consider those up-to-date.
+          (clock.now() - modified).to_std().map_or(true, |age| age < update_interval)
+        }
         Err(e) if e.kind() == io::ErrorKind::NotFound => false,
         Err(e) => Err(e)?,
       };
@@ -159,21 +286,175 @@ impl CratesIoDump {
       }
 
       const URL: &str = "https://static.crates.io/db-dump.tar.gz";
+      const CHECKSUM_URL: &str = "https://static.crates.io/db-dump.tar.gz.sha256";
+      // Get the checksum before downloading, as the download can only replace the file once it is verified.
+      let expected_checksum = reqwest::get(CHECKSUM_URL).await?
+        .error_for_status()?
+        .text().await?;
+      let expected_checksum = parse_checksum(&expected_checksum);
+
       info!("Downloading crates.io database dump '{}' into '{}'", URL, db_dump_file.display());
+      let response = reqwest::get(URL).await?.error_for_status()?;
+      download_verified(response.bytes_stream(), &db_dump_file, &expected_checksum, &cancel).await?;
+      Ok(true)
+    }
+  }
+}
 
-      if let Some(parent) = db_dump_file.parent() {
-        fs::create_dir_all(parent).await?;
-      }
-      let mut file = File::create(&db_dump_file).await?;
+/// Parses a SHA-256 checksum file, which may be in `sha256sum` format: the checksum followed by the file name.
+fn parse_checksum(checksum_file: &str) -> String {
+  checksum_file.split_whitespace().next().unwrap_or_default().to_lowercase()
+}
 
-      let response = reqwest::get(URL).await?;
-      let mut bytes_stream = response.bytes_stream();
+/// Downloads `bytes_stream` into `file_path`, verifying that its SHA-256 checksum is `expected_checksum`. The bytes are
+/// first written to a temporary file next to it, which only replaces the file once verified. Therefore, a failed,
+/// cancelled, or corrupt download does not truncate the existing file, and does not leave behind a file that would be
+/// considered up-to-date.
+async fn download_verified<B: AsRef<[u8]>, E: Into<InternalError>>(
+  bytes_stream: impl Stream<Item=Result<B, E>>,
+  file_path: &Path,
+  expected_checksum: &str,
+  cancel: &CancellationToken,
+) -> Result<(), InternalError> {
+  let mut temp_file_path = file_path.to_path_buf().into_os_string();
+  temp_file_path.push(".tmp");
+  let temp_file_path = PathBuf::from(temp_file_path);
 
-      while let Some(bytes) = bytes_stream.next().await {
-        let bytes = bytes?;
-        tokio::io::copy(&mut bytes.as_ref(), &mut file).await?;
+  let mut result = write_verified(bytes_stream, &temp_file_path, expected_checksum, cancel).await;
+  if result.is_ok() {
+    result = fs::rename(&temp_file_path, file_path).await.map_err(InternalError::from);
+  }
+  if result.is_err() {
+    let _ = fs::remove_file(&temp_file_path).await;
+  }
+  result
+}
+
+async fn write_verified<B: AsRef<[u8]>, E: Into<InternalError>>(
+  bytes_stream: impl Stream<Item=Result<B, E>>,
+  file_path: &Path,
+  expected_checksum: &str,
+  cancel: &CancellationToken,
+) -> Result<(), InternalError> {
+  if let Some(parent) = file_path.parent() {
+    fs::create_dir_all(parent).await?;
+  }
+  let mut file = File::create(file_path).await?;
+
+  let mut bytes_stream = pin!(bytes_stream);
+  let mut downloaded_bytes = 0;
+  let mut hasher = Sha256::new();
+  loop {
+    let bytes = tokio::select! {
+      biased;
+      _ = cancel.cancelled() => {
+        info!(downloaded_bytes, "Cancelled downloading crates.io database dump");
+        return Err(InternalError::Cancelled);
       }
-      Ok(true)
-    }
+      bytes = bytes_stream.next() => bytes,
+    };
+    let Some(bytes) = bytes else { break; };
+    let bytes = bytes.map_err(Into::into)?;
+    let bytes = bytes.as_ref();
+    downloaded_bytes += bytes.len();
+    hasher.update(bytes);
+    file.write_all(bytes).await?;
+  }
+  file.flush().await?;
+  file.sync_all().await?;
+
+  let actual_checksum = format!("{:x}", hasher.finalize());
+  if actual_checksum != expected_checksum {
+    error!(expected_checksum, actual_checksum, "Downloaded crates.io database dump is corrupt; discarding it");
+    return Err(InternalError::ChecksumMismatch { expected: expected_checksum.to_string(), actual: actual_checksum });
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use futures::stream;
+  use tempfile::TempDir;
+
+  use super::*;
+
+  /// Small stand-in for the database dump archive, in chunks as they would be streamed.
+  const FIXTURE: [&[u8]; 3] = [b"crates.io ", b"database dump ", b"fixture"];
+  /// SHA-256 checksum of the concatenated [`FIXTURE`].
+  const FIXTURE_CHECKSUM: &str = "d6277c29d5c14c0de0672b0786ba57a48348e430055aa2fabea17813f4765584";
+  const BAD_CHECKSUM: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+  fn fixture_stream() -> impl Stream<Item=Result<&'static [u8], io::Error>> {
+    stream::iter(FIXTURE.map(Ok))
+  }
+
+  /// Creates a temporary directory with an existing, previously downloaded database dump file.
+  fn existing_dump() -> (TempDir, PathBuf) {
+    let directory = TempDir::new().unwrap();
+    let file_path = directory.path().join("db-dump.tar.gz");
+    std::fs::write(&file_path, b"previous dump").unwrap();
+    (directory, file_path)
+  }
+
+  fn assert_no_temp_file(directory: &TempDir) {
+    assert!(!directory.path().join("db-dump.tar.gz.tmp").exists());
+  }
+
+  #[test]
+  fn parse_checksum_file() {
+    assert_eq!(parse_checksum(FIXTURE_CHECKSUM), FIXTURE_CHECKSUM);
+    assert_eq!(parse_checksum(&format!("{}  db-dump.tar.gz\n", FIXTURE_CHECKSUM.to_uppercase())), FIXTURE_CHECKSUM);
+    assert_eq!(parse_checksum(""), "");
+  }
+
+  #[tokio::test]
+  async fn download_with_good_checksum_replaces_file() {
+    let (directory, file_path) = existing_dump();
+    download_verified(fixture_stream(), &file_path, FIXTURE_CHECKSUM, &CancellationToken::new()).await.unwrap();
+
+    assert_eq!(std::fs::read(&file_path).unwrap(), FIXTURE.concat());
+    assert_no_temp_file(&directory);
+  }
+
+  #[tokio::test]
+  async fn download_with_bad_checksum_keeps_existing_file() {
+    let (directory, file_path) = existing_dump();
+    let result = download_verified(fixture_stream(), &file_path, BAD_CHECKSUM, &CancellationToken::new()).await;
+
+    assert!(matches!(result, Err(InternalError::ChecksumMismatch { actual, .. }) if actual == FIXTURE_CHECKSUM));
+    assert_eq!(std::fs::read(&file_path).unwrap(), b"previous dump");
+    assert_no_temp_file(&directory);
+  }
+
+  #[tokio::test]
+  async fn failed_download_keeps_existing_file() {
+    let (directory, file_path) = existing_dump();
+    let bytes_stream = stream::iter([Ok(FIXTURE[0]), Err(io::Error::from(io::ErrorKind::ConnectionReset))]);
+    let result = download_verified(bytes_stream, &file_path, FIXTURE_CHECKSUM, &CancellationToken::new()).await;
+
+    assert!(matches!(result, Err(InternalError::Io(_))));
+    assert_eq!(std::fs::read(&file_path).unwrap(), b"previous dump");
+    assert_no_temp_file(&directory);
+  }
+
+  #[tokio::test]
+  async fn cancelled_download_keeps_existing_file() {
+    let (directory, file_path) = existing_dump();
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let result = download_verified(fixture_stream(), &file_path, FIXTURE_CHECKSUM, &cancel).await;
+
+    assert!(matches!(result, Err(InternalError::Cancelled)));
+    assert_eq!(std::fs::read(&file_path).unwrap(), b"previous dump");
+    assert_no_temp_file(&directory);
+  }
+
+  #[tokio::test]
+  async fn download_without_existing_file() {
+    let directory = TempDir::new().unwrap();
+    let file_path = directory.path().join("dumps").join("db-dump.tar.gz");
+    download_verified(fixture_stream(), &file_path, FIXTURE_CHECKSUM, &CancellationToken::new()).await.unwrap();
+
+    assert_eq!(std::fs::read(&file_path).unwrap(), FIXTURE.concat());
   }
 }
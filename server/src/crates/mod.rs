@@ -1,52 +1,133 @@
-use std::error::Error;
-use std::future::Future;
 use std::path::PathBuf;
+use std::time::Duration;
 
+use chrono::TimeDelta;
+use crates_io_api::{CrateResponse, Version};
 use thiserror::Error;
-use tracing::instrument;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, instrument};
 
-use att_core::crates::{CrateError, CratesQuery, FullCrate};
+use att_core::crates::{CrateError, CrateId, CratesPage, CratesQuery, CratesSort, CrateVersion, FullCrate, NewSavedSearch, SavedSearch};
+use att_core::users::UserId;
 use att_server_db::{DbError, DbPool, DbPoolObj};
 use att_server_db::crates::{CratesDb, UpdateCrate};
-use crates_io_client::CratesIoClient;
 
+use crate::clock::SharedClock;
+use crate::config::Tunables;
 use crate::crates::crates_io_client::CratesIoClientError;
-use crate::crates::crates_io_dump::{CratesIoDump, UpdateCratesIoDumpJob};
+use crate::crates::crates_io_dump::{CratesIoDump, ImportProgress, UpdateCratesIoDumpJob};
+use crate::crates::registry::Registries;
+use crate::job_scheduler::{Job, JobAction, JobResult};
+use crate::util::CacheControl;
 
 pub mod crates_io_client;
 pub mod crates_io_dump;
+pub mod registry;
 pub mod route;
 
+/// Default number of seconds that responses which are not specific to a user may be cached.
+pub const DEFAULT_CACHE_MAX_AGE: u32 = 60;
+/// Default maximum age of followed crates before they are refreshed by [`Crates::refresh_outdated`].
+pub const DEFAULT_REFRESH_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+/// Interval at which [`RefreshOutdatedCratesJob`] runs. Crates with a shorter refresh interval are refreshed at most
+/// this often.
+pub const REFRESH_OUTDATED_INTERVAL: Duration = Duration::from_secs(60 * 5);
+/// Number of seconds after which clients should retry requests that were rejected due to maintenance mode.
+pub const MAINTENANCE_RETRY_AFTER: u32 = 300;
+
 #[derive(Clone)]
 pub struct Crates {
   db_pool: DbPool<CratesDb>,
-  crates_io_client: CratesIoClient,
+  registries: Registries,
   crates_io_dump: CratesIoDump,
+  clock: SharedClock,
+  tunables: watch::Receiver<Tunables>,
+  cancel: CancellationToken,
 }
 
 impl Crates {
   pub fn new(
     db_pool: DbPool,
-    crates_io_user_agent: &str,
-    crates_io_db_dump_file: PathBuf
-  ) -> Result<(Self, impl Future<Output=()>), Box<dyn Error>> {
+    registries: Registries,
+    clock: SharedClock,
+    crates_io_db_dump_file: PathBuf,
+    tunables: watch::Receiver<Tunables>,
+    cancel: CancellationToken,
+  ) -> Self {
     let db_pool = db_pool.with();
-    let (crates_io_client, task) = CratesIoClient::new(crates_io_user_agent)?;
-    let crates_io_dump = CratesIoDump::new(crates_io_db_dump_file, db_pool.clone());
-    let crates = Self { db_pool, crates_io_client, crates_io_dump };
-    Ok((crates, task))
+    let crates_io_dump = CratesIoDump::new(crates_io_db_dump_file, db_pool.clone(), clock.clone(), cancel.clone());
+    Self { db_pool, registries, crates_io_dump, clock, tunables, cancel }
+  }
+
+  /// Gets the cache control for responses that are not specific to a user.
+  #[inline]
+  pub fn cache_control(&self) -> CacheControl {
+    CacheControl::from_max_age(self.tunables.borrow().crates_cache_max_age)
   }
 
+  /// Returns whether the server is in maintenance mode, in which requests that modify data are rejected.
+  #[inline]
+  pub fn is_in_maintenance_mode(&self) -> bool { self.tunables.borrow().maintenance_mode }
+
   pub fn create_update_crates_io_dump_job(&self) -> UpdateCratesIoDumpJob {
     UpdateCratesIoDumpJob::new(self.crates_io_dump.clone())
   }
+
+  /// Update the crates.io database dump at `update_interval` instead of [`crates_io_dump::UPDATE_DURATION`].
+  #[inline]
+  pub fn with_crates_io_dump_interval(mut self, update_interval: Duration) -> Self {
+    self.crates_io_dump = self.crates_io_dump.with_update_interval(update_interval);
+    self
+  }
+
+  /// Gets the interval at which the crates.io database dump is updated.
+  #[inline]
+  pub fn crates_io_dump_interval(&self) -> Duration { self.crates_io_dump.update_interval() }
+
+  /// Subscribes to the progress of importing the crates.io database dump.
+  #[inline]
+  pub fn crates_io_dump_import_progress(&self) -> watch::Receiver<ImportProgress> {
+    self.crates_io_dump.import_progress()
+  }
+
+  /// Creates a job that refreshes followed crates older than `max_age`, unless their followers set a refresh interval.
+  pub fn create_refresh_outdated_crates_job(&self, max_age: Duration) -> RefreshOutdatedCratesJob {
+    RefreshOutdatedCratesJob { crates: self.clone(), max_age }
+  }
+}
+
+pub struct RefreshOutdatedCratesJob {
+  crates: Crates,
+  max_age: Duration,
+}
+
+impl Job for RefreshOutdatedCratesJob {
+  async fn run(&self) -> JobResult {
+    self.crates.refresh_outdated(self.max_age).await?;
+    if self.crates.cancel.is_cancelled() {
+      Ok(JobAction::Cancel)
+    } else {
+      Ok(JobAction::Continue)
+    }
+  }
 }
 
 
 #[derive(Debug, Error)]
 pub enum InternalError {
   #[error("Crate with ID {0} was not found")]
-  CrateNotFound(i32),
+  CrateNotFound(CrateId),
+  #[error("Cannot follow more than {0} crates")]
+  FollowLimitReached(u32),
+  #[error("Crate with ID {0} is not followed")]
+  CrateNotFollowed(CrateId),
+  #[error("Registry {0} is not configured")]
+  UnknownRegistry(String),
+  #[error("Saved search with ID {0} was not found")]
+  SavedSearchNotFound(i32),
+  #[error("A saved search named {0} already exists")]
+  SavedSearchNameTaken(String),
   #[error("crates.io API operation failed: {0}")]
   CratesIoClient(#[from] CratesIoClientError),
   #[error("Database operation failed: {0}")]
@@ -56,6 +137,11 @@ impl From<InternalError> for CrateError {
   fn from(e: InternalError) -> Self {
     match e {
       InternalError::CrateNotFound(_) => CrateError::NotFound,
+      InternalError::FollowLimitReached(max) => CrateError::FollowLimitReached(max),
+      InternalError::CrateNotFollowed(_) => CrateError::NotFound,
+      InternalError::SavedSearchNotFound(_) => CrateError::NotFound,
+      InternalError::SavedSearchNameTaken(_) => CrateError::SavedSearchNameTaken,
+      InternalError::Database(e) if e.is_pool_timeout() => CrateError::Unavailable,
       _ => CrateError::Internal,
     }
   }
@@ -63,21 +149,89 @@ impl From<InternalError> for CrateError {
 
 impl Crates {
   #[instrument(skip(self), err)]
-  pub async fn find(&self, crate_id: i32) -> Result<FullCrate, InternalError> {
+  pub async fn find(&self, crate_id: CrateId) -> Result<FullCrate, InternalError> {
     self.db_pool.perform(move |conn| conn.find(crate_id))
       .await?
       .ok_or_else(|| InternalError::CrateNotFound(crate_id))
   }
 
   #[instrument(skip(self), err)]
-  pub async fn search(&self, query: CratesQuery, user_id: Option<i32>) -> Result<Vec<FullCrate>, InternalError> {
-    let full_crates = self.db_pool.query(move |db| db.search(query, user_id))
+  pub async fn search(&self, query: CratesQuery, user_id: Option<UserId>) -> Result<CratesPage, InternalError> {
+    let crates_page = self.db_pool.query(move |db| db.search(query, user_id))
       .await?;
-    Ok(full_crates)
+    Ok(crates_page)
+  }
+
+  #[instrument(skip(self), err)]
+  pub async fn follow(&self, user_id: UserId, crate_id: CrateId) -> Result<(), InternalError> {
+    let max_followed_crates = self.tunables.borrow().max_followed_crates;
+    let followed = self.db_pool
+      .query(move |conn| conn.follow_within_limit(user_id, crate_id, max_followed_crates))
+      .await?;
+    match (followed, max_followed_crates) {
+      (false, Some(max)) => Err(InternalError::FollowLimitReached(max)),
+      _ => Ok(()),
+    }
+  }
+
+  #[instrument(skip(self), err)]
+  pub async fn unfollow(&self, user_id: UserId, crate_id: CrateId) -> Result<(), InternalError> {
+    self.db_pool.query(move |conn| conn.unfollow(user_id, crate_id)).await?;
+    Ok(())
+  }
+
+  #[instrument(skip(self), err)]
+  pub async fn unfollow_many(&self, user_id: UserId, crate_ids: Vec<CrateId>) -> Result<(), InternalError> {
+    self.db_pool.query(move |conn| conn.unfollow_many(user_id, crate_ids)).await?;
+    Ok(())
   }
 
+  /// Sets the interval in seconds at which crate `crate_id` followed by user `user_id` is refreshed by
+  /// [`refresh_outdated`](Self::refresh_outdated), or `None` to use its maximum age.
   #[instrument(skip(self), err)]
-  pub async fn refresh_one(&self, crate_id: i32) -> Result<FullCrate, InternalError> {
+  pub async fn set_refresh_interval(&self, user_id: UserId, crate_id: CrateId, refresh_interval_secs: Option<u32>) -> Result<(), InternalError> {
+    let refresh_interval_secs = refresh_interval_secs.map(|secs| secs.min(i32::MAX as u32) as i32);
+    let followed = self.db_pool.query(move |conn| conn.set_refresh_interval(user_id, crate_id, refresh_interval_secs)).await?;
+    if !followed {
+      return Err(InternalError::CrateNotFollowed(crate_id));
+    }
+    Ok(())
+  }
+
+  #[instrument(skip(self), err)]
+  pub async fn get_saved_searches(&self, user_id: UserId) -> Result<Vec<SavedSearch>, InternalError> {
+    let saved_searches = self.db_pool.query(move |conn| conn.get_saved_searches(user_id)).await?;
+    Ok(saved_searches)
+  }
+
+  #[instrument(skip(self), err)]
+  pub async fn save_search(&self, user_id: UserId, new_saved_search: NewSavedSearch) -> Result<SavedSearch, InternalError> {
+    let name = new_saved_search.name.clone();
+    self.db_pool.query(move |conn| conn.insert_saved_search(user_id, new_saved_search))
+      .await
+      .map_err(|e| saved_search_error(e, name))
+  }
+
+  #[instrument(skip(self), err)]
+  pub async fn update_saved_search(&self, user_id: UserId, saved_search_id: i32, new_saved_search: NewSavedSearch) -> Result<SavedSearch, InternalError> {
+    let name = new_saved_search.name.clone();
+    self.db_pool.query(move |conn| conn.update_saved_search(user_id, saved_search_id, new_saved_search))
+      .await
+      .map_err(|e| saved_search_error(e, name))?
+      .ok_or_else(|| InternalError::SavedSearchNotFound(saved_search_id))
+  }
+
+  #[instrument(skip(self), err)]
+  pub async fn delete_saved_search(&self, user_id: UserId, saved_search_id: i32) -> Result<(), InternalError> {
+    let deleted = self.db_pool.query(move |conn| conn.delete_saved_search(user_id, saved_search_id)).await?;
+    if !deleted {
+      return Err(InternalError::SavedSearchNotFound(saved_search_id));
+    }
+    Ok(())
+  }
+
+  #[instrument(skip(self), err)]
+  pub async fn refresh_one(&self, crate_id: CrateId) -> Result<FullCrate, InternalError> {
     let db_pool_obj = self.db_pool.get().await?;
 
     let mut full_crate = db_pool_obj.query(move |conn| conn.find(crate_id))
@@ -90,16 +244,54 @@ impl Crates {
     Ok(full_crate)
   }
 
+  /// Refreshes all crates followed by user `user_id`. When cancelled, stops refreshing and returns the followed crates,
+  /// of which only those refreshed before cancellation are up-to-date.
   #[instrument(skip(self), err)]
-  pub async fn refresh_followed(&self, user_id: i32) -> Result<Vec<FullCrate>, InternalError> {
+  pub async fn refresh_followed(&self, user_id: UserId) -> Result<Vec<FullCrate>, InternalError> {
     let db_pool_obj = self.db_pool.get().await?;
 
-    let mut full_crates = db_pool_obj.query(move |conn| conn.get_followed_crates(user_id))
+    let mut full_crates = db_pool_obj.query(move |conn| conn.get_followed_crates(user_id, CratesSort::default(), false))
       .await?;
 
-    for full_crate in &mut full_crates {
-      self.update(full_crate, &db_pool_obj)
-        .await?;
+    let count = full_crates.len();
+    for (refreshed, full_crate) in full_crates.iter_mut().enumerate() {
+      tokio::select! {
+        biased;
+        _ = self.cancel.cancelled() => {
+          info!(refreshed, count, "cancelled refreshing followed crates");
+          break;
+        }
+        result = self.update(full_crate, &db_pool_obj) => result?,
+      }
+    }
+
+    Ok(full_crates)
+  }
+
+  /// Refreshes followed crates that are outdated: crates that have not been refreshed within the shortest refresh
+  /// interval set by their followers, where followers without a refresh interval use `max_age`. When cancelled, stops
+  /// refreshing and returns the outdated crates, of which only those refreshed before cancellation are up-to-date.
+  #[instrument(skip(self), err)]
+  pub async fn refresh_outdated(&self, max_age: Duration) -> Result<Vec<FullCrate>, InternalError> {
+    let db_pool_obj = self.db_pool.get().await?;
+
+    let now = self.clock.now();
+    let max_age = TimeDelta::from_std(max_age).unwrap_or(TimeDelta::MAX);
+    let mut full_crates = db_pool_obj.query(move |conn| {
+      let crate_ids = conn.get_outdated_followed_crate_ids(now, max_age)?;
+      conn.find_many(crate_ids)
+    }).await?;
+
+    let count = full_crates.len();
+    for (refreshed, full_crate) in full_crates.iter_mut().enumerate() {
+      tokio::select! {
+        biased;
+        _ = self.cancel.cancelled() => {
+          info!(refreshed, count, "cancelled refreshing outdated crates");
+          break;
+        }
+        result = self.update(full_crate, &db_pool_obj) => result?,
+      }
     }
 
     Ok(full_crates)
@@ -108,7 +300,19 @@ impl Crates {
   async fn update(&self, full_crate: &mut FullCrate, db_pool_obj: &DbPoolObj<CratesDb>) -> Result<(), InternalError> {
     let crate_id = full_crate.krate.id;
 
-    let response = self.crates_io_client.refresh(full_crate.krate.name.clone()).await?;
+    let registry = self.registries.get(&full_crate.krate.registry)
+      .ok_or_else(|| InternalError::UnknownRegistry(full_crate.krate.registry.clone()))?;
+    let response = registry.refresh(full_crate.krate.name.clone()).await?;
+    let versions: Vec<_> = response.versions.iter()
+      .filter_map(|version| Some(CrateVersion {
+        id: i32::try_from(version.id).ok()?,
+        crate_id,
+        number: version.num.clone(),
+      }))
+      .collect();
+    let default_version = default_version(&response)
+      .and_then(|default_version| versions.iter().find(|version| version.number == default_version.num))
+      .cloned();
     let update_crate = UpdateCrate { // TODO: update more fields
       id: crate_id,
       updated_at: Some(response.crate_data.updated_at),
@@ -117,17 +321,322 @@ impl Crates {
       repository: Some(response.crate_data.repository),
       readme: None, // Not in `CrateResponse`.
       downloads: Some(response.crate_data.downloads as i64),
-      ..UpdateCrate::default()
+      default_version_id: default_version.as_ref().map(|version| version.id),
     };
-    // TODO: update versions and default version
 
-    let krate = db_pool_obj.perform::<InternalError, _>(move |conn| {
+    let refreshed_at = self.clock.now();
+    let krate = db_pool_obj.perform::<InternalError, _>(move |conn| conn.transaction(|conn| {
+      conn.upsert_crate_versions(versions)?;
       let krate = conn.update_crate(update_crate)?
         .ok_or_else(|| InternalError::CrateNotFound(crate_id))?;
+      conn.set_refreshed_at(crate_id, refreshed_at)?;
       Ok(krate)
-    }).await?;
+    })).await?;
     full_crate.krate = krate;
+    if let Some(default_version) = default_version {
+      full_crate.default_version = default_version;
+    }
 
     Ok(())
   }
 }
+
+/// Selects the default version of the crate in `response` the way crates.io does: the highest non-yanked stable version,
+/// or the highest non-yanked prerelease if the crate only has prereleases.
+fn default_version(response: &CrateResponse) -> Option<&Version> {
+  let crate_data = &response.crate_data;
+  let number = crate_data.max_stable_version.as_deref().unwrap_or(&crate_data.max_version);
+  response.versions.iter()
+    .find(|version| !version.yanked && version.num == number)
+    // Versions are sorted from newest to oldest.
+    .or_else(|| response.versions.iter().find(|version| !version.yanked))
+}
+
+fn saved_search_error(error: DbError, name: String) -> InternalError {
+  if error.is_unique_violation() {
+    InternalError::SavedSearchNameTaken(name)
+  } else {
+    error.into()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use att_server_db::test_util::TestDb;
+  use att_core::util::http_status_code::AsStatusCode;
+  use att_server_db::users::UsersDb;
+  use reqwest::StatusCode;
+
+  use crate::clock::MockClock;
+  use crate::crates::crates_io_client::{CratesIoClient, MockCratesIo};
+
+  use super::*;
+
+  /// Creates a crates.io response for crate `name` with `description` and no versions.
+  fn crate_response(name: &str, description: &str) -> CrateResponse {
+    serde_json::from_value(serde_json::json!({
+      "crate": {
+        "id": name,
+        "name": name,
+        "description": description,
+        "downloads": 7,
+        "max_version": "1.0.0",
+        "links": {
+          "owner_team": "",
+          "owner_user": "",
+          "owners": "",
+          "reverse_dependencies": "",
+          "version_downloads": "",
+        },
+        "created_at": "2024-01-01T00:00:00Z",
+        "updated_at": "2024-02-01T00:00:00Z",
+      },
+      "categories": [],
+      "keywords": [],
+      "versions": [],
+    })).unwrap()
+  }
+
+  fn default_tunables() -> Tunables {
+    Tunables { max_followed_crates: None, crates_cache_max_age: DEFAULT_CACHE_MAX_AGE, maintenance_mode: false }
+  }
+
+  fn crates(test_db: &TestDb, registries: Registries, clock: MockClock) -> Crates {
+    crates_with_tunables(test_db, registries, clock, default_tunables())
+  }
+
+  fn crates_with_tunables(test_db: &TestDb, registries: Registries, clock: MockClock, tunables: Tunables) -> Crates {
+    crates_with_cancel(test_db, registries, clock, tunables, CancellationToken::new())
+  }
+
+  fn crates_with_cancel(test_db: &TestDb, registries: Registries, clock: MockClock, tunables: Tunables, cancel: CancellationToken) -> Crates {
+    let (_, tunables) = watch::channel(tunables);
+    Crates::new(test_db.db_pool().clone(), registries, Arc::new(clock), PathBuf::new(), tunables, cancel)
+  }
+
+  #[tokio::test]
+  async fn refresh_queries_configured_alternative_registry() {
+    let test_db = TestDb::new().unwrap();
+    let crates_io = MockCratesIo::default();
+    crates_io.fail_crate("secret", StatusCode::INTERNAL_SERVER_ERROR);
+    let (crates_io_client, task) = CratesIoClient::new_mock(crates_io);
+    tokio::spawn(task);
+    let private = MockCratesIo::default();
+    private.set_crate("secret", crate_response("secret", "A private crate"));
+    let (private_client, task) = CratesIoClient::new_mock(private);
+    tokio::spawn(task);
+    let registries = Registries::new(crates_io_client).with_registry("private", private_client);
+    let crates = crates(&test_db, registries, MockClock::default());
+
+    let seeded = test_db.db_pool().with::<CratesDb>()
+      .query(|conn| conn.seed_crate_in_registry(CrateId(1), "secret", "0.1.0", "private"))
+      .await
+      .unwrap();
+    let refreshed = crates.refresh_one(seeded.krate.id).await.unwrap();
+    assert_eq!(refreshed.krate.registry, "private");
+    assert_eq!(refreshed.krate.description, "A private crate");
+    assert_eq!(refreshed.krate.downloads, 7);
+
+    let found = crates.find(seeded.krate.id).await.unwrap();
+    assert_eq!(found.krate.registry, "private");
+    assert_eq!(found.krate.description, "A private crate");
+  }
+
+  #[tokio::test]
+  async fn refresh_from_unconfigured_registry_fails() {
+    let test_db = TestDb::new().unwrap();
+    let (crates_io_client, task) = CratesIoClient::new_mock(MockCratesIo::default());
+    tokio::spawn(task);
+    let crates = crates(&test_db, Registries::new(crates_io_client), MockClock::default());
+
+    let seeded = test_db.db_pool().with::<CratesDb>()
+      .query(|conn| conn.seed_crate_in_registry(CrateId(1), "secret", "0.1.0", "private"))
+      .await
+      .unwrap();
+    let result = crates.refresh_one(seeded.krate.id).await;
+    assert!(matches!(result, Err(InternalError::UnknownRegistry(ref registry)) if registry == "private"));
+  }
+
+  #[tokio::test]
+  async fn refresh_outdated_refreshes_crates_from_max_age() {
+    let test_db = TestDb::new().unwrap();
+    let crates_io = MockCratesIo::default();
+    crates_io.set_crate("serde", crate_response("serde", "A serialization framework"));
+    let (crates_io_client, task) = CratesIoClient::new_mock(crates_io);
+    tokio::spawn(task);
+    let clock = MockClock::default();
+    let crates = crates(&test_db, Registries::new(crates_io_client), clock.clone());
+
+    let user = test_db.db_pool().with::<UsersDb>().query(|conn| conn.seed_user("alice")).await.unwrap();
+    test_db.db_pool().with::<CratesDb>().query(move |conn| {
+      conn.seed_crate(CrateId(1), "serde", "1.0.0")?;
+      conn.follow(user.id, CrateId(1))
+    }).await.unwrap();
+    crates.refresh_one(CrateId(1)).await.unwrap();
+
+    let max_age = Duration::from_secs(60 * 60);
+    // Just before the max age: not outdated.
+    clock.advance(TimeDelta::seconds(60 * 60 - 1));
+    assert!(refresh_outdated_ids(&crates, max_age).await.is_empty());
+    // At the max age: outdated, and refreshed at the current time.
+    clock.advance(TimeDelta::seconds(1));
+    assert_eq!(refresh_outdated_ids(&crates, max_age).await, vec![CrateId(1)]);
+    assert!(refresh_outdated_ids(&crates, max_age).await.is_empty());
+    // After the max age since the last refresh: outdated again.
+    clock.advance(TimeDelta::seconds(60 * 60 + 1));
+    assert_eq!(refresh_outdated_ids(&crates, max_age).await, vec![CrateId(1)]);
+  }
+
+  #[tokio::test]
+  async fn refresh_outdated_refreshes_crates_with_shorter_interval_first() {
+    let test_db = TestDb::new().unwrap();
+    let crates_io = MockCratesIo::default();
+    for name in ["serde", "tokio"] {
+      crates_io.set_crate(name, crate_response(name, "Refreshed"));
+    }
+    let (crates_io_client, task) = CratesIoClient::new_mock(crates_io);
+    tokio::spawn(task);
+    let clock = MockClock::default();
+    let crates = crates(&test_db, Registries::new(crates_io_client), clock.clone());
+
+    let user = test_db.db_pool().with::<UsersDb>().query(|conn| conn.seed_user("alice")).await.unwrap();
+    test_db.db_pool().with::<CratesDb>().query(move |conn| {
+      for (id, name) in [(1, "serde"), (2, "tokio")] {
+        conn.seed_crate(CrateId(id), name, "1.0.0")?;
+        conn.follow(user.id, CrateId(id))?;
+      }
+      Ok(())
+    }).await.unwrap();
+    crates.set_refresh_interval(user.id, CrateId(1), Some(10 * 60)).await.unwrap();
+    crates.refresh_one(CrateId(1)).await.unwrap();
+    crates.refresh_one(CrateId(2)).await.unwrap();
+
+    let max_age = Duration::from_secs(60 * 60);
+    // Between the refresh interval of serde and the max age: only serde is outdated.
+    clock.advance(TimeDelta::seconds(30 * 60));
+    assert_eq!(refresh_outdated_ids(&crates, max_age).await, vec![CrateId(1)]);
+    // Past the max age: tokio is outdated as well.
+    clock.advance(TimeDelta::seconds(30 * 60));
+    assert_eq!(refresh_outdated_ids(&crates, max_age).await, vec![CrateId(1), CrateId(2)]);
+  }
+
+  async fn refresh_outdated_ids(crates: &Crates, max_age: Duration) -> Vec<CrateId> {
+    let mut crate_ids: Vec<_> = crates.refresh_outdated(max_age).await.unwrap().into_iter().map(|full_crate| full_crate.krate.id).collect();
+    crate_ids.sort_unstable();
+    crate_ids
+  }
+
+  #[tokio::test]
+  async fn follow_beyond_limit_is_rejected_until_unfollowed() {
+    let test_db = TestDb::new().unwrap();
+    let (crates_io_client, task) = CratesIoClient::new_mock(MockCratesIo::default());
+    tokio::spawn(task);
+    let tunables = Tunables { max_followed_crates: Some(1), crates_cache_max_age: DEFAULT_CACHE_MAX_AGE, maintenance_mode: false };
+    let crates = crates_with_tunables(&test_db, Registries::new(crates_io_client), MockClock::default(), tunables);
+
+    let user = test_db.db_pool().with::<UsersDb>().query(|conn| conn.seed_user("alice")).await.unwrap();
+    test_db.db_pool().with::<CratesDb>().query(move |conn| {
+      conn.seed_crate(CrateId(1), "serde", "1.0.0")?;
+      conn.seed_crate(CrateId(2), "tokio", "1.0.0")?;
+      Ok(())
+    }).await.unwrap();
+
+    crates.follow(user.id, CrateId(1)).await.unwrap();
+    // Following an already followed crate at the limit succeeds.
+    crates.follow(user.id, CrateId(1)).await.unwrap();
+    let result = crates.follow(user.id, CrateId(2)).await;
+    assert!(matches!(result, Err(InternalError::FollowLimitReached(1))));
+
+    crates.unfollow(user.id, CrateId(1)).await.unwrap();
+    crates.follow(user.id, CrateId(2)).await.unwrap();
+  }
+
+  #[tokio::test]
+  async fn cancelling_refresh_followed_stops_further_crates_io_requests() {
+    let test_db = TestDb::new().unwrap();
+    let crates_io = MockCratesIo::default();
+    for name in ["serde", "tokio", "rand"] {
+      crates_io.set_crate(name, crate_response(name, "Refreshed"));
+    }
+    let cancel = CancellationToken::new();
+    {
+      let cancel = cancel.clone();
+      crates_io.on_crate_request(move |count| if count == 1 { cancel.cancel() });
+    }
+    let (crates_io_client, task) = CratesIoClient::new_mock(crates_io.clone());
+    tokio::spawn(task);
+    let crates = crates_with_cancel(&test_db, Registries::new(crates_io_client), MockClock::default(), default_tunables(), cancel);
+
+    let user = test_db.db_pool().with::<UsersDb>().query(|conn| conn.seed_user("alice")).await.unwrap();
+    test_db.db_pool().with::<CratesDb>().query(move |conn| {
+      for (id, name) in [(1, "serde"), (2, "tokio"), (3, "rand")] {
+        conn.seed_crate(CrateId(id), name, "1.0.0")?;
+        conn.follow(user.id, CrateId(id))?;
+      }
+      Ok(())
+    }).await.unwrap();
+
+    let full_crates = crates.refresh_followed(user.id).await.unwrap();
+    // All followed crates are returned, but only the first was refreshed before cancellation.
+    assert_eq!(full_crates.len(), 3);
+    assert_eq!(crates_io.crate_request_count(), 1);
+    let refreshed = full_crates.iter().filter(|full_crate| full_crate.krate.description == "Refreshed").count();
+    assert_eq!(refreshed, 1);
+  }
+
+  #[tokio::test]
+  async fn save_list_and_run_saved_search() {
+    let test_db = TestDb::new().unwrap();
+    let (crates_io_client, task) = CratesIoClient::new_mock(MockCratesIo::default());
+    tokio::spawn(task);
+    let crates = crates(&test_db, Registries::new(crates_io_client), MockClock::default());
+
+    let user = test_db.db_pool().with::<UsersDb>().query(|conn| conn.seed_user("alice")).await.unwrap();
+    test_db.db_pool().with::<CratesDb>().query(move |conn| {
+      conn.seed_crate(CrateId(1), "serde", "1.0.0")?;
+      conn.seed_crate(CrateId(2), "tokio", "1.0.0")?;
+      conn.follow(user.id, CrateId(2))
+    }).await.unwrap();
+
+    let new_saved_search = NewSavedSearch { name: "Followed".to_string(), query: CratesQuery::from_followed(true) };
+    let saved = crates.save_search(user.id, new_saved_search).await.unwrap();
+    assert_eq!(crates.get_saved_searches(user.id).await.unwrap(), [saved.clone()]);
+
+    let crates_page = crates.search(saved.query, Some(user.id)).await.unwrap();
+    assert_eq!(crates_page.crates.len(), 1);
+    assert_eq!(crates_page.crates[0].krate.id, CrateId(2));
+  }
+
+  #[tokio::test]
+  async fn saving_search_with_taken_name_is_rejected() {
+    let test_db = TestDb::new().unwrap();
+    let (crates_io_client, task) = CratesIoClient::new_mock(MockCratesIo::default());
+    tokio::spawn(task);
+    let crates = crates(&test_db, Registries::new(crates_io_client), MockClock::default());
+    let user = test_db.db_pool().with::<UsersDb>().query(|conn| conn.seed_user("alice")).await.unwrap();
+
+    let new_saved_search = NewSavedSearch { name: "Serde".to_string(), query: CratesQuery::default() };
+    crates.save_search(user.id, new_saved_search.clone()).await.unwrap();
+    let result = crates.save_search(user.id, new_saved_search).await;
+    assert!(matches!(result, Err(InternalError::SavedSearchNameTaken(ref name)) if name == "Serde"));
+    assert_eq!(CrateError::from(result.unwrap_err()).as_status_code(), StatusCode::CONFLICT);
+  }
+
+  #[test]
+  fn pool_timeout_is_unavailable() {
+    let error = CrateError::from(InternalError::Database(DbError::PoolTimeout));
+    assert_eq!(error, CrateError::Unavailable);
+    assert_eq!(error.as_status_code(), StatusCode::SERVICE_UNAVAILABLE);
+  }
+
+  #[test]
+  fn other_database_errors_are_internal() {
+    for db_error in [DbError::PoolClosed, DbError::PerformAbort, DbError::Timeout(Duration::from_secs(1))] {
+      let error = CrateError::from(InternalError::Database(db_error));
+      assert_eq!(error, CrateError::Internal);
+      assert_eq!(error.as_status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+  }
+}
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crates_io_api::{CrateResponse, CratesPage};
+use futures::future::BoxFuture;
+use futures::FutureExt;
+
+use att_core::crates::CRATES_IO_REGISTRY;
+
+use crate::crates::crates_io_client::{CratesIoClient, CratesIoClientError};
+
+/// A crates.io-like registry that crates can be searched in and refreshed from.
+pub trait Registry: Send + Sync {
+  /// Searches for crates matching `search_term`, returning `None` if the search was cancelled.
+  fn search(&self, search_term: String) -> BoxFuture<'_, Result<Option<CratesPage>, CratesIoClientError>>;
+  /// Gets the latest data of crate `crate_name`.
+  fn refresh(&self, crate_name: String) -> BoxFuture<'_, Result<CrateResponse, CratesIoClientError>>;
+}
+
+impl Registry for CratesIoClient {
+  #[inline]
+  fn search(&self, search_term: String) -> BoxFuture<'_, Result<Option<CratesPage>, CratesIoClientError>> {
+    CratesIoClient::search(self, search_term).boxed()
+  }
+  #[inline]
+  fn refresh(&self, crate_name: String) -> BoxFuture<'_, Result<CrateResponse, CratesIoClientError>> {
+    CratesIoClient::refresh(self, crate_name).boxed()
+  }
+}
+
+/// Registries by name, always containing the crates.io registry under [`CRATES_IO_REGISTRY`]. Crates are refreshed
+/// from the registry named by their [`registry`](att_core::crates::Crate::registry).
+#[derive(Clone)]
+pub struct Registries {
+  registries: Arc<HashMap<String, Arc<dyn Registry>>>,
+}
+
+impl Registries {
+  /// Creates registries containing only `crates_io` as the crates.io registry.
+  pub fn new(crates_io: impl Registry + 'static) -> Self {
+    let registries: HashMap<String, Arc<dyn Registry>> = HashMap::from([
+      (CRATES_IO_REGISTRY.to_string(), Arc::new(crates_io) as Arc<dyn Registry>),
+    ]);
+    Self { registries: Arc::new(registries) }
+  }
+
+  /// Adds alternative `registry` under `name`, replacing the registry with that name if it already exists.
+  pub fn with_registry(mut self, name: impl Into<String>, registry: impl Registry + 'static) -> Self {
+    Arc::make_mut(&mut self.registries).insert(name.into(), Arc::new(registry));
+    self
+  }
+
+  /// Gets the registry named `name`, or `None` if no such registry is configured.
+  #[inline]
+  pub fn get(&self, name: &str) -> Option<&dyn Registry> {
+    self.registries.get(name).map(|registry| registry.as_ref())
+  }
+
+  /// Gets the crates.io registry.
+  #[inline]
+  pub fn crates_io(&self) -> &dyn Registry {
+    self.get(CRATES_IO_REGISTRY).expect("crates.io registry is always configured")
+  }
+
+  /// Returns the names of all configured registries.
+  #[inline]
+  pub fn names(&self) -> impl Iterator<Item=&str> {
+    self.registries.keys().map(|name| name.as_str())
+  }
+}
@@ -1,66 +1,148 @@
-use axum::extract::{Path, Query, State};
-use axum::Router;
+use axum::extract::{Path, Query, Request, State};
+use axum::{Json, middleware, Router};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
 
-use att_core::crates::{CrateError, CratesQuery, FullCrate};
+use att_core::crates::{CrateError, CrateId, CratesPage, CratesQuery, FullCrate, NewSavedSearch, SavedSearch};
+use att_core::util::http_status_code::AsStatusCode;
 
-use crate::crates::Crates;
+use crate::crates::{Crates, MAINTENANCE_RETRY_AFTER};
 use crate::users::AuthSession;
-use crate::util::JsonResult;
+use crate::util::{CacheControl, JsonResult, RetryAfter};
 
-pub fn router() -> Router<Crates> {
-  use axum::routing::{get, post};
+pub fn router(state: Crates) -> Router<Crates> {
+  use axum::routing::{get, post, put};
   Router::new()
     .route("/", get(search))
     .route("/:crate_id", get(find))
     .route("/:crate_id/follow", post(follow).delete(unfollow))
+    .route("/:crate_id/refresh_interval", put(set_refresh_interval))
+    .route("/unfollow", post(unfollow_many))
     .route("/:crate_id/refresh", post(refresh))
     .route("/refresh_followed", post(refresh_followed_crates))
+    .route("/saved_searches", get(get_saved_searches).post(save_search))
+    .route("/saved_searches/:saved_search_id", put(update_saved_search).delete(delete_saved_search))
+    .route_layer(middleware::from_fn_with_state(state, reject_modifications_in_maintenance_mode))
+}
+
+/// Rejects requests that modify data with `503 Service Unavailable` and a `Retry-After` header while the server is in
+/// maintenance mode, for example during a long database dump import. Reads are still served.
+async fn reject_modifications_in_maintenance_mode(State(state): State<Crates>, request: Request, next: Next) -> Response {
+  if state.is_in_maintenance_mode() && !request.method().is_safe() {
+    let error = CrateError::UnderMaintenance(MAINTENANCE_RETRY_AFTER);
+    return (error.as_status_code(), RetryAfter(MAINTENANCE_RETRY_AFTER), Json(Err::<(), _>(error))).into_response();
+  }
+  next.run(request).await
 }
 
 async fn search(
   auth_session: AuthSession,
   State(state): State<Crates>,
   Query(query): Query<CratesQuery>
-) -> JsonResult<Vec<FullCrate>, CrateError> {
+) -> (CacheControl, JsonResult<CratesPage, CrateError>) {
   let user_id = auth_session.user.map(|u| u.id);
-  let full_crates = state.search(query, user_id)
+  // Search results of logged-in users can be user-specific (e.g., followed crates), so they must not be cached.
+  let cache_control = if user_id.is_some() { CacheControl::NoStore } else { state.cache_control() };
+  let result = state.search(query, user_id)
     .await
-    .map_err(|_| CrateError::Internal)?;
-  Ok(full_crates.into())
+    .map(Into::into)
+    .map_err(|e| CrateError::from(e).into());
+  (cache_control.for_result(&result), result)
 }
 
-async fn find(State(state): State<Crates>, Path(crate_id): Path<i32>) -> JsonResult<FullCrate, CrateError> {
-  let full_crate = state.find(crate_id)
+async fn find(State(state): State<Crates>, Path(crate_id): Path<CrateId>) -> (CacheControl, JsonResult<FullCrate, CrateError>) {
+  let result = state.find(crate_id)
+    .await
+    .map(Into::into)
+    .map_err(|e| CrateError::from(e).into());
+  (state.cache_control().for_result(&result), result)
+}
+
+async fn follow(auth_session: AuthSession, State(state): State<Crates>, Path(crate_id): Path<CrateId>) -> JsonResult<(), CrateError> {
+  let user_id = auth_session.user.ok_or(CrateError::NotLoggedIn)?.id;
+  state.follow(user_id, crate_id)
     .await
     .map_err(CrateError::from)?;
-  Ok(full_crate.into())
+  Ok(().into())
 }
 
-async fn follow(auth_session: AuthSession, State(state): State<Crates>, Path(crate_id): Path<i32>) -> JsonResult<(), CrateError> {
+async fn unfollow(auth_session: AuthSession, State(state): State<Crates>, Path(crate_id): Path<CrateId>) -> JsonResult<(), CrateError> {
   let user_id = auth_session.user.ok_or(CrateError::NotLoggedIn)?.id;
-  let krate = state.db_pool.query(move |db| db.follow(user_id, crate_id))
+  state.unfollow(user_id, crate_id)
     .await
-    .map_err(|_| CrateError::Internal)?;
-  Ok(krate.into())
+    .map_err(CrateError::from)?;
+  Ok(().into())
+}
+
+async fn unfollow_many(auth_session: AuthSession, State(state): State<Crates>, Json(crate_ids): Json<Vec<CrateId>>) -> JsonResult<(), CrateError> {
+  let user_id = auth_session.user.ok_or(CrateError::NotLoggedIn)?.id;
+  state.unfollow_many(user_id, crate_ids)
+    .await
+    .map_err(CrateError::from)?;
+  Ok(().into())
 }
 
-async fn unfollow(auth_session: AuthSession, State(state): State<Crates>, Path(crate_id): Path<i32>) -> JsonResult<(), CrateError> {
+async fn set_refresh_interval(
+  auth_session: AuthSession,
+  State(state): State<Crates>,
+  Path(crate_id): Path<CrateId>,
+  Json(refresh_interval_secs): Json<Option<u32>>,
+) -> JsonResult<(), CrateError> {
   let user_id = auth_session.user.ok_or(CrateError::NotLoggedIn)?.id;
-  state.db_pool.query(move |db| db.unfollow(user_id, crate_id))
+  state.set_refresh_interval(user_id, crate_id, refresh_interval_secs)
     .await
-    .map_err(|_| CrateError::Internal)?;
+    .map_err(CrateError::from)?;
   Ok(().into())
 }
 
-async fn refresh(State(state): State<Crates>, Path(crate_id): Path<i32>) -> JsonResult<FullCrate, CrateError> {
+async fn refresh(State(state): State<Crates>, Path(crate_id): Path<CrateId>) -> JsonResult<FullCrate, CrateError> {
   let full_crate = state.refresh_one(crate_id).await
     .map_err(CrateError::from)?;
   Ok(full_crate.into())
 }
 
-async fn refresh_followed_crates(auth_session: AuthSession, State(state): State<Crates>) -> JsonResult<Vec<FullCrate>, CrateError> {
+async fn refresh_followed_crates(auth_session: AuthSession, State(state): State<Crates>) -> (CacheControl, JsonResult<Vec<FullCrate>, CrateError>) {
+  let result: JsonResult<Vec<FullCrate>, CrateError> = async {
+    let user_id = auth_session.user.ok_or(CrateError::NotLoggedIn)?.id;
+    let full_crates = state.refresh_followed(user_id).await
+      .map_err(CrateError::from)?;
+    Ok(full_crates.into())
+  }.await;
+  (CacheControl::NoStore, result)
+}
+
+async fn get_saved_searches(auth_session: AuthSession, State(state): State<Crates>) -> (CacheControl, JsonResult<Vec<SavedSearch>, CrateError>) {
+  let result: JsonResult<Vec<SavedSearch>, CrateError> = async {
+    let user_id = auth_session.user.ok_or(CrateError::NotLoggedIn)?.id;
+    let saved_searches = state.get_saved_searches(user_id).await
+      .map_err(CrateError::from)?;
+    Ok(saved_searches.into())
+  }.await;
+  (CacheControl::NoStore, result)
+}
+
+async fn save_search(auth_session: AuthSession, State(state): State<Crates>, Json(new_saved_search): Json<NewSavedSearch>) -> JsonResult<SavedSearch, CrateError> {
   let user_id = auth_session.user.ok_or(CrateError::NotLoggedIn)?.id;
-  let full_crates = state.refresh_followed(user_id).await
+  let saved_search = state.save_search(user_id, new_saved_search).await
     .map_err(CrateError::from)?;
-  Ok(full_crates.into())
+  Ok(saved_search.into())
+}
+
+async fn update_saved_search(
+  auth_session: AuthSession,
+  State(state): State<Crates>,
+  Path(saved_search_id): Path<i32>,
+  Json(new_saved_search): Json<NewSavedSearch>,
+) -> JsonResult<SavedSearch, CrateError> {
+  let user_id = auth_session.user.ok_or(CrateError::NotLoggedIn)?.id;
+  let saved_search = state.update_saved_search(user_id, saved_search_id, new_saved_search).await
+    .map_err(CrateError::from)?;
+  Ok(saved_search.into())
+}
+
+async fn delete_saved_search(auth_session: AuthSession, State(state): State<Crates>, Path(saved_search_id): Path<i32>) -> JsonResult<(), CrateError> {
+  let user_id = auth_session.user.ok_or(CrateError::NotLoggedIn)?.id;
+  state.delete_saved_search(user_id, saved_search_id).await
+    .map_err(CrateError::from)?;
+  Ok(().into())
 }
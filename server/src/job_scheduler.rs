@@ -3,12 +3,16 @@
 use std::error::Error;
 use std::future::Future;
 use std::pin::Pin;
+use std::time::Duration;
 
 use tokio::sync::mpsc;
 use tokio::task::{block_in_place, JoinError, JoinSet};
-use tokio::time::Interval;
+use tokio::time::{Instant, Interval, sleep, sleep_until};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info};
 
+use att_core::util::RetryPolicy;
+
 // Public API
 
 pub struct JobScheduler {
@@ -20,34 +24,85 @@ impl JobScheduler {
     let task = Task::new(rx).run();
     (Self { tx }, task)
   }
-  pub fn blocking_schedule_job(&self, job: impl Job, interval: Interval, name: impl Into<String>) {
-    let _ = self.tx.blocking_send(Request::ScheduleJob(Box::new(job), interval, name.into()));
+  pub fn blocking_schedule_job(&self, job: impl Job, interval: Interval, name: impl Into<String>) -> JobHandle {
+    let handle = JobHandle::default();
+    let _ = self.tx.blocking_send(Request::ScheduleJob(Box::new(job), Schedule::Interval(interval), name.into(), handle.cancel_token.clone()));
+    handle
+  }
+  pub async fn schedule_job(&self, job: impl Job, interval: Interval, name: impl Into<String>) -> JobHandle {
+    let handle = JobHandle::default();
+    let _ = self.tx.send(Request::ScheduleJob(Box::new(job), Schedule::Interval(interval), name.into(), handle.cancel_token.clone())).await;
+    handle
+  }
+
+  /// Schedules `job` to run once `at` an instant, instead of at an interval. The job is only retried when it fails if it
+  /// has a [retry policy](Job::retry_policy) or [requests to be retried](JobAction::RetryAfter).
+  pub fn blocking_schedule_once(&self, job: impl Job, at: Instant, name: impl Into<String>) -> JobHandle {
+    let handle = JobHandle::default();
+    let _ = self.tx.blocking_send(Request::ScheduleJob(Box::new(job), Schedule::Once(at), name.into(), handle.cancel_token.clone()));
+    handle
   }
-  pub async fn schedule_job(&self, job: impl Job, interval: Interval, name: impl Into<String>) {
-    let _ = self.tx.send(Request::ScheduleJob(Box::new(job), interval, name.into())).await;
+  /// Schedules `job` to run once `at` an instant, instead of at an interval. The job is only retried when it fails if it
+  /// has a [retry policy](Job::retry_policy) or [requests to be retried](JobAction::RetryAfter).
+  pub async fn schedule_once(&self, job: impl Job, at: Instant, name: impl Into<String>) -> JobHandle {
+    let handle = JobHandle::default();
+    let _ = self.tx.send(Request::ScheduleJob(Box::new(job), Schedule::Once(at), name.into(), handle.cancel_token.clone())).await;
+    handle
   }
 
-  pub fn blocking_schedule_blocking_job(&self, job: impl BlockingJob, interval: Interval, name: impl Into<String>) {
-    let _ = self.tx.blocking_send(Request::ScheduleBlockingJob(Box::new(job), interval, name.into()));
+  pub fn blocking_schedule_blocking_job(&self, job: impl BlockingJob, interval: Interval, name: impl Into<String>) -> JobHandle {
+    let handle = JobHandle::default();
+    let _ = self.tx.blocking_send(Request::ScheduleBlockingJob(Box::new(job), interval, name.into(), handle.cancel_token.clone()));
+    handle
   }
-  pub async fn schedule_blocking_job(&self, job: impl BlockingJob, interval: Interval, name: impl Into<String>) {
-    let _ = self.tx.send(Request::ScheduleBlockingJob(Box::new(job), interval, name.into())).await;
+  pub async fn schedule_blocking_job(&self, job: impl BlockingJob, interval: Interval, name: impl Into<String>) -> JobHandle {
+    let handle = JobHandle::default();
+    let _ = self.tx.send(Request::ScheduleBlockingJob(Box::new(job), interval, name.into(), handle.cancel_token.clone())).await;
+    handle
   }
 }
 
+/// Handle to a scheduled job, which can cancel the job. Dropping the handle does not cancel the job.
+#[derive(Default, Clone, Debug)]
+pub struct JobHandle {
+  cancel_token: CancellationToken,
+}
+impl JobHandle {
+  /// Cancels the job, so that it is not run again. If the job is currently running, it is stopped at its next await
+  /// point, or for blocking jobs, after it completes.
+  #[inline]
+  pub fn cancel(&self) { self.cancel_token.cancel(); }
+
+  /// Returns whether the job was cancelled through this handle.
+  #[inline]
+  pub fn is_cancelled(&self) -> bool { self.cancel_token.is_cancelled() }
+}
+
 #[derive(Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 pub enum JobAction {
   #[default]
   Continue,
-  Cancel
+  Cancel,
+  /// Run the job again after the duration, instead of waiting for the next interval.
+  RetryAfter(Duration),
 }
 pub type JobResult = Result<JobAction, Box<dyn Error + Send + Sync + 'static>>;
 
 pub trait Job: Send + 'static {
   fn run(&self) -> impl Future<Output=JobResult> + Send;
+
+  /// Gets the policy for retrying this job when it fails, instead of waiting for the next interval. Defaults to
+  /// [never retrying](RetryPolicy::NONE).
+  #[inline]
+  fn retry_policy(&self) -> RetryPolicy { RetryPolicy::NONE }
 }
 pub trait BlockingJob: Send + 'static {
   fn run(&self) -> JobResult;
+
+  /// Gets the policy for retrying this job when it fails, instead of waiting for the next interval. Defaults to
+  /// [never retrying](RetryPolicy::NONE).
+  #[inline]
+  fn retry_policy(&self) -> RetryPolicy { RetryPolicy::NONE }
 }
 
 
@@ -55,19 +110,33 @@ pub trait BlockingJob: Send + 'static {
 
 trait JobDyn: Send {
   fn run(&self) -> Pin<Box<dyn Future<Output=JobResult> + Send + '_>>;
+  fn retry_policy(&self) -> RetryPolicy;
 }
 impl<T: Job> JobDyn for T {
   fn run(&self) -> Pin<Box<dyn Future<Output=JobResult> + Send + '_>> { Box::pin(<Self as Job>::run(self)) }
+  fn retry_policy(&self) -> RetryPolicy { <Self as Job>::retry_policy(self) }
+}
+
+/// What to do after running a job.
+enum JobNext {
+  WaitForInterval,
+  RetryAfter(Duration),
+  Stop,
+}
+
+enum Schedule {
+  Interval(Interval),
+  Once(Instant),
 }
 
 enum Request {
-  ScheduleJob(Box<dyn JobDyn>, Interval, String),
-  ScheduleBlockingJob(Box<dyn BlockingJob>, Interval, String),
+  ScheduleJob(Box<dyn JobDyn>, Schedule, String, CancellationToken),
+  ScheduleBlockingJob(Box<dyn BlockingJob>, Interval, String, CancellationToken),
 }
 
 struct Task {
   rx: mpsc::Receiver<Request>,
-  jobs: JoinSet<String>,
+  jobs: JoinSet<Option<String>>,
 }
 impl Task {
   fn new(rx: mpsc::Receiver<Request>) -> Self {
@@ -96,54 +165,106 @@ impl Task {
 
   fn handle_request(&mut self, request: Request) {
     match request {
-      Request::ScheduleJob(job, interval, name) => {
-        info!("registering job '{}' at interval: {:?}", name, interval.period());
-        self.jobs.spawn(Self::run_job(job, interval, name));
+      Request::ScheduleJob(job, schedule, name, cancel_token) => {
+        match &schedule {
+          Schedule::Interval(interval) => info!("registering job '{}' at interval: {:?}", name, interval.period()),
+          Schedule::Once(at) => info!("registering job '{}' to run once in: {:?}", name, at.saturating_duration_since(Instant::now())),
+        }
+        self.jobs.spawn(Self::cancellable(Self::run_job(job, schedule, name), cancel_token));
       },
-      Request::ScheduleBlockingJob(job, interval, name) => {
+      Request::ScheduleBlockingJob(job, interval, name, cancel_token) => {
         info!("registering blocking job '{}' at interval: {:?}", name, interval.period());
-        self.jobs.spawn(Self::run_blocking_job(job, interval, name));
+        self.jobs.spawn(Self::cancellable(Self::run_blocking_job(job, interval, name), cancel_token));
       }
     }
   }
-  async fn run_job(job: Box<dyn JobDyn>, mut interval: Interval, name: String) -> String {
+  /// Runs `job_future`, stopping it early when `cancel_token` is cancelled. Returns `None` when cancelled.
+  async fn cancellable(job_future: impl Future<Output=String>, cancel_token: CancellationToken) -> Option<String> {
+    tokio::select! {
+      name = job_future => Some(name),
+      _ = cancel_token.cancelled() => None,
+    }
+  }
+
+  async fn run_job(job: Box<dyn JobDyn>, schedule: Schedule, name: String) -> String {
+    let retry_policy = job.retry_policy();
+    let mut retry = 0;
+    let mut interval = match schedule {
+      Schedule::Interval(interval) => Some(interval),
+      Schedule::Once(at) => {
+        sleep_until(at).await;
+        None
+      }
+    };
     loop {
-      interval.tick().await;
-      info!("running job: {}", name);
-      let job_result = job.run().await;
-      if Self::handle_job_result(job_result, &name) {
+      if let Some(interval) = &mut interval {
+        interval.tick().await;
+      }
+      loop {
+        info!("running job: {}", name);
+        let job_result = job.run().await;
+        match Self::handle_job_result(job_result, &name, retry_policy, &mut retry) {
+          JobNext::WaitForInterval => break,
+          JobNext::RetryAfter(delay) => sleep(delay).await,
+          JobNext::Stop => return name,
+        }
+      }
+      if interval.is_none() {
         return name;
       }
     }
   }
   async fn run_blocking_job(job: Box<dyn BlockingJob>, mut interval: Interval, name: String) -> String {
+    let retry_policy = job.retry_policy();
+    let mut retry = 0;
     loop {
       interval.tick().await;
-      info!("running blocking job: {}", name);
-      let job_result = block_in_place(|| job.run());
-      if Self::handle_job_result(job_result, &name) {
-        return name;
+      loop {
+        info!("running blocking job: {}", name);
+        let job_result = block_in_place(|| job.run());
+        match Self::handle_job_result(job_result, &name, retry_policy, &mut retry) {
+          JobNext::WaitForInterval => break,
+          JobNext::RetryAfter(delay) => sleep(delay).await,
+          JobNext::Stop => return name,
+        }
       }
     }
   }
-  fn handle_job_result(result: JobResult, name: &str) -> bool {
+  /// Handles `result` of running job `name`, retrying failures according to `retry_policy`, where `retry` is the
+  /// number of retries since the job last succeeded.
+  fn handle_job_result(result: JobResult, name: &str, retry_policy: RetryPolicy, retry: &mut u32) -> JobNext {
     match result {
       Ok(action) => {
         info!("job '{}' was executed successfully", name);
+        *retry = 0;
         match action {
           JobAction::Cancel => {
             info!("job '{}' requested to be cancelled", name);
-            return true;
+            JobNext::Stop
           },
-          JobAction::Continue => {}
+          JobAction::Continue => JobNext::WaitForInterval,
+          JobAction::RetryAfter(delay) => {
+            info!("job '{}' requested to be retried after {:?}", name, delay);
+            JobNext::RetryAfter(delay)
+          }
+        }
+      }
+      Err(cause) => {
+        error!(?cause, "job '{}' was executed unsuccessfully", name);
+        if *retry < retry_policy.max_retries {
+          let delay = retry_policy.jittered_backoff(*retry);
+          *retry += 1;
+          info!("retrying job '{}' after {:?} (retry {} of {})", name, delay, retry, retry_policy.max_retries);
+          JobNext::RetryAfter(delay)
+        } else {
+          *retry = 0;
+          JobNext::WaitForInterval
         }
       }
-      Err(cause) => error!(?cause, "job '{}' was executed unsuccessfully", name),
     }
-    false
   }
 
-  fn handle_job_complete(result: Result<String, JoinError>) {
+  fn handle_job_complete(result: Result<Option<String>, JoinError>) {
     match result {
       Err(join_error) => {
         if let Ok(panic) = join_error.try_into_panic() {
@@ -152,9 +273,111 @@ impl Task {
           info!("a job was cancelled");
         }
       }
-      Ok(name) => {
+      Ok(Some(name)) => {
         info!("job '{}' has been cancelled", name);
       }
+      Ok(None) => {
+        info!("a job was cancelled through its handle");
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+  use std::sync::atomic::{AtomicU32, Ordering};
+
+  use tokio::time::interval;
+
+  use super::*;
+
+  /// Job that fails its first `failures` runs, and counts its runs.
+  struct FailingJob {
+    runs: Arc<AtomicU32>,
+    failures: u32,
+  }
+  impl Job for FailingJob {
+    async fn run(&self) -> JobResult {
+      let run = self.runs.fetch_add(1, Ordering::SeqCst) + 1;
+      if run <= self.failures {
+        Err(format!("run {run} failed").into())
+      } else {
+        Ok(JobAction::Continue)
+      }
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+      RetryPolicy::new(3, Duration::from_secs(1), Duration::from_secs(10), 0.0)
     }
   }
+
+  #[tokio::test(start_paused = true)]
+  async fn job_failing_twice_is_retried_until_it_succeeds() {
+    let (job_scheduler, task) = JobScheduler::new();
+    tokio::spawn(task);
+    let runs = Arc::new(AtomicU32::new(0));
+    let job = FailingJob { runs: runs.clone(), failures: 2 };
+    job_scheduler.schedule_job(job, interval(Duration::from_secs(3600)), "failing").await;
+
+    // Runs immediately, then retries after backoffs of 1 and 2 seconds instead of waiting for the next interval.
+    sleep(Duration::from_millis(500)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+    sleep(Duration::from_secs(1)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+    sleep(Duration::from_secs(2)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+    // Succeeded, so it is not retried again but waits for the next interval.
+    sleep(Duration::from_secs(60)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+    sleep(Duration::from_secs(3600)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 4);
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn cancelled_job_stops_running() {
+    let (job_scheduler, task) = JobScheduler::new();
+    tokio::spawn(task);
+    let runs = Arc::new(AtomicU32::new(0));
+    let job = FailingJob { runs: runs.clone(), failures: 0 };
+    let handle = job_scheduler.schedule_job(job, interval(Duration::from_secs(10)), "recurring").await;
+
+    sleep(Duration::from_secs(25)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+
+    handle.cancel();
+    assert!(handle.is_cancelled());
+    sleep(Duration::from_secs(100)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn one_shot_job_runs_exactly_once() {
+    let (job_scheduler, task) = JobScheduler::new();
+    tokio::spawn(task);
+    let runs = Arc::new(AtomicU32::new(0));
+    let job = FailingJob { runs: runs.clone(), failures: 0 };
+    job_scheduler.schedule_once(job, Instant::now() + Duration::from_secs(10), "once").await;
+
+    sleep(Duration::from_secs(5)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 0);
+    sleep(Duration::from_secs(10)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+    sleep(Duration::from_secs(3600)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn cancelled_one_shot_job_does_not_run() {
+    let (job_scheduler, task) = JobScheduler::new();
+    tokio::spawn(task);
+    let runs = Arc::new(AtomicU32::new(0));
+    let job = FailingJob { runs: runs.clone(), failures: 0 };
+    let handle = job_scheduler.schedule_once(job, Instant::now() + Duration::from_secs(10), "once").await;
+
+    sleep(Duration::from_secs(5)).await;
+    handle.cancel();
+    sleep(Duration::from_secs(3600)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 0);
+  }
 }
@@ -1,21 +1,32 @@
 use std::error::Error;
+use std::num::NonZeroU64;
+use std::path::PathBuf;
 
 use tokio::runtime::Runtime;
 use tokio::signal;
+use tokio::sync::watch;
 use tokio::time::{interval, Duration};
-use tracing::debug;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
 
 use att_core::app::env;
 use att_core::app::storage::Storage;
 use att_core::app::tracing::AppTracingBuilder;
+use att_core::util::RetryPolicy;
 use att_server_db::DbPool;
 
-use crate::crates::{crates_io_dump, Crates};
+use crate::clock::SystemClock;
+use crate::config::{ConfigFile, ConfigWatcher, Tunables};
+use crate::crates::{crates_io_client, crates_io_dump, Crates};
+use crate::crates::crates_io_client::CratesIoClient;
+use crate::crates::registry::Registries;
 use crate::job_scheduler::JobScheduler;
 use crate::server::Server;
 use crate::users::Users;
 
 pub mod server;
+pub mod clock;
+pub mod config;
 pub mod crates;
 pub mod job_scheduler;
 pub mod users;
@@ -24,21 +35,93 @@ pub mod util;
 fn main() -> Result<(), Box<dyn Error>> {
   env::load_dotenv_into_env();
   let storage = Storage::new("server");
-  let _tracing = AppTracingBuilder::default()
+  let app_tracing = AppTracingBuilder::default()
     .with_log_file_path_opt(storage.local_data_file("log.txt"))
     .build();
 
+  let config_file_path = std::env::var_os("ATT_CONFIG_FILE").map(PathBuf::from);
+  let config_file = match &config_file_path {
+    Some(path) => ConfigFile::from_path(path)?,
+    None => ConfigFile::default(),
+  };
+  if let Some(log_filter) = &config_file.log_filter {
+    app_tracing.filter_reloader().reload(log_filter);
+  }
+
   let runtime = tokio::runtime::Builder::new_multi_thread()
     .enable_all()
     .build()?;
   let runtime_guard = runtime.enter();
 
-  let db_pool = DbPool::new()?;
+  let max_retries = std::env::var("ATT_MAX_RETRIES").ok()
+    .map(|max| max.parse::<u32>().expect("ATT_MAX_RETRIES env var is not a valid unsigned integer"));
+  let retry_policy = match max_retries {
+    Some(max_retries) => RetryPolicy::default().with_max_retries(max_retries),
+    None => RetryPolicy::default(),
+  };
+
+  let db_pool = match &config_file.database_url {
+    Some(database_url) => DbPool::from_database_url(database_url)?,
+    None => DbPool::new()?,
+  };
+  let db_pool = db_pool.with_retry_policy(retry_policy);
 
   let crates_io_user_agent = std::env::var("ATT_CRATES_IO_USER_AGENT")
     .expect("ATT_CRATES_IO_USER_AGENT env var was not set");
+  let crates_io_api_base_url = std::env::var("ATT_CRATES_IO_API_BASE_URL")
+    .unwrap_or_else(|_| crates_io_client::DEFAULT_BASE_URL.to_string());
+  // Alternative crates.io-like registries, as a comma-separated list of `name=base_url` pairs.
+  let alternative_registries: Vec<(String, String)> = std::env::var("ATT_REGISTRIES").ok()
+    .map(|registries| registries.split(',')
+      .filter(|registry| !registry.trim().is_empty())
+      .map(|registry| {
+        let (name, base_url) = registry.split_once('=')
+          .expect("ATT_REGISTRIES env var is not a comma-separated list of `name=base_url` pairs");
+        (name.trim().to_string(), base_url.trim().to_string())
+      })
+      .collect())
+    .unwrap_or_default();
+  let max_followed_crates = std::env::var("ATT_MAX_FOLLOWED_CRATES").ok()
+    .map(|max| max.parse::<u32>().expect("ATT_MAX_FOLLOWED_CRATES env var is not a valid unsigned integer"));
+  let crates_cache_max_age = std::env::var("ATT_CRATES_CACHE_MAX_AGE").ok()
+    .map(|max_age| max_age.parse::<u32>().expect("ATT_CRATES_CACHE_MAX_AGE env var is not a valid unsigned integer"))
+    .unwrap_or(crates::DEFAULT_CACHE_MAX_AGE);
+  let max_body_size = std::env::var("ATT_MAX_BODY_SIZE").ok()
+    .map(|max| max.parse::<usize>().expect("ATT_MAX_BODY_SIZE env var is not a valid unsigned integer"))
+    .unwrap_or(server::DEFAULT_MAX_BODY_SIZE);
+
+  let refresh_max_age = std::env::var("ATT_REFRESH_MAX_AGE").ok()
+    .map(|max_age| max_age.parse::<u64>().expect("ATT_REFRESH_MAX_AGE env var is not a valid unsigned integer"))
+    .map(Duration::from_secs)
+    .unwrap_or(crates::DEFAULT_REFRESH_MAX_AGE);
+
+  let crates_io_dump_interval = std::env::var("ATT_CRATES_IO_DUMP_INTERVAL").ok()
+    .map(|interval| interval.parse::<NonZeroU64>().expect("ATT_CRATES_IO_DUMP_INTERVAL env var is not a valid positive integer"))
+    .map(|interval| Duration::from_secs(interval.get()))
+    .unwrap_or(crates_io_dump::UPDATE_DURATION);
+  info!(?crates_io_dump_interval, "updating crates.io database dump at interval");
+
+  let maintenance_mode = std::env::var("ATT_MAINTENANCE_MODE").ok()
+    .map(|enabled| enabled.parse::<bool>().expect("ATT_MAINTENANCE_MODE env var is not a valid boolean"))
+    .unwrap_or_default();
+
+  let base_tunables = Tunables { max_followed_crates, crates_cache_max_age, maintenance_mode };
+  let (tunables_tx, tunables) = watch::channel(base_tunables.with_config_file(&config_file));
+  let _config_watcher = match config_file_path {
+    Some(path) => Some(ConfigWatcher::new(path, config_file, base_tunables, tunables_tx, app_tracing.filter_reloader())?),
+    None => None,
+  };
 
-  let result = run(storage, &runtime, db_pool, &crates_io_user_agent);
+  let run_config = RunConfig {
+    crates_io_user_agent,
+    crates_io_api_base_url,
+    crates_io_retry_policy: retry_policy,
+    alternative_registries,
+    refresh_max_age,
+    crates_io_dump_interval,
+    max_body_size,
+  };
+  let result = run(storage, &runtime, db_pool, run_config, tunables);
 
   debug!("shutting down tokio runtime..");
   drop(runtime_guard);
@@ -48,22 +131,65 @@ fn main() -> Result<(), Box<dyn Error>> {
   result
 }
 
-fn run(storage: Storage, runtime: &Runtime, db_pool: DbPool, crates_io_user_agent: &str) -> Result<(), Box<dyn Error>> {
+/// Settings for [running](run) the server.
+struct RunConfig {
+  /// User agent sent to crates.io.
+  crates_io_user_agent: String,
+  /// Base URL of the crates.io API.
+  crates_io_api_base_url: String,
+  /// Policy for retrying failed crates.io API requests.
+  crates_io_retry_policy: RetryPolicy,
+  /// Names and base URLs of alternative crates.io-like registries.
+  alternative_registries: Vec<(String, String)>,
+  /// Age after which followed crates are refreshed.
+  refresh_max_age: Duration,
+  /// Interval at which the crates.io database dump is updated.
+  crates_io_dump_interval: Duration,
+  /// Maximum size of request bodies in bytes.
+  max_body_size: usize,
+}
+
+fn run(
+  storage: Storage,
+  runtime: &Runtime,
+  db_pool: DbPool,
+  config: RunConfig,
+  tunables: watch::Receiver<Tunables>,
+) -> Result<(), Box<dyn Error>> {
   let users = Users::from_db_pool(db_pool.clone());
+  let health_db_pool = db_pool.clone();
+  // Cancelled on shutdown to stop long-running operations such as refreshing crates and importing the database dump.
+  let cancel = CancellationToken::new();
 
-  let (crates, crates_io_client_task) = Crates::new(
+  let (crates_io_client, crates_io_client_task) = CratesIoClient::new(&config.crates_io_user_agent, &config.crates_io_api_base_url, config.crates_io_retry_policy)?;
+  runtime.spawn(crates_io_client_task);
+  let mut registries = Registries::new(crates_io_client);
+  for (name, base_url) in &config.alternative_registries {
+    let (client, client_task) = CratesIoClient::new(&config.crates_io_user_agent, base_url, config.crates_io_retry_policy)?;
+    runtime.spawn(client_task);
+    registries = registries.with_registry(name, client);
+  }
+  let crates = Crates::new(
     db_pool,
-    crates_io_user_agent,
+    registries,
+    SystemClock::shared(),
     storage.cache_file("db-dump.tar.gz").unwrap(),
-  )?;
-  runtime.spawn(crates_io_client_task);
+    tunables,
+    cancel.clone(),
+  ).with_crates_io_dump_interval(config.crates_io_dump_interval);
 
   let (job_scheduler, job_scheduler_task) = JobScheduler::new();
   runtime.spawn(job_scheduler_task);
-  job_scheduler.blocking_schedule_job(crates.create_update_crates_io_dump_job(), interval(crates_io_dump::UPDATE_DURATION), "update crates.io database dump");
-
-  let server = Server::new(users, crates);
-  let result = runtime.block_on(server.run(shutdown_signal()));
+  job_scheduler.blocking_schedule_job(crates.create_update_crates_io_dump_job(), interval(crates.crates_io_dump_interval()), "update crates.io database dump");
+  job_scheduler.blocking_schedule_job(crates.create_refresh_outdated_crates_job(config.refresh_max_age), interval(crates::REFRESH_OUTDATED_INTERVAL), "refresh outdated crates");
+
+  let server = Server::new(users, crates, health_db_pool)
+    .with_max_body_size(config.max_body_size);
+  let shutdown = async move {
+    shutdown_signal().await;
+    cancel.cancel();
+  };
+  let result = runtime.block_on(server.run(shutdown));
 
   result
 }
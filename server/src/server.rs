@@ -1,30 +1,76 @@
 use std::error::Error;
 use std::future::Future;
 use std::net::SocketAddr;
+use std::time::Duration as StdDuration;
 
+use axum::extract::{DefaultBodyLimit, Request, State};
+use axum::http::StatusCode;
+use axum::response::Response;
 use axum::Router;
+use axum::routing::get;
 use axum_login::AuthManagerLayerBuilder;
+use tokio::net::TcpListener;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
 use tower_http::trace::TraceLayer;
 use tower_sessions::{Expiry, MemoryStore, SessionManagerLayer};
 use tower_sessions::cookie::time::Duration;
+use tracing::{error, info, info_span, Span};
+use tracing::field::Empty;
+
+use att_core::util::request_id::REQUEST_ID_HEADER;
+use att_server_db::DbPool;
 
 use crate::crates::{self, Crates};
 use crate::users::{self, Users};
 
+/// Default maximum size of request bodies in bytes.
+pub const DEFAULT_MAX_BODY_SIZE: usize = 64 * 1024;
+
 #[derive(Clone)]
 pub struct Server {
   users: Users,
   crates: Crates,
+  db_pool: DbPool,
+  max_body_size: usize,
 }
 
 impl Server {
-  pub fn new(users: Users, crates: Crates) -> Self {
-    Self { users, crates }
+  pub fn new(users: Users, crates: Crates, db_pool: DbPool) -> Self {
+    Self { users, crates, db_pool, max_body_size: DEFAULT_MAX_BODY_SIZE }
+  }
+
+  /// Reject requests with bodies larger than `max_body_size` bytes with `413 Payload Too Large`.
+  #[inline]
+  pub fn with_max_body_size(mut self, max_body_size: usize) -> Self {
+    self.max_body_size = max_body_size;
+    self
   }
 
   pub async fn run(self, shutdown_signal: impl Future<Output=()> + Send + 'static) -> Result<(), Box<dyn Error>> {
+    let addr = SocketAddr::from(([127, 0, 0, 1], 1337));
+    let listener = TcpListener::bind(addr).await?;
+    self.run_with_listener(listener, shutdown_signal).await
+  }
+
+  /// Runs the server, accepting connections from `listener` until `shutdown_signal` completes. Bind `listener` to
+  /// port 0 to run the server on a free port, for example when running it in end-to-end tests.
+  pub async fn run_with_listener(
+    self,
+    listener: TcpListener,
+    shutdown_signal: impl Future<Output=()> + Send + 'static
+  ) -> Result<(), Box<dyn Error>> {
     self.users.ensure_default_user_exists().await?;
 
+    let router = self.into_router();
+    axum::serve(listener, router)
+      .with_graceful_shutdown(shutdown_signal)
+      .await?;
+
+    Ok(())
+  }
+
+  /// Creates the router with all API routes and layers.
+  pub fn into_router(self) -> Router {
     let session_store = MemoryStore::default();
     let session_layer = SessionManagerLayer::new(session_store)
       .with_expiry(Expiry::OnInactivity(Duration::days(30)))
@@ -34,7 +80,7 @@ impl Server {
       .build();
 
     let users_routes = users::router().with_state(());
-    let crates_routes = crates::route::router()
+    let crates_routes = crates::route::router(self.crates.clone())
       .with_state(self.crates);
 
     let api_routes = Router::new()
@@ -42,19 +88,258 @@ impl Server {
       .nest("/crates", crates_routes)
       ;
 
-    let router = Router::new()
+    let health_routes = Router::new()
+      .route("/health", get(health))
+      .with_state(self.db_pool);
+
+    Router::new()
       .nest("/api", api_routes)
+      .merge(health_routes)
+      .layer(DefaultBodyLimit::max(self.max_body_size))
       .layer(session_layer)
       .layer(authentication_layer)
-      .layer(TraceLayer::new_for_http())
-      ;
+      .layer(TraceLayer::new_for_http()
+        .make_span_with(make_request_span)
+        .on_response(on_response)
+      )
+      .layer(PropagateRequestIdLayer::x_request_id())
+      .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+  }
+}
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], 1337));
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, router)
-      .with_graceful_shutdown(shutdown_signal)
-      .await?;
+/// Responds with `200 OK` if the database is healthy, or `503 Service Unavailable` otherwise, so that container
+/// orchestration can check whether the server is ready to handle requests.
+async fn health(State(db_pool): State<DbPool>) -> StatusCode {
+  match db_pool.health_check().await {
+    Ok(()) => StatusCode::OK,
+    Err(cause) => {
+      error!(%cause, "health check failed: {cause:?}");
+      StatusCode::SERVICE_UNAVAILABLE
+    }
+  }
+}
 
-    Ok(())
+/// Creates a span for `request` with its ID, method, and path, and empty status and latency fields that are recorded
+/// when the response is sent. Database calls made while handling the request are recorded within this span.
+fn make_request_span(request: &Request) -> Span {
+  let request_id = request.headers().get(REQUEST_ID_HEADER).and_then(|v| v.to_str().ok());
+  info_span!("request", request_id, method = %request.method(), path = request.uri().path(), status = Empty, latency_ms = Empty)
+}
+
+/// Records the status and latency of `response` into `span`, and logs that the request has been handled.
+fn on_response(response: &Response, latency: StdDuration, span: &Span) {
+  let status = response.status().as_u16();
+  let latency_ms = latency.as_secs_f64() * 1000.0;
+  span.record("status", status);
+  span.record("latency_ms", latency_ms);
+  info!(status, latency_ms, "finished handling request");
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io;
+  use std::path::PathBuf;
+  use std::sync::{Arc, Mutex};
+
+  use axum::http::header::{CACHE_CONTROL, RETRY_AFTER};
+  use tokio::sync::watch;
+  use tokio_util::sync::CancellationToken;
+
+  use att_client::http_client::AttHttpClient;
+  use att_core::crates::{CrateId, CratesQuery};
+  use att_core::users::UserCredentials;
+  use att_server_db::crates::CratesDb;
+  use att_server_db::test_util::TestDb;
+
+  use crate::clock::MockClock;
+  use crate::config::Tunables;
+  use crate::crates::crates_io_client::{CratesIoClient, MockCratesIo};
+  use crate::crates::registry::Registries;
+
+  use super::*;
+
+  /// Creates a server on `test_db`, with a mock crates.io client that has no responses.
+  fn server(test_db: &TestDb) -> Server {
+    let tunables = Tunables { max_followed_crates: None, crates_cache_max_age: crates::DEFAULT_CACHE_MAX_AGE, maintenance_mode: false };
+    server_with_tunables(test_db, tunables)
+  }
+
+  /// Creates a server like [`server`], but with `tunables`.
+  fn server_with_tunables(test_db: &TestDb, tunables: Tunables) -> Server {
+    let (crates_io_client, crates_io_client_task) = CratesIoClient::new_mock(MockCratesIo::default());
+    tokio::spawn(crates_io_client_task);
+    let (_, tunables) = watch::channel(tunables);
+    let db_pool = test_db.db_pool().clone();
+    let crates = Crates::new(db_pool.clone(), Registries::new(crates_io_client), Arc::new(MockClock::default()), PathBuf::new(), tunables, CancellationToken::new());
+    Server::new(Users::from_db_pool(db_pool.clone()), crates, db_pool)
+  }
+
+  /// Runs `server` on a free port while running `f` with the base URL of its API, shutting it down once `f` completes.
+  async fn with_running_server<F: Future<Output=()>>(server: Server, f: impl FnOnce(String) -> F) {
+    let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await.unwrap();
+    let base_url = format!("http://{}/api/", listener.local_addr().unwrap());
+    let shutdown = CancellationToken::new();
+    let server = server.run_with_listener(listener, shutdown.clone().cancelled_owned());
+    let f = async {
+      f(base_url).await;
+      shutdown.cancel();
+    };
+    let (server_result, ()) = tokio::join!(server, f);
+    server_result.unwrap();
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn login_follow_list_unfollow() {
+    let test_db = TestDb::new().unwrap();
+    test_db.db_pool().with::<CratesDb>().query(|conn| {
+      conn.seed_crate(CrateId(1), "serde", "1.0.0")?;
+      conn.seed_crate(CrateId(2), "tokio", "1.0.0")
+    }).await.unwrap();
+
+    with_running_server(server(&test_db), |base_url| async move {
+      let client = AttHttpClient::from_base_url(base_url).unwrap();
+      // Following requires logging in.
+      assert!(client.follow_crate(CrateId(1)).await.is_err());
+      client.login(UserCredentials::default()).await.unwrap();
+
+      let found = client.search_crates(CratesQuery { name: Some("serde".to_string()), ..CratesQuery::default() }).await.unwrap();
+      assert_eq!(found.crates.len(), 1);
+      let serde_id = found.crates[0].krate.id;
+      assert_eq!(serde_id, CrateId(1));
+      assert!(client.search_crates(CratesQuery::from_followed(true)).await.unwrap().crates.is_empty());
+
+      client.follow_crate(serde_id).await.unwrap();
+      let followed = client.search_crates(CratesQuery::from_followed(true)).await.unwrap();
+      assert_eq!(followed.crates.len(), 1);
+      assert_eq!(followed.crates[0].krate.name, "serde");
+      let not_followed = client.search_crates(CratesQuery::from_followed(false)).await.unwrap();
+      assert!(not_followed.crates.iter().all(|full_crate| full_crate.krate.id != serde_id));
+
+      client.unfollow_crate(serde_id).await.unwrap();
+      assert!(client.search_crates(CratesQuery::from_followed(true)).await.unwrap().crates.is_empty());
+    }).await;
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn oversized_request_body_is_rejected() {
+    let test_db = TestDb::new().unwrap();
+    let server = server(&test_db).with_max_body_size(1024);
+
+    with_running_server(server, |base_url| async move {
+      let client = reqwest::Client::new();
+      let login_url = format!("{base_url}users/login");
+      let credentials = |password: String| serde_json::json!({ "name": "nobody", "password": password });
+
+      let response = client.post(&login_url).json(&credentials("a".repeat(2048))).send().await.unwrap();
+      assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+      // Bodies within the limit are handled as usual.
+      let response = client.post(&login_url).json(&credentials("a".repeat(16))).send().await.unwrap();
+      assert_ne!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }).await;
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn anonymous_search_is_cacheable_but_followed_list_and_errors_are_not() {
+    let test_db = TestDb::new().unwrap();
+    test_db.db_pool().with::<CratesDb>().query(|conn| conn.seed_crate(CrateId(1), "serde", "1.0.0")).await.unwrap();
+
+    with_running_server(server(&test_db), |base_url| async move {
+      let client = reqwest::Client::builder().cookie_store(true).build().unwrap();
+      let cache_control = |response: reqwest::Response| {
+        response.headers().get(CACHE_CONTROL).unwrap().to_str().unwrap().to_string()
+      };
+      let public = format!("public, max-age={}", crates::DEFAULT_CACHE_MAX_AGE);
+
+      let response = client.get(format!("{base_url}crates")).query(&[("name", "serde")]).send().await.unwrap();
+      assert_eq!(cache_control(response), public);
+      let response = client.get(format!("{base_url}crates/1")).send().await.unwrap();
+      assert_eq!(cache_control(response), public);
+      // Errors, such as not finding a crate, must not be cached.
+      let response = client.get(format!("{base_url}crates/2")).send().await.unwrap();
+      assert_eq!(response.status(), StatusCode::NOT_FOUND);
+      assert_eq!(cache_control(response), "no-store");
+
+      let credentials = UserCredentials::default();
+      client.post(format!("{base_url}users/login")).json(&credentials).send().await.unwrap().error_for_status().unwrap();
+      let response = client.get(format!("{base_url}crates")).query(&[("followed", "true")]).send().await.unwrap();
+      assert_eq!(cache_control(response), "no-store");
+    }).await;
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn maintenance_mode_rejects_modifications_but_serves_reads() {
+    let test_db = TestDb::new().unwrap();
+    test_db.db_pool().with::<CratesDb>().query(|conn| conn.seed_crate(CrateId(1), "serde", "1.0.0")).await.unwrap();
+    let tunables = Tunables { max_followed_crates: None, crates_cache_max_age: crates::DEFAULT_CACHE_MAX_AGE, maintenance_mode: true };
+
+    with_running_server(server_with_tunables(&test_db, tunables), |base_url| async move {
+      let client = reqwest::Client::builder().cookie_store(true).build().unwrap();
+      let credentials = UserCredentials::default();
+      client.post(format!("{base_url}users/login")).json(&credentials).send().await.unwrap().error_for_status().unwrap();
+
+      let response = client.post(format!("{base_url}crates/1/follow")).send().await.unwrap();
+      assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+      let retry_after = response.headers().get(RETRY_AFTER).unwrap().to_str().unwrap();
+      assert_eq!(retry_after, crates::MAINTENANCE_RETRY_AFTER.to_string());
+
+      let response = client.get(format!("{base_url}crates")).query(&[("name", "serde")]).send().await.unwrap();
+      assert_eq!(response.status(), StatusCode::OK);
+    }).await;
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn request_id_is_echoed_or_generated() {
+    let test_db = TestDb::new().unwrap();
+
+    with_running_server(server(&test_db), |base_url| async move {
+      let client = reqwest::Client::new();
+      let request_id = |response: &reqwest::Response| {
+        response.headers().get(REQUEST_ID_HEADER).map(|v| v.to_str().unwrap().to_string())
+      };
+
+      let response = client.get(format!("{base_url}crates")).header(REQUEST_ID_HEADER, "client-42").send().await.unwrap();
+      assert_eq!(request_id(&response).as_deref(), Some("client-42"));
+
+      let first = request_id(&client.get(format!("{base_url}crates")).send().await.unwrap()).unwrap();
+      let second = request_id(&client.get(format!("{base_url}crates")).send().await.unwrap()).unwrap();
+      assert!(!first.is_empty());
+      assert_ne!(first, second);
+    }).await;
+  }
+
+  /// Writer that appends to a shared buffer, to inspect logged output.
+  #[derive(Default, Clone)]
+  struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+  impl io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+      self.0.lock().unwrap().write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+  }
+
+  // Uses a current-thread runtime so that the thread-local subscriber also receives the events of the server.
+  #[tokio::test]
+  async fn completed_request_logs_status_and_latency() {
+    let test_db = TestDb::new().unwrap();
+    test_db.db_pool().with::<CratesDb>().query(|conn| conn.seed_crate(CrateId(1), "serde", "1.0.0")).await.unwrap();
+    let buffer = SharedBuffer::default();
+    let subscriber = {
+      let buffer = buffer.clone();
+      tracing_subscriber::fmt().with_ansi(false).with_writer(move || buffer.clone()).finish()
+    };
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    with_running_server(server(&test_db), |base_url| async move {
+      let response = reqwest::get(format!("{base_url}crates/1")).await.unwrap();
+      assert_eq!(response.status(), StatusCode::OK);
+    }).await;
+
+    let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    let line = output.lines()
+      .find(|line| line.contains("finished handling request"))
+      .unwrap_or_else(|| panic!("no request was logged in:\n{output}"));
+    assert!(line.contains("path=/api/crates/1"), "{line}");
+    assert!(line.contains("status=200"), "{line}");
+    assert!(line.contains("latency_ms="), "{line}");
   }
 }
@@ -4,12 +4,12 @@ use std::ops::Deref;
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
 use argon2::password_hash::{self, SaltString};
 use axum::{async_trait, Json, Router};
-use axum_login::{AuthnBackend, AuthUser, UserId};
+use axum_login::{AuthnBackend, AuthUser};
 use rand_core::OsRng;
 use thiserror::Error;
 use tracing::instrument;
 
-use att_core::users::{AuthError, UserCredentials};
+use att_core::users::{AuthError, UserCredentials, UserId};
 use att_server_db::{DbError, DbPool};
 use att_server_db::users::{NewUser, User, UsersDb};
 
@@ -104,7 +104,7 @@ impl Deref for LoginUser {
 }
 
 impl AuthUser for LoginUser {
-  type Id = i32;
+  type Id = UserId;
   fn id(&self) -> Self::Id { self.id }
 
   fn session_auth_hash(&self) -> &[u8] { self.password_hash.as_bytes() }
@@ -121,7 +121,7 @@ impl AuthnBackend for Users {
     Ok(user)
   }
 
-  async fn get_user(&self, user_id: &UserId<Self>) -> Result<Option<Self::User>, Self::Error> {
+  async fn get_user(&self, user_id: &UserId) -> Result<Option<Self::User>, Self::Error> {
     let user_id = *user_id;
     let user = self.db_pool.query(move |db| db.find(user_id)).await?.map(LoginUser);
     Ok(user)
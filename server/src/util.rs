@@ -1,5 +1,7 @@
+use axum::http::header::{CACHE_CONTROL, RETRY_AFTER};
+use axum::http::HeaderValue;
 use axum::Json;
-use axum::response::{IntoResponse, Response};
+use axum::response::{IntoResponse, IntoResponseParts, Response, ResponseParts};
 use serde::Serialize;
 
 use att_core::util::http_status_code::AsStatusCode;
@@ -76,3 +78,58 @@ impl<E: AsStatusCode> IntoResponse for StatusErr<E> {
     self.0.as_status_code().into_response()
   }
 }
+
+
+/// A `Cache-Control` header that implements [`IntoResponseParts`], to be returned alongside a response.
+#[derive(Copy, Clone, Debug)]
+pub enum CacheControl {
+  /// Response must not be stored by any cache. Use for authenticated, user-specific responses.
+  NoStore,
+  /// Response may be stored by any cache for `max_age` seconds.
+  Public { max_age: u32 },
+}
+
+impl CacheControl {
+  /// Creates a cache control that allows caching for `max_age` seconds, or that disallows caching if `max_age` is 0.
+  #[inline]
+  pub fn from_max_age(max_age: u32) -> Self {
+    if max_age == 0 { Self::NoStore } else { Self::Public { max_age } }
+  }
+
+  /// Gets this cache control for successful `result`s, or [`NoStore`](Self::NoStore) for errors, so that caches do not
+  /// store error responses which may be transient.
+  #[inline]
+  pub fn for_result<T, E>(self, result: &Result<T, E>) -> Self {
+    if result.is_ok() { self } else { Self::NoStore }
+  }
+
+  fn header_value(&self) -> HeaderValue {
+    match self {
+      CacheControl::NoStore => HeaderValue::from_static("no-store"),
+      CacheControl::Public { max_age } => HeaderValue::from_str(&format!("public, max-age={max_age}"))
+        .expect("BUG: max-age header value is invalid"),
+    }
+  }
+}
+
+impl IntoResponseParts for CacheControl {
+  type Error = std::convert::Infallible;
+  fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+    res.headers_mut().insert(CACHE_CONTROL, self.header_value());
+    Ok(res)
+  }
+}
+
+
+/// A `Retry-After` header with a number of seconds that implements [`IntoResponseParts`], to be returned alongside a
+/// `503 Service Unavailable` or `429 Too Many Requests` response.
+#[derive(Copy, Clone, Debug)]
+pub struct RetryAfter(pub u32);
+
+impl IntoResponseParts for RetryAfter {
+  type Error = std::convert::Infallible;
+  fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+    res.headers_mut().insert(RETRY_AFTER, HeaderValue::from(self.0));
+    Ok(res)
+  }
+}
@@ -0,0 +1,19 @@
+--
+-- Saved searches
+--
+
+CREATE TABLE saved_searches (
+  id         serial,
+  user_id    integer NOT NULL,
+  name       varchar NOT NULL,
+  followed   boolean NULL,
+  crate_name varchar NULL
+);
+ALTER TABLE ONLY saved_searches
+  ADD CONSTRAINT saved_searches_pkey PRIMARY KEY (id);
+ALTER TABLE ONLY saved_searches
+  -- ON DELETE CASCADE: delete saved searches when user is deleted.
+  ADD CONSTRAINT saved_searches_user_id_fkey FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE;
+ALTER TABLE ONLY saved_searches
+  -- UNIQUE: combination of user and saved search name.
+  ADD CONSTRAINT saved_searches_user_id_name_unique UNIQUE (user_id, name);
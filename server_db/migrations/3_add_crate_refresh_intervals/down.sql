@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS crate_refreshes;
+ALTER TABLE favorite_crates DROP COLUMN IF EXISTS refresh_interval_secs;
@@ -0,0 +1,22 @@
+--
+-- Per-follow refresh intervals
+--
+
+-- NULL: use the global refresh interval.
+ALTER TABLE favorite_crates
+  ADD COLUMN refresh_interval_secs integer NULL;
+
+
+--
+-- Crate refreshes
+--
+
+CREATE TABLE crate_refreshes (
+  crate_id     integer     NOT NULL,
+  refreshed_at timestamptz NOT NULL
+);
+ALTER TABLE ONLY crate_refreshes
+  ADD CONSTRAINT crate_refreshes_pkey PRIMARY KEY (crate_id);
+ALTER TABLE ONLY crate_refreshes
+  -- ON DELETE CASCADE: delete refresh time when crate is deleted.
+  ADD CONSTRAINT crate_refreshes_crate_id_fkey FOREIGN KEY (crate_id) REFERENCES crates (id) ON DELETE CASCADE;
@@ -0,0 +1,7 @@
+--
+-- Crate registries
+--
+
+-- Name of the registry the crate came from. Existing crates were imported from the crates.io database dump.
+ALTER TABLE crates
+  ADD COLUMN registry varchar NOT NULL DEFAULT 'crates-io';
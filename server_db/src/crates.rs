@@ -1,11 +1,15 @@
-use chrono::{DateTime, Utc};
-use diesel::{copy_from, delete, insert_into};
+use std::collections::HashMap;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use diesel::{copy_from, delete, insert_into, update};
 use diesel::pg::Pg;
+use diesel::upsert::excluded;
 use diesel::prelude::*;
 use tracing::{debug, instrument};
 
-use att_core::crates::{Crate, CratesQuery, CrateVersion, FullCrate};
-use att_core::schema::{crate_versions, crates, favorite_crates, import_crates_metadata};
+use att_core::crates::{Crate, CrateId, CRATES_IO_REGISTRY, CratesPage, CratesQuery, CratesSort, CrateVersion, FullCrate, NewSavedSearch, PageInfo, SavedSearch};
+use att_core::schema::{crate_refreshes, crate_versions, crates, favorite_crates, import_crates_metadata, saved_searches, users};
+use att_core::users::UserId;
 
 use crate::{DbConn, DbError};
 use crate::users::User;
@@ -13,12 +17,67 @@ use crate::users::User;
 #[derive(Copy, Clone)]
 pub struct CratesDb;
 
+/// Maximum number of values bound in a single `= ANY($1)` query. Batch operations with more values are split into
+/// chunks of this size.
+pub const MAX_BATCH_SIZE: usize = 1024;
+
 
 // Select crates
 
+/// Orders boxed crates query `$query` by `$sort_by`, in descending order if `$descending`, with ties broken by crate ID.
+/// Leaves the order of `$query` unchanged for [`CratesSort::Relevance`].
+macro_rules! order_by_sort {
+  ($query:expr, $sort_by:expr, $descending:expr) => {
+    match ($sort_by, $descending) {
+      (CratesSort::Relevance, _) => $query,
+      (CratesSort::Name, false) => $query.order((crates::name.asc(), crates::id)),
+      (CratesSort::Name, true) => $query.order((crates::name.desc(), crates::id)),
+      (CratesSort::Downloads, false) => $query.order((crates::downloads.asc(), crates::id)),
+      (CratesSort::Downloads, true) => $query.order((crates::downloads.desc(), crates::id)),
+      (CratesSort::UpdatedAt, false) => $query.order((crates::updated_at.asc(), crates::id)),
+      (CratesSort::UpdatedAt, true) => $query.order((crates::updated_at.desc(), crates::id)),
+    }
+  };
+}
+
+/// Escapes `%`, `_`, and `\` in `value` so that it only matches literally in a `LIKE` or `ILIKE` pattern.
+fn escape_like_pattern(value: &str) -> String {
+  let mut escaped = String::with_capacity(value.len());
+  for c in value.chars() {
+    if matches!(c, '%' | '_' | '\\') {
+      escaped.push('\\');
+    }
+    escaped.push(c);
+  }
+  escaped
+}
+
+/// Postgres full-text search document type.
+#[derive(diesel::sql_types::SqlType, diesel::query_builder::QueryId)]
+#[diesel(postgres_type(name = "tsvector"))]
+pub struct TsVector;
+
+/// Postgres full-text search query type.
+#[derive(diesel::sql_types::SqlType, diesel::query_builder::QueryId)]
+#[diesel(postgres_type(name = "tsquery"))]
+pub struct TsQuery;
+
+define_sql_function! {
+  /// Converts `document` to a full-text search document, using the default text search configuration.
+  fn to_tsvector(document: diesel::sql_types::Text) -> TsVector;
+}
+define_sql_function! {
+  /// Converts plain text `query` to a full-text search query, using the default text search configuration.
+  fn plainto_tsquery(query: diesel::sql_types::Text) -> TsQuery;
+}
+define_sql_function! {
+  /// Ranks how well full-text search `document` matches `query`.
+  fn ts_rank(document: TsVector, query: TsQuery) -> diesel::sql_types::Float;
+}
+
 impl DbConn<'_, CratesDb> {
   #[instrument(skip(self), err)]
-  pub fn find(&mut self, crate_id: i32) -> Result<Option<FullCrate>, DbError> {
+  pub fn find(&mut self, crate_id: CrateId) -> Result<Option<FullCrate>, DbError> {
     let full_crate = crates::table
       .find(crate_id)
       .inner_join(crate_versions::table.on(crate_versions::id.eq(crates::default_version_id)))
@@ -29,7 +88,17 @@ impl DbConn<'_, CratesDb> {
   }
 
   #[instrument(skip(self), err)]
-  pub fn find_name(&mut self, crate_id: i32) -> Result<Option<String>, DbError> {
+  pub fn find_many(&mut self, crate_ids: Vec<CrateId>) -> Result<Vec<FullCrate>, DbError> {
+    let full_crates = crates::table
+      .filter(crates::id.eq_any(crate_ids))
+      .inner_join(crate_versions::table.on(crate_versions::id.eq(crates::default_version_id)))
+      .select(FullCrate::as_select())
+      .load(self.conn)?;
+    Ok(full_crates)
+  }
+
+  #[instrument(skip(self), err)]
+  pub fn find_name(&mut self, crate_id: CrateId) -> Result<Option<String>, DbError> {
     let crate_name = crates::table
       .find(crate_id)
       .select(crates::name)
@@ -38,17 +107,60 @@ impl DbConn<'_, CratesDb> {
     Ok(crate_name)
   }
 
+  /// Resolves crate `names` into crate IDs, returning an ID for each name in the same order as `names`, or `None` if
+  /// no crate with that name exists.
+  #[instrument(skip_all, fields(names.len = names.len()), err)]
+  pub fn resolve_names(&mut self, names: &[String]) -> Result<Vec<Option<CrateId>>, DbError> {
+    let mut ids_by_name = HashMap::with_capacity(names.len());
+    for chunk in names.chunks(MAX_BATCH_SIZE) {
+      let ids: Vec<(String, CrateId)> = crates::table
+        .filter(crates::name.eq_any(chunk))
+        .select((crates::name, crates::id))
+        .load(self.conn)?;
+      ids_by_name.extend(ids);
+    }
+    let ids = names.iter().map(|name| ids_by_name.get(name).copied()).collect();
+    Ok(ids)
+  }
+
+  /// Searches for crates matching `crates_query`, returning the requested page of crates along with the total number
+  /// of matching crates. Queries that are not [paginated](CratesQuery::is_paginated) return all matching crates.
   #[instrument(skip(self), err)]
-  pub fn search(&mut self, crates_query: CratesQuery, user_id: Option<i32>) -> Result<Vec<FullCrate>, DbError> {
+  pub fn search(&mut self, crates_query: CratesQuery, user_id: Option<UserId>) -> Result<CratesPage, DbError> {
+    let total_count = self.count(crates_query.name.as_deref(), crates_query.followed, user_id)? as u64;
+    let (page, per_page) = if crates_query.is_paginated() {
+      (crates_query.page(), crates_query.per_page())
+    } else {
+      (1, total_count.min(u32::MAX as u64) as u32)
+    };
+
     let mut query = crates::table
       .inner_join(crate_versions::table.on(crate_versions::id.eq(crates::default_version_id)))
       .select(FullCrate::as_select())
       .order(crates::id)
       .into_boxed();
 
+    if crates_query.is_paginated() {
+      let offset = (page - 1) as i64 * per_page as i64;
+      query = query.limit(per_page as i64).offset(offset);
+    }
+
     if let Some(name) = crates_query.name {
-      query = query.filter(crates::name.ilike(format!("{}%", name)));
+      let escaped_name = escape_like_pattern(&name);
+      query = query.filter(crates::name.ilike(format!("%{}%", escaped_name)));
+      if crates_query.sort_by == CratesSort::Relevance {
+        // Order by relevance: exact name matches first, then names starting with `name`, then names containing `name`,
+        // with ties broken by the full-text rank of the name and description.
+        let document = crates::name.concat(" ").concat(crates::description);
+        query = query.order((
+          crates::name.ilike(escaped_name.clone()).desc(),
+          crates::name.ilike(format!("{}%", escaped_name)).desc(),
+          ts_rank(to_tsvector(document), plainto_tsquery(name)).desc(),
+          crates::id,
+        ));
+      }
     }
+    query = order_by_sort!(query, crates_query.sort_by, crates_query.descending);
 
     // TODO: can we do the joins while still assigning to `query`? Lots of type errors with joins and `into_boxed`.
     let full_crates = match (crates_query.followed, user_id) {
@@ -62,7 +174,30 @@ impl DbConn<'_, CratesDb> {
       _ => query.load::<FullCrate>(self.conn)?
     };
 
-    Ok(full_crates)
+    let page_info = PageInfo { page, per_page, total_count };
+    Ok(CratesPage { crates: full_crates, page_info })
+  }
+
+  /// Counts the crates with a name containing `name`, filtered by whether they are `followed` by user `user_id`.
+  fn count(&mut self, name: Option<&str>, followed: Option<bool>, user_id: Option<UserId>) -> Result<i64, DbError> {
+    let mut query = crates::table
+      .into_boxed();
+    if let Some(name) = name {
+      query = query.filter(crates::name.ilike(format!("%{}%", escape_like_pattern(name))));
+    }
+    let count = match (followed, user_id) {
+      (Some(true), Some(user_id)) => query
+        .inner_join(favorite_crates::table.on(favorite_crates::crate_id.eq(crates::id).and(favorite_crates::user_id.eq(user_id))))
+        .count()
+        .get_result(self.conn)?,
+      (Some(false), Some(user_id)) => query
+        .left_outer_join(favorite_crates::table.on(favorite_crates::crate_id.eq(crates::id).and(favorite_crates::user_id.eq(user_id))))
+        .filter(favorite_crates::crate_id.is_null())
+        .count()
+        .get_result(self.conn)?,
+      _ => query.count().get_result(self.conn)?
+    };
+    Ok(count)
   }
 }
 
@@ -72,7 +207,7 @@ impl DbConn<'_, CratesDb> {
 #[derive(Default, Debug, Identifiable, AsChangeset)]
 #[diesel(table_name = crates, check_for_backend(Pg))]
 pub struct UpdateCrate {
-  pub id: i32,
+  pub id: CrateId,
   pub updated_at: Option<DateTime<Utc>>,
   pub description: Option<String>,
   pub homepage: Option<Option<String>>,
@@ -80,13 +215,15 @@ pub struct UpdateCrate {
   pub repository: Option<Option<String>>,
 
   pub downloads: Option<i64>,
+
+  pub default_version_id: Option<i32>,
 }
 
 #[derive(Debug, Identifiable, AsChangeset)]
 #[diesel(table_name = crate_versions, check_for_backend(Pg))]
 pub struct UpdateVersion {
   pub id: i32,
-  pub crate_id: i32,
+  pub crate_id: CrateId,
   pub number: String,
 }
 
@@ -102,6 +239,22 @@ impl DbConn<'_, CratesDb> {
     let version = update.save_changes::<CrateVersion>(self.conn).optional()?;
     Ok(version)
   }
+
+  /// Inserts `versions`, replacing the crate and number of versions that already exist. Returns the number of inserted
+  /// or updated rows.
+  #[instrument(skip_all, fields(count = versions.len()), err)]
+  pub fn upsert_crate_versions(&mut self, versions: Vec<CrateVersion>) -> Result<usize, DbError> {
+    let count = insert_into(crate_versions::table)
+      .values(&versions)
+      .on_conflict(crate_versions::id)
+      .do_update()
+      .set((
+        crate_versions::crate_id.eq(excluded(crate_versions::crate_id)),
+        crate_versions::number.eq(excluded(crate_versions::number)),
+      ))
+      .execute(self.conn)?;
+    Ok(count)
+  }
 }
 
 
@@ -120,16 +273,34 @@ impl ImportCrates {
   }
 }
 
+/// Key of the Postgres advisory lock that is held while importing crates, so that only one import runs at a time.
+const IMPORT_LOCK_KEY: i64 = 0x6174745f696d7074; // "att_impt"
+
+define_sql_function! {
+  /// Tries to obtain an exclusive transaction-level advisory lock, released at the end of the transaction.
+  fn pg_try_advisory_xact_lock(key: diesel::sql_types::BigInt) -> diesel::sql_types::Bool;
+}
+
 impl DbConn<'_, CratesDb> {
+  /// Imports `import_crates` from the crates.io database dump, replacing all crates.io crates and their versions. Crates
+  /// from other registries are kept. Returns the number of inserted rows, or `None` if the import was skipped because
+  /// another import is already running.
   #[instrument(skip_all, err)]
-  pub fn import(&mut self, import_crates: ImportCrates) -> Result<usize, DbError> {
+  pub fn import(&mut self, import_crates: ImportCrates) -> Result<Option<usize>, DbError> {
     let inserted_rows = self.conn.transaction(|conn| {
+      let locked: bool = diesel::select(pg_try_advisory_xact_lock(IMPORT_LOCK_KEY)).get_result(conn)?;
+      if !locked {
+        return Ok(None);
+      }
+
       let mut inserted_rows: usize = 0;
 
-      debug!("Deleting table `crate_versions`");
-      delete(crate_versions::table).execute(conn)?;
-      debug!("Deleting table `crates`");
-      delete(crates::table).execute(conn)?;
+      let crates_io_crates = crates::table.filter(crates::registry.eq(CRATES_IO_REGISTRY));
+      debug!("Deleting crates.io versions from `crate_versions`");
+      delete(crate_versions::table.filter(crate_versions::crate_id.eq_any(crates_io_crates.select(crates::id))))
+        .execute(conn)?;
+      debug!("Deleting crates.io crates from `crates`");
+      delete(crates_io_crates).execute(conn)?;
 
       debug!("Copying {} crates into `crates`", import_crates.crates.len());
       inserted_rows += copy_from(crates::table)
@@ -146,7 +317,7 @@ impl DbConn<'_, CratesDb> {
         .values(import_crates_metadata::imported_at.eq(Utc::now()))
         .execute(conn)?;
 
-      Ok::<_, DbError>(inserted_rows)
+      Ok::<_, DbError>(Some(inserted_rows))
     })?;
 
     Ok(inserted_rows)
@@ -170,23 +341,27 @@ impl DbConn<'_, CratesDb> {
 #[diesel(table_name = favorite_crates, check_for_backend(Pg))]
 #[diesel(primary_key(user_id, crate_id), belongs_to(User), belongs_to(Crate))]
 pub struct FavoriteCrate {
-  pub user_id: i32,
-  pub crate_id: i32,
+  pub user_id: UserId,
+  pub crate_id: CrateId,
 }
 
 impl DbConn<'_, CratesDb> {
+  /// Gets the crates followed by user `user_id`, sorted by `sort_by` in descending order if `descending`.
   #[instrument(skip(self), err)]
-  pub fn get_followed_crates(&mut self, user_id: i32) -> Result<Vec<FullCrate>, DbError> {
-    let full_crates = crates::table
+  pub fn get_followed_crates(&mut self, user_id: UserId, sort_by: CratesSort, descending: bool) -> Result<Vec<FullCrate>, DbError> {
+    let query = crates::table
       .inner_join(crate_versions::table.on(crate_versions::id.eq(crates::default_version_id)))
       .select(FullCrate::as_select())
       .inner_join(favorite_crates::table.on(favorite_crates::crate_id.eq(crates::id).and(favorite_crates::user_id.eq(user_id))))
+      .order(crates::id)
+      .into_boxed();
+    let full_crates = order_by_sort!(query, sort_by, descending)
       .load::<FullCrate>(self.conn)?;
     Ok(full_crates)
   }
 
   #[instrument(skip(self), err)]
-  pub fn get_followed_crate_ids(&mut self, user_id: i32) -> Result<Vec<i32>, DbError> {
+  pub fn get_followed_crate_ids(&mut self, user_id: UserId) -> Result<Vec<CrateId>, DbError> {
     let crates_ids = crates::table
       .inner_join(favorite_crates::table.on(favorite_crates::crate_id.eq(crates::id).and(favorite_crates::user_id.eq(user_id))))
       .select(crates::id)
@@ -195,19 +370,497 @@ impl DbConn<'_, CratesDb> {
   }
 
   #[instrument(skip(self), err)]
-  pub fn follow(&mut self, user_id: i32, crate_id: i32) -> Result<(), DbError> {
+  pub fn count_followed_crates(&mut self, user_id: UserId) -> Result<i64, DbError> {
+    let count = favorite_crates::table
+      .filter(favorite_crates::user_id.eq(user_id))
+      .count()
+      .get_result(self.conn)?;
+    Ok(count)
+  }
+
+  #[instrument(skip(self), err)]
+  pub fn follow(&mut self, user_id: UserId, crate_id: CrateId) -> Result<(), DbError> {
     insert_into(favorite_crates::table)
       .values(&FavoriteCrate { crate_id, user_id })
       .execute(self.conn)?;
     Ok(())
   }
 
+  /// Makes user `user_id` follow crate `crate_id` if they follow fewer than `max_followed_crates` crates, or if there is
+  /// no maximum. Returns `false` if the crate was not followed because the limit is reached. Following an already
+  /// followed crate succeeds without checking the limit.
   #[instrument(skip(self), err)]
-  pub fn unfollow(&mut self, user_id: i32, crate_id: i32) -> Result<(), DbError> {
+  pub fn follow_within_limit(&mut self, user_id: UserId, crate_id: CrateId, max_followed_crates: Option<u32>) -> Result<bool, DbError> {
+    self.conn.transaction(|conn| {
+      // Lock the row of the user, so that concurrent follows by the same user cannot both pass the limit check.
+      users::table.filter(users::id.eq(user_id)).select(users::id).for_update().execute(conn)?;
+      let already_followed: bool = diesel::select(diesel::dsl::exists(favorite_crates::table
+        .filter(favorite_crates::user_id.eq(user_id))
+        .filter(favorite_crates::crate_id.eq(crate_id))
+      )).get_result(conn)?;
+      if already_followed {
+        return Ok(true);
+      }
+      if let Some(max) = max_followed_crates {
+        let count: i64 = favorite_crates::table
+          .filter(favorite_crates::user_id.eq(user_id))
+          .count()
+          .get_result(conn)?;
+        if count >= max as i64 {
+          return Ok(false);
+        }
+      }
+      insert_into(favorite_crates::table)
+        .values(&FavoriteCrate { crate_id, user_id })
+        .execute(conn)?;
+      Ok::<_, DbError>(true)
+    })
+  }
+
+  #[instrument(skip(self), err)]
+  pub fn unfollow(&mut self, user_id: UserId, crate_id: CrateId) -> Result<(), DbError> {
     delete(favorite_crates::table)
       .filter(favorite_crates::user_id.eq(user_id))
       .filter(favorite_crates::crate_id.eq(crate_id))
       .execute(self.conn)?;
     Ok(())
   }
+
+  #[instrument(skip(self, crate_ids), fields(crate_ids.len = crate_ids.len()), err)]
+  pub fn unfollow_many(&mut self, user_id: UserId, crate_ids: Vec<CrateId>) -> Result<usize, DbError> {
+    self.conn.transaction(|conn| {
+      let mut deleted_rows = 0;
+      for chunk in crate_ids.chunks(MAX_BATCH_SIZE) {
+        deleted_rows += delete(favorite_crates::table)
+          .filter(favorite_crates::user_id.eq(user_id))
+          .filter(favorite_crates::crate_id.eq_any(chunk))
+          .execute(conn)?;
+      }
+      Ok(deleted_rows)
+    })
+  }
+
+  /// Sets the interval in seconds at which crate `crate_id` followed by user `user_id` should be refreshed, or `None`
+  /// to use the global refresh interval. Returns whether the user follows the crate.
+  #[instrument(skip(self), err)]
+  pub fn set_refresh_interval(&mut self, user_id: UserId, crate_id: CrateId, refresh_interval_secs: Option<i32>) -> Result<bool, DbError> {
+    let updated_rows = update(favorite_crates::table)
+      .filter(favorite_crates::user_id.eq(user_id))
+      .filter(favorite_crates::crate_id.eq(crate_id))
+      .set(favorite_crates::refresh_interval_secs.eq(refresh_interval_secs))
+      .execute(self.conn)?;
+    Ok(updated_rows > 0)
+  }
+}
+
+
+// Crate refreshes
+
+impl DbConn<'_, CratesDb> {
+  /// Gets the IDs of followed crates that are outdated at `now`: crates that have never been refreshed, or that were
+  /// last refreshed longer ago than the shortest refresh interval of their followers. Followers without a refresh
+  /// interval use `default_interval`.
+  #[instrument(skip(self), err)]
+  pub fn get_outdated_followed_crate_ids(&mut self, now: DateTime<Utc>, default_interval: TimeDelta) -> Result<Vec<CrateId>, DbError> {
+    let rows: Vec<(CrateId, Option<i32>, Option<DateTime<Utc>>)> = favorite_crates::table
+      .left_outer_join(crate_refreshes::table.on(crate_refreshes::crate_id.eq(favorite_crates::crate_id)))
+      .select((favorite_crates::crate_id, favorite_crates::refresh_interval_secs, crate_refreshes::refreshed_at.nullable()))
+      .load(self.conn)?;
+
+    // Shortest refresh interval and last refresh time per crate.
+    let mut crates: HashMap<CrateId, (TimeDelta, Option<DateTime<Utc>>)> = HashMap::new();
+    for (crate_id, refresh_interval_secs, refreshed_at) in rows {
+      let interval = refresh_interval_secs.map_or(default_interval, |secs| TimeDelta::seconds(secs as i64));
+      crates.entry(crate_id)
+        .and_modify(|(shortest, _)| *shortest = (*shortest).min(interval))
+        .or_insert((interval, refreshed_at));
+    }
+
+    let mut crate_ids: Vec<CrateId> = crates.into_iter()
+      .filter(|(_, (interval, refreshed_at))| match refreshed_at {
+        Some(refreshed_at) => refreshed_at.checked_add_signed(*interval).map_or(false, |outdated_at| outdated_at <= now),
+        None => true,
+      })
+      .map(|(crate_id, _)| crate_id)
+      .collect();
+    crate_ids.sort_unstable();
+    Ok(crate_ids)
+  }
+
+  /// Records that crate `crate_id` was refreshed at `refreshed_at`.
+  #[instrument(skip(self), err)]
+  pub fn set_refreshed_at(&mut self, crate_id: CrateId, refreshed_at: DateTime<Utc>) -> Result<(), DbError> {
+    insert_into(crate_refreshes::table)
+      .values((crate_refreshes::crate_id.eq(crate_id), crate_refreshes::refreshed_at.eq(refreshed_at)))
+      .on_conflict(crate_refreshes::crate_id)
+      .do_update()
+      .set(crate_refreshes::refreshed_at.eq(refreshed_at))
+      .execute(self.conn)?;
+    Ok(())
+  }
+}
+
+
+// Saved searches
+
+#[derive(Debug, Identifiable, Selectable, Queryable, Associations)]
+#[diesel(table_name = saved_searches, check_for_backend(Pg), belongs_to(User))]
+pub struct SavedSearchRow {
+  pub id: i32,
+  pub user_id: UserId,
+  pub name: String,
+  pub followed: Option<bool>,
+  pub crate_name: Option<String>,
+}
+impl From<SavedSearchRow> for SavedSearch {
+  fn from(row: SavedSearchRow) -> Self {
+    let query = CratesQuery { followed: row.followed, name: row.crate_name, ..CratesQuery::default() };
+    Self { id: row.id, name: row.name, query }
+  }
+}
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = saved_searches, check_for_backend(Pg), treat_none_as_null = true)]
+struct SavedSearchValues {
+  user_id: UserId,
+  name: String,
+  followed: Option<bool>,
+  crate_name: Option<String>,
+}
+impl SavedSearchValues {
+  fn new(user_id: UserId, new_saved_search: NewSavedSearch) -> Self {
+    let NewSavedSearch { name, query } = new_saved_search;
+    Self { user_id, name, followed: query.followed, crate_name: query.name }
+  }
+}
+
+impl DbConn<'_, CratesDb> {
+  #[instrument(skip(self), err)]
+  pub fn get_saved_searches(&mut self, user_id: UserId) -> Result<Vec<SavedSearch>, DbError> {
+    let saved_searches = saved_searches::table
+      .filter(saved_searches::user_id.eq(user_id))
+      .order(saved_searches::name)
+      .select(SavedSearchRow::as_select())
+      .load(self.conn)?
+      .into_iter()
+      .map(SavedSearch::from)
+      .collect();
+    Ok(saved_searches)
+  }
+
+  #[instrument(skip(self), err)]
+  pub fn insert_saved_search(&mut self, user_id: UserId, new_saved_search: NewSavedSearch) -> Result<SavedSearch, DbError> {
+    let row: SavedSearchRow = insert_into(saved_searches::table)
+      .values(SavedSearchValues::new(user_id, new_saved_search))
+      .returning(SavedSearchRow::as_returning())
+      .get_result(self.conn)?;
+    Ok(row.into())
+  }
+
+  #[instrument(skip(self), err)]
+  pub fn update_saved_search(&mut self, user_id: UserId, saved_search_id: i32, new_saved_search: NewSavedSearch) -> Result<Option<SavedSearch>, DbError> {
+    let row: Option<SavedSearchRow> = update(saved_searches::table)
+      .filter(saved_searches::id.eq(saved_search_id))
+      .filter(saved_searches::user_id.eq(user_id))
+      .set(SavedSearchValues::new(user_id, new_saved_search))
+      .returning(SavedSearchRow::as_returning())
+      .get_result(self.conn)
+      .optional()?;
+    Ok(row.map(SavedSearch::from))
+  }
+
+  #[instrument(skip(self), err)]
+  pub fn delete_saved_search(&mut self, user_id: UserId, saved_search_id: i32) -> Result<bool, DbError> {
+    let deleted_rows = delete(saved_searches::table)
+      .filter(saved_searches::id.eq(saved_search_id))
+      .filter(saved_searches::user_id.eq(user_id))
+      .execute(self.conn)?;
+    Ok(deleted_rows > 0)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use att_core::crates::{CrateId, CratesSort};
+
+  use diesel::connection::SimpleConnection;
+
+  use crate::test_util::TestDb;
+  use crate::users::UsersDb;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn follow_crate() {
+    let test_db = TestDb::new().unwrap();
+    let user = test_db.db_pool().with::<UsersDb>().query(|conn| conn.seed_user("alice")).await.unwrap();
+
+    let followed_crates = test_db.db_pool().with::<CratesDb>().query(move |conn| {
+      conn.seed_crate(CrateId(1), "serde", "1.0.0")?;
+      conn.seed_crate(CrateId(2), "tokio", "1.0.0")?;
+      conn.follow(user.id, CrateId(2))?;
+      conn.get_followed_crates(user.id, CratesSort::Relevance, false)
+    }).await.unwrap();
+
+    assert_eq!(followed_crates.len(), 1);
+    assert_eq!(followed_crates[0].krate.id, CrateId(2));
+    assert_eq!(followed_crates[0].krate.name, "tokio");
+    assert_eq!(followed_crates[0].default_version.number, "1.0.0");
+  }
+
+  #[tokio::test]
+  async fn search_followed_is_scoped_to_user() {
+    let test_db = TestDb::new().unwrap();
+    let (alice, bob) = test_db.db_pool().with::<UsersDb>().query(|conn| {
+      Ok((conn.seed_user("alice")?, conn.seed_user("bob")?))
+    }).await.unwrap();
+
+    let (alice_followed, bob_followed) = test_db.db_pool().with::<CratesDb>().query(move |conn| {
+      conn.seed_crate(CrateId(1), "serde", "1.0.0")?;
+      conn.seed_crate(CrateId(2), "tokio", "1.0.0")?;
+      conn.follow(alice.id, CrateId(2))?;
+      let alice_followed = conn.search(CratesQuery::from_followed(true), Some(alice.id))?;
+      let bob_followed = conn.search(CratesQuery::from_followed(true), Some(bob.id))?;
+      Ok((alice_followed, bob_followed))
+    }).await.unwrap();
+
+    assert_eq!(alice_followed.crates.len(), 1);
+    assert_eq!(alice_followed.crates[0].krate.id, CrateId(2));
+    assert!(bob_followed.crates.is_empty());
+  }
+
+  #[tokio::test]
+  async fn outdated_crates_use_shortest_refresh_interval_of_followers() {
+    let test_db = TestDb::new().unwrap();
+    let (alice, bob) = test_db.db_pool().with::<UsersDb>().query(|conn| {
+      Ok((conn.seed_user("alice")?, conn.seed_user("bob")?))
+    }).await.unwrap();
+
+    let refreshed_at = DateTime::UNIX_EPOCH;
+    let now = refreshed_at + TimeDelta::seconds(120);
+    let default_interval = TimeDelta::seconds(3600);
+    let (outdated, outdated_after_bob) = test_db.db_pool().with::<CratesDb>().query(move |conn| {
+      for (id, name) in [(1, "serde"), (2, "tokio")] {
+        conn.seed_crate(CrateId(id), name, "1.0.0")?;
+        conn.follow(alice.id, CrateId(id))?;
+        conn.set_refreshed_at(CrateId(id), refreshed_at)?;
+      }
+      conn.set_refresh_interval(alice.id, CrateId(1), Some(60))?;
+      let outdated = conn.get_outdated_followed_crate_ids(now, default_interval)?;
+      conn.follow(bob.id, CrateId(2))?;
+      conn.set_refresh_interval(bob.id, CrateId(2), Some(30))?;
+      let outdated_after_bob = conn.get_outdated_followed_crate_ids(now, default_interval)?;
+      Ok((outdated, outdated_after_bob))
+    }).await.unwrap();
+
+    assert_eq!(outdated, [CrateId(1)]);
+    assert_eq!(outdated_after_bob, [CrateId(1), CrateId(2)]);
+  }
+
+  #[tokio::test]
+  async fn resolve_names_across_batches_preserves_order() {
+    let test_db = TestDb::new().unwrap();
+    let count = MAX_BATCH_SIZE as i32 + 100;
+
+    // Deterministically shuffle the seeded crates, and intersperse names of crates that do not exist.
+    let mut ids: Vec<i32> = (1..=count).collect();
+    ids.sort_by_key(|id| (id * 7919) % 1031);
+    let mut expected = Vec::with_capacity(ids.len());
+    for (index, id) in ids.into_iter().enumerate() {
+      if index % 97 == 0 {
+        expected.push((format!("unknown{index}"), None));
+      }
+      expected.push((format!("crate{id}"), Some(CrateId(id))));
+    }
+    let names: Vec<String> = expected.iter().map(|(name, _)| name.clone()).collect();
+
+    let resolved = test_db.db_pool().with::<CratesDb>().query(move |conn| {
+      for id in 1..=count {
+        conn.seed_crate(CrateId(id), &format!("crate{id}"), "1.0.0")?;
+      }
+      conn.resolve_names(&names)
+    }).await.unwrap();
+
+    let expected: Vec<Option<CrateId>> = expected.into_iter().map(|(_, id)| id).collect();
+    assert_eq!(resolved.len(), expected.len());
+    assert_eq!(resolved, expected);
+  }
+
+  #[tokio::test]
+  async fn follow_beyond_limit_is_rejected() {
+    let test_db = TestDb::new().unwrap();
+    let user = test_db.db_pool().with::<UsersDb>().query(|conn| conn.seed_user("alice")).await.unwrap();
+
+    let followed = test_db.db_pool().with::<CratesDb>().query(move |conn| {
+      for (id, name) in [(1, "serde"), (2, "tokio"), (3, "axum")] {
+        conn.seed_crate(CrateId(id), name, "1.0.0")?;
+      }
+      Ok([
+        conn.follow_within_limit(user.id, CrateId(1), Some(2))?,
+        conn.follow_within_limit(user.id, CrateId(2), Some(2))?,
+        conn.follow_within_limit(user.id, CrateId(3), Some(2))?,
+        // Following an already followed crate at the limit succeeds.
+        conn.follow_within_limit(user.id, CrateId(2), Some(2))?,
+        conn.follow_within_limit(user.id, CrateId(3), None)?,
+      ])
+    }).await.unwrap();
+    assert_eq!(followed, [true, true, false, true, true]);
+  }
+
+  #[tokio::test]
+  async fn unfollow_frees_followed_crate_slot() {
+    let test_db = TestDb::new().unwrap();
+    let user = test_db.db_pool().with::<UsersDb>().query(|conn| conn.seed_user("alice")).await.unwrap();
+
+    let (followed, followed_crates) = test_db.db_pool().with::<CratesDb>().query(move |conn| {
+      conn.seed_crate(CrateId(1), "serde", "1.0.0")?;
+      conn.seed_crate(CrateId(2), "tokio", "1.0.0")?;
+      let mut followed = vec![
+        conn.follow_within_limit(user.id, CrateId(1), Some(1))?,
+        conn.follow_within_limit(user.id, CrateId(2), Some(1))?,
+      ];
+      conn.unfollow(user.id, CrateId(1))?;
+      followed.push(conn.follow_within_limit(user.id, CrateId(2), Some(1))?);
+      Ok((followed, conn.get_followed_crates(user.id, CratesSort::Relevance, false)?))
+    }).await.unwrap();
+    assert_eq!(followed, [true, false, true]);
+    assert_eq!(followed_crates.len(), 1);
+    assert_eq!(followed_crates[0].krate.id, CrateId(2));
+  }
+
+  #[tokio::test]
+  async fn concurrent_follows_do_not_exceed_limit() {
+    let test_db = TestDb::new().unwrap();
+    let user = test_db.db_pool().with::<UsersDb>().query(|conn| conn.seed_user("alice")).await.unwrap();
+    let db_pool = test_db.db_pool().with::<CratesDb>();
+    db_pool.query(|conn| {
+      for id in 1..=8 {
+        conn.seed_crate(CrateId(id), &format!("crate{id}"), "1.0.0")?;
+      }
+      Ok(())
+    }).await.unwrap();
+
+    let follows: Vec<_> = (1..=8).map(|id| {
+      let db_pool = db_pool.clone();
+      tokio::spawn(async move { db_pool.query(move |conn| conn.follow_within_limit(user.id, CrateId(id), Some(3))).await })
+    }).collect();
+    let mut followed_count = 0;
+    for follow in follows {
+      if follow.await.unwrap().unwrap() { followed_count += 1; }
+    }
+    assert_eq!(followed_count, 3);
+    assert_eq!(db_pool.query(move |conn| conn.count_followed_crates(user.id)).await.unwrap(), 3);
+  }
+
+  #[tokio::test]
+  async fn concurrent_import_is_skipped() {
+    let test_db = TestDb::new().unwrap();
+    let db_pool = test_db.db_pool().with::<CratesDb>();
+    // Hold the import lock in an open transaction on another connection, as a concurrent import would.
+    let lock_holder = db_pool.get().await.unwrap();
+    lock_holder.query(|conn| {
+      conn.conn.batch_execute(&format!("BEGIN; SELECT pg_try_advisory_xact_lock({IMPORT_LOCK_KEY});"))?;
+      Ok(())
+    }).await.unwrap();
+
+    let inserted_rows = db_pool.query(|conn| conn.import(ImportCrates::with_expected_crate_count(0))).await.unwrap();
+    assert_eq!(inserted_rows, None);
+
+    lock_holder.query(|conn| Ok(conn.conn.batch_execute("ROLLBACK")?)).await.unwrap();
+    let inserted_rows = db_pool.query(|conn| conn.import(ImportCrates::with_expected_crate_count(0))).await.unwrap();
+    assert!(inserted_rows.is_some());
+  }
+
+  #[tokio::test]
+  async fn search_orders_by_relevance() {
+    let test_db = TestDb::new().unwrap();
+    let crates_page = test_db.db_pool().with::<CratesDb>().query(move |conn| {
+      conn.seed_crate(CrateId(1), "serde_json", "1.0.0")?;
+      conn.seed_crate(CrateId(2), "miniserde", "1.0.0")?;
+      conn.seed_crate(CrateId(3), "serde", "1.0.0")?;
+      conn.seed_crate(CrateId(4), "tokio", "1.0.0")?;
+      conn.search(CratesQuery { name: Some("serde".to_string()), ..CratesQuery::default() }, None)
+    }).await.unwrap();
+
+    let names: Vec<_> = crates_page.crates.iter().map(|full_crate| full_crate.krate.name.as_str()).collect();
+    assert_eq!(names, ["serde", "serde_json", "miniserde"]);
+    assert_eq!(crates_page.page_info.total_count, 3);
+  }
+
+  #[tokio::test]
+  async fn search_breaks_ties_by_full_text_rank() {
+    let test_db = TestDb::new().unwrap();
+    let crates_page = test_db.db_pool().with::<CratesDb>().query(move |conn| {
+      conn.seed_crate(CrateId(1), "async-foo", "1.0.0")?;
+      conn.seed_crate(CrateId(2), "async-bar", "1.0.0")?;
+      update(crates::table.find(CrateId(2)))
+        .set(crates::description.eq("Async runtime, async I/O, and async channels"))
+        .execute(conn.conn)?;
+      conn.search(CratesQuery { name: Some("async".to_string()), ..CratesQuery::default() }, None)
+    }).await.unwrap();
+
+    let names: Vec<_> = crates_page.crates.iter().map(|full_crate| full_crate.krate.name.as_str()).collect();
+    assert_eq!(names, ["async-bar", "async-foo"]);
+  }
+
+  #[tokio::test]
+  async fn search_matches_like_wildcards_literally() {
+    let test_db = TestDb::new().unwrap();
+    let (underscore, percent) = test_db.db_pool().with::<CratesDb>().query(move |conn| {
+      conn.seed_crate(CrateId(1), "serde_json", "1.0.0")?;
+      conn.seed_crate(CrateId(2), "serde-json", "1.0.0")?;
+      let underscore = conn.search(CratesQuery { name: Some("e_j".to_string()), ..CratesQuery::default() }, None)?;
+      let percent = conn.search(CratesQuery { name: Some("serde%".to_string()), ..CratesQuery::default() }, None)?;
+      Ok((underscore, percent))
+    }).await.unwrap();
+
+    assert_eq!(underscore.crates.len(), 1);
+    assert_eq!(underscore.crates[0].krate.name, "serde_json");
+    assert_eq!(underscore.page_info.total_count, 1);
+    assert!(percent.crates.is_empty());
+    assert_eq!(percent.page_info.total_count, 0);
+  }
+
+  #[tokio::test]
+  async fn upsert_crate_versions_updates_existing_versions() {
+    let test_db = TestDb::new().unwrap();
+    let versions = test_db.db_pool().with::<CratesDb>().query(move |conn| {
+      conn.seed_crate(CrateId(1), "serde", "1.0.0")?;
+      let version = |id, number: &str| CrateVersion { id, crate_id: CrateId(1), number: number.to_string() };
+      assert_eq!(conn.upsert_crate_versions(vec![version(1, "1.0.0"), version(2, "1.0.1")])?, 2);
+      assert_eq!(conn.upsert_crate_versions(vec![version(1, "1.0.0"), version(2, "1.0.2"), version(3, "1.1.0")])?, 3);
+      let versions = crate_versions::table
+        .filter(crate_versions::crate_id.eq(CrateId(1)))
+        .order(crate_versions::id)
+        .load::<CrateVersion>(conn.conn)?;
+      Ok(versions)
+    }).await.unwrap();
+
+    let numbers: Vec<_> = versions.iter().map(|version| (version.id, version.number.as_str())).collect();
+    assert_eq!(numbers, [(1, "1.0.0"), (2, "1.0.2"), (3, "1.1.0")]);
+  }
+
+  #[tokio::test]
+  async fn saved_searches() {
+    let test_db = TestDb::new().unwrap();
+    let user = test_db.db_pool().with::<UsersDb>().query(|conn| conn.seed_user("alice")).await.unwrap();
+
+    let (saved, saved_searches, found, duplicate) = test_db.db_pool().with::<CratesDb>().query(move |conn| {
+      conn.seed_crate(CrateId(1), "serde", "1.0.0")?;
+      conn.seed_crate(CrateId(2), "tokio", "1.0.0")?;
+      let query = CratesQuery { name: Some("serde".to_string()), ..CratesQuery::default() };
+      let saved = conn.insert_saved_search(user.id, NewSavedSearch { name: "Serde".to_string(), query })?;
+      let saved_searches = conn.get_saved_searches(user.id)?;
+      let found = conn.search(saved.query.clone(), Some(user.id))?;
+      let duplicate = conn.insert_saved_search(user.id, NewSavedSearch { name: "Serde".to_string(), query: CratesQuery::default() });
+      Ok((saved, saved_searches, found, duplicate))
+    }).await.unwrap();
+
+    assert_eq!(saved.name, "Serde");
+    assert_eq!(saved.query.name.as_deref(), Some("serde"));
+    assert_eq!(saved_searches, [saved]);
+    assert_eq!(found.crates.len(), 1);
+    assert_eq!(found.crates[0].krate.name, "serde");
+    assert!(duplicate.is_err_and(|e| e.is_unique_violation()));
+  }
 }
@@ -1,33 +1,77 @@
 use std::marker::PhantomData;
+use std::str::FromStr;
+use std::time::Duration;
 
 use deadpool_diesel::postgres::{BuildError, InteractError, Manager, Object, Pool, PoolError, Runtime};
-use diesel::PgConnection;
+use diesel::{PgConnection, RunQueryDsl};
 use thiserror::Error;
+use tracing::{Span, warn};
 
 use att_core::run_or_compile_time_env;
+use att_core::util::RetryPolicy;
+use att_core::util::time;
 
 pub mod users;
 pub mod crates;
+#[cfg(feature = "test_util")]
+pub mod test_util;
+
+/// Default maximum number of connections in a [`DbPool`], used when the `ATT_DB_POOL_MAX_SIZE` environment variable is
+/// not set.
+pub const DEFAULT_MAX_SIZE: usize = 8;
 
 /// Database connection pool.
 #[derive(Clone)]
 pub struct DbPool<M = ()> {
   pool: Pool,
+  retry_policy: RetryPolicy,
   marker: PhantomData<M>,
 }
 impl DbPool {
   pub fn new() -> Result<Self, BuildError> {
-    let manager = Manager::new(run_or_compile_time_env!("DATABASE_URL"), Runtime::Tokio1);
+    Self::from_database_url(run_or_compile_time_env!("DATABASE_URL"))
+  }
+
+  /// Creates a pool connecting to `database_url`. The maximum number of connections is read from the
+  /// `ATT_DB_POOL_MAX_SIZE` environment variable, defaulting to [`DEFAULT_MAX_SIZE`]. The number of seconds to wait for
+  /// a connection before timing out is read from the `ATT_DB_POOL_WAIT_TIMEOUT` environment variable, waiting
+  /// indefinitely if it is not set. Environment variables that fail to parse are ignored with a warning.
+  pub fn from_database_url(database_url: impl Into<String>) -> Result<Self, BuildError> {
+    let max_size = parse_env_var("ATT_DB_POOL_MAX_SIZE").unwrap_or(DEFAULT_MAX_SIZE);
+    let wait_timeout = parse_env_var("ATT_DB_POOL_WAIT_TIMEOUT").map(Duration::from_secs);
+
+    let manager = Manager::new(database_url, Runtime::Tokio1);
     let pool = Pool::builder(manager)
-      .max_size(8)
+      .max_size(max_size)
+      .wait_timeout(wait_timeout)
+      .runtime(Runtime::Tokio1)
       .build()?;
-    let db = Self { pool, marker: PhantomData };
+    let db = Self { pool, retry_policy: RetryPolicy::NONE, marker: PhantomData };
     Ok(db)
   }
 
+  /// Retry getting connections from the pool according to `retry_policy` when that times out or fails to connect.
+  #[inline]
+  pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+    self.retry_policy = retry_policy;
+    self
+  }
+
   #[inline]
   pub fn with<MM>(&self) -> DbPool<MM> {
-    DbPool { pool: self.pool.clone(), marker: PhantomData }
+    DbPool { pool: self.pool.clone(), retry_policy: self.retry_policy, marker: PhantomData }
+  }
+}
+
+
+fn parse_env_var<T: FromStr>(name: &str) -> Option<T> {
+  let value = std::env::var(name).ok()?;
+  match value.parse() {
+    Ok(value) => Some(value),
+    Err(_) => {
+      warn!(name, value, "ignoring {name} env var; it is not a valid unsigned integer");
+      None
+    }
   }
 }
 
@@ -37,8 +81,14 @@ impl DbPool {
 pub enum DbError {
   #[error("Database query failed: {0}")]
   Query(#[from] diesel::result::Error),
+  #[error("Database operation did not complete within {0:?}")]
+  Timeout(Duration),
+  #[error("Timed out getting database connection from pool; the database may be overloaded or unreachable, or the pool may be too small")]
+  PoolTimeout,
+  #[error("Failed to get database connection from pool because the pool is closed; the server is probably shutting down")]
+  PoolClosed,
   #[error("Failed to get database connection from pool: {0}")]
-  ConnectionFromPool(#[from] PoolError),
+  ConnectionFromPool(PoolError),
   #[error("Performing operation with database connection panicked: {0}")]
   PerformPanic(String),
   #[error("Performing operation with database connection panicked, but the panic does not contain a message")]
@@ -46,6 +96,29 @@ pub enum DbError {
   #[error("Performing operation with database connection was aborted")]
   PerformAbort,
 }
+impl DbError {
+  /// Returns whether this error was caused by violating a unique constraint, such as inserting a duplicate name.
+  pub fn is_unique_violation(&self) -> bool {
+    use diesel::result::{DatabaseErrorKind, Error};
+    matches!(self, DbError::Query(Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)))
+  }
+
+  /// Returns whether this error was caused by timing out while getting a connection from the pool, meaning that the
+  /// database is temporarily unavailable.
+  #[inline]
+  pub fn is_pool_timeout(&self) -> bool {
+    matches!(self, DbError::PoolTimeout)
+  }
+}
+impl From<PoolError> for DbError {
+  fn from(error: PoolError) -> Self {
+    match error {
+      PoolError::Timeout(_) => DbError::PoolTimeout,
+      PoolError::Closed => DbError::PoolClosed,
+      error => DbError::ConnectionFromPool(error),
+    }
+  }
+}
 impl From<InteractError> for DbError {
   fn from(error: InteractError) -> Self {
     match error {
@@ -62,13 +135,24 @@ impl From<InteractError> for DbError {
 }
 
 impl<M> DbPool<M> {
-  /// Obtain a database connection pool object from the pool.
+  /// Obtain a database connection pool object from the pool, retrying according to the retry policy of this pool when
+  /// that times out or fails to connect.
   #[inline]
   pub async fn get(&self) -> Result<DbPoolObj<M>, DbError> {
-    let obj = self.pool.get().await?;
+    let obj = self.retry_policy
+      .retry(|| self.pool.get(), |e| matches!(e, PoolError::Timeout(_) | PoolError::Backend(_)))
+      .await?;
     Ok(DbPoolObj { obj, marker: self.marker })
   }
 
+  /// Checks whether the database is healthy by obtaining a connection from the pool and running a trivial query on it.
+  pub async fn health_check(&self) -> Result<(), DbError> {
+    self.query(|conn| {
+      diesel::sql_query("SELECT 1").execute(conn.conn)?;
+      Ok(())
+    }).await
+  }
+
   /// Interact synchronously with `f` that returns `R`, on a database connection newly obtained from the pool.
   #[inline]
   pub async fn interact<R: Send + 'static>(
@@ -90,6 +174,27 @@ impl<M> DbPool<M> {
     Ok(output)
   }
 
+  /// Query synchronously with `f` like [`query`](Self::query), but fail with [`DbError::Timeout`] if `f` does not
+  /// complete within `timeout`.
+  ///
+  /// Timing out does not cancel `f`: it keeps running on a blocking thread until it completes, because database
+  /// connections are synchronous. Therefore, on timeout, the connection is detached from the pool so that it is not
+  /// handed out again while `f` is still running, and is closed once `f` completes.
+  pub async fn query_timeout<T: Send + 'static>(
+    &self,
+    timeout: Duration,
+    f: impl for<'c> FnOnce(&mut DbConn<'c, M>) -> Result<T, DbError> + Send + 'static
+  ) -> Result<T, DbError> {
+    let obj = self.get().await?;
+    match time::timeout(timeout, obj.query(f)).await {
+      Ok(output) => output,
+      Err(_) => {
+        drop(Object::take(obj.obj));
+        Err(DbError::Timeout(timeout))
+      }
+    }
+  }
+
   /// Perform `f` synchronously with `f` returning `Result<T, E>` where `E: From<DbError>`, on a database connection
   /// newly obtained from the pool.
   #[inline]
@@ -118,7 +223,10 @@ impl<M> DbPoolObj<M> {
     &self,
     f: impl for<'c> FnOnce(&mut DbConn<'c, M>) -> R + Send + 'static
   ) -> Result<R, DbError> {
-    let output = self.obj.interact(move |conn| f(&mut DbConn::new(conn))).await?;
+    // Enter the current span in the interaction, which runs on a different thread, so that it is traced as part of the
+    // current span.
+    let span = Span::current();
+    let output = self.obj.interact(move |conn| span.in_scope(|| f(&mut DbConn::new(conn)))).await?;
     Ok(output)
   }
 
@@ -157,4 +265,97 @@ impl<'c, M> DbConn<'c, M> {
 
   #[inline]
   pub fn inner(&'c mut self) -> &'c mut PgConnection { &mut self.conn }
+
+  /// Runs `f` in a transaction, committing it when `f` returns `Ok`, and rolling it back when `f` returns `Err`.
+  pub fn transaction<T, E: From<DbError>>(
+    &mut self,
+    f: impl for<'t> FnOnce(&mut DbConn<'t, M>) -> Result<T, E>
+  ) -> Result<T, E> {
+    use diesel::Connection;
+    let result = self.conn.transaction(|conn| f(&mut DbConn::new(conn)).map_err(TransactionError::Inner));
+    result.map_err(|e| match e {
+      TransactionError::Inner(e) => e,
+      TransactionError::Query(e) => DbError::from(e).into(),
+    })
+  }
+}
+
+/// Error of a transaction: either the error returned by the transaction closure, or an error beginning, committing, or
+/// rolling back the transaction.
+enum TransactionError<E> {
+  Inner(E),
+  Query(diesel::result::Error),
+}
+impl<E> From<diesel::result::Error> for TransactionError<E> {
+  fn from(error: diesel::result::Error) -> Self { Self::Query(error) }
+}
+
+#[cfg(test)]
+mod tests {
+  use att_core::crates::CrateId;
+  use deadpool_diesel::postgres::{HookError, PoolError, TimeoutType};
+
+  use crate::crates::CratesDb;
+  use crate::test_util::TestDb;
+
+  use super::*;
+
+  #[test]
+  fn pool_timeouts_map_to_pool_timeout() {
+    for timeout_type in [TimeoutType::Wait, TimeoutType::Create, TimeoutType::Recycle] {
+      let error = DbError::from(PoolError::Timeout(timeout_type));
+      assert!(matches!(error, DbError::PoolTimeout), "{timeout_type:?} mapped to {error:?}");
+      assert!(error.is_pool_timeout());
+    }
+  }
+
+  #[test]
+  fn closed_pool_maps_to_pool_closed() {
+    let error = DbError::from(PoolError::Closed);
+    assert!(matches!(error, DbError::PoolClosed));
+    assert!(!error.is_pool_timeout());
+  }
+
+  #[test]
+  fn other_pool_errors_map_to_connection_from_pool() {
+    let backend = || deadpool_diesel::Error::Connection(diesel::ConnectionError::BadConnection("refused".to_string()));
+    let errors = [
+      PoolError::Backend(backend()),
+      PoolError::NoRuntimeSpecified,
+      PoolError::PostCreateHook(HookError::Message("failed".into())),
+      PoolError::PostCreateHook(HookError::Backend(backend())),
+    ];
+    for error in errors {
+      let error = DbError::from(error);
+      assert!(matches!(error, DbError::ConnectionFromPool(_)), "mapped to {error:?}");
+      assert!(!error.is_pool_timeout());
+    }
+  }
+
+  #[tokio::test]
+  async fn transaction_rolls_back_on_error() {
+    let test_db = TestDb::new().unwrap();
+    let (result, found) = test_db.db_pool().with::<CratesDb>().interact(|conn| {
+      let result = conn.transaction(|conn| {
+        conn.seed_crate(CrateId(1), "serde", "1.0.0")?;
+        // Fails due to the crate ID already existing, which must roll back inserting the first crate.
+        conn.seed_crate(CrateId(1), "serde", "1.0.0")
+      });
+      (result, conn.find(CrateId(1)))
+    }).await.unwrap();
+
+    assert!(result.is_err_and(|e| e.is_unique_violation()));
+    assert!(found.unwrap().is_none());
+  }
+
+  #[tokio::test]
+  async fn transaction_commits_on_success() {
+    let test_db = TestDb::new().unwrap();
+    let found = test_db.db_pool().with::<CratesDb>().query(|conn| {
+      conn.transaction(|conn| conn.seed_crate(CrateId(1), "serde", "1.0.0"))?;
+      conn.find(CrateId(1))
+    }).await.unwrap();
+
+    assert_eq!(found.unwrap().krate.name, "serde");
+  }
 }
@@ -0,0 +1,140 @@
+//! Utilities for testing code that uses the database.
+
+use std::error::Error;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use chrono::Utc;
+use deadpool_diesel::postgres::BuildError;
+use diesel::{Connection, ConnectionError, insert_into, PgConnection, RunQueryDsl};
+use diesel::connection::SimpleConnection;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use thiserror::Error;
+use tracing::{debug, error};
+
+use att_core::crates::{Crate, CrateId, CrateVersion, CRATES_IO_REGISTRY, FullCrate};
+use att_core::run_or_compile_time_env;
+use att_core::schema::{crate_versions, crates};
+
+use crate::{DbConn, DbError, DbPool};
+use crate::crates::CratesDb;
+use crate::users::{NewUser, User, UsersDb};
+
+/// Migrations to apply to test databases, embedded from the migrations directory so that new migrations are always
+/// applied.
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/// A throwaway database that has all migrations applied, and is dropped when this value is dropped.
+pub struct TestDb {
+  server_url: String,
+  database_name: String,
+  db_pool: DbPool,
+}
+
+#[derive(Debug, Error)]
+pub enum TestDbError {
+  #[error("Failed to connect to database: {0}")]
+  Connection(#[from] ConnectionError),
+  #[error("Failed to create test database: {0}")]
+  Query(#[from] diesel::result::Error),
+  #[error("Failed to migrate test database: {0}")]
+  Migration(Box<dyn Error + Send + Sync>),
+  #[error("Failed to create database connection pool: {0}")]
+  Pool(#[from] BuildError),
+}
+
+impl TestDb {
+  /// Creates a new test database on the database server of `DATABASE_URL`.
+  pub fn new() -> Result<Self, TestDbError> {
+    Self::from_database_url(&run_or_compile_time_env!("DATABASE_URL"))
+  }
+
+  /// Creates a new test database on the database server of `database_url`. The user of `database_url` must be allowed
+  /// to create databases.
+  pub fn from_database_url(database_url: &str) -> Result<Self, TestDbError> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let database_name = format!("att_test_{}_{}", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed));
+    let server_url = server_url(database_url);
+
+    debug!(database_name, "creating test database");
+    let mut conn = PgConnection::establish(database_url)?;
+    conn.batch_execute(&format!(r#"CREATE DATABASE "{database_name}""#))?;
+
+    let test_database_url = format!("{server_url}/{database_name}");
+    let mut test_conn = PgConnection::establish(&test_database_url)?;
+    test_conn.run_pending_migrations(MIGRATIONS).map_err(TestDbError::Migration)?;
+
+    let db_pool = DbPool::from_database_url(test_database_url)?;
+    Ok(Self { server_url, database_name, db_pool })
+  }
+
+  #[inline]
+  pub fn db_pool(&self) -> &DbPool { &self.db_pool }
+}
+
+impl Drop for TestDb {
+  fn drop(&mut self) {
+    // Connect to the default database of the server, as a database cannot be dropped while connected to it.
+    let result = PgConnection::establish(&format!("{}/postgres", self.server_url))
+      .map_err(TestDbError::from)
+      .and_then(|mut conn| {
+        conn.batch_execute(&format!(r#"DROP DATABASE IF EXISTS "{}" WITH (FORCE)"#, self.database_name))?;
+        Ok(())
+      });
+    if let Err(cause) = result {
+      error!(database_name = self.database_name, %cause, "failed to drop test database: {cause:?}");
+    }
+  }
+}
+
+/// Gets the URL of the database server from `database_url`, by removing its database name and query.
+fn server_url(database_url: &str) -> String {
+  let without_query = database_url.split_once('?').map_or(database_url, |(url, _)| url);
+  match without_query.rsplit_once('/') {
+    Some((server_url, _)) if !server_url.ends_with('/') => server_url.to_string(),
+    _ => without_query.to_string(),
+  }
+}
+
+
+// Seed data
+
+impl DbConn<'_, CratesDb> {
+  /// Inserts a crates.io crate with `crate_id`, `name`, and a single default version `version_number`.
+  #[inline]
+  pub fn seed_crate(&mut self, crate_id: CrateId, name: &str, version_number: &str) -> Result<FullCrate, DbError> {
+    self.seed_crate_in_registry(crate_id, name, version_number, CRATES_IO_REGISTRY)
+  }
+
+  /// Inserts a crate of `registry` with `crate_id`, `name`, and a single default version `version_number`.
+  pub fn seed_crate_in_registry(&mut self, crate_id: CrateId, name: &str, version_number: &str, registry: &str) -> Result<FullCrate, DbError> {
+    let now = Utc::now();
+    let krate = Crate {
+      id: crate_id,
+      name: name.to_string(),
+      updated_at: now,
+      created_at: now,
+      description: format!("Description of {name}"),
+      default_version_id: crate_id.0,
+      registry: registry.to_string(),
+      ..Crate::default()
+    };
+    let default_version = CrateVersion { id: crate_id.0, crate_id, number: version_number.to_string() };
+    self.conn.transaction(|conn| {
+      insert_into(crates::table).values(&krate).execute(conn)?;
+      insert_into(crate_versions::table).values(&default_version).execute(conn)?;
+      Ok::<_, DbError>(())
+    })?;
+    Ok(FullCrate { krate, default_version })
+  }
+}
+
+impl DbConn<'_, UsersDb> {
+  /// Inserts a user with `name` and a dummy password hash.
+  pub fn seed_user(&mut self, name: &str) -> Result<User, DbError> {
+    let new_user = NewUser { name: name.to_string(), password_hash: String::new() };
+    let user = insert_into(att_core::schema::users::table)
+      .values(&new_user)
+      .get_result(self.conn)?;
+    Ok(user)
+  }
+}
@@ -7,6 +7,7 @@ use diesel::prelude::*;
 use tracing::instrument;
 
 use att_core::schema::users;
+use att_core::users::UserId;
 
 use crate::{DbConn, DbError};
 
@@ -16,7 +17,7 @@ pub struct UsersDb;
 #[derive(Clone, Queryable, Selectable, Identifiable, Insertable)]
 #[diesel(table_name = users, check_for_backend(Pg))]
 pub struct User {
-  pub id: i32,
+  pub id: UserId,
   pub name: String,
   pub password_hash: String,
 }
@@ -35,7 +36,7 @@ impl Debug for User {
 
 impl DbConn<'_, UsersDb> {
   #[instrument(skip(self), err)]
-  pub fn find(&mut self, user_id: i32) -> Result<Option<User>, DbError> {
+  pub fn find(&mut self, user_id: UserId) -> Result<Option<User>, DbError> {
     let user = users::table
       .find(user_id)
       .first(self.conn)